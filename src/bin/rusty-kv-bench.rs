@@ -0,0 +1,262 @@
+//! Benchmark tool for the rusty-kv-server.
+//!
+//! Opens a number of client connections to a running server, has them run
+//! SET and GET commands over a fixed set of keys, and reports throughput
+//! and latency percentiles for every command. Gives a repeatable number to
+//! compare changes to locking, eviction or sharding against.
+
+use std::{
+  sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+  },
+  time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Parser;
+use tokio::{
+  io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+  net::TcpStream,
+  task::JoinSet,
+};
+
+/// Command line options.
+#[derive(Parser, Debug)]
+#[command(name = "rusty-kv-bench", about = "Benchmark a running rusty-kv server")]
+struct Args {
+  /// Host of the server
+  #[arg(long, default_value = "127.0.0.1")]
+  host: String,
+
+  /// Port of the server
+  #[arg(short, long, default_value_t = 6379)]
+  port: u16,
+
+  /// Username to authenticate as, needs a password
+  #[arg(short, long, requires = "password")]
+  user: Option<String>,
+
+  /// Password to authenticate with
+  #[arg(short = 'a', long)]
+  password: Option<String>,
+
+  /// Number of parallel connections
+  #[arg(short, long, default_value_t = 50)]
+  clients: usize,
+
+  /// Number of requests per command
+  #[arg(short = 'n', long, default_value_t = 100_000)]
+  requests: usize,
+
+  /// Number of distinct keys the requests are spread over
+  #[arg(short, long, default_value_t = 10_000)]
+  keys: usize,
+
+  /// Size of the values written by SET, in bytes
+  #[arg(short = 'd', long, default_value_t = 3)]
+  value_size: usize,
+}
+
+/// Commands that can be benchmarked.
+#[derive(Debug, Clone, Copy)]
+enum Test {
+  Set,
+  Get,
+}
+
+impl Test {
+  /// Name the results are reported under.
+  fn name(&self) -> &'static str {
+    match self {
+      Test::Set => "SET",
+      Test::Get => "GET",
+    }
+  }
+}
+
+/// Runs every test in turn and prints its results.
+#[tokio::main]
+async fn main() -> Result<()> {
+  let args = Arc::new(Args::parse());
+  if args.clients == 0 || args.keys == 0 {
+    bail!("--clients and --keys must be at least 1");
+  }
+
+  println!(
+    "{} clients, {} requests, {} keys, {} byte values",
+    args.clients, args.requests, args.keys, args.value_size
+  );
+  // Connect every client up front and at once, so connecting and
+  // authenticating is neither measured nor done one client at a time
+  let mut opening = JoinSet::new();
+  for _ in 0..args.clients {
+    let args = args.clone();
+    opening.spawn(async move { Connection::open(&args).await });
+  }
+  let mut connections = Vec::with_capacity(args.clients);
+  while let Some(connection) = opening.join_next().await {
+    connections.push(connection??);
+  }
+
+  // SET runs first, so GET finds every key
+  for test in [Test::Set, Test::Get] {
+    connections = run(test, args.clone(), connections).await?;
+  }
+  Ok(())
+}
+
+/// Runs a single test and prints its throughput and latencies.
+///
+/// # Arguments
+///
+/// * `test` - Command to benchmark
+/// * `args` - Command line options
+/// * `connections` - One connection per client
+///
+/// # Returns
+///
+/// The connections, for the next test to reuse.
+async fn run(test: Test, args: Arc<Args>, connections: Vec<Connection>) -> Result<Vec<Connection>> {
+  let next = Arc::new(AtomicUsize::new(0));
+  let value = "x".repeat(args.value_size);
+  let started = Instant::now();
+  let mut clients = JoinSet::new();
+  for mut connection in connections {
+    let (args, next, value) = (args.clone(), next.clone(), value.clone());
+    clients.spawn(async move {
+      let mut latencies = Vec::new();
+      let mut errors = 0;
+      loop {
+        let request = next.fetch_add(1, Ordering::Relaxed);
+        if request >= args.requests {
+          break;
+        }
+        let key = format!("key:{}", request % args.keys);
+        let command = match test {
+          Test::Set => vec!["SET", &key, &value],
+          Test::Get => vec!["GET", &key],
+        };
+
+        let sent = Instant::now();
+        if let Reply::Error(_) = connection.request(&command).await? {
+          errors += 1;
+        }
+        latencies.push(sent.elapsed());
+      }
+      Ok::<_, anyhow::Error>((connection, latencies, errors))
+    });
+  }
+
+  let mut latencies = Vec::with_capacity(args.requests);
+  let mut errors = 0;
+  let mut connections = Vec::with_capacity(args.clients);
+  while let Some(result) = clients.join_next().await {
+    let (connection, client_latencies, client_errors) = result??;
+    connections.push(connection);
+    latencies.extend(client_latencies);
+    errors += client_errors;
+  }
+  report(test, started.elapsed(), latencies, errors);
+  Ok(connections)
+}
+
+/// Prints the results of a test.
+fn report(test: Test, elapsed: Duration, mut latencies: Vec<Duration>, errors: usize) {
+  latencies.sort_unstable();
+  let percentile = |p: f64| {
+    let index = ((latencies.len() as f64 * p).ceil() as usize).saturating_sub(1);
+    latencies
+      .get(index)
+      .map_or(0.0, |latency| latency.as_secs_f64() * 1000.0)
+  };
+
+  println!("====== {} ======", test.name());
+  println!(
+    "  {} requests completed in {:.2} seconds, {} errors",
+    latencies.len(),
+    elapsed.as_secs_f64(),
+    errors
+  );
+  println!(
+    "  throughput: {:.2} requests per second",
+    latencies.len() as f64 / elapsed.as_secs_f64()
+  );
+  println!(
+    "  latency (msec): p50={:.3} p95={:.3} p99={:.3} max={:.3}",
+    percentile(0.50),
+    percentile(0.95),
+    percentile(0.99),
+    percentile(1.0)
+  );
+}
+
+/// Reply to a benchmarked command. Only the kinds SET, GET and AUTH reply
+/// with are understood.
+enum Reply {
+  Ok,
+  Error(String),
+}
+
+/// A client connection to the server.
+struct Connection {
+  /// Buffered stream to the server
+  stream: BufReader<TcpStream>,
+}
+
+impl Connection {
+  /// Connects to the server, authenticating if credentials were given.
+  async fn open(args: &Args) -> Result<Self> {
+    let stream = TcpStream::connect((args.host.as_str(), args.port))
+      .await
+      .with_context(|| format!("Failed to connect to {}:{}", args.host, args.port))?;
+    stream.set_nodelay(true)?;
+    let mut connection = Self {
+      stream: BufReader::new(stream),
+    };
+
+    if let Some(password) = &args.password {
+      let mut command = vec!["AUTH"];
+      command.extend(args.user.as_deref());
+      command.push(password);
+      if let Reply::Error(e) = connection.request(&command).await? {
+        bail!("Failed to authenticate: {}", e);
+      }
+    }
+    Ok(connection)
+  }
+
+  /// Sends a command and waits for its reply.
+  async fn request(&mut self, command: &[&str]) -> Result<Reply> {
+    let mut buf = format!("*{}\r\n", command.len()).into_bytes();
+    for arg in command {
+      buf.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+      buf.extend_from_slice(arg.as_bytes());
+      buf.extend_from_slice(b"\r\n");
+    }
+    self.stream.get_mut().write_all(&buf).await?;
+    self.read_reply().await
+  }
+
+  /// Reads a single reply, skipping over the payload of bulk strings.
+  async fn read_reply(&mut self) -> Result<Reply> {
+    let mut line = String::new();
+    if self.stream.read_line(&mut line).await? == 0 {
+      bail!("Connection closed by the server");
+    }
+    let line = line.trim_end();
+    match line.split_at_checked(1) {
+      Some(("+" | ":" | "_", _)) => Ok(Reply::Ok),
+      Some(("-", message)) => Ok(Reply::Error(message.to_string())),
+      Some(("$", len)) => {
+        // A negative length is a null without a payload
+        if let Ok(len) = len.parse::<usize>() {
+          let mut payload = vec![0; len + 2];
+          self.stream.read_exact(&mut payload).await?;
+        }
+        Ok(Reply::Ok)
+      }
+      _ => Err(anyhow!("Unexpected reply '{}'", line)),
+    }
+  }
+}
@@ -1,15 +1,36 @@
 //! Authentication command implementation.
 //!
 //! Handles user authentication against a database of credentials,
-//! using secure password hashing (Keccak256).
+//! using salted password hashing (Argon2).
+
+use std::borrow::Cow;
 
 use anyhow::{Result, anyhow};
+use async_trait::async_trait;
 use log::{info, warn};
-use sha3::{Digest, Keccak256};
+use rusqlite::{OptionalExtension, params};
 
 use crate::{
-  resp::value::Value,
+  commands::registry::{Command, CommandContext},
+  resp::{
+    error::{CommandError, ErrorCode},
+    value::Value,
+  },
   storage::{db::InternalDB, memory::MemoryStore, memory::Store},
+  utils::{
+    client::Client,
+    password::{credential_hash, hash_password, needs_rehash, verify_password},
+    settings::{Settings, SharedSettings},
+  },
+};
+
+/// ACL user `AUTH <password>` falls back to when no default user is configured.
+const DEFAULT_USER: &str = "default";
+
+/// Error returned for a wrong username or password, without telling which.
+const WRONG_PASS: CommandError = CommandError {
+  code: ErrorCode::WrongPass,
+  message: Cow::Borrowed("invalid username-password pair or user is disabled."),
 };
 
 /// Authentication command handler.
@@ -23,9 +44,11 @@ impl AuthCommand {
   ///
   /// # Arguments
   ///
-  /// * `args` - Command arguments (should contain username and password)
-  /// * `store` - Memory store to set authentication state on
+  /// * `args` - Command arguments (username and password, or just a password)
+  /// * `store` - The connection's memory store handle to authenticate
   /// * `db` - Database connection for credential verification
+  /// * `settings` - Server settings naming the default user
+  /// * `client` - Connection to record the user's root flag on
   ///
   /// # Returns
   ///
@@ -36,50 +59,129 @@ impl AuthCommand {
   ///
   /// ```
   /// // Client sends: AUTH username password
-  /// let result = AuthCommand::execute(vec!["username".to_string(), "password".to_string()], store, db).await;
+  /// let result = AuthCommand::execute(vec!["username".to_string(), "password".to_string()], store, db, settings, client).await;
+  ///
+  /// // Client sends: AUTH password
+  /// let result = AuthCommand::execute(vec!["password".to_string()], store, db, settings, client).await;
   /// ```
-  pub async fn execute(args: Vec<String>, store: MemoryStore, db: InternalDB) -> Result<Value> {
-    if args.len() < 2 {
-      return Err(anyhow!("AUTH requires username and password"));
-    }
-
-    let username = &args[0];
-    let password = &args[1];
-
-    // Hash the password for comparison
-    let mut hasher = Keccak256::new();
-    hasher.update(password.as_bytes());
-    let password_hash = format!("{:x}", hasher.finalize());
+  pub async fn execute(
+    args: Vec<String>,
+    store: &mut MemoryStore,
+    db: InternalDB,
+    settings: &SharedSettings,
+    client: &mut Client,
+  ) -> Result<Value> {
+    // With a single argument, authenticate as the default user
+    let default_user = Self::default_user(&settings.read().unwrap());
+    let (username, password) = match args.as_slice() {
+      [password] => (default_user.as_str(), password),
+      [username, password, ..] => (username.as_str(), password),
+      [] => return Err(anyhow!(WRONG_PASS)),
+    };
 
     // Get a database connection from the pool
     let conn = db.pool.get()?;
 
     // Query the database for the user
-    let mut stmt = conn.prepare("SELECT username, password FROM users WHERE username = ?")?;
-    let mut rows = stmt.query(&[username])?;
+    let mut stmt = conn.prepare("SELECT id, password, root_user FROM users WHERE username = ?")?;
+    let mut rows = stmt.query([username])?;
 
     if let Some(row) = rows.next()? {
+      let id: String = row.get(0)?;
       let db_password: String = row.get(1)?;
 
-      if db_password == password_hash {
+      if verify_password(password, &db_password) {
         info!("User '{}' authenticated successfully", username);
 
-        // Create a user-specific credential hash
-        let mut hasher = Keccak256::new();
-        hasher.update(format!("{}:{}", username, db_password).as_bytes());
-        let credential_hash = format!("{:x}", hasher.finalize());
+        // Data saved before stores were keyed by user id lives under the
+        // credential hash, move it over
+        store.rekey_user(&credential_hash(username, &db_password), &id);
+
+        // Upgrade a legacy Keccak256 hash now that the password is known
+        if needs_rehash(&db_password) {
+          let upgraded = hash_password(password)?;
+          conn.execute(
+            "UPDATE users SET password = ? WHERE id = ?",
+            params![upgraded, id],
+          )?;
+          info!("Upgraded password hash of user '{}' to Argon2", username);
+        }
 
         // Set the current user in the store
-        store.set_current_user(Some(credential_hash));
+        store.set_current_user(Some(id));
+        client.set_root(row.get(2)?);
 
         return Ok(Value::SimpleString("OK".to_string()));
       } else {
         warn!("Invalid password for user '{}'", username);
-        return Err(anyhow!("Invalid username or password"));
+        return Err(anyhow!(WRONG_PASS));
       }
     } else {
       warn!("User '{}' not found", username);
-      return Err(anyhow!("Invalid username or password"));
+      return Err(anyhow!(WRONG_PASS));
     }
   }
+
+  /// Authenticates a connection as a user without checking a password.
+  ///
+  /// Used for connections that start authenticated because
+  /// `server.network.requireauth` is off.
+  ///
+  /// # Arguments
+  ///
+  /// * `username` - User to authenticate as
+  /// * `store` - The connection's memory store handle to authenticate
+  /// * `db` - Database holding the users
+  /// * `client` - Connection to record the user's root flag on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - The connection is authenticated
+  /// * `Err` - The user doesn't exist or the database can't be read
+  pub fn login(
+    username: &str,
+    store: &mut MemoryStore,
+    db: &InternalDB,
+    client: &mut Client,
+  ) -> Result<()> {
+    let conn = db.pool.get()?;
+    let (id, root): (String, bool) = conn
+      .query_row(
+        "SELECT id, root_user FROM users WHERE username = ?",
+        [username],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+      )
+      .optional()?
+      .ok_or_else(|| anyhow!("user '{}' not found", username))?;
+
+    store.set_current_user(Some(id));
+    client.set_root(root);
+    Ok(())
+  }
+
+  /// Returns the user `AUTH <password>` authenticates as.
+  ///
+  /// This is the configured regular user, or the `default` user if none
+  /// is configured. Connections also start authenticated as this user
+  /// when authentication isn't required.
+  pub fn default_user(settings: &Settings) -> String {
+    match settings.server.network.user.as_str() {
+      "" => DEFAULT_USER.to_string(),
+      user => user.to_string(),
+    }
+  }
+}
+
+#[async_trait]
+impl Command for AuthCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(
+      ctx.args,
+      ctx.store,
+      ctx.db.clone(),
+      ctx.settings,
+      ctx.client,
+    )
+    .await
+  }
 }
@@ -0,0 +1,160 @@
+//! HELLO command implementation.
+//!
+//! Answers the handshake Redis clients send when they connect, describing
+//! the server and optionally authenticating and naming the connection.
+//! `HELLO 3` switches the connection to RESP3, whose extra reply types are
+//! otherwise sent as their closest RESP2 equivalent.
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    acl::auth::AuthCommand,
+    general::client::ClientCommand,
+    registry::{Command, CommandContext},
+  },
+  resp::{
+    error::{CommandError, ErrorCode},
+    value::Value,
+  },
+  storage::{db::InternalDB, memory::MemoryStore, memory::Store},
+  utils::{client::Client, settings::SharedSettings},
+};
+
+/// Protocol versions the server speaks.
+const SUPPORTED_PROTOCOLS: [u8; 2] = [2, 3];
+
+/// HELLO command handler.
+///
+/// Unauthenticated connections may run it, but must then authenticate with
+/// its `AUTH` option.
+pub struct HelloCommand;
+
+impl HelloCommand {
+  /// Executes the HELLO command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (an optional protocol version, followed by
+  ///   `AUTH <username> <password>` and `SETNAME <name>` options)
+  /// * `store` - The connection's memory store handle to authenticate
+  /// * `db` - Database connection for credential verification
+  /// * `settings` - Server settings naming the default user
+  /// * `client` - State of the connection to authenticate, name and switch
+  ///   protocol
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - Field names each followed by their value,
+  ///   describing the server as configured and the connection
+  /// * `Err` - A protocol version other than 2 or 3, invalid options, wrong
+  ///   credentials, or an unauthenticated connection without `AUTH`
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: HELLO 2 AUTH admin securepassword SETNAME worker-1
+  /// let args = vec!["2", "AUTH", "admin", "securepassword", "SETNAME", "worker-1"];
+  /// let result = HelloCommand::execute(args.into_iter().map(String::from).collect(), store, db, settings, client).await;
+  /// // Returns ["server", <server.name>, "version", <server.version>, "proto", 2, ...]
+  /// ```
+  pub async fn execute(
+    args: Vec<String>,
+    store: &mut MemoryStore,
+    db: InternalDB,
+    settings: &SharedSettings,
+    client: &mut Client,
+  ) -> Result<Value> {
+    let protocol = match args.first() {
+      Some(version) => {
+        let version = version
+          .parse::<i64>()
+          .map_err(|_| anyhow!("Protocol version is not an integer or out of range"))?;
+        match u8::try_from(version) {
+          Ok(version) if SUPPORTED_PROTOCOLS.contains(&version) => Some(version),
+          _ => bail!(CommandError::new(
+            ErrorCode::NoProto,
+            "unsupported protocol version"
+          )),
+        }
+      }
+      None => None,
+    };
+
+    // Every option is checked before any of them is applied
+    let mut credentials = None;
+    let mut name = None;
+    let mut options = args.iter().skip(1);
+    while let Some(option) = options.next() {
+      match option.to_uppercase().as_str() {
+        "AUTH" => match (options.next(), options.next()) {
+          (Some(username), Some(password)) => {
+            credentials = Some(vec![username.clone(), password.clone()])
+          }
+          _ => bail!("syntax error in HELLO option 'auth'"),
+        },
+        "SETNAME" => match options.next() {
+          Some(value) => name = Some(value.clone()),
+          None => bail!("syntax error in HELLO option 'setname'"),
+        },
+        other => bail!("syntax error in HELLO option '{}'", other.to_lowercase()),
+      }
+    }
+
+    match credentials {
+      Some(credentials) => {
+        AuthCommand::execute(credentials, store, db, settings, client).await?;
+      }
+      None if !store.is_authenticated() => bail!(CommandError::new(
+        ErrorCode::NoAuth,
+        "HELLO must be called with the client already authenticated, otherwise the HELLO <proto> AUTH <user> <pass> option can be used to authenticate the client and select the RESP protocol version at the same time"
+      )),
+      None => {}
+    }
+    if let Some(name) = name {
+      ClientCommand::execute(vec!["SETNAME".to_string(), name], client)?;
+    }
+    if let Some(protocol) = protocol {
+      client.set_protocol(protocol);
+    }
+
+    let (server, version) = {
+      let settings = settings.read().unwrap();
+      (
+        settings.server.name.clone(),
+        settings.server.version.clone(),
+      )
+    };
+    Ok(Value::Array(vec![
+      Value::BulkString("server".to_string()),
+      Value::BulkString(server),
+      Value::BulkString("version".to_string()),
+      Value::BulkString(version),
+      Value::BulkString("proto".to_string()),
+      Value::Integer(client.protocol() as i64),
+      Value::BulkString("id".to_string()),
+      Value::Integer(client.id as i64),
+      Value::BulkString("mode".to_string()),
+      Value::BulkString("standalone".to_string()),
+      Value::BulkString("role".to_string()),
+      Value::BulkString("master".to_string()),
+      Value::BulkString("modules".to_string()),
+      Value::Array(Vec::new()),
+    ]))
+  }
+}
+
+#[async_trait]
+impl Command for HelloCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(
+      ctx.args,
+      ctx.store,
+      ctx.db.clone(),
+      ctx.settings,
+      ctx.client,
+    )
+    .await
+  }
+}
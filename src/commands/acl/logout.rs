@@ -0,0 +1,51 @@
+//! LOGOUT command implementation.
+//!
+//! Drops the authentication of the connection without disconnecting it.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::{MemoryStore, Store},
+  utils::client::Client,
+};
+
+/// LOGOUT command handler.
+///
+/// Clears the authenticated user, so every following command that needs
+/// authentication fails until the connection runs AUTH again.
+pub struct LogoutCommand;
+
+impl LogoutCommand {
+  /// Executes the LOGOUT command.
+  ///
+  /// # Arguments
+  ///
+  /// * `store` - The connection's memory store handle to log out
+  /// * `client` - Connection to drop the user's root flag from
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::SimpleString("OK"))` - Also if no user was authenticated
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LOGOUT
+  /// let result = LogoutCommand::execute(store, client);
+  /// ```
+  pub fn execute(store: &mut MemoryStore, client: &mut Client) -> Result<Value> {
+    store.set_current_user(None);
+    client.set_root(false);
+    Ok(Value::SimpleString("OK".to_string()))
+  }
+}
+
+#[async_trait]
+impl Command for LogoutCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.store, ctx.client)
+  }
+}
@@ -3,6 +3,14 @@
 //! This module contains commands for managing authentication and authorization.
 //! Currently implements:
 //! - `auth`: User authentication
+//! - `hello`: Connection handshake, optionally authenticating
+//! - `logout`: Dropping authentication
+//! - `passwd`: Password changes
+//! - `user`: Runtime user management
 
 pub mod auth;
+pub mod hello;
+pub mod logout;
+pub mod passwd;
+pub mod user;
 pub mod whoami;
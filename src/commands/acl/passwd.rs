@@ -0,0 +1,93 @@
+//! PASSWD command implementation.
+//!
+//! Lets an authenticated user change their own password.
+
+use std::time::SystemTime;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use rusqlite::{OptionalExtension, params};
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{
+    db::InternalDB,
+    memory::{AUTH_REQUIRED, MemoryStore, Store},
+  },
+  utils::password::{hash_password, verify_password},
+};
+
+/// PASSWD command handler.
+///
+/// Verifies the current password and stores the new one. The user's data
+/// is keyed by their id, so it stays where it is.
+pub struct PasswdCommand;
+
+impl PasswdCommand {
+  /// Executes the PASSWD command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (old password and new password)
+  /// * `store` - Memory store holding the authenticated user and their data
+  /// * `db` - Database connection holding the users table
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - OK once the password was changed
+  /// * `Err` - Not authenticated, or the old password is wrong
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: PASSWD oldsecret newsecret
+  /// let result = PasswdCommand::execute(
+  ///     vec!["oldsecret".to_string(), "newsecret".to_string()],
+  ///     store,
+  ///     db
+  /// ).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore, db: InternalDB) -> Result<Value> {
+    let Some(id) = store.get_current_user() else {
+      return Err(anyhow!(AUTH_REQUIRED));
+    };
+
+    let conn = db.pool.get()?;
+
+    let user: Option<(String, String)> = conn
+      .query_row(
+        "SELECT username, password FROM users WHERE id = ?",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+      )
+      .optional()?;
+    let Some((username, password)) = user else {
+      return Err(anyhow!("User not found in database"));
+    };
+
+    if !verify_password(&args[0], &password) {
+      warn!("Invalid old password for user '{}'", username);
+      return Err(anyhow!("Invalid password"));
+    }
+
+    let new_password = hash_password(&args[1])?;
+    let time_stamp: DateTime<Utc> = SystemTime::now().into();
+    conn.execute(
+      "UPDATE users SET password = ?, updated_at = ? WHERE id = ?",
+      params![new_password, time_stamp.to_rfc3339(), id],
+    )?;
+
+    info!("User '{}' changed their password", username);
+    Ok(Value::SimpleString("OK".to_string()))
+  }
+}
+
+#[async_trait]
+impl Command for PasswdCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), ctx.db.clone()).await
+  }
+}
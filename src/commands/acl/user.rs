@@ -0,0 +1,135 @@
+//! USER command implementation.
+//!
+//! Lets root users manage the accounts in the users table at runtime.
+//! The executor only lets root users run it.
+
+use std::time::SystemTime;
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::warn;
+use rusqlite::{OptionalExtension, params};
+use uuid::Uuid;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::db::InternalDB,
+  utils::password::hash_password,
+};
+
+/// USER command handler.
+///
+/// Supports the `ADD` and `DEL` subcommands.
+pub struct UserCommand;
+
+impl UserCommand {
+  /// Executes the USER command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the upper-case subcommand
+  /// * `db` - Database connection holding the users table
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - OK once the user was added or deleted
+  /// * `Err` - Unknown subcommand, wrong argument count, duplicate or missing
+  ///   user, or an attempt to delete the last root user
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: USER ADD alice secret
+  /// let result = UserCommand::execute(
+  ///     vec!["ADD".to_string(), "alice".to_string(), "secret".to_string()],
+  ///     db
+  /// ).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, db: InternalDB) -> Result<Value> {
+    let subcommand = &args[0];
+    let expected = match subcommand.as_str() {
+      "ADD" => 3,
+      "DEL" => 2,
+      other => return Err(anyhow!("unknown subcommand '{}'", other.to_lowercase())),
+    };
+    if args.len() != expected {
+      bail!(
+        "wrong number of arguments for 'user|{}' command",
+        subcommand.to_lowercase()
+      );
+    }
+
+    match subcommand.as_str() {
+      "ADD" => Self::add(&db, &args[1], &args[2])?,
+      _ => Self::delete(&db, &args[1])?,
+    }
+    Ok(Value::SimpleString("OK".to_string()))
+  }
+
+  /// Inserts a regular user with an Argon2-hashed password.
+  fn add(db: &InternalDB, username: &str, password: &str) -> Result<()> {
+    let password_hash = hash_password(password)?;
+
+    let time_stamp: DateTime<Utc> = SystemTime::now().into();
+    let time_stamp = time_stamp.to_rfc3339();
+
+    let conn = db.pool.get()?;
+    match conn.execute(
+      "INSERT INTO users (id, username, password, created_at, updated_at, root_user) VALUES (?, ?, ?, ?, ?, ?);",
+      params![Uuid::new_v4().to_string(), username, password_hash, time_stamp, time_stamp, 0],
+    ) {
+      Ok(_) => {
+        warn!("User created: {}", username);
+        Ok(())
+      }
+      Err(e) if e.to_string().contains("UNIQUE constraint failed") => {
+        Err(anyhow!("User '{}' already exists", username))
+      }
+      Err(e) => Err(e.into()),
+    }
+  }
+
+  /// Deletes a user, refusing to remove the last root user.
+  fn delete(db: &InternalDB, username: &str) -> Result<()> {
+    let mut conn = db.pool.get()?;
+    // Check and delete in one transaction so two deletes can't both pass
+    // the last-root check
+    let tx = conn.transaction()?;
+
+    let root: Option<bool> = tx
+      .query_row(
+        "SELECT root_user FROM users WHERE username = ?",
+        params![username],
+        |row| row.get(0),
+      )
+      .optional()?;
+    let Some(root) = root else {
+      bail!("User '{}' not found", username);
+    };
+
+    if root {
+      let roots: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM users WHERE root_user = 1",
+        [],
+        |row| row.get(0),
+      )?;
+      if roots <= 1 {
+        bail!("Can't delete the last root user");
+      }
+    }
+
+    tx.execute("DELETE FROM users WHERE username = ?", params![username])?;
+    tx.commit()?;
+    warn!("User deleted: {}", username);
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl Command for UserCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.db.clone()).await
+  }
+}
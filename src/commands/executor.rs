@@ -3,35 +3,74 @@
 //! This module handles the parsing, routing, and execution of all commands.
 //! It maps command strings to their corresponding handler implementations.
 
+use std::time::Instant;
+
 use anyhow::{Result, anyhow};
-use log::info;
+use log::{Level, info, log_enabled};
 
 use crate::{
-  commands::acl::whoami::WhoAmi,
-  resp::value::Value,
+  resp::{
+    error::{CommandError, ErrorCode},
+    value::Value,
+  },
   storage::{
+    aof,
     db::InternalDB,
-    memory::{MemoryStore, Store},
+    memory::{AUTH_REQUIRED, MemoryStore, Store},
+    pubsub::{PubSub, Subscriber, Subscription},
+  },
+  utils::{
+    client::{Client, DEFAULT_PROTOCOL},
+    settings::SharedSettings,
+    shutdown::ShutdownTrigger,
   },
 };
 
 use super::{
   acl::auth::AuthCommand,
-  general::{
-    delete::DeleteCommand, echo::EchoCommand, get::GetCommand, help::HelpCommand,
-    ping::PingCommand, set::SetCommand,
-  },
+  general::shutdown::ShutdownCommand,
+  pubsub::{subscribe::SubscribeCommand, unsubscribe::UnsubscribeCommand},
+  registry::{self, CommandContext, Privileged},
+  stats,
+  transaction::Transaction,
 };
 
+/// Commands a connection may still run while it is subscribed to channels.
+const SUBSCRIBED_MODE_COMMANDS: [&str; 5] = ["SUBSCRIBE", "UNSUBSCRIBE", "PING", "QUIT", "RESET"];
+
+/// Commands whose first argument names a subcommand. It is upper-cased
+/// before the handler runs, so `client setname` and `CLIENT SETNAME` are
+/// handled alike.
+const SUBCOMMAND_COMMANDS: [&str; 8] = [
+  "CLIENT", "COMMAND", "CONFIG", "DEBUG", "MEMORY", "OBJECT", "SLOWLOG", "USER",
+];
+
 /// Command executor and router.
 ///
 /// Routes incoming commands to the appropriate command handler
 /// and manages shared state (storage, database connections).
+/// One executor is created per connection, so it also holds the
+/// connection's pub/sub subscription.
 pub struct CommandExecutor {
-  /// Shared memory store for key-value operations
+  /// Memory store handle for key-value operations, holding the
+  /// connection's selected database
   store: MemoryStore,
   /// Database connection for persistent storage
   db: InternalDB,
+  /// Server settings
+  settings: SharedSettings,
+  /// Shared pub/sub channel registry
+  pubsub: PubSub,
+  /// Channels this connection is subscribed to
+  subscription: Subscription,
+  /// Commands queued since MULTI, if a transaction is open
+  transaction: Option<Transaction>,
+  /// Identity of the connection this executor serves
+  client: Client,
+  /// Trigger used by SHUTDOWN to stop the server
+  shutdown: ShutdownTrigger,
+  /// Set by QUIT, the connection is closed once the reply is sent
+  quitting: bool,
 }
 
 impl CommandExecutor {
@@ -41,17 +80,74 @@ impl CommandExecutor {
   ///
   /// * `store` - Shared memory store
   /// * `db` - Database connection
+  /// * `settings` - Server settings
+  /// * `pubsub` - Shared pub/sub channel registry
+  /// * `subscriber` - Queue used to deliver published messages to this connection
+  /// * `client` - State of the connection the executor serves
+  /// * `shutdown` - Trigger used to stop the server
   ///
   /// # Returns
   ///
   /// A new CommandExecutor instance
-  pub fn new(store: MemoryStore, db: InternalDB) -> Self {
-    Self { store, db }
+  pub fn new(
+    store: MemoryStore,
+    db: InternalDB,
+    settings: SharedSettings,
+    pubsub: PubSub,
+    subscriber: Subscriber,
+    client: Client,
+    shutdown: ShutdownTrigger,
+  ) -> Self {
+    Self {
+      store,
+      db,
+      settings,
+      pubsub,
+      subscription: Subscription::new(subscriber),
+      transaction: None,
+      client,
+      shutdown,
+      quitting: false,
+    }
+  }
+
+  /// Runs later commands as `user_id`, without authenticating.
+  ///
+  /// Only used to replay the AOF, which records which user ran each command.
+  pub fn impersonate(&mut self, user_id: String) {
+    self.store.set_current_user(Some(user_id));
+  }
+
+  /// Authenticates the connection as the default user, without a password.
+  ///
+  /// Connections start out this way when `server.network.requireauth` is
+  /// off.
+  pub fn login_default_user(&mut self) -> Result<()> {
+    let username = AuthCommand::default_user(&self.settings.read().unwrap());
+    AuthCommand::login(&username, &mut self.store, &self.db, &mut self.client)
+  }
+
+  /// Returns the RESP version negotiated with HELLO.
+  pub fn protocol(&self) -> u8 {
+    self.client.protocol()
+  }
+
+  /// Returns true once QUIT ran, the connection should be closed after
+  /// sending its reply.
+  pub fn is_quitting(&self) -> bool {
+    self.quitting
+  }
+
+  /// Returns true if the connection is subscribed to at least one channel.
+  pub fn is_subscribed(&self) -> bool {
+    self.subscription.is_active()
   }
 
   /// Executes a command with its arguments.
   ///
   /// Routes the command to the appropriate handler based on the command name.
+  /// While a transaction is open, commands are queued instead and only run
+  /// once EXEC is received.
   ///
   /// # Arguments
   ///
@@ -60,56 +156,293 @@ impl CommandExecutor {
   ///
   /// # Returns
   ///
-  /// * `Ok(Value)` - Command execution result
+  /// * `Ok(Vec<Value>)` - Reply frames to send back, in order. Most commands
+  ///   produce exactly one; SUBSCRIBE and UNSUBSCRIBE produce one per channel.
   /// * `Err` - Error if command is invalid or execution fails
   ///
   /// # Example
   ///
   /// ```
   /// // Execute a GET command
-  /// let result = executor.execute("GET", vec!["mykey".to_string()]).await;
+  /// let result = executor.execute("GET", vec![Value::BulkString("mykey".to_string())]).await;
   /// ```
-  pub async fn execute(&self, command: &str, args: Vec<Value>) -> Result<Value> {
-    // Log command with auth status
-    let auth_status = if self.store.is_authenticated() {
-      "authenticated"
-    } else {
-      "unauthenticated"
+  pub async fn execute(&mut self, command: &str, args: Vec<Value>) -> Result<Vec<Value>> {
+    self.client.touch();
+
+    // Log the command with the auth status. Arguments are logged the way
+    // the slow log keeps them, without credentials
+    if log_enabled!(Level::Info) {
+      let auth_status = if self.store.is_authenticated() {
+        "authenticated"
+      } else {
+        "unauthenticated"
+      };
+      info!(
+        "Executing command '{}' ({} mode) with args: {:?}",
+        command,
+        auth_status,
+        &stats::slowlog_args(command, &args)[1..]
+      );
+    }
+
+    // A subscribed connection may only manage its subscriptions
+    if self.is_subscribed() && !SUBSCRIBED_MODE_COMMANDS.contains(&command) {
+      return Err(anyhow!(
+        "Can't execute '{}': only SUBSCRIBE / UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context",
+        command.to_lowercase()
+      ));
+    }
+
+    // Unknown commands, wrong argument counts and missing permissions are
+    // rejected before anything runs, and abort an open transaction
+    let checked = registry::lookup(command)
+      .ok_or_else(|| anyhow!("unknown command '{}'", command))
+      .and_then(|spec| spec.check_arity(args.len()))
+      .and_then(|()| self.check_auth(command))
+      .and_then(|()| self.check_permission(command, &args));
+    if let Err(e) = checked {
+      if let Some(transaction) = self.transaction.as_mut() {
+        transaction.abort();
+      }
+      return Err(e);
+    }
+
+    // Transaction control, RESET and QUIT are handled before anything gets queued
+    let started = Instant::now();
+    let control = match command {
+      "MULTI" => Some(self.multi()),
+      "EXEC" => Some(self.exec().await),
+      "DISCARD" => Some(self.discard()),
+      "RESET" => Some(self.reset()),
+      "QUIT" => Some(self.quit()),
+      _ => None,
+    };
+    if let Some(result) = control {
+      stats::record(command, started.elapsed());
+      return result;
+    }
+
+    if let Some(transaction) = self.transaction.as_mut() {
+      transaction.queue(command, args);
+      return Ok(vec![Value::SimpleString("QUEUED".to_string())]);
+    }
+
+    self.dispatch(command, args).await
+  }
+
+  /// Fails if `command` needs an authenticated connection and this one
+  /// isn't.
+  ///
+  /// Commands rely on this check instead of each checking for themselves.
+  /// The ones allowed without authentication are flagged `no_auth` in the
+  /// command registry, unknown commands are never allowed.
+  fn check_auth(&self, command: &str) -> Result<()> {
+    let no_auth = registry::lookup(command).is_some_and(|spec| spec.no_auth);
+    if !no_auth && !self.store.is_authenticated() {
+      return Err(anyhow!(AUTH_REQUIRED));
+    }
+    Ok(())
+  }
+
+  /// Fails if the connection's user may not run `command`.
+  ///
+  /// The commands and subcommands reserved for root users are flagged
+  /// `privileged` in the command registry.
+  fn check_permission(&self, command: &str, args: &[Value]) -> Result<()> {
+    let Some(spec) = registry::lookup(command) else {
+      return Ok(());
+    };
+    // Subcommands are matched upper-cased, like their handlers see them
+    let subcommand = match spec.privileged {
+      Privileged::Subcommands(_) => args
+        .first()
+        .and_then(Value::to_bytes)
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_uppercase()),
+      _ => None,
     };
-    info!(
-      "Executing command '{}' ({} mode) with args: {:?}",
-      command, auth_status, args
-    );
+    if !spec.privileged.applies_to(subcommand.as_deref()) || self.client.is_root() {
+      return Ok(());
+    }
+    let name = match subcommand {
+      Some(subcommand) => format!("{}|{}", command, subcommand),
+      None => command.to_string(),
+    };
+    Err(anyhow!(CommandError::new(
+      ErrorCode::NoPerm,
+      format!(
+        "this user has no permissions to run the '{}' command",
+        name.to_lowercase()
+      )
+    )))
+  }
+
+  /// Runs a single command, recording how long it took in the command stats
+  /// and, if it was slow, in the slow log.
+  ///
+  /// Queued commands are only counted once EXEC runs them.
+  async fn dispatch(&mut self, command: &str, args: Vec<Value>) -> Result<Vec<Value>> {
+    // Queued commands were checked when queued, but a LOGOUT may run first
+    self.check_auth(command)?;
+
+    // The arguments are moved into the handler, so keep what the slow log
+    // and the AOF need
+    let argv = stats::slowlog_enabled().then(|| stats::slowlog_args(command, &args));
+    let logged_args = aof::logs(command).then(|| args.clone());
+
+    let ordered = aof::ordered(command).await;
+    let started = Instant::now();
+    let result = self.route(command, args).await;
+    let elapsed = started.elapsed();
+    if let (Ok(replies), Some(args)) = (&result, logged_args) {
+      aof::append(&self.store, command, &args, replies);
+    }
+    drop(ordered);
+
+    stats::record(command, elapsed);
+    if let Some(argv) = argv {
+      stats::log_if_slow(elapsed, argv, self.client.addr(), self.client.name());
+    }
+    result
+  }
 
+  /// Routes a single command to its handler and runs it.
+  ///
+  /// SUBSCRIBE and UNSUBSCRIBE change the connection's subscription and may
+  /// reply with several frames, and SHUTDOWN replies with none, so they are
+  /// handled here. Every other command is looked up in the command registry.
+  async fn route(&mut self, command: &str, args: Vec<Value>) -> Result<Vec<Value>> {
     // Convert Values to strings for commands that still expect strings
-    let string_args: Vec<String> = args
+    let mut string_args: Vec<String> = args
       .iter()
       .map(|v| match v {
         Value::SimpleString(s) => s.clone(),
         Value::BulkString(s) => s.clone(),
+        Value::VerbatimString(_, text) => text.clone(),
+        Value::BulkBytes(bytes) => String::from_utf8_lossy(bytes).into_owned(),
         Value::Integer(i) => i.to_string(),
+        Value::BigNumber(digits) => digits.clone(),
         Value::Boolean(b) => b.to_string(),
         _ => "".to_string(),
       })
       .collect();
 
+    if SUBCOMMAND_COMMANDS.contains(&command)
+      && let Some(subcommand) = string_args.first_mut()
+    {
+      subcommand.make_ascii_uppercase();
+    }
+
     match command {
-      // @INFO Utility commands
-      "PING" => PingCommand::execute(string_args),
-      "HELP" => HelpCommand::execute(string_args),
-      "ECHO" => EchoCommand::execute(string_args),
+      // @INFO Pub/Sub subscription commands
+      "SUBSCRIBE" => SubscribeCommand::execute(
+        string_args,
+        self.store.to_owned(),
+        self.pubsub.clone(),
+        &mut self.subscription,
+      ),
+      "UNSUBSCRIBE" => {
+        UnsubscribeCommand::execute(string_args, self.pubsub.clone(), &mut self.subscription)
+      }
 
-      // @INFO Basic commands for data manipulation
-      "GET" => GetCommand::execute(string_args, self.store.to_owned()).await,
-      "SET" => SetCommand::execute(string_args, self.store.to_owned(), args).await,
-      "DEL" => DeleteCommand::execute(string_args, self.store.to_owned()).await,
+      // @INFO The connection is closed by the shutdown, so nothing is sent back
+      "SHUTDOWN" => {
+        ShutdownCommand::execute(string_args, self.store.to_owned(), self.shutdown.clone())
+          .map(|()| Vec::new())
+      }
 
-      // @INFO ACL commands
-      "AUTH" => AuthCommand::execute(string_args, self.store.to_owned(), self.db.clone()).await,
-      "WHOAMI" => WhoAmi::execute(self.store.clone(), self.db.clone()).await,
+      // @INFO Everything else goes through the command registry
+      _ => {
+        let handler =
+          registry::handler(command).ok_or_else(|| anyhow!("unknown command '{}'", command))?;
+        let ctx = CommandContext {
+          args: string_args,
+          raw_args: args,
+          store: &mut self.store,
+          db: &self.db,
+          settings: &self.settings,
+          pubsub: &self.pubsub,
+          client: &mut self.client,
+        };
+        handler.execute(ctx).await.map(|value| vec![value])
+      }
+    }
+  }
 
-      // @INFO Catch-all for unknown commands
-      _ => Err(anyhow!("Unknown command: {}", command)),
+  /// Starts a transaction (MULTI).
+  fn multi(&mut self) -> Result<Vec<Value>> {
+    if self.transaction.is_some() {
+      return Err(anyhow!("MULTI calls can not be nested"));
     }
+
+    self.transaction = Some(Transaction::new());
+    Ok(vec![Value::SimpleString("OK".to_string())])
+  }
+
+  /// Runs every queued command in order (EXEC).
+  ///
+  /// Replies with an array holding each command's result. If a command
+  /// failed to queue, nothing is run and the transaction is aborted.
+  async fn exec(&mut self) -> Result<Vec<Value>> {
+    let Some(transaction) = self.transaction.take() else {
+      return Err(anyhow!("EXEC without MULTI"));
+    };
+
+    if transaction.is_aborted() {
+      return Ok(vec![Value::Error(
+        "EXECABORT Transaction discarded because of previous errors.".to_string(),
+      )]);
+    }
+
+    let mut results = Vec::new();
+    for (command, args) in transaction.into_commands() {
+      match self.dispatch(&command, args).await {
+        Ok(replies) => results.extend(replies),
+        Err(e) => results.push(CommandError::reply(&e)),
+      }
+    }
+
+    Ok(vec![Value::Array(results)])
+  }
+
+  /// Drops every queued command (DISCARD).
+  fn discard(&mut self) -> Result<Vec<Value>> {
+    match self.transaction.take() {
+      Some(_) => Ok(vec![Value::SimpleString("OK".to_string())]),
+      None => Err(anyhow!("DISCARD without MULTI")),
+    }
+  }
+
+  /// Puts the connection back into the state it was accepted in (RESET).
+  ///
+  /// Drops an open transaction and every subscription, selects database 0,
+  /// switches back to RESP2 and clears the authenticated user, or goes back
+  /// to the default user if authentication isn't required.
+  fn reset(&mut self) -> Result<Vec<Value>> {
+    self.transaction = None;
+    self.pubsub.unsubscribe_all(&mut self.subscription);
+    self.store.select(0)?;
+    self.store.set_current_user(None);
+    self.client.set_root(false);
+    self.client.set_protocol(DEFAULT_PROTOCOL);
+    if !self.settings.read().unwrap().server.network.requireauth {
+      self.login_default_user()?;
+    }
+    Ok(vec![Value::SimpleString("RESET".to_string())])
+  }
+
+  /// Asks for the connection to be closed once the reply is sent (QUIT).
+  ///
+  /// An open transaction is dropped without running.
+  fn quit(&mut self) -> Result<Vec<Value>> {
+    self.transaction = None;
+    self.quitting = true;
+    Ok(vec![Value::SimpleString("OK".to_string())])
+  }
+}
+
+impl Drop for CommandExecutor {
+  /// Drops the connection's subscriptions so no dead senders linger in the registry.
+  fn drop(&mut self) {
+    self.pubsub.unsubscribe_all(&mut self.subscription);
   }
 }
@@ -0,0 +1,78 @@
+//! BITCOUNT command implementation.
+//!
+//! Counts the set bits of a string value. Requires authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    general::getrange::{byte_range, parse_offset},
+    registry::{Command, CommandContext},
+  },
+  resp::value::Value,
+  storage::{
+    entities::EntityKind,
+    memory::{MemoryStore, Store, WRONG_TYPE},
+  },
+};
+
+/// BITCOUNT command handler.
+///
+/// Counts the bits set to 1, optionally only within a byte range.
+pub struct BitCountCommand;
+
+impl BitCountCommand {
+  /// Executes the BITCOUNT command.
+  ///
+  /// The optional range is given in bytes, inclusive, with negative
+  /// offsets counting from the end like GETRANGE. A missing key has no
+  /// bits set.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and optional start and end offsets)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - Integer number of set bits
+  /// * `Err` - Error if the range is invalid or the value is not a string
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: BITCOUNT mykey 0 -1
+  /// let result = BitCountCommand::execute(
+  ///     vec!["mykey".to_string(), "0".to_string(), "-1".to_string()],
+  ///     store
+  /// ).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let (start, end) = match args.len() {
+      1 => (0, -1),
+      3 => (parse_offset(&args[1])?, parse_offset(&args[2])?),
+      _ => return Err(anyhow!("syntax error")),
+    };
+
+    store.expect_kind(&args[0], EntityKind::String)?;
+    let bytes = match store.get(&args[0]).await {
+      Some(value) => value.to_bytes().ok_or_else(|| anyhow!(WRONG_TYPE))?,
+      None => Vec::new(),
+    };
+
+    let count: u32 = byte_range(bytes.len(), start, end)
+      .map_or(&[][..], |range| &bytes[range])
+      .iter()
+      .map(|byte| byte.count_ones())
+      .sum();
+    Ok(Value::Integer(count as i64))
+  }
+}
+
+#[async_trait]
+impl Command for BitCountCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned()).await
+  }
+}
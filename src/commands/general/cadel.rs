@@ -0,0 +1,58 @@
+//! CADEL command implementation.
+//!
+//! Deletes a key only if it holds an expected value, the building block of
+//! distributed locks: a client only releases a lock it still owns. Requires
+//! authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// CADEL (compare-and-delete) command handler.
+///
+/// Comparing and deleting happen under one lock, so no other command can
+/// change the key in between.
+pub struct CaDelCommand;
+
+impl CaDelCommand {
+  /// Executes the CADEL command.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key to delete
+  /// * `expected` - The bytes the key must hold to be deleted
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(1))` - The key held `expected` and was deleted
+  /// * `Ok(Value::Integer(0))` - The key doesn't exist or holds another value
+  /// * `Err` - Not authenticated, or the key holds a value that is not a string
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: CADEL lock:orders client-42
+  /// let result = CaDelCommand::execute("lock:orders", b"client-42", store);
+  /// ```
+  pub fn execute(key: &str, expected: &[u8], store: MemoryStore) -> Result<Value> {
+    let deleted = store.compare_and_delete(key, expected)?;
+    Ok(Value::Integer(deleted as i64))
+  }
+}
+
+#[async_trait]
+impl Command for CaDelCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    // The expected value may not be valid UTF-8
+    let expected = ctx.raw_args[1]
+      .to_bytes()
+      .ok_or_else(|| anyhow!("value is not a string"))?;
+    Self::execute(&ctx.args[0], &expected, ctx.store.to_owned())
+  }
+}
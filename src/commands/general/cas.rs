@@ -0,0 +1,76 @@
+//! CAS command implementation.
+//!
+//! Writes a key only if it still holds the value the client last read,
+//! allowing optimistic updates without `MULTI`. Requires authentication.
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// CAS (compare-and-set) command handler.
+///
+/// Comparing and writing happen under one lock, so no other command can
+/// change the key in between.
+pub struct CasCommand;
+
+impl CasCommand {
+  /// Executes the CAS command.
+  ///
+  /// `NX` as the expected value only matches a key that doesn't exist, so
+  /// a client can create a key it never read. The key's expiry is removed
+  /// unless `KEEPTTL` is given.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, expected value or `NX`, new value,
+  ///   then an optional `KEEPTTL`)
+  /// * `raw_args` - Arguments as they were received
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(1))` - The key held the expected value and was written
+  /// * `Ok(Value::Integer(0))` - The key holds another value, nothing was written
+  /// * `Err` - Not authenticated, the key holds a value that is not a string,
+  ///   or the options are invalid
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: CAS counter 41 42 KEEPTTL
+  /// let args = vec!["counter", "41", "42", "KEEPTTL"];
+  /// let result = CasCommand::execute(args.into_iter().map(String::from).collect(), raw_args, store);
+  /// ```
+  pub fn execute(args: Vec<String>, raw_args: Vec<Value>, store: MemoryStore) -> Result<Value> {
+    let keep_ttl = match args.get(3) {
+      None => false,
+      Some(option) if option.eq_ignore_ascii_case("KEEPTTL") => true,
+      Some(_) => bail!("syntax error"),
+    };
+    let expected = if args[1].eq_ignore_ascii_case("NX") {
+      None
+    } else {
+      Some(
+        raw_args[1]
+          .to_bytes()
+          .ok_or_else(|| anyhow!("value is not a string"))?,
+      )
+    };
+
+    let stored =
+      store.compare_and_set(&args[0], expected.as_deref(), raw_args[2].clone(), keep_ttl)?;
+    Ok(Value::Integer(stored as i64))
+  }
+}
+
+#[async_trait]
+impl Command for CasCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.raw_args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,118 @@
+//! CLIENT command implementation.
+//!
+//! Lets a connection inspect and label itself, and lets operators list
+//! and disconnect other connections.
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  utils::client::{Client, ClientSummary},
+};
+
+/// CLIENT command handler.
+///
+/// Supports the `ID`, `GETNAME`, `SETNAME`, `INFO`, `LIST` and `KILL`
+/// subcommands.
+pub struct ClientCommand;
+
+impl ClientCommand {
+  /// Executes the CLIENT command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the upper-case subcommand
+  /// * `client` - State of the connection running the command
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - The connection's id for `ID`
+  /// * `Ok(Value::BulkString)` - The connection's name for `GETNAME`, empty if unset
+  /// * `Ok(Value::SimpleString)` - OK for `SETNAME`
+  /// * `Ok(Value::BulkString)` - The connection's own `LIST` line for `INFO`
+  /// * `Ok(Value::BulkString)` - One line per live connection for `LIST`
+  /// * `Ok(Value::Integer)` - Number of connections killed for `KILL ID <id>`
+  /// * `Err` - Unknown subcommand, wrong argument count or invalid name
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: CLIENT SETNAME worker-1
+  /// let result = ClientCommand::execute(
+  ///     vec!["SETNAME".to_string(), "worker-1".to_string()],
+  ///     client
+  /// );
+  /// ```
+  pub fn execute(args: Vec<String>, client: &mut Client) -> Result<Value> {
+    let subcommand = &args[0];
+    let expected = match subcommand.as_str() {
+      "ID" | "GETNAME" | "INFO" | "LIST" => 1,
+      "SETNAME" => 2,
+      "KILL" => 3,
+      other => return Err(anyhow!("unknown subcommand '{}'", other.to_lowercase())),
+    };
+    if args.len() != expected {
+      bail!(
+        "wrong number of arguments for 'client|{}' command",
+        subcommand.to_lowercase()
+      );
+    }
+
+    match subcommand.as_str() {
+      "ID" => Ok(Value::Integer(client.id as i64)),
+      "GETNAME" => Ok(Value::BulkString(
+        client.name().unwrap_or_default().to_string(),
+      )),
+      "INFO" => {
+        let line = client
+          .registry()
+          .list()
+          .into_iter()
+          .find(|c| c.id == client.id)
+          .map(|c| Self::line(&c))
+          .unwrap_or_default();
+        Ok(Value::BulkString(line))
+      }
+      "LIST" => {
+        let lines: String = client.registry().list().iter().map(Self::line).collect();
+        Ok(Value::BulkString(lines))
+      }
+      "KILL" => {
+        if !args[1].eq_ignore_ascii_case("ID") {
+          bail!("syntax error");
+        }
+        let id = args[2]
+          .parse::<u64>()
+          .map_err(|_| anyhow!("client-id should be greater than 0"))?;
+        Ok(Value::Integer(client.registry().kill(id) as i64))
+      }
+      _ => {
+        let name = &args[1];
+        // Names are printed space-separated, so they must be a single word
+        if !name.bytes().all(|b| b.is_ascii_graphic()) {
+          bail!("Client names cannot contain spaces, newlines or special characters.");
+        }
+        // An empty name removes the current one
+        client.set_name((!name.is_empty()).then(|| name.clone()));
+        Ok(Value::SimpleString("OK".to_string()))
+      }
+    }
+  }
+
+  /// Formats a connection as a line of `CLIENT LIST`.
+  fn line(client: &ClientSummary) -> String {
+    format!(
+      "id={} addr={} name={} age={} idle={}\n",
+      client.id, client.addr, client.name, client.age, client.idle
+    )
+  }
+}
+
+#[async_trait]
+impl Command for ClientCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.client)
+  }
+}
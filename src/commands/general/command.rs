@@ -0,0 +1,129 @@
+//! COMMAND command implementation.
+//!
+//! Lets clients and tooling introspect the commands the server implements,
+//! as described by the command registry.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{self, COMMANDS, Command, CommandContext, CommandSpec, Privileged},
+  resp::value::Value,
+};
+
+/// COMMAND command handler.
+///
+/// Supports the `COUNT`, `DOCS` and `INFO` subcommands. Without a subcommand
+/// every command is described the way `COMMAND INFO` does, which is what
+/// older `redis-cli` versions ask for when connecting.
+pub struct CommandCommand;
+
+impl CommandCommand {
+  /// Executes the COMMAND command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the upper-case subcommand, if any
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Number of implemented commands for `COUNT`
+  /// * `Ok(Value::Array)` - Command names each followed by their docs for `DOCS`
+  /// * `Ok(Value::Array)` - One entry per command for `INFO` or no subcommand,
+  ///   null for unknown names
+  /// * `Err` - Unknown subcommand
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: COMMAND DOCS get
+  /// let result = CommandCommand::execute(vec!["DOCS".to_string(), "get".to_string()]);
+  /// // Returns ["get", ["summary", "Get value for key", "group", "string", ...]]
+  /// ```
+  pub fn execute(args: Vec<String>) -> Result<Value> {
+    let Some(subcommand) = args.first() else {
+      return Ok(Value::Array(COMMANDS.iter().map(Self::info).collect()));
+    };
+
+    match subcommand.as_str() {
+      "COUNT" => Ok(Value::Integer(COMMANDS.len() as i64)),
+      "DOCS" => {
+        // Without names every command is described, unknown names are skipped
+        let specs: Vec<&CommandSpec> = if args.len() > 1 {
+          args[1..]
+            .iter()
+            .filter_map(|name| registry::lookup(&name.to_uppercase()))
+            .collect()
+        } else {
+          COMMANDS.iter().collect()
+        };
+
+        let docs = specs
+          .into_iter()
+          .flat_map(|spec| {
+            [
+              Value::BulkString(spec.name.to_lowercase()),
+              Self::docs(spec),
+            ]
+          })
+          .collect();
+        Ok(Value::Array(docs))
+      }
+      "INFO" => {
+        let infos = args[1..]
+          .iter()
+          .map(|name| match registry::lookup(&name.to_uppercase()) {
+            Some(spec) => Self::info(spec),
+            None => Value::Null,
+          })
+          .collect();
+        Ok(Value::Array(infos))
+      }
+      other => Err(anyhow!("unknown subcommand '{}'", other.to_lowercase())),
+    }
+  }
+
+  /// Describes a single command as name, arity, flags and key positions.
+  ///
+  /// Key positions aren't tracked by the registry, so they are always 0.
+  fn info(spec: &CommandSpec) -> Value {
+    let mut flags = vec![Value::SimpleString(
+      if spec.write { "write" } else { "readonly" }.to_string(),
+    )];
+    if spec.no_auth {
+      flags.push(Value::SimpleString("no_auth".to_string()));
+    }
+    if matches!(spec.privileged, Privileged::Always) {
+      flags.push(Value::SimpleString("admin".to_string()));
+    }
+    Value::Array(vec![
+      Value::BulkString(spec.name.to_lowercase()),
+      Value::Integer(spec.arity()),
+      Value::Array(flags),
+      Value::Integer(0),
+      Value::Integer(0),
+      Value::Integer(0),
+    ])
+  }
+
+  /// Describes a single command as a flat list of field/value pairs.
+  fn docs(spec: &CommandSpec) -> Value {
+    Value::Array(vec![
+      Value::BulkString("summary".to_string()),
+      Value::BulkString(spec.summary.to_string()),
+      Value::BulkString("group".to_string()),
+      Value::BulkString(spec.group.to_string()),
+      Value::BulkString("arity".to_string()),
+      Value::Integer(spec.arity()),
+      Value::BulkString("syntax".to_string()),
+      Value::BulkString(spec.syntax.to_string()),
+    ])
+  }
+}
+
+#[async_trait]
+impl Command for CommandCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args)
+  }
+}
@@ -0,0 +1,120 @@
+//! CONFIG command implementation.
+//!
+//! Reads server parameters, and lets root users change the ones that can
+//! be tuned without a restart or reset the command statistics. Requires
+//! authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    registry::{Command, CommandContext},
+    stats,
+  },
+  resp::value::Value,
+  storage::memory::MemoryStore,
+  utils::{
+    glob,
+    settings::{PARAMETERS, SharedSettings},
+  },
+};
+
+/// CONFIG command handler.
+///
+/// Supports the `GET`, `SET` and `RESETSTAT` subcommands.
+pub struct ConfigCommand;
+
+impl ConfigCommand {
+  /// Executes the CONFIG command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the upper-case subcommand
+  /// * `store` - Memory store the new limits are applied to
+  /// * `settings` - Settings shared by every connection
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - Name and value of every parameter matching one of the globs for `GET`
+  /// * `Ok(Value::SimpleString("OK"))` - The parameter was changed for `SET`,
+  ///   or the command statistics were zeroed for `RESETSTAT`
+  /// * `Err` - Unknown subcommand or
+  ///   parameter, a parameter that can't change at runtime, or an invalid value
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: CONFIG GET max*
+  /// let result = ConfigCommand::execute(
+  ///     vec!["GET".to_string(), "max*".to_string()],
+  ///     store,
+  ///     settings
+  /// );
+  /// ```
+  pub fn execute(
+    args: Vec<String>,
+    store: &MemoryStore,
+    settings: &SharedSettings,
+  ) -> Result<Value> {
+    let subcommand = &args[0];
+    match subcommand.as_str() {
+      "GET" if args.len() >= 2 => Ok(Self::get(&args[1..], settings)),
+      "SET" if args.len() == 3 => {
+        Self::set(&args[1], &args[2], store, settings)?;
+        Ok(Value::SimpleString("OK".to_string()))
+      }
+      "RESETSTAT" if args.len() == 1 => {
+        stats::reset();
+        Ok(Value::SimpleString("OK".to_string()))
+      }
+      "GET" | "SET" | "RESETSTAT" => Err(anyhow!(
+        "wrong number of arguments for 'config|{}' command",
+        subcommand.to_lowercase()
+      )),
+      other => Err(anyhow!("unknown subcommand '{}'", other.to_lowercase())),
+    }
+  }
+
+  /// Lists every parameter matching one of the patterns, as name and value pairs.
+  fn get(patterns: &[String], settings: &SharedSettings) -> Value {
+    let settings = settings.read().unwrap();
+    let pairs = PARAMETERS
+      .iter()
+      .filter(|name| {
+        patterns
+          .iter()
+          .any(|pattern| glob::matches(&pattern.to_lowercase(), name))
+      })
+      .filter_map(|name| Some((name, settings.parameter(name)?)))
+      .flat_map(|(name, value)| {
+        [
+          Value::BulkString(name.to_string()),
+          Value::BulkString(value),
+        ]
+      })
+      .collect();
+    Value::Array(pairs)
+  }
+
+  /// Changes a parameter and applies it to the memory store.
+  fn set(name: &str, value: &str, store: &MemoryStore, settings: &SharedSettings) -> Result<()> {
+    let mut settings = settings.write().unwrap();
+    settings.set_parameter(&name.to_lowercase(), value)?;
+
+    // The store keeps its own copy of the limits, shared by every handle
+    store.set_quota(settings.server.network.quota());
+    store.set_eviction(settings.server.network.eviction());
+    store
+      .notifier()
+      .set_events(settings.server.notify.keyspace_events);
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl Command for ConfigCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store, ctx.settings)
+  }
+}
@@ -0,0 +1,75 @@
+//! COPY command implementation.
+//!
+//! Duplicates a key's value into another key, optionally in another database.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// COPY command handler.
+///
+/// Copies the value and remaining expiry of a key to a destination key.
+pub struct CopyCommand;
+
+impl CopyCommand {
+  /// Executes the COPY command.
+  ///
+  /// Supports the following options after the keys:
+  /// * `DB <index>` - Copy into another database
+  /// * `REPLACE` - Overwrite the destination key if it exists
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the source key, args[1] the
+  ///   destination key, followed by the options
+  /// * `store` - The memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(1))` - The key was copied
+  /// * `Ok(Value::Integer(0))` - The source doesn't exist or the destination already exists
+  /// * `Err` - Not authenticated, invalid options or database
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: COPY src dst DB 1 REPLACE
+  /// let args = vec!["src", "dst", "DB", "1", "REPLACE"];
+  /// let result = CopyCommand::execute(args.into_iter().map(String::from).collect(), store);
+  /// assert_eq!(result.unwrap(), Value::Integer(1));
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let mut db = None;
+    let mut replace = false;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+      match option.to_uppercase().as_str() {
+        "REPLACE" => replace = true,
+        "DB" => {
+          let index = options.next().ok_or_else(|| anyhow!("syntax error"))?;
+          db = Some(
+            index
+              .parse::<usize>()
+              .map_err(|_| anyhow!("value is not an integer or out of range"))?,
+          );
+        }
+        _ => return Err(anyhow!("syntax error")),
+      }
+    }
+
+    let copied = store.copy_key(&args[0], &args[1], db, replace)?;
+    Ok(Value::Integer(copied as i64))
+  }
+}
+
+#[async_trait]
+impl Command for CopyCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
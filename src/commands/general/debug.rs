@@ -0,0 +1,151 @@
+//! DEBUG and WAIT command implementations.
+//!
+//! Commands client libraries and test suites expect to exist, even though
+//! the server has no replicas to wait for.
+
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::{MemoryStore, ObjectDebug},
+};
+
+/// DEBUG command handler.
+///
+/// Supports the `SLEEP` and `OBJECT` subcommands.
+pub struct DebugCommand;
+
+impl DebugCommand {
+  /// Executes the DEBUG command.
+  ///
+  /// `OBJECT` exposes how the store lays out a key, so only root users may
+  /// run it. Its reply is a single line of space separated `field:value`
+  /// pairs, always in the same order:
+  ///
+  /// ```text
+  /// entity:HashMap value:BulkString serializedlength:11 expires:1 inserted_at:1760000000000
+  /// ```
+  ///
+  /// `value` and `inserted_at` (Unix time in milliseconds) are `-` for keys
+  /// that are not strings, those don't keep them.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the upper-case subcommand
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::SimpleString("OK"))` - Once the connection slept for `SLEEP <seconds>`
+  /// * `Ok(Value::BulkString)` - The key's internal details for `OBJECT <key>`
+  /// * `Err` - Unknown subcommand, wrong argument count, invalid duration or
+  ///   a missing key
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: DEBUG SLEEP 0.5
+  /// let result = DebugCommand::execute(vec!["SLEEP".to_string(), "0.5".to_string()], store).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let subcommand = &args[0];
+    match subcommand.as_str() {
+      "SLEEP" => {
+        if args.len() != 2 {
+          bail!("wrong number of arguments for 'debug|sleep' command");
+        }
+        // Fractions of a second are allowed, as in Redis
+        let seconds = args[1]
+          .parse::<f64>()
+          .ok()
+          .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+          .ok_or_else(|| anyhow!("value is not a valid float"))?;
+
+        // Only this connection waits, others keep being served
+        tokio::time::sleep(seconds).await;
+        Ok(Value::SimpleString("OK".to_string()))
+      }
+      "OBJECT" => {
+        if args.len() != 2 {
+          bail!("wrong number of arguments for 'debug|object' command");
+        }
+        let details = store
+          .debug_object(&args[1])?
+          .ok_or_else(|| anyhow!("no such key"))?;
+        Ok(Value::BulkString(describe(&details)))
+      }
+      other => Err(anyhow!("unknown subcommand '{}'", other.to_lowercase())),
+    }
+  }
+}
+
+/// Formats the reply of `DEBUG OBJECT`.
+fn describe(details: &ObjectDebug) -> String {
+  let inserted_at = details
+    .inserted_at
+    .map(|stamp| {
+      let millis = stamp
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+      millis.to_string()
+    })
+    .unwrap_or_else(|| "-".to_string());
+  format!(
+    "entity:{} value:{} serializedlength:{} expires:{} inserted_at:{}",
+    details.entity,
+    details.value.unwrap_or("-"),
+    details.serialized_length,
+    details.expires as u8,
+    inserted_at,
+  )
+}
+
+#[async_trait]
+impl Command for DebugCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned()).await
+  }
+}
+
+/// WAIT command handler.
+///
+/// The server has no replicas, so there is never anything to wait for.
+pub struct WaitCommand;
+
+impl WaitCommand {
+  /// Executes the WAIT command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (number of replicas and timeout in milliseconds)
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(0))` - No replica acknowledged the writes
+  /// * `Err` - If an argument is not an integer
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: WAIT 1 100
+  /// let result = WaitCommand::execute(vec!["1".to_string(), "100".to_string()]);
+  /// ```
+  pub fn execute(args: Vec<String>) -> Result<Value> {
+    if args[0].parse::<u64>().is_err() || args[1].parse::<u64>().is_err() {
+      bail!("value is not an integer or out of range");
+    }
+    Ok(Value::Integer(0))
+  }
+}
+
+#[async_trait]
+impl Command for WaitCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args)
+  }
+}
@@ -3,9 +3,10 @@
 //! Removes one or more keys from the store.
 
 use anyhow::Result;
-use anyhow::anyhow;
+use async_trait::async_trait;
 use log::debug;
 
+use crate::commands::registry::{Command, CommandContext};
 use crate::resp::value::Value;
 use crate::storage::memory::MemoryStore;
 use crate::storage::memory::Store;
@@ -27,7 +28,6 @@ impl DeleteCommand {
   /// # Returns
   ///
   /// * `Ok(Value)` - Integer count of keys deleted
-  /// * `Err` - Error if no arguments are provided
   ///
   /// # Example
   ///
@@ -40,17 +40,21 @@ impl DeleteCommand {
   /// // Returns integer representing number of keys actually deleted
   /// ```
   pub async fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
-    if args.is_empty() {
-      return Err(anyhow!("DEL requires at least one key"));
-    }
-
-    for key in args.clone() {
-      if let Some(value) = store.get(key.as_str()).await {
-        debug!("Deleting key {} with value {:?}", key, value);
-        store.delete(key.as_str()).await;
+    let mut deleted = 0;
+    for key in args.iter() {
+      if let Some(value) = store.delete(key).await {
+        debug!("Deleted key {} with value {:?}", key, value);
+        deleted += 1;
       }
     }
 
-    Ok(Value::Integer(args.len() as i64))
+    Ok(Value::Integer(deleted))
+  }
+}
+
+#[async_trait]
+impl Command for DeleteCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned()).await
   }
 }
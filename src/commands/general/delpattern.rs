@@ -0,0 +1,73 @@
+//! DELPATTERN command implementation.
+//!
+//! Deletes every key of the selected database matching a glob pattern.
+//! Only root users may run it.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use log::debug;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::{MemoryStore, Store},
+};
+
+/// Number of keys deleted between two yields.
+const BATCH_SIZE: usize = 1000;
+
+/// DELPATTERN command handler.
+///
+/// The matching keys are collected in a single walk with
+/// [`MemoryStore::keys_matching`], then deleted in batches, yielding between
+/// them so other clients keep being served.
+pub struct DelPatternCommand;
+
+impl DelPatternCommand {
+  /// Executes the DELPATTERN command.
+  ///
+  /// Only keys that matched when the command started are deleted, keys
+  /// created while it runs are left alone.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (glob pattern)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(count))` - The number of keys deleted
+  /// * `Err` - Not authenticated
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: DELPATTERN user:*
+  /// let result = DelPatternCommand::execute(vec!["user:*".to_string()], store).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let pattern = &args[0];
+    let mut deleted = 0;
+
+    let keys = store.keys_matching(pattern)?;
+    for batch in keys.chunks(BATCH_SIZE) {
+      for key in batch {
+        if let Some(value) = store.delete(key).await {
+          store.lazy_free(value);
+          deleted += 1;
+        }
+      }
+      tokio::task::yield_now().await;
+    }
+
+    debug!("Deleted {} keys matching '{}'", deleted, pattern);
+    Ok(Value::Integer(deleted))
+  }
+}
+
+#[async_trait]
+impl Command for DelPatternCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned()).await
+  }
+}
@@ -0,0 +1,57 @@
+//! DUMP command implementation.
+//!
+//! Serializes the value of a key into an opaque blob that `RESTORE` can
+//! read back. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::{serde, value::Value},
+  storage::{entities::EntityKind, memory::MemoryStore},
+};
+
+/// DUMP command handler.
+///
+/// Encodes a string value in the format described in [`crate::resp::serde`].
+pub struct DumpCommand;
+
+impl DumpCommand {
+  /// Executes the DUMP command.
+  ///
+  /// The expiry of the key is not part of the blob, `RESTORE` takes it as
+  /// an argument instead.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the key
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::BulkBytes)` - The serialized value
+  /// * `Ok(Value::Null)` - If the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a string
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: DUMP mykey
+  /// let result = DumpCommand::execute(vec!["mykey".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let key = &args[0];
+    store.expect_kind(key, EntityKind::String)?;
+
+    let blob = store.inspect(key, serde::dump)?;
+    Ok(blob.map_or(Value::Null, Value::BulkBytes))
+  }
+}
+
+#[async_trait]
+impl Command for DumpCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -2,8 +2,12 @@
 //!
 //! Echoes back the message provided as an argument.
 
-use crate::resp::value::Value;
-use anyhow::{Result, anyhow};
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+};
+use anyhow::Result;
+use async_trait::async_trait;
 
 /// ECHO command handler.
 ///
@@ -13,31 +17,32 @@ pub struct EchoCommand;
 impl EchoCommand {
   /// Executes the ECHO command.
   ///
+  /// Like Redis, ECHO takes exactly one argument, which the executor's
+  /// arity check guarantees. Messages with spaces have to be quoted.
+  ///
   /// # Arguments
   ///
   /// * `args` - Message to echo back
   ///
   /// # Returns
   ///
-  /// * `Ok(Value)` - The echoed message
-  /// * `Err` - Error if no arguments are provided
+  /// * `Ok(Value)` - The echoed message, unchanged
   ///
   /// # Example
   ///
   /// ```
-  /// // Client sends: ECHO hello world
-  /// let result = EchoCommand::execute(vec!["hello".to_string(), "world".to_string()]);
-  /// // Returns "hello" as a bulk string (behavior currently only echoes first arg)
+  /// // Client sends: ECHO "hello world"
+  /// let result = EchoCommand::execute(vec!["hello world".to_string()]);
+  /// assert_eq!(result.unwrap(), Value::BulkString("hello world".to_string()));
   /// ```
-  pub fn execute(args: Vec<String>) -> Result<Value> {
-    let message = args.clone().join(" ");
+  pub fn execute(mut args: Vec<String>) -> Result<Value> {
+    Ok(Value::BulkString(args.swap_remove(0)))
+  }
+}
 
-    if !args.is_empty() {
-      Ok(Value::BulkString(args[0].clone()))
-    } else if !message.is_empty() {
-      Ok(Value::BulkString(message.clone()))
-    } else {
-      Err(anyhow!("ECHO requires at least one argument"))
-    }
+#[async_trait]
+impl Command for EchoCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args)
   }
 }
@@ -0,0 +1,76 @@
+//! EXPIREAT and PEXPIREAT command implementations.
+//!
+//! Set a key to expire at an absolute Unix time. Requires authentication.
+
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// EXPIREAT / PEXPIREAT command handler.
+///
+/// The same handler serves both commands, they only differ in the unit of
+/// the timestamp.
+pub struct ExpireAtCommand {
+  /// Whether the timestamp is in milliseconds (PEXPIREAT) rather than seconds
+  pub millis: bool,
+}
+
+impl ExpireAtCommand {
+  /// Executes the EXPIREAT or PEXPIREAT command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and Unix timestamp)
+  /// * `store` - Memory store to operate on
+  /// * `millis` - Whether the timestamp is in milliseconds
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(1))` - The expiry was set, or the key deleted as the time already passed
+  /// * `Ok(Value::Integer(0))` - The key doesn't exist
+  /// * `Err` - Not authenticated, or the timestamp is not an integer
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: EXPIREAT mykey 1767225600
+  /// let result = ExpireAtCommand::execute(
+  ///     vec!["mykey".to_string(), "1767225600".to_string()],
+  ///     store,
+  ///     false
+  /// );
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore, millis: bool) -> Result<Value> {
+    let timestamp = args[1]
+      .parse::<i64>()
+      .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+
+    // Timestamps before the epoch are simply in the past
+    let offset = u64::try_from(timestamp).unwrap_or(0);
+    let offset = if millis {
+      Duration::from_millis(offset)
+    } else {
+      Duration::from_secs(offset)
+    };
+    let deadline = SystemTime::UNIX_EPOCH
+      .checked_add(offset)
+      .ok_or_else(|| anyhow!("invalid expire time"))?;
+
+    let updated = store.expire_at(&args[0], deadline)?;
+    Ok(Value::Integer(updated as i64))
+  }
+}
+
+#[async_trait]
+impl Command for ExpireAtCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), self.millis)
+  }
+}
@@ -0,0 +1,71 @@
+//! EXPIRETIME and PEXPIRETIME command implementations.
+//!
+//! Report the absolute Unix time at which a key expires. Requires
+//! authentication.
+
+use std::time::SystemTime;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::{KeyExpiry, MemoryStore},
+};
+
+/// EXPIRETIME / PEXPIRETIME command handler.
+///
+/// The same handler serves both commands, they only differ in the unit of
+/// the timestamp.
+pub struct ExpireTimeCommand {
+  /// Whether the timestamp is in milliseconds (PEXPIRETIME) rather than seconds
+  pub millis: bool,
+}
+
+impl ExpireTimeCommand {
+  /// Executes the EXPIRETIME or PEXPIRETIME command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key)
+  /// * `store` - Memory store to operate on
+  /// * `millis` - Whether to reply in milliseconds
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(timestamp))` - The Unix time the key expires at
+  /// * `Ok(Value::Integer(-1))` - The key exists but has no expiry
+  /// * `Ok(Value::Integer(-2))` - The key doesn't exist
+  /// * `Err` - Not authenticated
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: PEXPIRETIME mykey
+  /// let result = ExpireTimeCommand::execute(vec!["mykey".to_string()], store, true);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore, millis: bool) -> Result<Value> {
+    let deadline = match store.expire_time(&args[0])? {
+      KeyExpiry::Missing => return Ok(Value::Integer(-2)),
+      KeyExpiry::Persistent => return Ok(Value::Integer(-1)),
+      KeyExpiry::At(deadline) => deadline,
+    };
+    let since_epoch = deadline
+      .duration_since(SystemTime::UNIX_EPOCH)
+      .unwrap_or_default();
+    let timestamp = if millis {
+      since_epoch.as_millis() as i64
+    } else {
+      since_epoch.as_secs() as i64
+    };
+    Ok(Value::Integer(timestamp))
+  }
+}
+
+#[async_trait]
+impl Command for ExpireTimeCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), self.millis)
+  }
+}
@@ -3,8 +3,15 @@
 //! Retrieves stored values by key. Requires authentication.
 
 use anyhow::{Result, anyhow};
+use async_trait::async_trait;
 
-use crate::{resp::value::Value, storage::memory::MemoryStore, storage::memory::Store};
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::entities::EntityKind,
+  storage::memory::MemoryStore,
+  storage::memory::Store,
+};
 
 /// GET command handler.
 ///
@@ -22,7 +29,7 @@ impl GetCommand {
   /// # Returns
   ///
   /// * `Ok(Value)` - The retrieved value
-  /// * `Err` - Error if key not found or arguments are invalid
+  /// * `Err` - Error if key not found, holds a value that is not a string, or arguments are invalid
   ///
   /// # Example
   ///
@@ -31,15 +38,8 @@ impl GetCommand {
   /// let result = GetCommand::execute(vec!["mykey".to_string()], store).await;
   /// ```
   pub async fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
-    if !store.is_authenticated() {
-      return Err(anyhow!("Authentication required"));
-    }
-
-    if args.is_empty() {
-      return Err(anyhow!("GET requires a key"));
-    }
-
     let key = &args[0];
+    store.expect_kind(key, EntityKind::String)?;
 
     let value = store.get(&key).await;
     if let Some(value) = value {
@@ -49,3 +49,10 @@ impl GetCommand {
     }
   }
 }
+
+#[async_trait]
+impl Command for GetCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned()).await
+  }
+}
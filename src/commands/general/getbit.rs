@@ -0,0 +1,73 @@
+//! GETBIT command implementation.
+//!
+//! Reads a single bit of a string value. Requires authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    general::setbit::parse_bit_offset,
+    registry::{Command, CommandContext},
+  },
+  resp::value::Value,
+  storage::{
+    entities::EntityKind,
+    memory::{MemoryStore, Store, WRONG_TYPE},
+  },
+};
+
+/// GETBIT command handler.
+///
+/// Returns a bit of the string value, counted from the most significant
+/// bit of the first byte.
+pub struct GetBitCommand;
+
+impl GetBitCommand {
+  /// Executes the GETBIT command.
+  ///
+  /// Bits past the end of the string, or of a missing key, read as 0.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and bit offset)
+  /// * `store` - Memory store to operate on
+  /// * `max_len` - Longest string a bitmap may grow to, in bytes
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - Integer value of the bit
+  /// * `Err` - Error if the offset is invalid or the value is not a string
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: GETBIT mykey 7
+  /// let result = GetBitCommand::execute(
+  ///     vec!["mykey".to_string(), "7".to_string()],
+  ///     store,
+  ///     max_len
+  /// ).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore, max_len: usize) -> Result<Value> {
+    let offset = parse_bit_offset(&args[1], max_len)?;
+    store.expect_kind(&args[0], EntityKind::String)?;
+    let bytes = match store.get(&args[0]).await {
+      Some(value) => value.to_bytes().ok_or_else(|| anyhow!(WRONG_TYPE))?,
+      None => Vec::new(),
+    };
+
+    let bit = bytes
+      .get(offset / 8)
+      .is_some_and(|byte| byte & (0x80 >> (offset % 8)) != 0);
+    Ok(Value::Integer(bit as i64))
+  }
+}
+
+#[async_trait]
+impl Command for GetBitCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    let max_len = ctx.settings.read().unwrap().server.network.max_bulk_len;
+    Self::execute(ctx.args, ctx.store.to_owned(), max_len).await
+  }
+}
@@ -0,0 +1,103 @@
+//! GETEX command implementation.
+//!
+//! Gets the value of a key and optionally changes its expiry. Requires
+//! authentication.
+
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{
+    entities::EntityKind,
+    memory::{ExpiryUpdate, MemoryStore},
+  },
+};
+
+/// GETEX command handler.
+///
+/// Reading the value and changing its expiry happen under one lock, so no
+/// other command sees the key in between.
+pub struct GetExCommand;
+
+impl GetExCommand {
+  /// Executes the GETEX command.
+  ///
+  /// Supports at most one of the following options:
+  /// * `EX <seconds>` / `PX <milliseconds>` - Expire the key after the given time
+  /// * `EXAT <unix-seconds>` / `PXAT <unix-milliseconds>` - Expire the key at the given time
+  /// * `PERSIST` - Remove the key's expiry
+  ///
+  /// Without an option GETEX behaves like GET.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then an optional expiry option)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - The value of the key
+  /// * `Ok(Value::Null)` - The key doesn't exist
+  /// * `Err` - Not authenticated, the key holds a value that is not a string,
+  ///   or the options are invalid
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: GETEX mykey EX 60
+  /// let args = vec!["mykey".to_string(), "EX".to_string(), "60".to_string()];
+  /// let result = GetExCommand::execute(args, store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let key = &args[0];
+    let update = Self::parse_update(&args[1..])?;
+    store.expect_kind(key, EntityKind::String)?;
+
+    Ok(store.get_ex(key, update)?.unwrap_or(Value::Null))
+  }
+
+  /// Parses the expiry option given after the key.
+  fn parse_update(args: &[String]) -> Result<ExpiryUpdate> {
+    let (option, amount) = match args {
+      [] => return Ok(ExpiryUpdate::Keep),
+      [option] if option.eq_ignore_ascii_case("PERSIST") => return Ok(ExpiryUpdate::Persist),
+      [option, amount] => (option.to_uppercase(), amount),
+      _ => bail!("syntax error"),
+    };
+    // Relative times count from now, absolute ones from the epoch
+    let (base, millis) = match option.as_str() {
+      "EX" => (SystemTime::now(), false),
+      "PX" => (SystemTime::now(), true),
+      "EXAT" => (SystemTime::UNIX_EPOCH, false),
+      "PXAT" => (SystemTime::UNIX_EPOCH, true),
+      _ => bail!("syntax error"),
+    };
+
+    let amount = amount
+      .parse::<i64>()
+      .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+    if amount <= 0 {
+      bail!("invalid expire time in 'getex' command");
+    }
+    let offset = if millis {
+      Duration::from_millis(amount as u64)
+    } else {
+      Duration::from_secs(amount as u64)
+    };
+    base
+      .checked_add(offset)
+      .map(ExpiryUpdate::At)
+      .ok_or_else(|| anyhow!("invalid expire time in 'getex' command"))
+  }
+}
+
+#[async_trait]
+impl Command for GetExCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
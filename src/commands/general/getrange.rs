@@ -0,0 +1,99 @@
+//! GETRANGE command implementation.
+//!
+//! Returns a byte range of a string value. Requires authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{
+    entities::EntityKind,
+    memory::{MemoryStore, Store, WRONG_TYPE},
+  },
+};
+
+/// GETRANGE command handler.
+///
+/// Returns the bytes between two inclusive offsets of a string value.
+pub struct GetRangeCommand;
+
+impl GetRangeCommand {
+  /// Executes the GETRANGE command.
+  ///
+  /// Negative offsets count from the end of the string, and offsets past
+  /// either end are clamped to it. A missing key reads as an empty string.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, start and end offsets)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - The requested range, empty if it selects no bytes
+  /// * `Err` - Error if the offsets are not integers or the value is not a string
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: GETRANGE mykey 0 -1
+  /// let result = GetRangeCommand::execute(
+  ///     vec!["mykey".to_string(), "0".to_string(), "-1".to_string()],
+  ///     store
+  /// ).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let start = parse_offset(&args[1])?;
+    let end = parse_offset(&args[2])?;
+
+    store.expect_kind(&args[0], EntityKind::String)?;
+    let bytes = match store.get(&args[0]).await {
+      Some(value) => value.to_bytes().ok_or_else(|| anyhow!(WRONG_TYPE))?,
+      None => Vec::new(),
+    };
+
+    let range = byte_range(bytes.len(), start, end).map_or(&[][..], |range| &bytes[range]);
+    Ok(Value::from_bytes(range.to_vec()))
+  }
+}
+
+/// Parses a signed byte offset.
+pub fn parse_offset(arg: &str) -> Result<i64> {
+  arg
+    .parse()
+    .map_err(|_| anyhow!("value is not an integer or out of range"))
+}
+
+/// Resolves inclusive, possibly negative offsets into a range of a string
+/// of length `len`.
+///
+/// # Returns
+///
+/// * `Some(range)` - The bytes selected by the offsets
+/// * `None` - If the offsets select no bytes
+pub fn byte_range(len: usize, start: i64, end: i64) -> Option<std::ops::Range<usize>> {
+  let len = len as i64;
+  let resolve = |offset: i64| {
+    if offset < 0 {
+      (len + offset).max(0)
+    } else {
+      offset
+    }
+  };
+  let start = resolve(start);
+  let end = resolve(end).min(len - 1);
+
+  if start > end {
+    return None;
+  }
+  Some(start as usize..end as usize + 1)
+}
+
+#[async_trait]
+impl Command for GetRangeCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned()).await
+  }
+}
@@ -2,12 +2,17 @@
 //!
 //! Provides help text describing available commands.
 
-use crate::resp::value::Value;
+use crate::{
+  commands::registry::{COMMANDS, Command, CommandContext},
+  resp::value::Value,
+};
 use anyhow::Result;
+use async_trait::async_trait;
 
 /// HELP command handler.
 ///
-/// Returns help text with a list of available commands and brief descriptions.
+/// Returns help text with a list of available commands and brief descriptions,
+/// built from the command registry.
 #[allow(dead_code)]
 pub struct HelpCommand;
 
@@ -30,14 +35,18 @@ impl HelpCommand {
   /// // Returns a bulk string with help text
   /// ```
   pub fn execute(_args: Vec<String>) -> Result<Value> {
-    let help_text = "Available commands:\n\
-                         PING - Test connection\n\
-                         ECHO <message> - Echo back a message\n\
-                         GET <key> - Get value for key\n\
-                         SET <key> <value> - Set key to value\n\
-                         DEL <key> [<key> ...] - Delete keys\n\
-                         HELP - Show this help";
+    let mut help_text = "Available commands:".to_string();
+    for spec in COMMANDS {
+      help_text.push_str(&format!("\n{} - {}", spec.syntax, spec.summary));
+    }
 
-    Ok(Value::BulkString(help_text.to_string()))
+    Ok(Value::BulkString(help_text))
+  }
+}
+
+#[async_trait]
+impl Command for HelpCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args)
   }
 }
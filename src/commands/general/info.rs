@@ -0,0 +1,181 @@
+//! INFO command implementation.
+//!
+//! Reports server statistics in the `field:value` line format used by
+//! Redis, grouped into `# Section` blocks.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    registry::{Command, CommandContext},
+    stats,
+  },
+  resp::value::Value,
+  storage::{kdb, memory::MemoryStore},
+  utils::{network::NetworkUtils, settings::Settings},
+};
+
+/// Sections reported by INFO, in output order.
+const SECTIONS: [&str; 6] = [
+  "server",
+  "clients",
+  "memory",
+  "persistence",
+  "commandstats",
+  "keyspace",
+];
+
+/// Sections left out unless asked for by name or with `all`.
+const EXTRA_SECTIONS: [&str; 1] = ["commandstats"];
+
+/// INFO command handler.
+///
+/// Returns statistics about the server, its clients and the stored data.
+pub struct InfoCommand;
+
+impl InfoCommand {
+  /// Executes the INFO command.
+  ///
+  /// Without arguments, or with `default`, every section but `commandstats`
+  /// is returned, and `all` adds it. Otherwise only the named section is,
+  /// and an unknown section yields an empty string. The `keyspace` section
+  /// only covers the current user's databases.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is an optional section name
+  /// * `store` - The memory store to report on
+  /// * `settings` - Server settings
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::VerbatimString)` - The requested sections
+  /// * `Err` - Not authenticated
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: INFO clients
+  /// let result = InfoCommand::execute(vec!["clients".to_string()], store, &settings);
+  /// // Returns "# Clients\r\nconnected_clients:1\r\ntotal_connections_received:1\r\n"
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore, settings: &Settings) -> Result<Value> {
+    let filter = args.first().map(|s| s.to_lowercase());
+    let sections = SECTIONS
+      .iter()
+      .filter(|section| match filter.as_deref() {
+        None | Some("default") => !EXTRA_SECTIONS.contains(section),
+        Some("all") | Some("everything") => true,
+        Some(name) => name == **section,
+      })
+      .map(|section| match *section {
+        "server" => Self::server(settings),
+        "clients" => Self::clients(),
+        "memory" => Self::memory(&store),
+        "persistence" => Self::persistence(&store),
+        "commandstats" => Self::commandstats(),
+        _ => Self::keyspace(&store),
+      })
+      .collect::<Vec<_>>();
+
+    Ok(Value::VerbatimString(
+      "txt".to_string(),
+      sections.join("\r\n"),
+    ))
+  }
+
+  /// Builds the `server` section.
+  fn server(settings: &Settings) -> String {
+    let server = &settings.server;
+    format!(
+      "# Server\r\nserver_name:{}\r\nserver_version:{}\r\nserver_description:{}\r\nrusty_kv_version:{}\r\ntcp_port:{}\r\n",
+      server.name,
+      server.version,
+      server.description,
+      env!("CARGO_PKG_VERSION"),
+      server.network.port,
+    )
+  }
+
+  /// Builds the `clients` section.
+  fn clients() -> String {
+    format!(
+      "# Clients\r\nconnected_clients:{}\r\ntotal_connections_received:{}\r\n",
+      NetworkUtils::connected_clients(),
+      NetworkUtils::total_connections()
+    )
+  }
+
+  /// Builds the `memory` section, with the server's totals followed by the
+  /// current user's usage and quota.
+  fn memory(store: &MemoryStore) -> String {
+    let (users, keys, bytes) = store.usage();
+    let (user_keys, user_bytes) = store.user_usage().unwrap_or_default();
+    let quota = store.quota();
+    let eviction = store.eviction();
+    format!(
+      "# Memory\r\nusers:{}\r\nkeys:{}\r\nused_bytes:{}\r\n\
+       user_keys:{}\r\nuser_bytes:{}\r\nmax_keys_per_user:{}\r\nmax_bytes_per_user:{}\r\n\
+       maxmemory:{}\r\nmaxmemory_policy:{}\r\n",
+      users,
+      keys,
+      bytes,
+      user_keys,
+      user_bytes,
+      quota.max_keys,
+      quota.max_bytes,
+      eviction.maxmemory,
+      eviction.policy.as_str()
+    )
+  }
+
+  /// Builds the `persistence` section.
+  fn persistence(store: &MemoryStore) -> String {
+    format!(
+      "# Persistence\r\nkdb_bgsave_in_progress:{}\r\nkdb_last_save_duration_usec:{}\r\n\
+       rdb_changes_since_last_save:{}\r\nrdb_last_save_time:{}\r\n",
+      kdb::bgsave_in_progress(store) as u8,
+      kdb::last_save_duration(store),
+      store.changes_since_last_save(),
+      kdb::last_save(store)
+    )
+  }
+
+  /// Builds the `commandstats` section, with one line per command that ran.
+  fn commandstats() -> String {
+    let mut section = "# Commandstats\r\n".to_string();
+    for (command, stat) in stats::snapshot() {
+      section.push_str(&format!(
+        "cmdstat_{}:calls={},usec={},usec_per_call={:.2}\r\n",
+        command.to_lowercase(),
+        stat.calls,
+        stat.usec,
+        stat.usec_per_call()
+      ));
+    }
+    section
+  }
+
+  /// Builds the `keyspace` section, listing only non-empty databases.
+  fn keyspace(store: &MemoryStore) -> String {
+    let mut section = "# Keyspace\r\n".to_string();
+    for (db, (keys, expires)) in store.keyspace().unwrap_or_default().into_iter().enumerate() {
+      if keys > 0 {
+        section.push_str(&format!("db{}:keys={},expires={}\r\n", db, keys, expires));
+      }
+    }
+    section
+  }
+}
+
+#[async_trait]
+impl Command for InfoCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(
+      ctx.args,
+      ctx.store.to_owned(),
+      &ctx.settings.read().unwrap(),
+    )
+  }
+}
@@ -0,0 +1,80 @@
+//! LOLWUT command implementation.
+//!
+//! Replies with a banner naming the server and its version, a cheap way
+//! for clients to check they are talking to rusty-kv and which build.
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  utils::settings::Settings,
+};
+
+/// Drawn above the server details.
+const BANNER: &str = r"  ____            _           _  ____     __
+ |  _ \ _   _ ___| |_ _   _  | |/ /\ \   / /
+ | |_) | | | / __| __| | | | | ' /  \ \ / /
+ |  _ <| |_| \__ \ |_| |_| | | . \   \ V /
+ |_| \_\\__,_|___/\__|\__, | |_|\_\   \_/
+                      |___/";
+
+/// LOLWUT command handler.
+pub struct LolwutCommand;
+
+impl LolwutCommand {
+  /// Executes the LOLWUT command.
+  ///
+  /// `VERSION <n>` is accepted for compatibility with Redis, which draws
+  /// different art per version. rusty-kv always draws the same banner.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, optionally `VERSION <n>`
+  /// * `settings` - Server settings the details are read from
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::VerbatimString)` - The banner, followed by the server's
+  ///   name, description and version, one per line
+  /// * `Err` - The arguments are invalid
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LOLWUT VERSION 5
+  /// let result = LolwutCommand::execute(vec!["VERSION".to_string(), "5".to_string()], &settings);
+  /// ```
+  pub fn execute(args: Vec<String>, settings: &Settings) -> Result<Value> {
+    match args.as_slice() {
+      [] => {}
+      [option, version] if option.eq_ignore_ascii_case("VERSION") => {
+        version
+          .parse::<u32>()
+          .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+      }
+      _ => bail!("syntax error"),
+    }
+
+    let server = &settings.server;
+    Ok(Value::VerbatimString(
+      "txt".to_string(),
+      format!(
+        "{}\n\n{} - {}\nrusty-kv ver. {} (server version {})\n",
+        BANNER,
+        server.name,
+        server.description,
+        env!("CARGO_PKG_VERSION"),
+        server.version,
+      ),
+    ))
+  }
+}
+
+#[async_trait]
+impl Command for LolwutCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, &ctx.settings.read().unwrap())
+  }
+}
@@ -0,0 +1,71 @@
+//! MEMORY command implementation.
+//!
+//! Reports how much memory keys take up. Requires authentication.
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// MEMORY command handler.
+///
+/// Supports the `USAGE` subcommand.
+pub struct MemoryCommand;
+
+impl MemoryCommand {
+  /// Executes the MEMORY command.
+  ///
+  /// `USAGE` accepts `SAMPLES <count>` for compatibility with Redis, but
+  /// always measures every element.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the upper-case subcommand and args[1] the key
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Approximate bytes the key and its value take up
+  /// * `Ok(Value::Null)` - If the key doesn't exist
+  /// * `Err` - Not authenticated, unknown subcommand or invalid arguments
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: MEMORY USAGE mykey
+  /// let result = MemoryCommand::execute(
+  ///     vec!["USAGE".to_string(), "mykey".to_string()],
+  ///     store
+  /// );
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let subcommand = &args[0];
+    if subcommand != "USAGE" {
+      bail!("unknown subcommand '{}'", subcommand.to_lowercase());
+    }
+    match &args[1..] {
+      [_] => {}
+      [_, option, count] if option.eq_ignore_ascii_case("SAMPLES") => {
+        count
+          .parse::<u64>()
+          .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+      }
+      [_, _, _] => bail!("syntax error"),
+      _ => bail!("wrong number of arguments for 'memory|usage' command"),
+    }
+
+    let usage = store.memory_usage(&args[1])?;
+    Ok(usage.map_or(Value::Null, |bytes| Value::Integer(bytes as i64)))
+  }
+}
+
+#[async_trait]
+impl Command for MemoryCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,199 @@
+//! MIGRATE command implementation.
+//!
+//! Moves a key to another rusty-kv instance by sending it a `RESTORE` of
+//! the key's `DUMP`. Only root users may run it, as it connects to any
+//! host the client names.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use tokio::{net::TcpStream, time};
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::{handler::RespHandler, parser::ParserLimits, serde, value::Value},
+  storage::{aof, memory::MemoryStore},
+};
+
+/// Timeout used when MIGRATE is given a timeout of 0, in milliseconds.
+const DEFAULT_TIMEOUT_MS: u64 = 1000;
+
+/// MIGRATE command handler.
+///
+/// Connects to the target instance for every call, the connection is not
+/// kept between migrations.
+pub struct MigrateCommand;
+
+/// Options given after the timeout.
+#[derive(Default)]
+struct MigrateOptions {
+  /// Keep the local key after it was migrated
+  copy: bool,
+  /// Overwrite the key on the target if it exists
+  replace: bool,
+  /// Arguments of the AUTH command sent to the target, if any
+  auth: Option<Vec<String>>,
+}
+
+impl MigrateOptions {
+  /// Parses the options given after the timeout.
+  fn parse(args: &[String]) -> Result<Self> {
+    let mut options = Self::default();
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+      match option.to_uppercase().as_str() {
+        "COPY" => options.copy = true,
+        "REPLACE" => options.replace = true,
+        "AUTH" => {
+          let password = args.next().ok_or_else(|| anyhow!("syntax error"))?;
+          options.auth = Some(vec![password.clone()]);
+        }
+        "AUTH2" => {
+          let (Some(username), Some(password)) = (args.next(), args.next()) else {
+            bail!("syntax error");
+          };
+          options.auth = Some(vec![username.clone(), password.clone()]);
+        }
+        _ => bail!("syntax error"),
+      }
+    }
+    Ok(options)
+  }
+}
+
+impl MigrateCommand {
+  /// Executes the MIGRATE command.
+  ///
+  /// Supports the following options after the timeout:
+  /// * `COPY` - Keep the local key
+  /// * `REPLACE` - Overwrite the key on the target
+  /// * `AUTH <password>` / `AUTH2 <username> <password>` - Credentials for the target
+  ///
+  /// The key keeps its remaining time to live on the target. It is only
+  /// deleted locally once the target has stored it, and only if it still
+  /// holds the migrated value, so a failed migration leaves it intact.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments: host, port, key, destination database and
+  ///   timeout in milliseconds, followed by the options
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::SimpleString("OK"))` - The key was migrated
+  /// * `Ok(Value::SimpleString("NOKEY"))` - The key doesn't exist
+  /// * `Err` - Not authenticated, invalid arguments, the target couldn't be
+  ///   reached in time or replied with an error
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: MIGRATE 10.0.0.2 6379 mykey 0 5000 AUTH2 admin secret
+  /// let args = vec!["10.0.0.2", "6379", "mykey", "0", "5000", "AUTH2", "admin", "secret"];
+  /// let result = MigrateCommand::execute(args.into_iter().map(String::from).collect(), store).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let host = &args[0];
+    let key = &args[2];
+    let (Ok(port), Ok(db), Ok(timeout)) = (
+      args[1].parse::<u16>(),
+      args[3].parse::<usize>(),
+      args[4].parse::<u64>(),
+    ) else {
+      bail!("value is not an integer or out of range");
+    };
+    let timeout = Duration::from_millis(match timeout {
+      0 => DEFAULT_TIMEOUT_MS,
+      timeout => timeout,
+    });
+    let options = MigrateOptions::parse(&args[5..])?;
+
+    let Some((value, ttl)) = store.get_with_ttl(key)? else {
+      return Ok(Value::SimpleString("NOKEY".to_string()));
+    };
+    let blob = serde::dump(&value);
+    // A TTL of 0 would restore the key without an expiry
+    let ttl = ttl.map_or(0, |ttl| (ttl.as_millis() as u64).max(1));
+
+    let mut commands = Vec::new();
+    if let Some(auth) = options.auth {
+      commands.push(Self::command(
+        "AUTH",
+        auth.into_iter().map(Value::BulkString),
+      ));
+    }
+    commands.push(Self::command("SELECT", [Value::BulkString(db.to_string())]));
+    let mut restore = vec![
+      Value::BulkString(key.clone()),
+      Value::BulkString(ttl.to_string()),
+      Value::BulkBytes(blob),
+    ];
+    if options.replace {
+      restore.push(Value::BulkString("REPLACE".to_string()));
+    }
+    commands.push(Self::command("RESTORE", restore));
+
+    Self::send(host, port, commands, timeout).await?;
+
+    // The key may have changed while the target was storing it, a newer
+    // value is kept rather than lost. Other writes were only held up from
+    // here, not while waiting on the target
+    if !options.copy
+      && let Some(expected) = value.to_bytes()
+    {
+      let _order = aof::write_order().await;
+      if store.compare_and_delete(key, &expected)? {
+        let args = [Value::BulkString(key.clone())];
+        aof::append(&store, "DEL", &args, &[Value::Integer(1)]);
+      }
+    }
+    Ok(Value::SimpleString("OK".to_string()))
+  }
+
+  /// Builds a command to send to the target.
+  fn command(name: &str, args: impl IntoIterator<Item = Value>) -> Value {
+    let mut command = vec![Value::BulkString(name.to_string())];
+    command.extend(args);
+    Value::Array(command)
+  }
+
+  /// Runs commands on the target one by one, failing on the first error.
+  ///
+  /// # Arguments
+  ///
+  /// * `host` - Host of the target instance
+  /// * `port` - Port of the target instance
+  /// * `commands` - Commands to run
+  /// * `timeout` - Time allowed for connecting and for every reply
+  async fn send(host: &str, port: u16, commands: Vec<Value>, timeout: Duration) -> Result<()> {
+    let stream = match time::timeout(timeout, TcpStream::connect((host, port))).await {
+      Ok(Ok(stream)) => stream,
+      _ => bail!("IOERR error or timeout connecting to the client"),
+    };
+    let mut target = RespHandler::new(stream, ParserLimits::default());
+
+    for command in commands {
+      let reply = time::timeout(timeout, async {
+        target.write_value(command).await?;
+        target.flush().await?;
+        target.read_value().await
+      })
+      .await;
+      match reply {
+        Ok(Ok(Some(Value::Error(e)))) => bail!("Target instance replied with error: {}", e),
+        Ok(Ok(Some(_))) => {}
+        _ => bail!("IOERR error or timeout reading to target instance"),
+      }
+    }
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl Command for MigrateCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned()).await
+  }
+}
@@ -4,9 +4,41 @@
 //! commands such as GET, SET, DEL, as well as utility commands like
 //! PING, ECHO, and HELP.
 
+pub mod bitcount;
+pub mod cadel;
+pub mod cas;
+pub mod client;
+pub mod command;
+pub mod config;
+pub mod copy;
+pub mod debug;
 pub mod delete;
+pub mod delpattern;
+pub mod dump;
 pub mod echo;
+pub mod expireat;
+pub mod expiretime;
 pub mod get;
+pub mod getbit;
+pub mod getex;
+pub mod getrange;
 pub mod help;
+pub mod info;
+pub mod lolwut;
+pub mod memory;
+pub mod migrate;
+pub mod move_key;
+pub mod msetnx;
+pub mod object;
 pub mod ping;
+pub mod restore;
+pub mod select;
 pub mod set;
+pub mod setbit;
+pub mod setrange;
+pub mod shutdown;
+pub mod slowlog;
+pub mod swapdb;
+pub mod touch;
+pub mod unlink;
+pub mod wait_key;
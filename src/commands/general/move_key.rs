@@ -0,0 +1,55 @@
+//! MOVE command implementation.
+//!
+//! Transfers a key from the selected database to another one.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// MOVE command handler.
+///
+/// Moves a key together with its expiry to another numbered database.
+pub struct MoveCommand;
+
+impl MoveCommand {
+  /// Executes the MOVE command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the key and args[1] the destination database
+  /// * `store` - The memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(1))` - The key was moved
+  /// * `Ok(Value::Integer(0))` - The key doesn't exist or already exists in the destination
+  /// * `Err` - Not authenticated, or the destination is invalid or the selected database
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: MOVE mykey 1
+  /// let result = MoveCommand::execute(vec!["mykey".to_string(), "1".to_string()], store);
+  /// assert_eq!(result.unwrap(), Value::Integer(1));
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let db = args[1]
+      .parse::<usize>()
+      .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+
+    let moved = store.move_key(&args[0], db)?;
+    Ok(Value::Integer(moved as i64))
+  }
+}
+
+#[async_trait]
+impl Command for MoveCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
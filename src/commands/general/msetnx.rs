@@ -0,0 +1,65 @@
+//! MSETNX command implementation.
+//!
+//! Sets several keys at once, only if none of them exists. Requires
+//! authentication.
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// MSETNX command handler.
+///
+/// Checking the keys and setting them happen under one lock, so no other
+/// connection can create one of the keys in between.
+pub struct MSetNxCommand;
+
+impl MSetNxCommand {
+  /// Executes the MSETNX command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and value pairs)
+  /// * `store` - Memory store to operate on
+  /// * `orig_args` - Arguments with their original types, the values are
+  ///   stored as they were received
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(1))` - Every key was set
+  /// * `Ok(Value::Integer(0))` - A key already exists, none was set
+  /// * `Err` - Not authenticated, an odd number of arguments, or the values
+  ///   don't fit in the user's quota
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: MSETNX key1 a key2 b
+  /// let args = vec!["key1", "a", "key2", "b"];
+  /// let result = MSetNxCommand::execute(args.into_iter().map(String::from).collect(), store, orig_args);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore, orig_args: Vec<Value>) -> Result<Value> {
+    if !args.len().is_multiple_of(2) {
+      bail!("wrong number of arguments for 'msetnx' command");
+    }
+
+    let pairs: Vec<(String, Value)> = args
+      .into_iter()
+      .step_by(2)
+      .zip(orig_args.into_iter().skip(1).step_by(2))
+      .collect();
+    let stored = store.set_all_if_absent(&pairs)?;
+    Ok(Value::Integer(stored as i64))
+  }
+}
+
+#[async_trait]
+impl Command for MSetNxCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), ctx.raw_args)
+  }
+}
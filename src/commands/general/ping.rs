@@ -3,8 +3,12 @@
 //! Simple connectivity check command that returns PONG or echoes
 //! an optional message.
 
-use crate::resp::value::Value;
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+};
 use anyhow::Result;
+use async_trait::async_trait;
 
 /// PING command handler.
 ///
@@ -44,3 +48,10 @@ impl PingCommand {
     }
   }
 }
+
+#[async_trait]
+impl Command for PingCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args)
+  }
+}
@@ -0,0 +1,101 @@
+//! RESTORE command implementation.
+//!
+//! Stores a value serialized by `DUMP` under a key. Requires authentication.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    general::set::Options,
+    registry::{Command, CommandContext},
+  },
+  resp::{
+    error::{CommandError, ErrorCode},
+    serde,
+    value::Value,
+  },
+  storage::memory::{MemoryStore, Store},
+};
+
+/// RESTORE command handler.
+///
+/// Checks the blob's checksum before decoding it, so a corrupted or
+/// tampered payload is never stored.
+pub struct RestoreCommand;
+
+impl RestoreCommand {
+  /// Executes the RESTORE command.
+  ///
+  /// Supports the `REPLACE` option after the payload, to overwrite a key
+  /// that already exists.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the key, args[1] the time to
+  ///   live in milliseconds (0 for none), followed by the options
+  /// * `blob` - Payload returned by `DUMP`
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::SimpleString("OK"))` - The value was stored
+  /// * `Err` - Not authenticated, invalid TTL or options, the key exists
+  ///   without `REPLACE`, or the payload is malformed
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: RESTORE mykey 0 <payload> REPLACE
+  /// let result = RestoreCommand::execute(
+  ///     vec!["mykey".to_string(), "0".to_string(), "REPLACE".to_string()],
+  ///     payload,
+  ///     store
+  /// ).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, blob: Vec<u8>, store: MemoryStore) -> Result<Value> {
+    let ttl = args[1]
+      .parse::<i64>()
+      .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+    if ttl < 0 {
+      bail!("Invalid TTL value, must be >= 0");
+    }
+
+    let mut replace = false;
+    for option in &args[3..] {
+      match option.to_uppercase().as_str() {
+        "REPLACE" => replace = true,
+        _ => bail!("syntax error"),
+      }
+    }
+
+    let value = serde::restore(&blob)?;
+
+    let mut options = HashMap::new();
+    if ttl > 0 {
+      options.insert(Options::Px, ttl as u64);
+    }
+    if !replace {
+      options.insert(Options::Nx, 1);
+    }
+    if !store.set(&args[0], value, options).await?.stored {
+      bail!(CommandError::new(
+        ErrorCode::BusyKey,
+        "Target key name already exists."
+      ));
+    }
+    Ok(Value::SimpleString("OK".to_string()))
+  }
+}
+
+#[async_trait]
+impl Command for RestoreCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    let blob = ctx.raw_args[2]
+      .to_bytes()
+      .ok_or_else(|| anyhow!("Bad data format"))?;
+    Self::execute(ctx.args, blob, ctx.store.to_owned()).await
+  }
+}
@@ -0,0 +1,58 @@
+//! SELECT command implementation.
+//!
+//! Switches the connection to another numbered logical database.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// SELECT command handler.
+///
+/// Changes which logical database subsequent key operations on the
+/// connection are routed to.
+pub struct SelectCommand;
+
+impl SelectCommand {
+  /// Executes the SELECT command.
+  ///
+  /// The selection only affects the connection owning `store`, other
+  /// connections of the same user keep their own selected database.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the database index
+  /// * `store` - The connection's memory store handle
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::SimpleString("OK"))` - The database was selected
+  /// * `Err` - Invalid index, or the index is out of range
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SELECT 1
+  /// let result = SelectCommand::execute(vec!["1".to_string()], &mut store);
+  /// assert_eq!(result.unwrap(), Value::SimpleString("OK".to_string()));
+  /// ```
+  pub fn execute(args: Vec<String>, store: &mut MemoryStore) -> Result<Value> {
+    let index = args[0]
+      .parse::<usize>()
+      .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+
+    store.select(index)?;
+    Ok(Value::SimpleString("OK".to_string()))
+  }
+}
+
+#[async_trait]
+impl Command for SelectCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store)
+  }
+}
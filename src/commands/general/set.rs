@@ -1,12 +1,21 @@
 //! SET command implementation.
 //!
-//! Handles storing key-value pairs with optional modifiers (EX, PX, NX, XX).
-//! Requires authentication before executing.
+//! Handles storing key-value pairs with optional modifiers (EX, PX, EXAT,
+//! PXAT, KEEPTTL, NX, XX, GET). Requires authentication before executing.
 
-use std::collections::HashMap;
+use std::{
+  collections::HashMap,
+  time::{Duration, SystemTime},
+};
 
-use crate::{resp::value::Value, storage::memory::MemoryStore, storage::memory::Store};
-use anyhow::{Result, anyhow};
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+  storage::memory::Store,
+};
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
 use log::debug;
 
 /// SET command handler.
@@ -26,14 +35,185 @@ pub struct SetCommand;
 /// SET my key myvalue PX 1000
 /// SET my key myvalue NX
 /// SET my key myvalue XX
+/// SET my key myvalue GET
+/// SET my key myvalue KEEPTTL
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-#[allow(dead_code)]
 pub enum Options {
-  Ex, // Expiration in seconds
-  Px, // Expiration in milliseconds
-  Nx, // Only set if not exists
-  Xx, // Only set if exists
+  Ex,      // Expiration in seconds
+  Px,      // Expiration in milliseconds
+  Nx,      // Only set if not exists
+  Xx,      // Only set if exists
+  Get,     // Return the previous value
+  KeepTtl, // Keep the expiry of the value being replaced
+}
+
+/// When a key written by SET expires. Only one may be given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expiry {
+  /// `EX seconds`
+  Ex(u64),
+  /// `PX milliseconds`
+  Px(u64),
+  /// `EXAT unix-time-seconds`
+  ExAt(u64),
+  /// `PXAT unix-time-milliseconds`
+  PxAt(u64),
+  /// `KEEPTTL`
+  KeepTtl,
+}
+
+/// Condition the write depends on. Only one may be given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Condition {
+  /// `NX`, only set if the key doesn't exist
+  Nx,
+  /// `XX`, only set if the key exists
+  Xx,
+}
+
+/// Modifiers parsed from a SET command.
+#[derive(Debug, Default)]
+struct Modifiers {
+  expiry: Option<Expiry>,
+  condition: Option<Condition>,
+  get: bool,
+}
+
+/// What the parser expects next.
+enum State {
+  /// A modifier name
+  Modifier,
+  /// The argument of an expiry modifier, built into an [`Expiry`] by the function
+  Argument(fn(u64) -> Expiry),
+}
+
+impl Modifiers {
+  /// Parses the modifiers following the key and value.
+  ///
+  /// Repeating a modifier keeps the last value, as in Redis, but combining
+  /// different expiries, or NX with XX, is rejected.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Modifiers)` - The parsed modifiers
+  /// * `Err` - An unknown or incomplete modifier, conflicting modifiers, or
+  ///   an invalid expire time
+  fn parse(args: &[String]) -> Result<Self> {
+    let mut modifiers = Self::default();
+    let mut state = State::Modifier;
+
+    for arg in args {
+      state = match state {
+        State::Modifier => match arg.to_uppercase().as_str() {
+          "EX" => State::Argument(Expiry::Ex),
+          "PX" => State::Argument(Expiry::Px),
+          "EXAT" => State::Argument(Expiry::ExAt),
+          "PXAT" => State::Argument(Expiry::PxAt),
+          "KEEPTTL" => {
+            modifiers.set_expiry(Expiry::KeepTtl)?;
+            State::Modifier
+          }
+          "NX" => {
+            modifiers.set_condition(Condition::Nx)?;
+            State::Modifier
+          }
+          "XX" => {
+            modifiers.set_condition(Condition::Xx)?;
+            State::Modifier
+          }
+          "GET" => {
+            modifiers.get = true;
+            State::Modifier
+          }
+          _ => bail!("syntax error"),
+        },
+        State::Argument(expiry) => {
+          let time = arg
+            .parse::<i64>()
+            .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+          if time <= 0 {
+            bail!("invalid expire time in 'set' command");
+          }
+          modifiers.set_expiry(expiry(time as u64))?;
+          State::Modifier
+        }
+      };
+    }
+
+    // An expiry modifier was left without its argument
+    match state {
+      State::Modifier => Ok(modifiers),
+      State::Argument(_) => bail!("syntax error"),
+    }
+  }
+
+  /// Records the expiry, failing if a different kind was already given.
+  fn set_expiry(&mut self, expiry: Expiry) -> Result<()> {
+    match self.expiry {
+      Some(existing) if std::mem::discriminant(&existing) != std::mem::discriminant(&expiry) => {
+        bail!("syntax error")
+      }
+      _ => self.expiry = Some(expiry),
+    }
+    Ok(())
+  }
+
+  /// Records the condition, failing if the other one was already given.
+  fn set_condition(&mut self, condition: Condition) -> Result<()> {
+    match self.condition {
+      Some(existing) if existing != condition => bail!("syntax error"),
+      _ => self.condition = Some(condition),
+    }
+    Ok(())
+  }
+
+  /// Converts the modifiers into the options the store takes.
+  ///
+  /// Absolute expiries are stored as the milliseconds left until then, a
+  /// time already passed leaves the key expired right away.
+  fn into_options(self) -> HashMap<Options, u64> {
+    let mut options = HashMap::new();
+    let until = |deadline: Duration| {
+      let deadline = SystemTime::UNIX_EPOCH + deadline;
+      deadline
+        .duration_since(SystemTime::now())
+        .map(|left| left.as_millis() as u64)
+        .unwrap_or(0)
+    };
+
+    match self.expiry {
+      Some(Expiry::Ex(secs)) => {
+        options.insert(Options::Ex, secs);
+      }
+      Some(Expiry::Px(millis)) => {
+        options.insert(Options::Px, millis);
+      }
+      Some(Expiry::ExAt(secs)) => {
+        options.insert(Options::Px, until(Duration::from_secs(secs)));
+      }
+      Some(Expiry::PxAt(millis)) => {
+        options.insert(Options::Px, until(Duration::from_millis(millis)));
+      }
+      Some(Expiry::KeepTtl) => {
+        options.insert(Options::KeepTtl, 1);
+      }
+      None => {}
+    }
+    match self.condition {
+      Some(Condition::Nx) => {
+        options.insert(Options::Nx, 1);
+      }
+      Some(Condition::Xx) => {
+        options.insert(Options::Xx, 1);
+      }
+      None => {}
+    }
+    if self.get {
+      options.insert(Options::Get, 1);
+    }
+    options
+  }
 }
 
 impl SetCommand {
@@ -47,15 +227,23 @@ impl SetCommand {
   ///
   /// # Returns
   ///
-  /// * `Ok(Value)` - Success response
-  /// * `Err` - Error if command fails or arguments are invalid
+  /// * `Ok(Value)` - OK, or Null if NX or XX prevented the write. With GET,
+  ///   the previous value or Null instead
+  /// * `Err` - Error if command fails, a modifier is unknown, or modifiers conflict
   ///
   /// # Optional Modifiers
   ///
   /// * `EX seconds` - Set expiration time in seconds
   /// * `PX milliseconds` - Set expiration time in milliseconds
+  /// * `EXAT timestamp` - Expire at a Unix time in seconds
+  /// * `PXAT timestamp` - Expire at a Unix time in milliseconds
+  /// * `KEEPTTL` - Keep the expiry of the value being replaced
   /// * `NX` - Only set key if it does not exist
   /// * `XX` - Only set key if it already exists
+  /// * `GET` - Return the previous value, even if NX or XX prevented the write
+  ///
+  /// Only one of `EX`, `PX`, `EXAT`, `PXAT` and `KEEPTTL` may be given, and
+  /// `NX` can't be combined with `XX`.
   ///
   /// # Example
   ///
@@ -68,20 +256,13 @@ impl SetCommand {
   /// ).await;
   /// ```
   pub async fn execute(
-    mut args: Vec<String>,
+    args: Vec<String>,
     store: MemoryStore,
     orig_args: Vec<Value>,
   ) -> Result<Value> {
-    if !store.is_authenticated() {
-      return Err(anyhow!("Authentication required"));
-    }
-
-    if args.len() < 2 {
-      return Err(anyhow!("SET requires a key and a value"));
-    }
-
     let key = args[0].to_owned();
-    let mut extra_args = HashMap::<Options, u64>::new();
+    let extra_args = Modifiers::parse(&args[2..])?.into_options();
+    debug!("Got SET options: {:?}", extra_args);
 
     // Get the original value with its type preserved
     let value = if orig_args.len() > 1 {
@@ -90,66 +271,17 @@ impl SetCommand {
       Value::SimpleString(args[1].clone())
     };
 
-    // @NOTE Find any other optional arguments
-    // Such as EX, PX, NX, XX
-    let mut arg_index = 2;
-    while arg_index < args.len() {
-      let arg = args[arg_index].clone();
-      arg_index += 1;
-
-      match arg.to_uppercase().as_str() {
-        "EX" => {
-          // Handle expiration in seconds
-          if let Some(expiration) = args.get(arg_index) {
-            debug!("Setting expiration to {} seconds", expiration);
-
-            // Parse the expiration value and add that to the extra_args
-            match expiration.parse::<u64>() {
-              Ok(exp) => {
-                extra_args.insert(Options::Ex, exp);
-              }
-              Err(_) => {
-                return Err(anyhow!("Invalid expiration value: {}", expiration));
-              }
-            }
-
-            // Move to the next argument
-            arg_index += 1;
-          }
-        }
-        "PX" => {
-          // Handle expiration in milliseconds
-          if let Some(expiration) = args.get(arg_index) {
-            debug!("Setting expiration to {} milliseconds", expiration);
-
-            // Parse the expiration value and add that to the extra_args
-            match expiration.parse::<u64>() {
-              Ok(exp) => {
-                extra_args.insert(Options::Px, exp);
-              }
-              Err(_) => {
-                return Err(anyhow!("Invalid expiration value: {}", expiration));
-              }
-            }
-
-            // Move to the next argument
-            arg_index += 1;
-          }
-        }
-        "NX" => {
-          // Handle only set if not exists
-          // Logic for NX goes here
-        }
-        "XX" => {
-          // Handle only set if exists
-          // Logic for XX goes here
-        }
-        _ => {}
-      }
-    }
+    let get = extra_args.contains_key(&Options::Get);
 
     // Set the value in the store
-    store.set(key.as_str(), value.clone(), extra_args).await?;
+    let outcome = store.set(key.as_str(), value.clone(), extra_args).await?;
+    if get {
+      return Ok(outcome.previous.unwrap_or(Value::Null));
+    }
+    if !outcome.stored {
+      debug!("Key {} was not set, the NX/XX condition did not hold", key);
+      return Ok(Value::Null);
+    }
 
     // Log with the display representation of the value
     let display_value = match &value {
@@ -164,3 +296,10 @@ impl SetCommand {
     Ok(Value::SimpleString("OK".to_string()))
   }
 }
+
+#[async_trait]
+impl Command for SetCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), ctx.raw_args).await
+  }
+}
@@ -0,0 +1,89 @@
+//! SETBIT command implementation.
+//!
+//! Sets or clears a single bit of a string value. Requires authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{memory::MemoryStore, notify::EventClass},
+};
+
+/// SETBIT command handler.
+///
+/// Treats the string value as a bitmap, with bit 0 being the most
+/// significant bit of the first byte.
+pub struct SetBitCommand;
+
+impl SetBitCommand {
+  /// Executes the SETBIT command.
+  ///
+  /// The string is grown with zero bytes if the offset lies past its end,
+  /// and a missing key is created. The read and write happen atomically.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, bit offset and bit value)
+  /// * `store` - Memory store to operate on
+  /// * `max_len` - Longest string the bitmap may grow to, in bytes
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - Integer value the bit had before
+  /// * `Err` - Error if the offset or bit is invalid or the value is not a string
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SETBIT mykey 7 1
+  /// let result = SetBitCommand::execute(
+  ///     vec!["mykey".to_string(), "7".to_string(), "1".to_string()],
+  ///     store,
+  ///     max_len
+  /// ).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore, max_len: usize) -> Result<Value> {
+    let offset = parse_bit_offset(&args[1], max_len)?;
+    let bit = match args[2].as_str() {
+      "0" => false,
+      "1" => true,
+      _ => return Err(anyhow!("bit is not an integer or out of range")),
+    };
+
+    let byte = offset / 8;
+    let mask = 0x80 >> (offset % 8);
+    let old = store.update_bytes(&args[0], |bytes| {
+      if bytes.len() <= byte {
+        bytes.resize(byte + 1, 0);
+      }
+      let old = bytes[byte] & mask != 0;
+      if bit {
+        bytes[byte] |= mask;
+      } else {
+        bytes[byte] &= !mask;
+      }
+      old
+    })?;
+    store.notify(EventClass::String, "setbit", &args[0]);
+
+    Ok(Value::Integer(old as i64))
+  }
+}
+
+/// Parses a bit offset, which must address a byte within `max_len`.
+pub fn parse_bit_offset(arg: &str, max_len: usize) -> Result<usize> {
+  match arg.parse::<usize>() {
+    Ok(offset) if offset / 8 < max_len => Ok(offset),
+    _ => Err(anyhow!("bit offset is not an integer or out of range")),
+  }
+}
+
+#[async_trait]
+impl Command for SetBitCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    let max_len = ctx.settings.read().unwrap().server.network.max_bulk_len;
+    Self::execute(ctx.args, ctx.store.to_owned(), max_len).await
+  }
+}
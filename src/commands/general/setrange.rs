@@ -0,0 +1,88 @@
+//! SETRANGE command implementation.
+//!
+//! Overwrites part of a string value starting at a byte offset.
+//! Requires authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{memory::MemoryStore, notify::EventClass},
+};
+
+/// SETRANGE command handler.
+///
+/// Writes bytes into a string value at an offset, growing the string as
+/// needed. The read and write happen atomically in the store.
+pub struct SetRangeCommand;
+
+impl SetRangeCommand {
+  /// Executes the SETRANGE command.
+  ///
+  /// If the offset lies past the end of the current value, the gap is
+  /// filled with zero bytes. A missing key is treated as an empty string.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and offset)
+  /// * `value` - Bytes to write at the offset
+  /// * `store` - Memory store to operate on
+  /// * `max_len` - Longest string the value may grow to
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - Integer length of the string after the write
+  /// * `Err` - Error if the offset is invalid or the value is not a string
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SETRANGE mykey 6 Redis
+  /// let result = SetRangeCommand::execute(
+  ///     vec!["mykey".to_string(), "6".to_string()],
+  ///     b"Redis".to_vec(),
+  ///     store,
+  ///     max_len
+  /// ).await;
+  /// ```
+  pub async fn execute(
+    args: Vec<String>,
+    value: Vec<u8>,
+    store: MemoryStore,
+    max_len: usize,
+  ) -> Result<Value> {
+    let offset = args[1]
+      .parse::<usize>()
+      .map_err(|_| anyhow!("offset is out of range"))?;
+    if offset.saturating_add(value.len()) > max_len {
+      return Err(anyhow!("string exceeds maximum allowed size"));
+    }
+
+    let len = store.update_bytes(&args[0], |bytes| {
+      if !value.is_empty() {
+        let end = offset + value.len();
+        if bytes.len() < end {
+          bytes.resize(end, 0);
+        }
+        bytes[offset..end].copy_from_slice(&value);
+      }
+      bytes.len()
+    })?;
+    store.notify(EventClass::String, "setrange", &args[0]);
+
+    Ok(Value::Integer(len as i64))
+  }
+}
+
+#[async_trait]
+impl Command for SetRangeCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    let value = ctx.raw_args[2]
+      .to_bytes()
+      .ok_or_else(|| anyhow!("value is not a string"))?;
+    let max_len = ctx.settings.read().unwrap().server.network.max_bulk_len;
+    Self::execute(ctx.args, value, ctx.store.to_owned(), max_len).await
+  }
+}
@@ -0,0 +1,53 @@
+//! SHUTDOWN command implementation.
+//!
+//! Stops the server over the protocol. Only root users may run it, which
+//! the executor checks before the command runs.
+
+use anyhow::{Result, bail};
+use log::warn;
+
+use crate::{
+  storage::memory::MemoryStore,
+  utils::shutdown::{ShutdownMode, ShutdownTrigger},
+};
+
+/// SHUTDOWN command handler.
+///
+/// Hands the request over to the main loop, which closes every connection,
+/// saves the KDB file unless told otherwise and exits. It is run by the
+/// executor directly because it has no reply: the connection just closes.
+pub struct ShutdownCommand;
+
+impl ShutdownCommand {
+  /// Executes the SHUTDOWN command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Optional `SAVE` or `NOSAVE` modifier
+  /// * `store` - Memory store holding the authenticated user
+  /// * `shutdown` - Trigger the main loop waits on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - Shutdown was requested
+  /// * `Err` - Not authenticated, or an unknown modifier
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SHUTDOWN NOSAVE
+  /// let result = ShutdownCommand::execute(vec!["NOSAVE".to_string()], store, shutdown);
+  /// ```
+  pub fn execute(args: Vec<String>, _store: MemoryStore, shutdown: ShutdownTrigger) -> Result<()> {
+    let mode = match args.first().map(|arg| arg.to_uppercase()).as_deref() {
+      None => ShutdownMode::Default,
+      Some("SAVE") => ShutdownMode::Save,
+      Some("NOSAVE") => ShutdownMode::NoSave,
+      Some(_) => bail!("syntax error"),
+    };
+
+    warn!("Shutdown requested by client ({:?})", mode);
+    shutdown.trigger(mode);
+    Ok(())
+  }
+}
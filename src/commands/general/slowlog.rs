@@ -0,0 +1,96 @@
+//! SLOWLOG command implementation.
+//!
+//! Reads and clears the log of commands that ran longer than
+//! `server.slowlog.threshold_micros`. Root only, as the log holds every
+//! user's keys and values.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    registry::{Command, CommandContext},
+    stats::{self, SlowEntry},
+  },
+  resp::value::Value,
+};
+
+/// Number of entries `SLOWLOG GET` returns without a count.
+const DEFAULT_COUNT: usize = 10;
+
+/// SLOWLOG command handler.
+///
+/// Supports the `GET`, `LEN` and `RESET` subcommands.
+pub struct SlowlogCommand;
+
+impl SlowlogCommand {
+  /// Executes the SLOWLOG command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] is the upper-case subcommand
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - The newest entries for `GET [count]`, all of them with a count of -1
+  /// * `Ok(Value::Integer)` - The number of entries for `LEN`
+  /// * `Ok(Value::SimpleString("OK"))` - The log was cleared for `RESET`
+  /// * `Err` - Unknown subcommand, wrong argument count or invalid count
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SLOWLOG GET 5
+  /// let result = SlowlogCommand::execute(vec!["GET".to_string(), "5".to_string()]);
+  /// // Returns [[id, timestamp, usec, [command, args...], addr, name], ...]
+  /// ```
+  pub fn execute(args: Vec<String>) -> Result<Value> {
+    let subcommand = &args[0];
+    match subcommand.as_str() {
+      "GET" if args.len() <= 2 => {
+        let count = match args.get(1) {
+          None => Some(DEFAULT_COUNT),
+          Some(count) => match count.parse::<i64>() {
+            Ok(-1) => None,
+            Ok(count) if count >= 0 => Some(count as usize),
+            _ => return Err(anyhow!("count should be greater than or equal to -1")),
+          },
+        };
+        let entries = stats::slowlog_entries(count)
+          .into_iter()
+          .map(Self::entry)
+          .collect();
+        Ok(Value::Array(entries))
+      }
+      "LEN" if args.len() == 1 => Ok(Value::Integer(stats::slowlog_len() as i64)),
+      "RESET" if args.len() == 1 => {
+        stats::slowlog_reset();
+        Ok(Value::SimpleString("OK".to_string()))
+      }
+      "GET" | "LEN" | "RESET" => Err(anyhow!(
+        "wrong number of arguments for 'slowlog|{}' command",
+        subcommand.to_lowercase()
+      )),
+      other => Err(anyhow!("unknown subcommand '{}'", other.to_lowercase())),
+    }
+  }
+
+  /// Formats an entry the way Redis replies to `SLOWLOG GET`.
+  fn entry(entry: SlowEntry) -> Value {
+    Value::Array(vec![
+      Value::Integer(entry.id as i64),
+      Value::Integer(entry.timestamp as i64),
+      Value::Integer(entry.usec as i64),
+      Value::Array(entry.args.into_iter().map(Value::BulkString).collect()),
+      Value::BulkString(entry.addr),
+      Value::BulkString(entry.name),
+    ])
+  }
+}
+
+#[async_trait]
+impl Command for SlowlogCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args)
+  }
+}
@@ -0,0 +1,57 @@
+//! SWAPDB command implementation.
+//!
+//! Atomically exchanges the contents of two numbered databases.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// SWAPDB command handler.
+///
+/// Useful for warming up a spare database and then swapping it into place.
+pub struct SwapDbCommand;
+
+impl SwapDbCommand {
+  /// Executes the SWAPDB command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments, where args[0] and args[1] are the database indices
+  /// * `store` - The memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::SimpleString("OK"))` - The databases were swapped
+  /// * `Err` - Not authenticated, or an index is invalid or out of range
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SWAPDB 0 1
+  /// let result = SwapDbCommand::execute(vec!["0".to_string(), "1".to_string()], store);
+  /// assert_eq!(result.unwrap(), Value::SimpleString("OK".to_string()));
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let first = args[0]
+      .parse::<usize>()
+      .map_err(|_| anyhow!("invalid first DB index"))?;
+    let second = args[1]
+      .parse::<usize>()
+      .map_err(|_| anyhow!("invalid second DB index"))?;
+
+    store.swap_db(first, second)?;
+    Ok(Value::SimpleString("OK".to_string()))
+  }
+}
+
+#[async_trait]
+impl Command for SwapDbCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
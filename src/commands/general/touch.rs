@@ -0,0 +1,53 @@
+//! TOUCH command implementation.
+//!
+//! Marks keys as recently used without fetching their values. Requires
+//! authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// TOUCH command handler.
+///
+/// Resets the idle time of every given key that exists.
+pub struct TouchCommand;
+
+impl TouchCommand {
+  /// Executes the TOUCH command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Keys to touch
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Number of keys that exist and were touched
+  /// * `Err` - Not authenticated
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: TOUCH key1 key2
+  /// let result = TouchCommand::execute(
+  ///     vec!["key1".to_string(), "key2".to_string()],
+  ///     store
+  /// );
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let touched = store.touch(&args)?;
+    Ok(Value::Integer(touched as i64))
+  }
+}
+
+#[async_trait]
+impl Command for TouchCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
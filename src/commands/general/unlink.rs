@@ -0,0 +1,62 @@
+//! UNLINK command implementation.
+//!
+//! Removes keys like DEL, but frees large values in the background.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use log::debug;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::{MemoryStore, Store},
+};
+
+/// UNLINK command handler.
+///
+/// Removes keys immediately and returns how many existed, while dropping
+/// large values is left to the store's reclaim queue.
+pub struct UnlinkCommand;
+
+impl UnlinkCommand {
+  /// Executes the UNLINK command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Keys to unlink
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - Integer count of keys removed
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: UNLINK key1 key2
+  /// let result = UnlinkCommand::execute(
+  ///     vec!["key1".to_string(), "key2".to_string()],
+  ///     store
+  /// ).await;
+  /// // Returns integer representing number of keys actually removed
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let mut unlinked = 0;
+    for key in args.iter() {
+      if let Some(value) = store.delete(key).await {
+        debug!("Unlinked key {}", key);
+        store.lazy_free(value);
+        unlinked += 1;
+      }
+    }
+
+    Ok(Value::Integer(unlinked))
+  }
+}
+
+#[async_trait]
+impl Command for UnlinkCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned()).await
+  }
+}
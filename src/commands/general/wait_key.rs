@@ -0,0 +1,69 @@
+//! WAITKEY command implementation.
+//!
+//! Blocks the connection until a key exists. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::time;
+
+use crate::{
+  commands::{
+    lists::blpop::parse_timeout,
+    registry::{Command, CommandContext},
+  },
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// WAITKEY command handler.
+///
+/// Meant for tests and for coordinating clients. The client is woken up by
+/// the write that creates the key, it doesn't poll.
+pub struct WaitKeyCommand;
+
+impl WaitKeyCommand {
+  /// Executes the WAITKEY command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and timeout in seconds, 0 waits forever)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(1))` - The key exists, right away or before the timeout
+  /// * `Ok(Value::Integer(0))` - The timeout elapsed first
+  /// * `Err` - Not authenticated, or the timeout is invalid
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: WAITKEY ready 10
+  /// let result = WaitKeyCommand::execute(vec!["ready".to_string(), "10".to_string()], store).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let timeout = parse_timeout(&args[1])?;
+    let Some(mut receiver) = store.wait_for_key(&args[0])? else {
+      return Ok(Value::Integer(1));
+    };
+    let appeared = match timeout {
+      Some(timeout) => match time::timeout(timeout, &mut receiver).await {
+        Ok(received) => received.is_ok(),
+        // The key may have appeared right as the timeout elapsed
+        Err(_) => {
+          receiver.close();
+          receiver.try_recv().is_ok()
+        }
+      },
+      None => receiver.await.is_ok(),
+    };
+    Ok(Value::Integer(i64::from(appeared)))
+  }
+}
+
+#[async_trait]
+impl Command for WaitKeyCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned()).await
+  }
+}
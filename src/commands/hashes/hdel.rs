@@ -0,0 +1,49 @@
+//! HDEL command implementation.
+//!
+//! Removes fields from a hash. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// HDEL command handler.
+///
+/// A hash left without fields is deleted.
+pub struct HDelCommand;
+
+impl HDelCommand {
+  /// Executes the HDEL command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then the fields to remove)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Number of fields that were removed
+  /// * `Err` - Not authenticated, or the key holds a value that is not a hash
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: HDEL user:1 name
+  /// let result = HDelCommand::execute(vec!["user:1".to_string(), "name".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let removed = store.hash_delete(&args[0], &args[1..])?;
+    Ok(Value::Integer(removed as i64))
+  }
+}
+
+#[async_trait]
+impl Command for HDelCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
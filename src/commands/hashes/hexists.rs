@@ -0,0 +1,48 @@
+//! HEXISTS command implementation.
+//!
+//! Checks whether a hash has a field. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// HEXISTS command handler.
+pub struct HExistsCommand;
+
+impl HExistsCommand {
+  /// Executes the HEXISTS command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and field)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(1))` - The hash has the field
+  /// * `Ok(Value::Integer(0))` - The field or the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a hash
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: HEXISTS user:1 name
+  /// let result = HExistsCommand::execute(vec!["user:1".to_string(), "name".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let exists = store.inspect_hash(&args[0], |hash| hash.contains_key(&args[1]))?;
+    Ok(Value::Integer(exists.unwrap_or(false) as i64))
+  }
+}
+
+#[async_trait]
+impl Command for HExistsCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
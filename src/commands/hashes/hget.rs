@@ -0,0 +1,47 @@
+//! HGET command implementation.
+//!
+//! Returns the value of a hash field. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// HGET command handler.
+pub struct HGetCommand;
+
+impl HGetCommand {
+  /// Executes the HGET command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and field)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - The field's value
+  /// * `Ok(Value::Null)` - The field or the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a hash
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: HGET user:1 name
+  /// let result = HGetCommand::execute(vec!["user:1".to_string(), "name".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    Ok(store.hash_get(&args[0], &args[1])?.unwrap_or(Value::Null))
+  }
+}
+
+#[async_trait]
+impl Command for HGetCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
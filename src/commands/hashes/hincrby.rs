@@ -0,0 +1,103 @@
+//! HINCRBY and HINCRBYFLOAT command implementations.
+//!
+//! Add to the number stored in a hash field. Requires authentication.
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{memory::MemoryStore, notify::EventClass},
+};
+
+/// HINCRBY / HINCRBYFLOAT command handler.
+///
+/// The same handler serves both commands, they only differ in the kind of
+/// number the field holds. A missing field counts as 0.
+pub struct HIncrByCommand {
+  /// Whether the field holds a float (HINCRBYFLOAT) rather than an integer
+  pub float: bool,
+}
+
+impl HIncrByCommand {
+  /// Executes the HINCRBY or HINCRBYFLOAT command.
+  ///
+  /// The field is read and written back under a single lock, so concurrent
+  /// increments are never lost.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, field and increment)
+  /// * `store` - Memory store to operate on
+  /// * `float` - Whether the field holds a float
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - The new value, for HINCRBY
+  /// * `Ok(Value::BulkString)` - The new value, for HINCRBYFLOAT
+  /// * `Err` - Not authenticated, the increment or the field's value is not
+  ///   a number, the result overflows, or the key holds a value that is not a hash
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: HINCRBY user:1 visits 1
+  /// let args = vec!["user:1".to_string(), "visits".to_string(), "1".to_string()];
+  /// let result = HIncrByCommand::execute(args, store, false);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore, float: bool) -> Result<Value> {
+    let (key, field) = (&args[0], &args[1]);
+    if float {
+      let increment = parse_float(&args[2]).ok_or_else(|| anyhow!("value is not a valid float"))?;
+      let value = store.hash_update(key, field, |value| {
+        let current = match value {
+          Some(value) => value
+            .to_bytes()
+            .and_then(|bytes| parse_float(&String::from_utf8_lossy(&bytes)))
+            .ok_or_else(|| anyhow!("hash value is not a float"))?,
+          None => 0.0,
+        };
+        let sum = current + increment;
+        if !sum.is_finite() {
+          bail!("increment would produce NaN or Infinity");
+        }
+        Ok(Value::BulkString(sum.to_string()))
+      })?;
+      store.notify(EventClass::Hash, "hincrbyfloat", key);
+      return Ok(value);
+    }
+
+    let increment = args[2]
+      .parse::<i64>()
+      .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+    let mut sum = 0;
+    store.hash_update(key, field, |value| {
+      let current = match value {
+        Some(value) => value
+          .to_bytes()
+          .and_then(|bytes| String::from_utf8_lossy(&bytes).parse::<i64>().ok())
+          .ok_or_else(|| anyhow!("hash value is not an integer"))?,
+        None => 0,
+      };
+      sum = current
+        .checked_add(increment)
+        .ok_or_else(|| anyhow!("increment or decrement would overflow"))?;
+      Ok(Value::BulkString(sum.to_string()))
+    })?;
+    store.notify(EventClass::Hash, "hincrby", key);
+    Ok(Value::Integer(sum))
+  }
+}
+
+/// Parses a finite float, rejecting `inf` and `nan`.
+fn parse_float(s: &str) -> Option<f64> {
+  s.parse::<f64>().ok().filter(|float| float.is_finite())
+}
+
+#[async_trait]
+impl Command for HIncrByCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), self.float)
+  }
+}
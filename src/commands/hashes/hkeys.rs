@@ -0,0 +1,66 @@
+//! HKEYS and HVALS command implementations.
+//!
+//! Return every field name or every value of a hash. Requires
+//! authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// HKEYS / HVALS command handler.
+///
+/// The same handler serves both commands, they only differ in which half
+/// of every field is returned. Fields come in no particular order.
+pub struct HKeysCommand {
+  /// Whether values (HVALS) are returned rather than field names
+  pub values: bool,
+}
+
+impl HKeysCommand {
+  /// Executes the HKEYS or HVALS command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key of the hash)
+  /// * `store` - Memory store to operate on
+  /// * `values` - Whether values are returned rather than field names
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - The field names or values, empty if the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a hash
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: HVALS user:1
+  /// let result = HKeysCommand::execute(vec!["user:1".to_string()], store, true);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore, values: bool) -> Result<Value> {
+    let items = store.inspect_hash(&args[0], |hash| {
+      hash
+        .iter()
+        .map(|(field, (value, ..))| {
+          if values {
+            value.clone()
+          } else {
+            Value::BulkString(field.clone())
+          }
+        })
+        .collect()
+    })?;
+    Ok(Value::Array(items.unwrap_or_default()))
+  }
+}
+
+#[async_trait]
+impl Command for HKeysCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), self.values)
+  }
+}
@@ -0,0 +1,47 @@
+//! HLEN command implementation.
+//!
+//! Returns the number of fields of a hash. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// HLEN command handler.
+pub struct HLenCommand;
+
+impl HLenCommand {
+  /// Executes the HLEN command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key of the hash)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Number of fields, 0 if the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a hash
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: HLEN user:1
+  /// let result = HLenCommand::execute(vec!["user:1".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let len = store.inspect_hash(&args[0], |hash| hash.len())?;
+    Ok(Value::Integer(len.unwrap_or(0) as i64))
+  }
+}
+
+#[async_trait]
+impl Command for HLenCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,61 @@
+//! HMGET command implementation.
+//!
+//! Returns the values of several hash fields. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// HMGET command handler.
+pub struct HMGetCommand;
+
+impl HMGetCommand {
+  /// Executes the HMGET command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then the fields to read)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - The value of every field in order, Null for
+  ///   missing fields or if the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a hash
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: HMGET user:1 name email
+  /// let args = vec!["user:1".to_string(), "name".to_string(), "email".to_string()];
+  /// let result = HMGetCommand::execute(args, store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let fields = &args[1..];
+    let values = store.inspect_hash(&args[0], |hash| {
+      fields
+        .iter()
+        .map(|field| {
+          hash
+            .get(field)
+            .map_or(Value::Null, |(value, ..)| value.clone())
+        })
+        .collect()
+    })?;
+    Ok(Value::Array(
+      values.unwrap_or_else(|| vec![Value::Null; fields.len()]),
+    ))
+  }
+}
+
+#[async_trait]
+impl Command for HMGetCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
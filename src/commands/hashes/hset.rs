@@ -0,0 +1,60 @@
+//! HSET command implementation.
+//!
+//! Sets fields of a hash, creating it if needed. Requires authentication.
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// HSET command handler.
+pub struct HSetCommand;
+
+impl HSetCommand {
+  /// Executes the HSET command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then field and value pairs)
+  /// * `store` - Memory store to operate on
+  /// * `orig_args` - Original value objects, so values keep their bytes
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Number of fields that didn't exist yet
+  /// * `Err` - Not authenticated, a field has no value, or the key holds a
+  ///   value that is not a hash
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: HSET user:1 name alice
+  /// let args = vec!["user:1".to_string(), "name".to_string(), "alice".to_string()];
+  /// let result = HSetCommand::execute(args, store, raw_args);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore, orig_args: Vec<Value>) -> Result<Value> {
+    if args.len().is_multiple_of(2) {
+      bail!("wrong number of arguments for 'hset' command");
+    }
+
+    let fields = args[1..]
+      .iter()
+      .step_by(2)
+      .cloned()
+      .zip(orig_args.into_iter().skip(2).step_by(2))
+      .collect();
+    let added = store.hash_set(&args[0], fields)?;
+    Ok(Value::Integer(added as i64))
+  }
+}
+
+#[async_trait]
+impl Command for HSetCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), ctx.raw_args)
+  }
+}
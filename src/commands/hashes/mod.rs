@@ -0,0 +1,15 @@
+//! Hash commands.
+//!
+//! This module contains the commands working on hashes of fields:
+//! - `hset` / `hget` / `hdel`: Write, read or remove fields
+//! - `hincrby`: Add to a numeric field
+//! - `hmget` / `hkeys` / `hlen` / `hexists`: Inspect several fields or the whole hash
+
+pub mod hdel;
+pub mod hexists;
+pub mod hget;
+pub mod hincrby;
+pub mod hkeys;
+pub mod hlen;
+pub mod hmget;
+pub mod hset;
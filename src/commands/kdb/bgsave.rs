@@ -0,0 +1,54 @@
+//! BGSAVE command implementation.
+//!
+//! Writes a snapshot of every user's data to the KDB file without blocking
+//! the connection. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{kdb, memory::MemoryStore},
+  utils::settings::Settings,
+};
+
+/// BGSAVE command handler.
+///
+/// Starts persisting the memory store in a background task and replies
+/// right away. The outcome of the save is reported in the server log.
+pub struct BgSaveCommand;
+
+impl BgSaveCommand {
+  /// Executes the BGSAVE command.
+  ///
+  /// # Arguments
+  ///
+  /// * `store` - Memory store to snapshot
+  /// * `settings` - Settings holding the KDB file location
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - Confirmation that the save started
+  /// * `Err` - Error if not authenticated or a background save is running
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: BGSAVE
+  /// let result = BgSaveCommand::execute(store, settings);
+  /// ```
+  pub fn execute(store: MemoryStore, settings: Settings) -> Result<Value> {
+    kdb::background_save(store, settings)?;
+    Ok(Value::SimpleString("Background saving started".to_string()))
+  }
+}
+
+#[async_trait]
+impl Command for BgSaveCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    // Snapshot the settings, the save outlives the command
+    let settings = ctx.settings.read().unwrap().clone();
+    Self::execute(ctx.store.to_owned(), settings)
+  }
+}
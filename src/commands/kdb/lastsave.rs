@@ -0,0 +1,49 @@
+//! LASTSAVE command implementation.
+//!
+//! Reports when the data was last persisted to the KDB file. Requires
+//! authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{kdb, memory::MemoryStore},
+};
+
+/// LASTSAVE command handler.
+///
+/// Clients run it before and after BGSAVE to tell when the background save
+/// finished.
+pub struct LastSaveCommand;
+
+impl LastSaveCommand {
+  /// Executes the LASTSAVE command.
+  ///
+  /// # Arguments
+  ///
+  /// * `store` - Memory store whose saves are reported
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(timestamp))` - Unix time of the last successful
+  ///   save, in seconds, 0 if nothing was saved since startup
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LASTSAVE
+  /// let result = LastSaveCommand::execute(&store);
+  /// ```
+  pub fn execute(store: &MemoryStore) -> Result<Value> {
+    Ok(Value::Integer(kdb::last_save(store)))
+  }
+}
+
+#[async_trait]
+impl Command for LastSaveCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.store)
+  }
+}
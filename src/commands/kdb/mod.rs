@@ -1,3 +1,12 @@
+//! KDB persistence commands.
+//!
+//! This module contains commands for persisting the in-memory data:
+//! - `save`: Write a snapshot of all data to the KDB file
+//! - `bgsave`: Write the snapshot from a background task
+//! - `lastsave`: Report when the last snapshot was written
+
+pub mod bgsave;
+pub mod lastsave;
 pub mod load;
-pub mod save;
 pub mod persist;
+pub mod save;
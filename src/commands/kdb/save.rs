@@ -0,0 +1,58 @@
+//! SAVE command implementation.
+//!
+//! Writes a snapshot of every user's data to the KDB file. Requires authentication.
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{kdb, memory::MemoryStore},
+  utils::settings::Settings,
+};
+
+/// SAVE command handler.
+///
+/// Persists the memory store to the configured KDB file and replies once the
+/// file is written. The write runs on the blocking pool, so only the calling
+/// connection waits for it.
+pub struct SaveCommand;
+
+impl SaveCommand {
+  /// Executes the SAVE command.
+  ///
+  /// # Arguments
+  ///
+  /// * `store` - Memory store to snapshot
+  /// * `settings` - Settings holding the KDB file location
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - OK once the file is written
+  /// * `Err` - Error if a background save is running or the write fails
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SAVE
+  /// let result = SaveCommand::execute(store, settings).await;
+  /// ```
+  pub async fn execute(store: MemoryStore, settings: Settings) -> Result<Value> {
+    // Waiting for the background save would only write the same data again
+    if kdb::bgsave_in_progress(&store) {
+      bail!("Background save already in progress");
+    }
+    tokio::task::spawn_blocking(move || kdb::save(&store, &settings)).await??;
+    Ok(Value::SimpleString("OK".to_string()))
+  }
+}
+
+#[async_trait]
+impl Command for SaveCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    // Snapshot the settings, CONFIG SET mustn't wait for the write
+    let settings = ctx.settings.read().unwrap().clone();
+    Self::execute(ctx.store.to_owned(), settings).await
+  }
+}
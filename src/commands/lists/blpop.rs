@@ -0,0 +1,110 @@
+//! BLPOP and BRPOP command implementation.
+//!
+//! Pops a value from the first non-empty list among several keys, waiting
+//! for one to be pushed if they are all empty. Requires authentication.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use tokio::time;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::{ListPop, MemoryStore},
+};
+
+/// BLPOP / BRPOP command handler.
+///
+/// Clients blocked on the same key are served in the order they blocked.
+/// No lock is held while waiting, the push that serves a client hands it
+/// the value directly.
+pub struct BLPopCommand {
+  /// Pop from the head of the lists (BLPOP) instead of their tail (BRPOP)
+  pub front: bool,
+}
+
+impl BLPopCommand {
+  /// Executes the BLPOP or BRPOP command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments: the keys to pop from, checked in order,
+  ///   followed by the timeout in seconds. A timeout of 0 waits forever
+  /// * `store` - Memory store to operate on
+  /// * `front` - Whether to pop from the head of the lists
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - The key the value was popped from and the value
+  /// * `Ok(Value::Null)` - The timeout elapsed before any value was pushed
+  /// * `Err` - Not authenticated, the timeout is invalid, or a key holds a
+  ///   value that is not a list
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: BLPOP jobs urgent 5
+  /// let args = vec!["jobs", "urgent", "5"];
+  /// let result = BLPopCommand::execute(args.into_iter().map(String::from).collect(), store, true).await;
+  /// ```
+  pub async fn execute(args: Vec<String>, store: MemoryStore, front: bool) -> Result<Value> {
+    let (timeout, keys) = args.split_last().unwrap();
+    let timeout = parse_timeout(timeout)?;
+
+    let mut receiver = match store.list_pop_or_block(keys, front)? {
+      ListPop::Popped(key, value) => return Ok(Self::reply(key, value)),
+      ListPop::Blocked(receiver) => receiver,
+    };
+    let received = match timeout {
+      Some(timeout) => time::timeout(timeout, &mut receiver).await.ok(),
+      None => Some((&mut receiver).await),
+    };
+    let popped = match received {
+      Some(popped) => popped.ok(),
+      None => {
+        // A push may have served this client right as the timeout elapsed
+        receiver.close();
+        receiver.try_recv().ok()
+      }
+    };
+    Ok(popped.map_or(Value::Null, |(key, value)| Self::reply(key, value)))
+  }
+
+  /// Builds the reply for a value popped from a key.
+  fn reply(key: String, value: String) -> Value {
+    Value::Array(vec![Value::BulkString(key), Value::BulkString(value)])
+  }
+}
+
+/// Parses a timeout in seconds, which may be fractional.
+///
+/// # Returns
+///
+/// * `Ok(Some(Duration))` - The timeout
+/// * `Ok(None)` - The timeout is 0, meaning wait forever
+/// * `Err` - The timeout is negative or not a number
+pub fn parse_timeout(arg: &str) -> Result<Option<Duration>> {
+  let seconds = arg
+    .parse::<f64>()
+    .ok()
+    .filter(|seconds| seconds.is_finite())
+    .ok_or_else(|| anyhow!("timeout is not a float or out of range"))?;
+  if seconds < 0.0 {
+    bail!("timeout is negative");
+  }
+  if seconds == 0.0 {
+    return Ok(None);
+  }
+  Duration::try_from_secs_f64(seconds)
+    .map(Some)
+    .map_err(|_| anyhow!("timeout is out of range"))
+}
+
+#[async_trait]
+impl Command for BLPopCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), self.front).await
+  }
+}
@@ -0,0 +1,70 @@
+//! LINDEX command implementation.
+//!
+//! Returns the value at a position in a list. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    general::getrange::parse_offset,
+    registry::{Command, CommandContext},
+  },
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// LINDEX command handler.
+///
+/// Positions are 0-based, negative positions count from the tail, -1 being
+/// the last value.
+pub struct LIndexCommand;
+
+impl LIndexCommand {
+  /// Executes the LINDEX command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and position)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::BulkString)` - The value at the position
+  /// * `Ok(Value::Null)` - The position is out of range or the key doesn't exist
+  /// * `Err` - Not authenticated, the position is not an integer, or the key
+  ///   holds a value that is not a list
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LINDEX mylist -1
+  /// let result = LIndexCommand::execute(vec!["mylist".to_string(), "-1".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let index = parse_offset(&args[1])?;
+    let value = store.inspect_list(&args[0], |list| {
+      let index = resolve_index(index, list.len())?;
+      list.iter().nth(index).cloned()
+    })?;
+    Ok(value.flatten().map_or(Value::Null, Value::BulkString))
+  }
+}
+
+/// Resolves a possibly negative position in a list of length `len`.
+///
+/// # Returns
+///
+/// * `Some(index)` - The position counted from the head
+/// * `None` - If the position is outside the list
+pub fn resolve_index(index: i64, len: usize) -> Option<usize> {
+  let index = if index < 0 { index + len as i64 } else { index };
+  usize::try_from(index).ok().filter(|&index| index < len)
+}
+
+#[async_trait]
+impl Command for LIndexCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,72 @@
+//! LINSERT command implementation.
+//!
+//! Inserts a value before or after the first occurrence of another one in
+//! a list. Requires authentication.
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{memory::MemoryStore, notify::EventClass},
+};
+
+/// LINSERT command handler.
+pub struct LInsertCommand;
+
+impl LInsertCommand {
+  /// Executes the LINSERT command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments: key, `BEFORE` or `AFTER`, the pivot value
+  ///   to look for and the value to insert
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Length of the list after the insert
+  /// * `Ok(Value::Integer(-1))` - The pivot isn't in the list
+  /// * `Ok(Value::Integer(0))` - The key doesn't exist
+  /// * `Err` - Not authenticated, invalid arguments, or the key holds a value
+  ///   that is not a list
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LINSERT mylist BEFORE b a
+  /// let args = vec!["mylist", "BEFORE", "b", "a"];
+  /// let result = LInsertCommand::execute(args.into_iter().map(String::from).collect(), store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let after = match args[1].to_uppercase().as_str() {
+      "BEFORE" => false,
+      "AFTER" => true,
+      _ => bail!("syntax error"),
+    };
+    let (pivot, value) = (&args[2], &args[3]);
+
+    let len = store.update_list(&args[0], Some(value), |list| {
+      let Some(position) = list.iter().position(|item| item == pivot) else {
+        return -1;
+      };
+      // Split the list where the value goes and put it back together
+      let mut tail = list.split_off(position + usize::from(after));
+      list.push_back(value.clone());
+      list.append(&mut tail);
+      list.len() as i64
+    })?;
+    if len.is_some_and(|len| len > 0) {
+      store.notify(EventClass::List, "linsert", &args[0]);
+    }
+    Ok(Value::Integer(len.unwrap_or(0)))
+  }
+}
+
+#[async_trait]
+impl Command for LInsertCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
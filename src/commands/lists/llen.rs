@@ -0,0 +1,47 @@
+//! LLEN command implementation.
+//!
+//! Returns the length of a list. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// LLEN command handler.
+pub struct LLenCommand;
+
+impl LLenCommand {
+  /// Executes the LLEN command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Length of the list, 0 if the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a list
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LLEN mylist
+  /// let result = LLenCommand::execute(vec!["mylist".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let len = store.inspect_list(&args[0], |list| list.len())?;
+    Ok(Value::Integer(len.unwrap_or(0) as i64))
+  }
+}
+
+#[async_trait]
+impl Command for LLenCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
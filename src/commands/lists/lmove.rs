@@ -0,0 +1,75 @@
+//! LMOVE and RPOPLPUSH command implementation.
+//!
+//! Atomically pops a value from one list and pushes it onto another.
+//! Requires authentication.
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// LMOVE / RPOPLPUSH command handler.
+///
+/// RPOPLPUSH is LMOVE with the ends fixed to `RIGHT LEFT`. Moving a value
+/// within the same list rotates it.
+pub struct LMoveCommand {
+  /// Take the ends from the arguments (LMOVE) instead of using RIGHT LEFT
+  /// (RPOPLPUSH)
+  pub explicit: bool,
+}
+
+impl LMoveCommand {
+  /// Executes the LMOVE or RPOPLPUSH command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments: source and destination keys, followed for
+  ///   LMOVE by the end to pop from and the end to push onto, each `LEFT` or `RIGHT`
+  /// * `store` - Memory store to operate on
+  /// * `explicit` - Whether the ends are given in the arguments
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::BulkString)` - The moved value
+  /// * `Ok(Value::Null)` - The source doesn't exist
+  /// * `Err` - Not authenticated, an end is invalid, or either key holds a
+  ///   value that is not a list
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LMOVE pending processing RIGHT LEFT
+  /// let args = vec!["pending", "processing", "RIGHT", "LEFT"];
+  /// let result = LMoveCommand::execute(args.into_iter().map(String::from).collect(), store, true);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore, explicit: bool) -> Result<Value> {
+    let (from_front, to_front) = if explicit {
+      (Self::parse_end(&args[2])?, Self::parse_end(&args[3])?)
+    } else {
+      (false, true)
+    };
+
+    let moved = store.list_move(&args[0], &args[1], from_front, to_front)?;
+    Ok(moved.map_or(Value::Null, Value::BulkString))
+  }
+
+  /// Parses a list end, true for the head (`LEFT`).
+  fn parse_end(arg: &str) -> Result<bool> {
+    match arg.to_uppercase().as_str() {
+      "LEFT" => Ok(true),
+      "RIGHT" => Ok(false),
+      _ => bail!("syntax error"),
+    }
+  }
+}
+
+#[async_trait]
+impl Command for LMoveCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), self.explicit)
+  }
+}
@@ -0,0 +1,86 @@
+//! LPOP and RPOP command implementation.
+//!
+//! Removes and returns values from the head or tail of a list. Requires
+//! authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{memory::MemoryStore, notify::EventClass},
+};
+
+/// LPOP / RPOP command handler.
+///
+/// A list left without values is deleted.
+pub struct LPopCommand {
+  /// Pop from the head of the list (LPOP) instead of its tail (RPOP)
+  pub front: bool,
+}
+
+impl LPopCommand {
+  /// Executes the LPOP or RPOP command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then optionally the number of values to pop)
+  /// * `store` - Memory store to operate on
+  /// * `front` - Whether to pop from the head of the list
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::BulkString)` - The popped value, when no count was given
+  /// * `Ok(Value::Array)` - The popped values in pop order, at most the
+  ///   whole list, when a count was given
+  /// * `Ok(Value::Null)` - The key doesn't exist
+  /// * `Err` - Not authenticated, the count is invalid, or the key holds a
+  ///   value that is not a list
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LPOP mylist 2
+  /// let result = LPopCommand::execute(vec!["mylist".to_string(), "2".to_string()], store, true);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore, front: bool) -> Result<Value> {
+    let count = match args.get(1) {
+      Some(count) => {
+        let count = count
+          .parse::<i64>()
+          .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+        Some(
+          usize::try_from(count).map_err(|_| anyhow!("value is out of range, must be positive"))?,
+        )
+      }
+      None => None,
+    };
+
+    let popped = store
+      .list_pop(&args[0], count.unwrap_or(1), front)?
+      .map(|popped| {
+        popped
+          .into_iter()
+          .map(Value::BulkString)
+          .collect::<Vec<_>>()
+      });
+    if popped.as_ref().is_some_and(|popped| !popped.is_empty()) {
+      let event = if front { "lpop" } else { "rpop" };
+      store.notify(EventClass::List, event, &args[0]);
+    }
+
+    Ok(match (popped, count) {
+      (None, _) => Value::Null,
+      (Some(popped), Some(_)) => Value::Array(popped),
+      (Some(popped), None) => popped.into_iter().next().unwrap_or(Value::Null),
+    })
+  }
+}
+
+#[async_trait]
+impl Command for LPopCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), self.front)
+  }
+}
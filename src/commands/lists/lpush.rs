@@ -0,0 +1,56 @@
+//! LPUSH and RPUSH command implementation.
+//!
+//! Pushes values onto the head or tail of a list, creating it if needed.
+//! Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// LPUSH / RPUSH command handler.
+pub struct LPushCommand {
+  /// Push onto the head of the list (LPUSH) instead of its tail (RPUSH)
+  pub front: bool,
+}
+
+impl LPushCommand {
+  /// Executes the LPUSH or RPUSH command.
+  ///
+  /// LPUSH pushes the values one after the other, so they end up at the
+  /// head of the list in reverse order.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then the values to push)
+  /// * `store` - Memory store to operate on
+  /// * `front` - Whether to push onto the head of the list
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Length of the list after the push
+  /// * `Err` - Not authenticated, or the key holds a value that is not a list
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: RPUSH mylist a b c
+  /// let args = vec!["mylist", "a", "b", "c"];
+  /// let result = LPushCommand::execute(args.into_iter().map(String::from).collect(), store, false);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore, front: bool) -> Result<Value> {
+    let len = store.list_push(&args[0], &args[1..], front)?;
+    Ok(Value::Integer(len as i64))
+  }
+}
+
+#[async_trait]
+impl Command for LPushCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), self.front)
+  }
+}
@@ -0,0 +1,71 @@
+//! LRANGE command implementation.
+//!
+//! Returns the values of a list between two positions. Requires
+//! authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    general::getrange::{byte_range, parse_offset},
+    registry::{Command, CommandContext},
+  },
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// LRANGE command handler.
+///
+/// Positions are 0-based, negative positions count from the tail, -1 being
+/// the last value.
+pub struct LRangeCommand;
+
+impl LRangeCommand {
+  /// Executes the LRANGE command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, start and stop positions, both inclusive)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - The values in the range, empty if the key doesn't
+  ///   exist or the range is empty
+  /// * `Err` - Not authenticated, a position is not an integer, or the key
+  ///   holds a value that is not a list
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LRANGE mylist 0 -1
+  /// let args = vec!["mylist", "0", "-1"];
+  /// let result = LRangeCommand::execute(args.into_iter().map(String::from).collect(), store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let start = parse_offset(&args[1])?;
+    let stop = parse_offset(&args[2])?;
+
+    let values = store.inspect_list(&args[0], |list| {
+      // Positions resolve like string offsets
+      let Some(range) = byte_range(list.len(), start, stop) else {
+        return Vec::new();
+      };
+      list
+        .iter()
+        .skip(range.start)
+        .take(range.len())
+        .map(|value| Value::BulkString(value.clone()))
+        .collect()
+    })?;
+    Ok(Value::Array(values.unwrap_or_default()))
+  }
+}
+
+#[async_trait]
+impl Command for LRangeCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,88 @@
+//! LREM command implementation.
+//!
+//! Removes occurrences of a value from a list. Requires authentication.
+
+use std::collections::LinkedList;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    general::getrange::parse_offset,
+    registry::{Command, CommandContext},
+  },
+  resp::value::Value,
+  storage::{memory::MemoryStore, notify::EventClass},
+};
+
+/// LREM command handler.
+///
+/// A list left without values is deleted.
+pub struct LRemCommand;
+
+impl LRemCommand {
+  /// Executes the LREM command.
+  ///
+  /// A positive count removes that many occurrences starting from the head,
+  /// a negative one starting from the tail, and 0 removes all of them.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, count and the value to remove)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Number of values removed, 0 if the key doesn't exist
+  /// * `Err` - Not authenticated, the count is not an integer, or the key
+  ///   holds a value that is not a list
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LREM mylist -2 a
+  /// let args = vec!["mylist", "-2", "a"];
+  /// let result = LRemCommand::execute(args.into_iter().map(String::from).collect(), store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let count = parse_offset(&args[1])?;
+    let target = &args[2];
+    let limit = match count.unsigned_abs() {
+      0 => usize::MAX,
+      limit => usize::try_from(limit).unwrap_or(usize::MAX),
+    };
+
+    let removed = store.update_list(&args[0], None, |list| {
+      // Drain the list from the end the count starts at, keeping what stays
+      let mut kept = LinkedList::new();
+      let mut removed = 0;
+      while let Some(item) = if count < 0 {
+        list.pop_back()
+      } else {
+        list.pop_front()
+      } {
+        if removed < limit && item == *target {
+          removed += 1;
+        } else if count < 0 {
+          kept.push_front(item);
+        } else {
+          kept.push_back(item);
+        }
+      }
+      *list = kept;
+      removed
+    })?;
+    if removed.is_some_and(|removed| removed > 0) {
+      store.notify(EventClass::List, "lrem", &args[0]);
+    }
+    Ok(Value::Integer(removed.unwrap_or(0) as i64))
+  }
+}
+
+#[async_trait]
+impl Command for LRemCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
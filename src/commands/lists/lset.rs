@@ -0,0 +1,67 @@
+//! LSET command implementation.
+//!
+//! Replaces the value at a position in a list. Requires authentication.
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    general::getrange::parse_offset,
+    lists::lindex::resolve_index,
+    registry::{Command, CommandContext},
+  },
+  resp::value::Value,
+  storage::{memory::MemoryStore, notify::EventClass},
+};
+
+/// LSET command handler.
+pub struct LSetCommand;
+
+impl LSetCommand {
+  /// Executes the LSET command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, position and new value)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::SimpleString("OK"))` - The value was replaced
+  /// * `Err` - Not authenticated, the key doesn't exist, the position is out
+  ///   of range, or the key holds a value that is not a list
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: LSET mylist 0 first
+  /// let args = vec!["mylist", "0", "first"];
+  /// let result = LSetCommand::execute(args.into_iter().map(String::from).collect(), store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let index = parse_offset(&args[1])?;
+    let replaced = store.update_list(&args[0], Some(&args[2]), |list| {
+      let index = resolve_index(index, list.len())?;
+      let slot = list.iter_mut().nth(index)?;
+      *slot = args[2].clone();
+      Some(())
+    })?;
+
+    match replaced {
+      None => bail!("no such key"),
+      Some(None) => bail!("index out of range"),
+      Some(Some(())) => {
+        store.notify(EventClass::List, "lset", &args[0]);
+        Ok(Value::SimpleString("OK".to_string()))
+      }
+    }
+  }
+}
+
+#[async_trait]
+impl Command for LSetCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,19 @@
+//! List commands.
+//!
+//! This module contains the commands working on lists of strings:
+//! - `lpush` / `lpop`: Push or pop values at either end
+//! - `blpop`: Pop values, waiting for one to be pushed
+//! - `lrange` / `lindex` / `llen`: Read a list
+//! - `lset` / `linsert` / `lrem`: Change, insert or remove values inside a list
+//! - `lmove`: Move a value from one list to another
+
+pub mod blpop;
+pub mod lindex;
+pub mod linsert;
+pub mod llen;
+pub mod lmove;
+pub mod lpop;
+pub mod lpush;
+pub mod lrange;
+pub mod lrem;
+pub mod lset;
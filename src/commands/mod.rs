@@ -5,8 +5,24 @@
 //! - `acl`: Authentication and authorization commands
 //! - `executor`: Command execution and routing
 //! - `general`: General data manipulation commands (GET, SET, etc.)
+//! - `hashes`: Hash commands (HSET, HINCRBY, etc.)
+//! - `lists`: List commands (LPUSH, LINSERT, etc.)
+//! - `pubsub`: Publish/subscribe messaging commands
+//! - `registry`: Metadata for every implemented command
+//! - `sets`: Set commands (SADD, SINTER, etc.)
+//! - `stats`: Call counts and latency per command
+//! - `transaction`: Command queuing for MULTI/EXEC
+//! - `zsets`: Sorted set commands (ZADD, ZRANGE, etc.)
 
 pub mod acl;
 pub mod executor;
 pub mod general;
+pub mod hashes;
 pub mod kdb;
+pub mod lists;
+pub mod pubsub;
+pub mod registry;
+pub mod sets;
+pub mod stats;
+pub mod transaction;
+pub mod zsets;
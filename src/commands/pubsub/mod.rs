@@ -0,0 +1,10 @@
+//! Publish/subscribe commands.
+//!
+//! This module contains the commands used for channel based messaging:
+//! - `subscribe`: Listen for messages on one or more channels
+//! - `unsubscribe`: Stop listening on channels
+//! - `publish`: Send a message to every subscriber of a channel
+
+pub mod publish;
+pub mod subscribe;
+pub mod unsubscribe;
@@ -0,0 +1,50 @@
+//! PUBLISH command implementation.
+//!
+//! Sends a message to every subscriber of a channel. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::{memory::MemoryStore, pubsub::PubSub},
+};
+
+/// PUBLISH command handler.
+///
+/// Delivers a message to all connections subscribed to a channel.
+pub struct PublishCommand;
+
+impl PublishCommand {
+  /// Executes the PUBLISH command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Channel name and message
+  /// * `store` - Memory store used to check authentication
+  /// * `pubsub` - Shared channel registry
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - Integer count of clients that received the message
+  /// * `Err` - Error if not authenticated
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: PUBLISH news "hello"
+  /// let result = PublishCommand::execute(vec!["news".to_string(), "hello".to_string()], store, pubsub);
+  /// ```
+  pub fn execute(args: Vec<String>, _store: MemoryStore, pubsub: PubSub) -> Result<Value> {
+    let received = pubsub.publish(&args[0], Value::BulkString(args[1].clone()));
+    Ok(Value::Integer(received as i64))
+  }
+}
+
+#[async_trait]
+impl Command for PublishCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), ctx.pubsub.clone())
+  }
+}
@@ -0,0 +1,61 @@
+//! SUBSCRIBE command implementation.
+//!
+//! Subscribes the connection to one or more channels. Requires authentication.
+
+use anyhow::Result;
+
+use crate::{
+  resp::value::Value,
+  storage::{
+    memory::MemoryStore,
+    pubsub::{PubSub, Subscription},
+  },
+};
+
+/// SUBSCRIBE command handler.
+///
+/// Registers the connection as a listener on the given channels. Once
+/// subscribed, published messages are forwarded to the connection.
+pub struct SubscribeCommand;
+
+impl SubscribeCommand {
+  /// Executes the SUBSCRIBE command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Channels to subscribe to
+  /// * `store` - Memory store used to check authentication
+  /// * `pubsub` - Shared channel registry
+  /// * `subscription` - The connection's subscription state
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Vec<Value>)` - One confirmation per channel, written as an array
+  ///   to RESP2 connections
+  /// * `Err` - Error if not authenticated
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SUBSCRIBE news sports
+  /// // Replies: ["subscribe", "news", 1] and ["subscribe", "sports", 2]
+  /// ```
+  pub fn execute(
+    args: Vec<String>,
+    _store: MemoryStore,
+    pubsub: PubSub,
+    subscription: &mut Subscription,
+  ) -> Result<Vec<Value>> {
+    let mut replies = Vec::with_capacity(args.len());
+    for channel in args {
+      pubsub.subscribe(subscription, &channel);
+      replies.push(Value::Push(vec![
+        Value::BulkString("subscribe".to_string()),
+        Value::BulkString(channel),
+        Value::Integer(subscription.count() as i64),
+      ]));
+    }
+
+    Ok(replies)
+  }
+}
@@ -0,0 +1,70 @@
+//! UNSUBSCRIBE command implementation.
+//!
+//! Removes the connection from one or more channels.
+
+use anyhow::Result;
+
+use crate::{
+  resp::value::Value,
+  storage::pubsub::{PubSub, Subscription},
+};
+
+/// UNSUBSCRIBE command handler.
+///
+/// Stops delivering messages from the given channels, or from every
+/// channel when called without arguments.
+pub struct UnsubscribeCommand;
+
+impl UnsubscribeCommand {
+  /// Executes the UNSUBSCRIBE command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Channels to unsubscribe from (all channels if empty)
+  /// * `pubsub` - Shared channel registry
+  /// * `subscription` - The connection's subscription state
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Vec<Value>)` - One confirmation per channel, written as an array
+  ///   to RESP2 connections
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: UNSUBSCRIBE news
+  /// // Replies: ["unsubscribe", "news", 1]
+  /// ```
+  pub fn execute(
+    args: Vec<String>,
+    pubsub: PubSub,
+    subscription: &mut Subscription,
+  ) -> Result<Vec<Value>> {
+    let channels = if args.is_empty() {
+      subscription.channels()
+    } else {
+      args
+    };
+
+    // Nothing to unsubscribe from, still confirm like Redis does
+    if channels.is_empty() {
+      return Ok(vec![Value::Push(vec![
+        Value::BulkString("unsubscribe".to_string()),
+        Value::Null,
+        Value::Integer(0),
+      ])]);
+    }
+
+    let mut replies = Vec::with_capacity(channels.len());
+    for channel in channels {
+      pubsub.unsubscribe(subscription, &channel);
+      replies.push(Value::Push(vec![
+        Value::BulkString("unsubscribe".to_string()),
+        Value::BulkString(channel),
+        Value::Integer(subscription.count() as i64),
+      ]));
+    }
+
+    Ok(replies)
+  }
+}
@@ -0,0 +1,1470 @@
+//! Static table of every command the server implements.
+//!
+//! Routing, HELP and COMMAND all read from this table, so a command is only
+//! known to the server once it is listed here. Commands are run through the
+//! [`Command`] trait, and adding one means listing it in [`COMMANDS`]
+//! along with its handler.
+//!
+//! The table is also the security boundary for unauthenticated connections:
+//! they may only run the commands flagged `no_auth`. Likewise, the commands
+//! and subcommands reserved for root users are the ones marked `privileged`.
+//! Commands flagged `write` are the ones logged to the append-only file.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::{
+    acl::{
+      auth::AuthCommand, hello::HelloCommand, logout::LogoutCommand, passwd::PasswdCommand,
+      user::UserCommand, whoami::WhoAmi,
+    },
+    general::{
+      bitcount::BitCountCommand,
+      cadel::CaDelCommand,
+      cas::CasCommand,
+      client::ClientCommand,
+      command::CommandCommand,
+      config::ConfigCommand,
+      copy::CopyCommand,
+      debug::{DebugCommand, WaitCommand},
+      delete::DeleteCommand,
+      delpattern::DelPatternCommand,
+      dump::DumpCommand,
+      echo::EchoCommand,
+      expireat::ExpireAtCommand,
+      expiretime::ExpireTimeCommand,
+      get::GetCommand,
+      getbit::GetBitCommand,
+      getex::GetExCommand,
+      getrange::GetRangeCommand,
+      help::HelpCommand,
+      info::InfoCommand,
+      lolwut::LolwutCommand,
+      memory::MemoryCommand,
+      migrate::MigrateCommand,
+      move_key::MoveCommand,
+      msetnx::MSetNxCommand,
+      object::ObjectCommand,
+      ping::PingCommand,
+      restore::RestoreCommand,
+      select::SelectCommand,
+      set::SetCommand,
+      setbit::SetBitCommand,
+      setrange::SetRangeCommand,
+      slowlog::SlowlogCommand,
+      swapdb::SwapDbCommand,
+      touch::TouchCommand,
+      unlink::UnlinkCommand,
+      wait_key::WaitKeyCommand,
+    },
+    hashes::{
+      hdel::HDelCommand, hexists::HExistsCommand, hget::HGetCommand, hincrby::HIncrByCommand,
+      hkeys::HKeysCommand, hlen::HLenCommand, hmget::HMGetCommand, hset::HSetCommand,
+    },
+    kdb::{bgsave::BgSaveCommand, lastsave::LastSaveCommand, save::SaveCommand},
+    lists::{
+      blpop::BLPopCommand, lindex::LIndexCommand, linsert::LInsertCommand, llen::LLenCommand,
+      lmove::LMoveCommand, lpop::LPopCommand, lpush::LPushCommand, lrange::LRangeCommand,
+      lrem::LRemCommand, lset::LSetCommand,
+    },
+    pubsub::publish::PublishCommand,
+    sets::{
+      sadd::SAddCommand, scard::SCardCommand, setops::SetOpCommand, sismember::SIsMemberCommand,
+      smembers::SMembersCommand, spop::SPopCommand, srandmember::SRandMemberCommand,
+      srem::SRemCommand,
+    },
+    zsets::{
+      zadd::ZAddCommand, zincrby::ZIncrByCommand, zrange::ZRangeCommand,
+      zrangebyscore::ZRangeByScoreCommand, zrank::ZRankCommand, zscore::ZScoreCommand,
+    },
+  },
+  resp::value::Value,
+  storage::{
+    db::InternalDB,
+    memory::{MemoryStore, SetOp},
+    pubsub::PubSub,
+  },
+  utils::{client::Client, settings::SharedSettings},
+};
+
+/// Everything a command can read or change while it runs.
+pub struct CommandContext<'a> {
+  /// Arguments converted to strings
+  pub args: Vec<String>,
+  /// Arguments as they were received
+  pub raw_args: Vec<Value>,
+  /// The connection's memory store handle
+  pub store: &'a mut MemoryStore,
+  /// Database connection for persistent storage
+  pub db: &'a InternalDB,
+  /// Server settings
+  pub settings: &'a SharedSettings,
+  /// Shared pub/sub channel registry
+  pub pubsub: &'a PubSub,
+  /// State of the connection running the command
+  pub client: &'a mut Client,
+}
+
+/// A command that can be routed by the executor.
+#[async_trait]
+pub trait Command: Send + Sync {
+  /// Runs the command.
+  ///
+  /// # Arguments
+  ///
+  /// * `ctx` - Arguments and state the command runs against
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - The reply to send back
+  /// * `Err` - Error if the arguments are invalid or execution fails
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value>;
+}
+
+/// How a command is run.
+pub enum Handler {
+  /// Run by the executor itself, for the commands that change connection
+  /// state (MULTI, EXEC, DISCARD, RESET, SUBSCRIBE and UNSUBSCRIBE) or don't
+  /// reply (SHUTDOWN)
+  Executor,
+  /// Run through the [`Command`] trait
+  Command(&'static dyn Command),
+}
+
+/// Which invocations of a command only root users may run.
+pub enum Privileged {
+  /// Every authenticated user may run the command
+  Never,
+  /// Only root users may run the command
+  Always,
+  /// Only root users may run these subcommands, given upper-case
+  Subcommands(&'static [&'static str]),
+}
+
+impl Privileged {
+  /// Returns true if running the command with `subcommand`, upper-cased, as
+  /// its first argument needs a root user.
+  pub fn applies_to(&self, subcommand: Option<&str>) -> bool {
+    match self {
+      Privileged::Never => false,
+      Privileged::Always => true,
+      Privileged::Subcommands(names) => subcommand.is_some_and(|name| names.contains(&name)),
+    }
+  }
+}
+
+/// Metadata describing a single command.
+pub struct CommandSpec {
+  /// Upper-case command name
+  pub name: &'static str,
+  /// Minimum number of arguments, not counting the command name
+  pub min_args: usize,
+  /// Maximum number of arguments, or None if unbounded
+  pub max_args: Option<usize>,
+  /// Usage line shown by HELP
+  pub syntax: &'static str,
+  /// One-line description of the command
+  pub summary: &'static str,
+  /// Group the command belongs to
+  pub group: &'static str,
+  /// Whether the command may run before the connection is authenticated
+  pub no_auth: bool,
+  /// Whether the command may change the keyspace, these are logged to the AOF
+  pub write: bool,
+  /// Whether only root users may run the command
+  pub privileged: Privileged,
+  /// What runs the command
+  pub handler: Handler,
+}
+
+impl CommandSpec {
+  /// Checks whether the command accepts `count` arguments.
+  pub fn accepts(&self, count: usize) -> bool {
+    count >= self.min_args && self.max_args.is_none_or(|max| count <= max)
+  }
+
+  /// Returns the arity in the Redis convention: the number of arguments
+  /// including the command name, negative if that is only a minimum.
+  pub fn arity(&self) -> i64 {
+    let arity = self.min_args as i64 + 1;
+    match self.max_args {
+      Some(max) if max == self.min_args => arity,
+      _ => -arity,
+    }
+  }
+
+  /// Fails with the standard error if the command doesn't accept `count` arguments.
+  pub fn check_arity(&self, count: usize) -> Result<()> {
+    if !self.accepts(count) {
+      bail!(
+        "wrong number of arguments for '{}' command",
+        self.name.to_lowercase()
+      );
+    }
+    Ok(())
+  }
+}
+
+/// [`COMMANDS`] keyed by name, built once on first use.
+static INDEX: LazyLock<HashMap<&'static str, &'static CommandSpec>> =
+  LazyLock::new(|| COMMANDS.iter().map(|spec| (spec.name, spec)).collect());
+
+/// Every implemented command, in the order HELP lists them.
+pub const COMMANDS: &[CommandSpec] = &[
+  CommandSpec {
+    name: "PING",
+    min_args: 0,
+    max_args: Some(1),
+    syntax: "PING [<message>]",
+    summary: "Test connection",
+    group: "connection",
+    no_auth: true,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&PingCommand),
+  },
+  CommandSpec {
+    name: "ECHO",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "ECHO <message>",
+    summary: "Echo back a message",
+    group: "connection",
+    no_auth: true,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&EchoCommand),
+  },
+  CommandSpec {
+    name: "INFO",
+    min_args: 0,
+    max_args: Some(1),
+    syntax: "INFO [<section>]",
+    summary: "Show server statistics",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&InfoCommand),
+  },
+  CommandSpec {
+    name: "LOLWUT",
+    min_args: 0,
+    max_args: Some(2),
+    syntax: "LOLWUT [VERSION <version>]",
+    summary: "Show a banner with the server's name and version",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LolwutCommand),
+  },
+  CommandSpec {
+    name: "COMMAND",
+    min_args: 0,
+    max_args: None,
+    syntax: "COMMAND [COUNT | DOCS [<command> ...] | INFO <command> ...]",
+    summary: "Describe the available commands",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&CommandCommand),
+  },
+  CommandSpec {
+    name: "CLIENT",
+    min_args: 1,
+    max_args: None,
+    syntax: "CLIENT ID | GETNAME | SETNAME <name> | INFO | LIST | KILL ID <id>",
+    summary: "Identify and name connections, list or kill them",
+    group: "connection",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ClientCommand),
+  },
+  CommandSpec {
+    name: "CONFIG",
+    min_args: 1,
+    max_args: None,
+    syntax: "CONFIG GET <pattern> [<pattern> ...] | SET <parameter> <value> | RESETSTAT",
+    summary: "Read server parameters or change them at runtime",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Subcommands(&["SET", "RESETSTAT"]),
+    handler: Handler::Command(&ConfigCommand),
+  },
+  CommandSpec {
+    name: "GET",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "GET <key>",
+    summary: "Get value for key",
+    group: "string",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&GetCommand),
+  },
+  CommandSpec {
+    name: "SET",
+    min_args: 2,
+    max_args: None,
+    syntax: "SET <key> <value> [EX <seconds> | PX <milliseconds> | EXAT <unix-seconds> | PXAT <unix-milliseconds> | KEEPTTL] [NX | XX] [GET]",
+    summary: "Set key to value",
+    group: "string",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SetCommand),
+  },
+  CommandSpec {
+    name: "CAS",
+    min_args: 3,
+    max_args: Some(4),
+    syntax: "CAS <key> <expected | NX> <value> [KEEPTTL]",
+    summary: "Set key only if it holds the expected value",
+    group: "string",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&CasCommand),
+  },
+  CommandSpec {
+    name: "MSETNX",
+    min_args: 2,
+    max_args: None,
+    syntax: "MSETNX <key> <value> [<key> <value> ...]",
+    summary: "Set several keys only if none of them exists",
+    group: "string",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&MSetNxCommand),
+  },
+  CommandSpec {
+    name: "GETEX",
+    min_args: 1,
+    max_args: Some(3),
+    syntax: "GETEX <key> [EX <seconds> | PX <milliseconds> | EXAT <unix-seconds> | PXAT <unix-milliseconds> | PERSIST]",
+    summary: "Get value for key and optionally change its expiry",
+    group: "string",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&GetExCommand),
+  },
+  CommandSpec {
+    name: "GETRANGE",
+    min_args: 3,
+    max_args: Some(3),
+    syntax: "GETRANGE <key> <start> <end>",
+    summary: "Get a byte range of a string",
+    group: "string",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&GetRangeCommand),
+  },
+  CommandSpec {
+    name: "SETRANGE",
+    min_args: 3,
+    max_args: Some(3),
+    syntax: "SETRANGE <key> <offset> <value>",
+    summary: "Overwrite part of a string at an offset",
+    group: "string",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SetRangeCommand),
+  },
+  CommandSpec {
+    name: "SETBIT",
+    min_args: 3,
+    max_args: Some(3),
+    syntax: "SETBIT <key> <offset> 0|1",
+    summary: "Set or clear a bit of a string",
+    group: "bitmap",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SetBitCommand),
+  },
+  CommandSpec {
+    name: "GETBIT",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "GETBIT <key> <offset>",
+    summary: "Get a bit of a string",
+    group: "bitmap",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&GetBitCommand),
+  },
+  CommandSpec {
+    name: "BITCOUNT",
+    min_args: 1,
+    max_args: Some(3),
+    syntax: "BITCOUNT <key> [<start> <end>]",
+    summary: "Count the set bits of a string",
+    group: "bitmap",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&BitCountCommand),
+  },
+  CommandSpec {
+    name: "DEL",
+    min_args: 1,
+    max_args: None,
+    syntax: "DEL <key> [<key> ...]",
+    summary: "Delete keys",
+    group: "generic",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&DeleteCommand),
+  },
+  CommandSpec {
+    name: "CADEL",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "CADEL <key> <expected>",
+    summary: "Delete key only if it holds the expected value",
+    group: "string",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&CaDelCommand),
+  },
+  CommandSpec {
+    name: "DELPATTERN",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "DELPATTERN <pattern>",
+    summary: "Delete every key matching a glob pattern, slow on large keyspaces",
+    group: "generic",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Always,
+    handler: Handler::Command(&DelPatternCommand),
+  },
+  CommandSpec {
+    name: "UNLINK",
+    min_args: 1,
+    max_args: None,
+    syntax: "UNLINK <key> [<key> ...]",
+    summary: "Delete keys, freeing large values in the background",
+    group: "generic",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&UnlinkCommand),
+  },
+  CommandSpec {
+    name: "SELECT",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "SELECT <index>",
+    summary: "Switch the connection to another database",
+    group: "connection",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SelectCommand),
+  },
+  CommandSpec {
+    name: "SWAPDB",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "SWAPDB <index> <index>",
+    summary: "Swap the contents of two databases",
+    group: "server",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SwapDbCommand),
+  },
+  CommandSpec {
+    name: "MOVE",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "MOVE <key> <index>",
+    summary: "Move a key to another database",
+    group: "generic",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&MoveCommand),
+  },
+  CommandSpec {
+    name: "COPY",
+    min_args: 2,
+    max_args: Some(5),
+    syntax: "COPY <src> <dst> [DB <index>] [REPLACE]",
+    summary: "Copy a key",
+    group: "generic",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&CopyCommand),
+  },
+  CommandSpec {
+    name: "DUMP",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "DUMP <key>",
+    summary: "Serialize the value of a key",
+    group: "generic",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&DumpCommand),
+  },
+  CommandSpec {
+    name: "RESTORE",
+    min_args: 3,
+    max_args: Some(4),
+    syntax: "RESTORE <key> <ttl-milliseconds> <payload> [REPLACE]",
+    summary: "Create a key from a value serialized by DUMP",
+    group: "generic",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&RestoreCommand),
+  },
+  CommandSpec {
+    name: "MIGRATE",
+    min_args: 5,
+    max_args: None,
+    syntax: "MIGRATE <host> <port> <key> <destination-db> <timeout-milliseconds> [COPY] [REPLACE] [AUTH <password> | AUTH2 <username> <password>]",
+    summary: "Move a key to another instance",
+    group: "generic",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Always,
+    handler: Handler::Command(&MigrateCommand),
+  },
+  CommandSpec {
+    name: "EXPIREAT",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "EXPIREAT <key> <unix-time-seconds>",
+    summary: "Expire a key at a Unix time in seconds",
+    group: "generic",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ExpireAtCommand { millis: false }),
+  },
+  CommandSpec {
+    name: "PEXPIREAT",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "PEXPIREAT <key> <unix-time-milliseconds>",
+    summary: "Expire a key at a Unix time in milliseconds",
+    group: "generic",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ExpireAtCommand { millis: true }),
+  },
+  CommandSpec {
+    name: "EXPIRETIME",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "EXPIRETIME <key>",
+    summary: "Get the Unix time in seconds a key expires at",
+    group: "generic",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ExpireTimeCommand { millis: false }),
+  },
+  CommandSpec {
+    name: "PEXPIRETIME",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "PEXPIRETIME <key>",
+    summary: "Get the Unix time in milliseconds a key expires at",
+    group: "generic",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ExpireTimeCommand { millis: true }),
+  },
+  CommandSpec {
+    name: "HSET",
+    min_args: 3,
+    max_args: None,
+    syntax: "HSET <key> <field> <value> [<field> <value> ...]",
+    summary: "Set fields of a hash",
+    group: "hash",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HSetCommand),
+  },
+  CommandSpec {
+    name: "HGET",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "HGET <key> <field>",
+    summary: "Get the value of a hash field",
+    group: "hash",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HGetCommand),
+  },
+  CommandSpec {
+    name: "HDEL",
+    min_args: 2,
+    max_args: None,
+    syntax: "HDEL <key> <field> [<field> ...]",
+    summary: "Remove fields from a hash",
+    group: "hash",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HDelCommand),
+  },
+  CommandSpec {
+    name: "HINCRBY",
+    min_args: 3,
+    max_args: Some(3),
+    syntax: "HINCRBY <key> <field> <increment>",
+    summary: "Add an integer to a hash field",
+    group: "hash",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HIncrByCommand { float: false }),
+  },
+  CommandSpec {
+    name: "HINCRBYFLOAT",
+    min_args: 3,
+    max_args: Some(3),
+    syntax: "HINCRBYFLOAT <key> <field> <increment>",
+    summary: "Add a float to a hash field",
+    group: "hash",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HIncrByCommand { float: true }),
+  },
+  CommandSpec {
+    name: "HMGET",
+    min_args: 2,
+    max_args: None,
+    syntax: "HMGET <key> <field> [<field> ...]",
+    summary: "Get the values of several hash fields",
+    group: "hash",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HMGetCommand),
+  },
+  CommandSpec {
+    name: "HKEYS",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "HKEYS <key>",
+    summary: "Get all field names of a hash",
+    group: "hash",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HKeysCommand { values: false }),
+  },
+  CommandSpec {
+    name: "HVALS",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "HVALS <key>",
+    summary: "Get all values of a hash",
+    group: "hash",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HKeysCommand { values: true }),
+  },
+  CommandSpec {
+    name: "HLEN",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "HLEN <key>",
+    summary: "Get the number of fields of a hash",
+    group: "hash",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HLenCommand),
+  },
+  CommandSpec {
+    name: "HEXISTS",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "HEXISTS <key> <field>",
+    summary: "Check whether a hash has a field",
+    group: "hash",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HExistsCommand),
+  },
+  CommandSpec {
+    name: "LPUSH",
+    min_args: 2,
+    max_args: None,
+    syntax: "LPUSH <key> <value> [<value> ...]",
+    summary: "Push values onto the head of a list",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LPushCommand { front: true }),
+  },
+  CommandSpec {
+    name: "RPUSH",
+    min_args: 2,
+    max_args: None,
+    syntax: "RPUSH <key> <value> [<value> ...]",
+    summary: "Push values onto the tail of a list",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LPushCommand { front: false }),
+  },
+  CommandSpec {
+    name: "LPOP",
+    min_args: 1,
+    max_args: Some(2),
+    syntax: "LPOP <key> [<count>]",
+    summary: "Remove and get values from the head of a list",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LPopCommand { front: true }),
+  },
+  CommandSpec {
+    name: "RPOP",
+    min_args: 1,
+    max_args: Some(2),
+    syntax: "RPOP <key> [<count>]",
+    summary: "Remove and get values from the tail of a list",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LPopCommand { front: false }),
+  },
+  CommandSpec {
+    name: "BLPOP",
+    min_args: 2,
+    max_args: None,
+    syntax: "BLPOP <key> [<key> ...] <timeout>",
+    summary: "Remove and get a value from the head of a list, waiting for one",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&BLPopCommand { front: true }),
+  },
+  CommandSpec {
+    name: "BRPOP",
+    min_args: 2,
+    max_args: None,
+    syntax: "BRPOP <key> [<key> ...] <timeout>",
+    summary: "Remove and get a value from the tail of a list, waiting for one",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&BLPopCommand { front: false }),
+  },
+  CommandSpec {
+    name: "LRANGE",
+    min_args: 3,
+    max_args: Some(3),
+    syntax: "LRANGE <key> <start> <stop>",
+    summary: "Get list values by position",
+    group: "list",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LRangeCommand),
+  },
+  CommandSpec {
+    name: "LLEN",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "LLEN <key>",
+    summary: "Get the length of a list",
+    group: "list",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LLenCommand),
+  },
+  CommandSpec {
+    name: "LINDEX",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "LINDEX <key> <index>",
+    summary: "Get the list value at a position",
+    group: "list",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LIndexCommand),
+  },
+  CommandSpec {
+    name: "LSET",
+    min_args: 3,
+    max_args: Some(3),
+    syntax: "LSET <key> <index> <value>",
+    summary: "Replace the list value at a position",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LSetCommand),
+  },
+  CommandSpec {
+    name: "LINSERT",
+    min_args: 4,
+    max_args: Some(4),
+    syntax: "LINSERT <key> BEFORE|AFTER <pivot> <value>",
+    summary: "Insert a value before or after another one in a list",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LInsertCommand),
+  },
+  CommandSpec {
+    name: "LREM",
+    min_args: 3,
+    max_args: Some(3),
+    syntax: "LREM <key> <count> <value>",
+    summary: "Remove occurrences of a value from a list",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LRemCommand),
+  },
+  CommandSpec {
+    name: "LMOVE",
+    min_args: 4,
+    max_args: Some(4),
+    syntax: "LMOVE <source> <destination> LEFT|RIGHT LEFT|RIGHT",
+    summary: "Pop a value from one list and push it onto another",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LMoveCommand { explicit: true }),
+  },
+  CommandSpec {
+    name: "RPOPLPUSH",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "RPOPLPUSH <source> <destination>",
+    summary: "Pop a value from the tail of a list and push it onto the head of another",
+    group: "list",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LMoveCommand { explicit: false }),
+  },
+  CommandSpec {
+    name: "SADD",
+    min_args: 2,
+    max_args: None,
+    syntax: "SADD <key> <member> [<member> ...]",
+    summary: "Add members to a set",
+    group: "set",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SAddCommand),
+  },
+  CommandSpec {
+    name: "SREM",
+    min_args: 2,
+    max_args: None,
+    syntax: "SREM <key> <member> [<member> ...]",
+    summary: "Remove members from a set",
+    group: "set",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SRemCommand),
+  },
+  CommandSpec {
+    name: "SMEMBERS",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "SMEMBERS <key>",
+    summary: "Get all members of a set",
+    group: "set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SMembersCommand),
+  },
+  CommandSpec {
+    name: "SISMEMBER",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "SISMEMBER <key> <member>",
+    summary: "Check whether a member is in a set",
+    group: "set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SIsMemberCommand),
+  },
+  CommandSpec {
+    name: "SCARD",
+    min_args: 1,
+    max_args: Some(1),
+    syntax: "SCARD <key>",
+    summary: "Get the number of members of a set",
+    group: "set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SCardCommand),
+  },
+  CommandSpec {
+    name: "SPOP",
+    min_args: 1,
+    max_args: Some(2),
+    syntax: "SPOP <key> [<count>]",
+    summary: "Remove and return random members of a set",
+    group: "set",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SPopCommand),
+  },
+  CommandSpec {
+    name: "SRANDMEMBER",
+    min_args: 1,
+    max_args: Some(2),
+    syntax: "SRANDMEMBER <key> [<count>]",
+    summary: "Get random members of a set",
+    group: "set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SRandMemberCommand),
+  },
+  CommandSpec {
+    name: "SINTER",
+    min_args: 1,
+    max_args: None,
+    syntax: "SINTER <key> [<key> ...]",
+    summary: "Intersect sets",
+    group: "set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SetOpCommand {
+      op: SetOp::Inter,
+      store: false,
+    }),
+  },
+  CommandSpec {
+    name: "SUNION",
+    min_args: 1,
+    max_args: None,
+    syntax: "SUNION <key> [<key> ...]",
+    summary: "Union sets",
+    group: "set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SetOpCommand {
+      op: SetOp::Union,
+      store: false,
+    }),
+  },
+  CommandSpec {
+    name: "SDIFF",
+    min_args: 1,
+    max_args: None,
+    syntax: "SDIFF <key> [<key> ...]",
+    summary: "Subtract sets from the first one",
+    group: "set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SetOpCommand {
+      op: SetOp::Diff,
+      store: false,
+    }),
+  },
+  CommandSpec {
+    name: "SINTERSTORE",
+    min_args: 2,
+    max_args: None,
+    syntax: "SINTERSTORE <destination> <key> [<key> ...]",
+    summary: "Intersect sets and store the result",
+    group: "set",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SetOpCommand {
+      op: SetOp::Inter,
+      store: true,
+    }),
+  },
+  CommandSpec {
+    name: "SUNIONSTORE",
+    min_args: 2,
+    max_args: None,
+    syntax: "SUNIONSTORE <destination> <key> [<key> ...]",
+    summary: "Union sets and store the result",
+    group: "set",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SetOpCommand {
+      op: SetOp::Union,
+      store: true,
+    }),
+  },
+  CommandSpec {
+    name: "SDIFFSTORE",
+    min_args: 2,
+    max_args: None,
+    syntax: "SDIFFSTORE <destination> <key> [<key> ...]",
+    summary: "Subtract sets from the first one and store the result",
+    group: "set",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&SetOpCommand {
+      op: SetOp::Diff,
+      store: true,
+    }),
+  },
+  CommandSpec {
+    name: "ZADD",
+    min_args: 3,
+    max_args: None,
+    syntax: "ZADD <key> <score> <member> [<score> <member> ...]",
+    summary: "Add members to a sorted set or update their scores",
+    group: "sorted-set",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ZAddCommand),
+  },
+  CommandSpec {
+    name: "ZSCORE",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "ZSCORE <key> <member>",
+    summary: "Get the score of a sorted set member",
+    group: "sorted-set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ZScoreCommand),
+  },
+  CommandSpec {
+    name: "ZRANGE",
+    min_args: 3,
+    max_args: Some(4),
+    syntax: "ZRANGE <key> <start> <stop> [WITHSCORES]",
+    summary: "Get sorted set members by position",
+    group: "sorted-set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ZRangeCommand),
+  },
+  CommandSpec {
+    name: "ZRANK",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "ZRANK <key> <member>",
+    summary: "Get the position of a sorted set member",
+    group: "sorted-set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ZRankCommand),
+  },
+  CommandSpec {
+    name: "ZRANGEBYSCORE",
+    min_args: 3,
+    max_args: Some(7),
+    syntax: "ZRANGEBYSCORE <key> <min> <max> [WITHSCORES] [LIMIT <offset> <count>]",
+    summary: "Get sorted set members with a score in a range",
+    group: "sorted-set",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ZRangeByScoreCommand),
+  },
+  CommandSpec {
+    name: "ZINCRBY",
+    min_args: 3,
+    max_args: Some(3),
+    syntax: "ZINCRBY <key> <increment> <member>",
+    summary: "Add to the score of a sorted set member",
+    group: "sorted-set",
+    no_auth: false,
+    write: true,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ZIncrByCommand),
+  },
+  CommandSpec {
+    name: "TOUCH",
+    min_args: 1,
+    max_args: None,
+    syntax: "TOUCH <key> [key ...]",
+    summary: "Mark keys as recently used",
+    group: "generic",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&TouchCommand),
+  },
+  CommandSpec {
+    name: "WAITKEY",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "WAITKEY <key> <timeout>",
+    summary: "Block until a key exists or the timeout in seconds elapses",
+    group: "generic",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&WaitKeyCommand),
+  },
+  CommandSpec {
+    name: "OBJECT",
+    min_args: 1,
+    max_args: None,
+    syntax: "OBJECT IDLETIME | ENCODING | REFCOUNT <key>",
+    summary: "Inspect how a key is stored",
+    group: "generic",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&ObjectCommand),
+  },
+  CommandSpec {
+    name: "MEMORY",
+    min_args: 1,
+    max_args: None,
+    syntax: "MEMORY USAGE <key> [SAMPLES <count>]",
+    summary: "Estimate the bytes a key and its value take up",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&MemoryCommand),
+  },
+  CommandSpec {
+    name: "AUTH",
+    min_args: 1,
+    max_args: Some(2),
+    syntax: "AUTH [username] <password>",
+    summary: "Authenticate the connection",
+    group: "connection",
+    no_auth: true,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&AuthCommand),
+  },
+  CommandSpec {
+    name: "HELLO",
+    min_args: 0,
+    max_args: None,
+    syntax: "HELLO [<protover> [AUTH <username> <password>] [SETNAME <name>]]",
+    summary: "Handshake with the server, optionally authenticating",
+    group: "connection",
+    no_auth: true,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HelloCommand),
+  },
+  CommandSpec {
+    name: "WHOAMI",
+    min_args: 0,
+    max_args: Some(0),
+    syntax: "WHOAMI",
+    summary: "Show the authenticated user",
+    group: "connection",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&WhoAmi),
+  },
+  CommandSpec {
+    name: "USER",
+    min_args: 2,
+    max_args: None,
+    syntax: "USER ADD <username> <password> | DEL <username>",
+    summary: "Add or delete users (root only)",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Always,
+    handler: Handler::Command(&UserCommand),
+  },
+  CommandSpec {
+    name: "PASSWD",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "PASSWD <old password> <new password>",
+    summary: "Change the authenticated user's password",
+    group: "connection",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&PasswdCommand),
+  },
+  CommandSpec {
+    name: "LOGOUT",
+    min_args: 0,
+    max_args: Some(0),
+    syntax: "LOGOUT",
+    summary: "Drop the connection's authentication",
+    group: "connection",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LogoutCommand),
+  },
+  CommandSpec {
+    name: "RESET",
+    min_args: 0,
+    max_args: Some(0),
+    syntax: "RESET",
+    summary: "Reset the connection to its initial state",
+    group: "connection",
+    no_auth: true,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Executor,
+  },
+  CommandSpec {
+    name: "QUIT",
+    min_args: 0,
+    max_args: Some(0),
+    syntax: "QUIT",
+    summary: "Close the connection",
+    group: "connection",
+    no_auth: true,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Executor,
+  },
+  CommandSpec {
+    name: "SUBSCRIBE",
+    min_args: 1,
+    max_args: None,
+    syntax: "SUBSCRIBE <channel> [<channel> ...]",
+    summary: "Listen for messages",
+    group: "pubsub",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Executor,
+  },
+  CommandSpec {
+    name: "UNSUBSCRIBE",
+    min_args: 0,
+    max_args: None,
+    syntax: "UNSUBSCRIBE [<channel> ...]",
+    summary: "Stop listening on channels",
+    group: "pubsub",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Executor,
+  },
+  CommandSpec {
+    name: "PUBLISH",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "PUBLISH <channel> <message>",
+    summary: "Send a message to a channel",
+    group: "pubsub",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&PublishCommand),
+  },
+  CommandSpec {
+    name: "MULTI",
+    min_args: 0,
+    max_args: Some(0),
+    syntax: "MULTI",
+    summary: "Start queuing commands for a transaction",
+    group: "transactions",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Executor,
+  },
+  CommandSpec {
+    name: "EXEC",
+    min_args: 0,
+    max_args: Some(0),
+    syntax: "EXEC",
+    summary: "Run all queued commands",
+    group: "transactions",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Executor,
+  },
+  CommandSpec {
+    name: "DISCARD",
+    min_args: 0,
+    max_args: Some(0),
+    syntax: "DISCARD",
+    summary: "Drop all queued commands",
+    group: "transactions",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Executor,
+  },
+  CommandSpec {
+    name: "SAVE",
+    min_args: 0,
+    max_args: Some(0),
+    syntax: "SAVE",
+    summary: "Persist all data to the KDB file",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Always,
+    handler: Handler::Command(&SaveCommand),
+  },
+  CommandSpec {
+    name: "BGSAVE",
+    min_args: 0,
+    max_args: Some(0),
+    syntax: "BGSAVE",
+    summary: "Persist all data in the background",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Always,
+    handler: Handler::Command(&BgSaveCommand),
+  },
+  CommandSpec {
+    name: "LASTSAVE",
+    min_args: 0,
+    max_args: Some(0),
+    syntax: "LASTSAVE",
+    summary: "Unix time of the last successful save",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&LastSaveCommand),
+  },
+  CommandSpec {
+    name: "SHUTDOWN",
+    min_args: 0,
+    max_args: Some(1),
+    syntax: "SHUTDOWN [SAVE | NOSAVE]",
+    summary: "Stop the server, saving data first unless NOSAVE is given",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Always,
+    handler: Handler::Executor,
+  },
+  CommandSpec {
+    name: "WAIT",
+    min_args: 2,
+    max_args: Some(2),
+    syntax: "WAIT <numreplicas> <timeout>",
+    summary: "Wait for replicas, always 0 as there are none",
+    group: "generic",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&WaitCommand),
+  },
+  CommandSpec {
+    name: "DEBUG",
+    min_args: 1,
+    max_args: None,
+    syntax: "DEBUG SLEEP <seconds> | DEBUG OBJECT <key>",
+    summary: "Debugging helpers",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Subcommands(&["OBJECT"]),
+    handler: Handler::Command(&DebugCommand),
+  },
+  CommandSpec {
+    name: "SLOWLOG",
+    min_args: 1,
+    max_args: None,
+    syntax: "SLOWLOG GET [<count>] | LEN | RESET",
+    summary: "Read or clear the log of slow commands (root only)",
+    group: "server",
+    no_auth: false,
+    write: false,
+    privileged: Privileged::Always,
+    handler: Handler::Command(&SlowlogCommand),
+  },
+  CommandSpec {
+    name: "HELP",
+    min_args: 0,
+    max_args: None,
+    syntax: "HELP",
+    summary: "Show this help",
+    group: "server",
+    no_auth: true,
+    write: false,
+    privileged: Privileged::Never,
+    handler: Handler::Command(&HelpCommand),
+  },
+];
+
+/// Looks up a command by its upper-case name.
+///
+/// # Returns
+///
+/// * `Some(&CommandSpec)` - The command's metadata
+/// * `None` - If no such command is implemented
+pub fn lookup(name: &str) -> Option<&'static CommandSpec> {
+  INDEX.get(name).copied()
+}
+
+/// Looks up the handler of a command by its upper-case name.
+///
+/// # Returns
+///
+/// * `Some(&dyn Command)` - The command's handler
+/// * `None` - If no such command is implemented, or the executor runs it
+pub fn handler(name: &str) -> Option<&'static dyn Command> {
+  match lookup(name)?.handler {
+    Handler::Command(handler) => Some(handler),
+    Handler::Executor => None,
+  }
+}
@@ -0,0 +1,17 @@
+//! Set commands.
+//!
+//! This module contains the commands working on sets of unique strings:
+//! - `sadd` / `srem`: Add or remove members
+//! - `smembers` / `sismember`: Read the members of a set
+//! - `scard`: Count the members of a set
+//! - `spop` / `srandmember`: Pop or pick random members
+//! - `setops`: Intersect, union or subtract sets, optionally storing the result
+
+pub mod sadd;
+pub mod scard;
+pub mod setops;
+pub mod sismember;
+pub mod smembers;
+pub mod spop;
+pub mod srandmember;
+pub mod srem;
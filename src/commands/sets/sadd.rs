@@ -0,0 +1,48 @@
+//! SADD command implementation.
+//!
+//! Adds members to a set, creating it if needed. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// SADD command handler.
+pub struct SAddCommand;
+
+impl SAddCommand {
+  /// Executes the SADD command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then the members to add)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Number of members that were not in the set yet
+  /// * `Err` - Not authenticated, or the key holds a value that is not a set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SADD myset a b
+  /// let args = vec!["myset".to_string(), "a".to_string(), "b".to_string()];
+  /// let result = SAddCommand::execute(args, store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let added = store.set_add(&args[0], &args[1..])?;
+    Ok(Value::Integer(added as i64))
+  }
+}
+
+#[async_trait]
+impl Command for SAddCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,47 @@
+//! SCARD command implementation.
+//!
+//! Returns the number of members of a set. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// SCARD command handler.
+pub struct SCardCommand;
+
+impl SCardCommand {
+  /// Executes the SCARD command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key of the set)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Number of members, 0 if the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SCARD myset
+  /// let result = SCardCommand::execute(vec!["myset".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let count = store.set_len(&args[0])?;
+    Ok(Value::Integer(count as i64))
+  }
+}
+
+#[async_trait]
+impl Command for SCardCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,73 @@
+//! SINTER, SUNION and SDIFF command implementations, and their STORE
+//! variants.
+//!
+//! Combine the sets of several keys. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::{MemoryStore, SetOp},
+};
+
+/// SINTER / SUNION / SDIFF command handler, and SINTERSTORE /
+/// SUNIONSTORE / SDIFFSTORE when `store` is set.
+///
+/// Missing keys count as empty sets.
+pub struct SetOpCommand {
+  /// How the sets are combined
+  pub op: SetOp,
+  /// Whether the result is stored at the first argument rather than returned
+  pub store: bool,
+}
+
+impl SetOpCommand {
+  /// Executes the command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - The keys holding the sets, preceded by the destination key
+  ///   for the STORE variants
+  /// * `store` - Memory store to operate on
+  /// * `op` - How the sets are combined
+  /// * `store_result` - Whether the result is stored rather than returned
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - Members of the resulting set
+  /// * `Ok(Value::Integer)` - Number of members stored, for the STORE variants
+  /// * `Err` - Not authenticated, or a key holds a value that is not a set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SINTERSTORE both set1 set2
+  /// let args = vec!["both".to_string(), "set1".to_string(), "set2".to_string()];
+  /// let result = SetOpCommand::execute(args, store, SetOp::Inter, true);
+  /// ```
+  pub fn execute(
+    args: Vec<String>,
+    store: MemoryStore,
+    op: SetOp,
+    store_result: bool,
+  ) -> Result<Value> {
+    if store_result {
+      let count = store.set_combine_store(op, &args[0], &args[1..])?;
+      return Ok(Value::Integer(count as i64));
+    }
+
+    let members = store.set_combine(op, &args)?;
+    Ok(Value::Array(
+      members.into_iter().map(Value::BulkString).collect(),
+    ))
+  }
+}
+
+#[async_trait]
+impl Command for SetOpCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned(), self.op, self.store)
+  }
+}
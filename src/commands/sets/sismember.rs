@@ -0,0 +1,48 @@
+//! SISMEMBER command implementation.
+//!
+//! Checks whether a member is in a set. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// SISMEMBER command handler.
+pub struct SIsMemberCommand;
+
+impl SIsMemberCommand {
+  /// Executes the SISMEMBER command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and member)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer(1))` - The member is in the set
+  /// * `Ok(Value::Integer(0))` - The member or the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SISMEMBER myset a
+  /// let result = SIsMemberCommand::execute(vec!["myset".to_string(), "a".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let found = store.set_contains(&args[0], &args[1])?;
+    Ok(Value::Integer(found as i64))
+  }
+}
+
+#[async_trait]
+impl Command for SIsMemberCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,52 @@
+//! SMEMBERS command implementation.
+//!
+//! Returns every member of a set. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::{MemoryStore, SetOp},
+};
+
+/// SMEMBERS command handler.
+///
+/// Members are returned in no particular order.
+pub struct SMembersCommand;
+
+impl SMembersCommand {
+  /// Executes the SMEMBERS command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key of the set)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - The members, empty if the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SMEMBERS myset
+  /// let result = SMembersCommand::execute(vec!["myset".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    // The union of a single set is all of its members
+    let members = store.set_combine(SetOp::Union, &args[..1])?;
+    Ok(Value::Array(
+      members.into_iter().map(Value::BulkString).collect(),
+    ))
+  }
+}
+
+#[async_trait]
+impl Command for SMembersCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,69 @@
+//! SPOP command implementation.
+//!
+//! Removes and returns random members of a set. Requires authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// SPOP command handler.
+///
+/// A set left without members is deleted.
+pub struct SPopCommand;
+
+impl SPopCommand {
+  /// Executes the SPOP command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then optionally the number of members to pop)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::BulkString)` - The popped member, when no count was given
+  /// * `Ok(Value::Null)` - The key doesn't exist and no count was given
+  /// * `Ok(Value::Array)` - The popped members, at most the whole set, when a count was given
+  /// * `Err` - Not authenticated, the count is invalid, or the key holds a
+  ///   value that is not a set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SPOP myset 2
+  /// let result = SPopCommand::execute(vec!["myset".to_string(), "2".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let Some(count) = args.get(1) else {
+      let popped = store.set_pop(&args[0], 1)?;
+      return Ok(
+        popped
+          .into_iter()
+          .next()
+          .map_or(Value::Null, Value::BulkString),
+      );
+    };
+    let count = count
+      .parse::<i64>()
+      .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+    let count =
+      usize::try_from(count).map_err(|_| anyhow!("value is out of range, must be positive"))?;
+
+    let popped = store.set_pop(&args[0], count)?;
+    Ok(Value::Array(
+      popped.into_iter().map(Value::BulkString).collect(),
+    ))
+  }
+}
+
+#[async_trait]
+impl Command for SPopCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,69 @@
+//! SRANDMEMBER command implementation.
+//!
+//! Returns random members of a set without removing them. Requires
+//! authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// SRANDMEMBER command handler.
+pub struct SRandMemberCommand;
+
+impl SRandMemberCommand {
+  /// Executes the SRANDMEMBER command.
+  ///
+  /// A positive count returns distinct members, at most the whole set. A
+  /// negative count returns exactly that many members, possibly repeated.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then optionally the number of members)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::BulkString)` - A random member, when no count was given
+  /// * `Ok(Value::Null)` - The key doesn't exist and no count was given
+  /// * `Ok(Value::Array)` - The picked members, when a count was given
+  /// * `Err` - Not authenticated, the count is not an integer, or the key
+  ///   holds a value that is not a set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SRANDMEMBER myset -5
+  /// let result = SRandMemberCommand::execute(vec!["myset".to_string(), "-5".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let Some(count) = args.get(1) else {
+      let picked = store.set_random(&args[0], 1, true)?;
+      return Ok(
+        picked
+          .into_iter()
+          .next()
+          .map_or(Value::Null, Value::BulkString),
+      );
+    };
+    let count = count
+      .parse::<i64>()
+      .map_err(|_| anyhow!("value is not an integer or out of range"))?;
+
+    let picked = store.set_random(&args[0], count.unsigned_abs() as usize, count >= 0)?;
+    Ok(Value::Array(
+      picked.into_iter().map(Value::BulkString).collect(),
+    ))
+  }
+}
+
+#[async_trait]
+impl Command for SRandMemberCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,49 @@
+//! SREM command implementation.
+//!
+//! Removes members from a set. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// SREM command handler.
+///
+/// A set left without members is deleted.
+pub struct SRemCommand;
+
+impl SRemCommand {
+  /// Executes the SREM command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then the members to remove)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Number of members that were removed
+  /// * `Err` - Not authenticated, or the key holds a value that is not a set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: SREM myset a
+  /// let result = SRemCommand::execute(vec!["myset".to_string(), "a".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let removed = store.set_remove(&args[0], &args[1..])?;
+    Ok(Value::Integer(removed as i64))
+  }
+}
+
+#[async_trait]
+impl Command for SRemCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
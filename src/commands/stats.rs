@@ -0,0 +1,250 @@
+//! Per-command call statistics and the slow log.
+//!
+//! The executor times every command it runs and adds the latency here,
+//! keyed by command name. `INFO commandstats` reports the totals and
+//! `CONFIG RESETSTAT` zeroes them. Commands running longer than
+//! `server.slowlog.threshold_micros` are also kept in a bounded log, read
+//! with `SLOWLOG`.
+
+use std::{
+  collections::{HashMap, VecDeque},
+  sync::{
+    LazyLock, Mutex,
+    atomic::{AtomicU64, Ordering},
+  },
+  time::{Duration, SystemTime},
+};
+
+use crate::{resp::value::Value, utils::settings::SlowlogSettings};
+
+/// Most arguments kept per slow log entry, counting the command name.
+const SLOWLOG_MAX_ARGS: usize = 32;
+
+/// Most characters kept per slow log argument.
+const SLOWLOG_MAX_ARG_LEN: usize = 128;
+
+/// Commands whose arguments hold credentials, kept out of the slow log and
+/// the server log.
+const REDACTED_COMMANDS: [&str; 5] = ["AUTH", "HELLO", "USER", "PASSWD", "MIGRATE"];
+
+/// Totals for every command run since startup or the last reset.
+static COMMAND_STATS: LazyLock<Mutex<HashMap<String, CommandStat>>> =
+  LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Call count and total latency of a single command.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommandStat {
+  /// Number of times the command ran
+  pub calls: u64,
+  /// Total time spent running it, in microseconds
+  pub usec: u64,
+}
+
+impl CommandStat {
+  /// Average time per call, in microseconds.
+  pub fn usec_per_call(&self) -> f64 {
+    if self.calls == 0 {
+      return 0.0;
+    }
+    self.usec as f64 / self.calls as f64
+  }
+}
+
+/// Adds one call of `command` taking `elapsed`.
+///
+/// Only allocates the first time a command name is seen.
+///
+/// # Arguments
+///
+/// * `command` - Upper-case command name
+/// * `elapsed` - How long the command took to run
+pub fn record(command: &str, elapsed: Duration) {
+  let usec = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+  let mut stats = COMMAND_STATS.lock().unwrap();
+  let stat = match stats.get_mut(command) {
+    Some(stat) => stat,
+    None => stats.entry(command.to_string()).or_default(),
+  };
+  stat.calls += 1;
+  stat.usec = stat.usec.saturating_add(usec);
+}
+
+/// Returns the totals of every command that ran, sorted by name.
+pub fn snapshot() -> Vec<(String, CommandStat)> {
+  let mut stats: Vec<_> = COMMAND_STATS
+    .lock()
+    .unwrap()
+    .iter()
+    .map(|(name, stat)| (name.clone(), *stat))
+    .collect();
+  stats.sort_by(|a, b| a.0.cmp(&b.0));
+  stats
+}
+
+/// Forgets every recorded call.
+pub fn reset() {
+  COMMAND_STATS.lock().unwrap().clear();
+}
+
+/// Slow commands, newest first, as configured at startup.
+static SLOWLOG: LazyLock<Mutex<SlowLog>> = LazyLock::new(|| Mutex::new(SlowLog::default()));
+
+/// Microseconds a command must run to be logged, `u64::MAX` while the slow
+/// log is disabled. Kept outside the lock so fast commands never take it.
+static SLOWLOG_THRESHOLD: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// A command that ran longer than the slow log threshold.
+#[derive(Debug, Clone)]
+pub struct SlowEntry {
+  /// Unique id, increasing in the order entries were logged
+  pub id: u64,
+  /// Unix time the command finished at, in seconds
+  pub timestamp: u64,
+  /// Time the command took to run, in microseconds
+  pub usec: u64,
+  /// Command name and arguments, truncated
+  pub args: Vec<String>,
+  /// Address of the client that ran it
+  pub addr: String,
+  /// Name of the client that ran it, empty if none was set
+  pub name: String,
+}
+
+/// Bounded log of slow commands.
+#[derive(Debug, Default)]
+struct SlowLog {
+  /// Logged entries, newest first
+  entries: VecDeque<SlowEntry>,
+  /// Id the next entry gets, not reset by `SLOWLOG RESET`
+  next_id: u64,
+  /// Number of entries kept
+  max_len: usize,
+}
+
+/// Applies the `server.slowlog` settings. Called once at startup.
+///
+/// # Arguments
+///
+/// * `settings` - Threshold and length of the slow log
+pub fn configure_slowlog(settings: &SlowlogSettings) {
+  let mut slowlog = SLOWLOG.lock().unwrap();
+  slowlog.max_len = settings.max_len;
+  slowlog.entries.truncate(settings.max_len);
+
+  let threshold = match settings.max_len {
+    0 => u64::MAX,
+    _ => settings.threshold_micros,
+  };
+  SLOWLOG_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+/// Returns true if commands may be slow logged at all.
+///
+/// Lets the executor skip copying arguments while the slow log is disabled.
+pub fn slowlog_enabled() -> bool {
+  SLOWLOG_THRESHOLD.load(Ordering::Relaxed) != u64::MAX
+}
+
+/// Copies a command's arguments for the slow log, truncated as in Redis.
+///
+/// Arguments of commands carrying credentials are replaced, so passwords
+/// never end up in the log.
+///
+/// # Arguments
+///
+/// * `command` - Upper-case command name
+/// * `args` - Arguments as they were received
+pub fn slowlog_args(command: &str, args: &[Value]) -> Vec<String> {
+  let mut argv = vec![command.to_string()];
+  if REDACTED_COMMANDS.contains(&command) {
+    argv.push("(redacted)".to_string());
+    return argv;
+  }
+
+  let kept = args.len().min(SLOWLOG_MAX_ARGS - 1);
+  // The last slot says how many were left out instead
+  let kept = if kept < args.len() { kept - 1 } else { kept };
+  argv.extend(args[..kept].iter().map(truncate_arg));
+  if kept < args.len() {
+    argv.push(format!("... ({} more arguments)", args.len() - kept));
+  }
+  argv
+}
+
+/// Renders a single argument, keeping at most [`SLOWLOG_MAX_ARG_LEN`] bytes.
+fn truncate_arg(arg: &Value) -> String {
+  let (kept, end, total) = match arg {
+    Value::SimpleString(s) | Value::BulkString(s) => {
+      // Cut on a character boundary, so the result stays valid UTF-8
+      let end = (0..=s.len().min(SLOWLOG_MAX_ARG_LEN))
+        .rev()
+        .find(|&end| s.is_char_boundary(end))
+        .unwrap_or(0);
+      (s[..end].to_string(), end, s.len())
+    }
+    Value::BulkBytes(bytes) => {
+      let end = bytes.len().min(SLOWLOG_MAX_ARG_LEN);
+      let kept = String::from_utf8_lossy(&bytes[..end]).into_owned();
+      (kept, end, bytes.len())
+    }
+    Value::Integer(i) => return i.to_string(),
+    Value::Boolean(b) => return b.to_string(),
+    _ => return String::new(),
+  };
+
+  match total - end {
+    0 => kept,
+    more => format!("{}... ({} more bytes)", kept, more),
+  }
+}
+
+/// Logs a command if it ran longer than the threshold.
+///
+/// # Arguments
+///
+/// * `elapsed` - How long the command took to run
+/// * `args` - Command name and arguments, from [`slowlog_args`]
+/// * `addr` - Address of the client that ran it
+/// * `name` - Name of the client that ran it, if one was set
+pub fn log_if_slow(elapsed: Duration, args: Vec<String>, addr: &str, name: Option<&str>) {
+  let usec = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+  if usec < SLOWLOG_THRESHOLD.load(Ordering::Relaxed) {
+    return;
+  }
+
+  let timestamp = SystemTime::now()
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .map(|since| since.as_secs())
+    .unwrap_or(0);
+
+  let mut slowlog = SLOWLOG.lock().unwrap();
+  let entry = SlowEntry {
+    id: slowlog.next_id,
+    timestamp,
+    usec,
+    args,
+    addr: addr.to_string(),
+    name: name.unwrap_or_default().to_string(),
+  };
+  slowlog.next_id += 1;
+  slowlog.entries.push_front(entry);
+  let max_len = slowlog.max_len;
+  slowlog.entries.truncate(max_len);
+}
+
+/// Returns up to `count` of the newest slow log entries, or all of them with None.
+pub fn slowlog_entries(count: Option<usize>) -> Vec<SlowEntry> {
+  let slowlog = SLOWLOG.lock().unwrap();
+  let count = count.unwrap_or(slowlog.entries.len());
+  slowlog.entries.iter().take(count).cloned().collect()
+}
+
+/// Returns the number of entries in the slow log.
+pub fn slowlog_len() -> usize {
+  SLOWLOG.lock().unwrap().entries.len()
+}
+
+/// Drops every slow log entry.
+pub fn slowlog_reset() {
+  SLOWLOG.lock().unwrap().entries.clear();
+}
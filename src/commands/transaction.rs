@@ -0,0 +1,45 @@
+//! Transaction state for MULTI/EXEC/DISCARD.
+//!
+//! Once a connection issues MULTI, every following command is queued here
+//! instead of being executed, until EXEC runs the queue or DISCARD drops it.
+
+use crate::resp::value::Value;
+
+/// A command waiting to be executed by EXEC.
+pub type QueuedCommand = (String, Vec<Value>);
+
+/// Per-connection transaction buffer.
+#[derive(Debug, Default)]
+pub struct Transaction {
+  /// Commands queued since MULTI, in arrival order
+  queue: Vec<QueuedCommand>,
+  /// Set when a command failed to queue, so EXEC must abort
+  aborted: bool,
+}
+
+impl Transaction {
+  /// Creates an empty transaction.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a command to the end of the queue.
+  pub fn queue(&mut self, command: &str, args: Vec<Value>) {
+    self.queue.push((command.to_string(), args));
+  }
+
+  /// Marks the transaction as failed because a command could not be queued.
+  pub fn abort(&mut self) {
+    self.aborted = true;
+  }
+
+  /// Returns true if a command failed to queue.
+  pub fn is_aborted(&self) -> bool {
+    self.aborted
+  }
+
+  /// Consumes the transaction, returning the queued commands in order.
+  pub fn into_commands(self) -> Vec<QueuedCommand> {
+    self.queue
+  }
+}
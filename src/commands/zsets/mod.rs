@@ -0,0 +1,13 @@
+//! Sorted set commands.
+//!
+//! This module contains the commands working on sets ordered by score:
+//! - `zadd` / `zincrby`: Add members or change their scores
+//! - `zscore` / `zrank`: Look up a member's score or position
+//! - `zrange` / `zrangebyscore`: Read members by position or score
+
+pub mod zadd;
+pub mod zincrby;
+pub mod zrange;
+pub mod zrangebyscore;
+pub mod zrank;
+pub mod zscore;
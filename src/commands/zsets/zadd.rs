@@ -0,0 +1,67 @@
+//! ZADD command implementation.
+//!
+//! Adds members to a sorted set, creating it if needed. Requires
+//! authentication.
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  ds::zset::KvSortedSet,
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// ZADD command handler.
+///
+/// Members already in the set move to their new score.
+pub struct ZAddCommand;
+
+impl ZAddCommand {
+  /// Executes the ZADD command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, then score and member pairs)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - Number of members that were not in the set yet
+  /// * `Err` - Not authenticated, a score is missing or not a float, or the
+  ///   key holds a value that is not a sorted set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: ZADD board 10 alice 20 bob
+  /// let args = vec!["board", "10", "alice", "20", "bob"];
+  /// let result = ZAddCommand::execute(args.into_iter().map(String::from).collect(), store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    if args.len().is_multiple_of(2) {
+      bail!("wrong number of arguments for 'zadd' command");
+    }
+
+    // Nothing is added unless every score is valid
+    let members = args[1..]
+      .chunks(2)
+      .map(|pair| {
+        let score = KvSortedSet::parse_score(&pair[0])
+          .ok_or_else(|| anyhow!("value is not a valid float"))?;
+        Ok((pair[1].clone(), score))
+      })
+      .collect::<Result<Vec<_>>>()?;
+
+    let added = store.zset_add(&args[0], members)?;
+    Ok(Value::Integer(added as i64))
+  }
+}
+
+#[async_trait]
+impl Command for ZAddCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
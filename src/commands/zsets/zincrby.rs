@@ -0,0 +1,58 @@
+//! ZINCRBY command implementation.
+//!
+//! Adds to the score of a sorted set member. Requires authentication.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  ds::zset::KvSortedSet,
+  resp::value::{Value, format_double},
+  storage::memory::MemoryStore,
+};
+
+/// ZINCRBY command handler.
+///
+/// Reading and updating the score happen under one lock, so concurrent
+/// increments are never lost.
+pub struct ZIncrByCommand;
+
+impl ZIncrByCommand {
+  /// Executes the ZINCRBY command.
+  ///
+  /// The set and the member are created if needed, a new member starts at
+  /// a score of 0.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, increment and member)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::BulkString)` - The member's new score
+  /// * `Err` - Not authenticated, the increment is not a float, the new score
+  ///   would be NaN, or the key holds a value that is not a sorted set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: ZINCRBY board 2.5 alice
+  /// let args = vec!["board", "2.5", "alice"];
+  /// let result = ZIncrByCommand::execute(args.into_iter().map(String::from).collect(), store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let delta =
+      KvSortedSet::parse_score(&args[1]).ok_or_else(|| anyhow!("value is not a valid float"))?;
+    let score = store.zset_incr(&args[0], &args[2], delta)?;
+    Ok(Value::BulkString(format_double(score)))
+  }
+}
+
+#[async_trait]
+impl Command for ZIncrByCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
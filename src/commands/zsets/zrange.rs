@@ -0,0 +1,93 @@
+//! ZRANGE command implementation.
+//!
+//! Returns the members of a sorted set between two positions. Requires
+//! authentication.
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// ZRANGE command handler.
+///
+/// Positions are 0-based and count from the lowest score. Negative
+/// positions count from the end, -1 being the last member.
+pub struct ZRangeCommand;
+
+impl ZRangeCommand {
+  /// Executes the ZRANGE command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key, start and stop positions, both
+  ///   inclusive, then optionally `WITHSCORES`)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - The members in order, each followed by its score
+  ///   if `WITHSCORES` was given. Empty if the key doesn't exist or the range is empty
+  /// * `Err` - Not authenticated, a position is not an integer, or the key
+  ///   holds a value that is not a sorted set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: ZRANGE board 0 -1 WITHSCORES
+  /// let args = vec!["board", "0", "-1", "WITHSCORES"];
+  /// let result = ZRangeCommand::execute(args.into_iter().map(String::from).collect(), store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let (Ok(start), Ok(stop)) = (args[1].parse::<i64>(), args[2].parse::<i64>()) else {
+      bail!("value is not an integer or out of range");
+    };
+    let with_scores = match args.get(3) {
+      None => false,
+      Some(option) if option.eq_ignore_ascii_case("WITHSCORES") => true,
+      Some(_) => bail!("syntax error"),
+    };
+
+    let members = store.inspect_zset(&args[0], |set| {
+      let Some((start, stop)) = Self::clamp(start, stop, set.len()) else {
+        return Vec::new();
+      };
+      let mut members = Vec::new();
+      for (member, score) in set.iter().skip(start).take(stop - start + 1) {
+        members.push(Value::BulkString(member.clone()));
+        if with_scores {
+          members.push(Value::Double(score));
+        }
+      }
+      members
+    })?;
+    Ok(Value::Array(members.unwrap_or_default()))
+  }
+
+  /// Resolves a start and stop position against a set of `len` members.
+  ///
+  /// # Returns
+  ///
+  /// * `Some((start, stop))` - The first and last position, both within the set
+  /// * `None` - If the range holds no members
+  fn clamp(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let resolve = |index: i64| if index < 0 { index + len } else { index };
+    let start = resolve(start).max(0);
+    let stop = resolve(stop).min(len - 1);
+    if start > stop {
+      return None;
+    }
+    Some((start as usize, stop as usize))
+  }
+}
+
+#[async_trait]
+impl Command for ZRangeCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -0,0 +1,127 @@
+//! ZRANGEBYSCORE command implementation.
+//!
+//! Returns the members of a sorted set whose score lies in a range.
+//! Requires authentication.
+
+use std::ops::Bound;
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  ds::zset::KvSortedSet,
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// ZRANGEBYSCORE command handler.
+///
+/// Only the scores within the range are visited, members outside of it
+/// cost nothing.
+pub struct ZRangeByScoreCommand;
+
+/// Options given after the score range.
+#[derive(Default)]
+struct RangeOptions {
+  /// Follow every member with its score
+  with_scores: bool,
+  /// Number of matching members to skip
+  offset: usize,
+  /// Maximum number of members to return, None for all of them
+  count: Option<usize>,
+}
+
+impl RangeOptions {
+  /// Parses the options given after the score range.
+  fn parse(args: &[String]) -> Result<Self> {
+    let mut options = Self::default();
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+      match option.to_uppercase().as_str() {
+        "WITHSCORES" => options.with_scores = true,
+        "LIMIT" => {
+          let (Some(offset), Some(count)) = (args.next(), args.next()) else {
+            bail!("syntax error");
+          };
+          let (Ok(offset), Ok(count)) = (offset.parse::<i64>(), count.parse::<i64>()) else {
+            bail!("value is not an integer or out of range");
+          };
+          // A negative offset matches nothing, a negative count everything
+          options.offset = usize::try_from(offset).unwrap_or(usize::MAX);
+          options.count = usize::try_from(count).ok();
+        }
+        _ => bail!("syntax error"),
+      }
+    }
+    Ok(options)
+  }
+}
+
+impl ZRangeByScoreCommand {
+  /// Executes the ZRANGEBYSCORE command.
+  ///
+  /// Supports the following options after the range:
+  /// * `WITHSCORES` - Follow every member with its score
+  /// * `LIMIT <offset> <count>` - Skip `offset` matching members and return
+  ///   at most `count` of the rest, all of them if `count` is negative
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments: key, minimum and maximum score, followed
+  ///   by the options. Scores may be `-inf` or `+inf`, and are exclusive
+  ///   when prefixed with `(`
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Array)` - The matching members in order, each followed by
+  ///   its score if `WITHSCORES` was given. Empty if the key doesn't exist
+  /// * `Err` - Not authenticated, invalid arguments, or the key holds a value
+  ///   that is not a sorted set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: ZRANGEBYSCORE board (10 +inf LIMIT 0 5
+  /// let args = vec!["board", "(10", "+inf", "LIMIT", "0", "5"];
+  /// let result = ZRangeByScoreCommand::execute(args.into_iter().map(String::from).collect(), store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let (Some(min), Some(max)) = (Self::parse_bound(&args[1]), Self::parse_bound(&args[2])) else {
+      bail!("min or max is not a float");
+    };
+    let options = RangeOptions::parse(&args[3..])?;
+
+    let members = store.inspect_zset(&args[0], |set| {
+      let mut members = Vec::new();
+      let matching = set
+        .range_by_score(min, max)
+        .skip(options.offset)
+        .take(options.count.unwrap_or(usize::MAX));
+      for (member, score) in matching {
+        members.push(Value::BulkString(member.clone()));
+        if options.with_scores {
+          members.push(Value::Double(score));
+        }
+      }
+      members
+    })?;
+    Ok(Value::Array(members.unwrap_or_default()))
+  }
+
+  /// Parses a score bound, exclusive if prefixed with `(`.
+  fn parse_bound(arg: &str) -> Option<Bound<f64>> {
+    match arg.strip_prefix('(') {
+      Some(score) => KvSortedSet::parse_score(score).map(Bound::Excluded),
+      None => KvSortedSet::parse_score(arg).map(Bound::Included),
+    }
+  }
+}
+
+#[async_trait]
+impl Command for ZRangeByScoreCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
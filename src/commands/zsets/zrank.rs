@@ -0,0 +1,55 @@
+//! ZRANK command implementation.
+//!
+//! Returns the position of a member in a sorted set. Requires
+//! authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// ZRANK command handler.
+///
+/// Ranks are 0-based and count from the lowest score.
+pub struct ZRankCommand;
+
+impl ZRankCommand {
+  /// Executes the ZRANK command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and member)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Integer)` - The member's rank
+  /// * `Ok(Value::Null)` - The member or the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a sorted set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: ZRANK board alice
+  /// let result = ZRankCommand::execute(vec!["board".to_string(), "alice".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let rank = store.inspect_zset(&args[0], |set| set.rank(&args[1]))?;
+    Ok(
+      rank
+        .flatten()
+        .map_or(Value::Null, |rank| Value::Integer(rank as i64)),
+    )
+  }
+}
+
+#[async_trait]
+impl Command for ZRankCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
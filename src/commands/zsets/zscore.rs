@@ -0,0 +1,48 @@
+//! ZSCORE command implementation.
+//!
+//! Returns the score of a sorted set member. Requires authentication.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+  commands::registry::{Command, CommandContext},
+  resp::value::Value,
+  storage::memory::MemoryStore,
+};
+
+/// ZSCORE command handler.
+pub struct ZScoreCommand;
+
+impl ZScoreCommand {
+  /// Executes the ZSCORE command.
+  ///
+  /// # Arguments
+  ///
+  /// * `args` - Command arguments (key and member)
+  /// * `store` - Memory store to operate on
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value::Double)` - The member's score
+  /// * `Ok(Value::Null)` - The member or the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a sorted set
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// // Client sends: ZSCORE board alice
+  /// let result = ZScoreCommand::execute(vec!["board".to_string(), "alice".to_string()], store);
+  /// ```
+  pub fn execute(args: Vec<String>, store: MemoryStore) -> Result<Value> {
+    let score = store.inspect_zset(&args[0], |set| set.score(&args[1]))?;
+    Ok(score.flatten().map_or(Value::Null, Value::Double))
+  }
+}
+
+#[async_trait]
+impl Command for ZScoreCommand {
+  async fn execute(&self, ctx: CommandContext<'_>) -> Result<Value> {
+    Self::execute(ctx.args, ctx.store.to_owned())
+  }
+}
@@ -2,6 +2,7 @@ use super::Entity;
 use crate::resp::value::Value;
 use std::collections::HashMap;
 
+#[derive(Default)]
 pub struct KvHashMap {
   data: HashMap<String, String>,
 }
@@ -9,6 +9,7 @@ use std::collections::HashSet;
 /// A set data structure implementing the Entity trait.
 ///
 /// Stores unique string values and provides set operations.
+#[derive(Default)]
 pub struct KvSet {
   /// The internal HashSet containing the data
   data: HashSet<String>,
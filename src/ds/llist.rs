@@ -7,6 +7,7 @@ use crate::resp::value::Value;
 use std::collections::LinkedList;
 
 /// A linked list data structure implementing the Entity trait.
+#[derive(Default)]
 pub struct KvLinkedList {
   /// The internal LinkedList containing the data
   data: LinkedList<String>,
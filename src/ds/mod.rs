@@ -8,6 +8,7 @@ use crate::resp::value::Value;
 pub mod hmap;
 pub mod hset;
 pub mod llist;
+pub mod zset;
 
 /// Common interface for data structure entities.
 ///
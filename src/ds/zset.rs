@@ -0,0 +1,202 @@
+//! Sorted set data structure implementation.
+//!
+//! Provides a set of unique members ordered by a floating point score,
+//! which implements the Entity trait.
+
+use super::Entity;
+use crate::resp::value::Value;
+use ordered_float::OrderedFloat;
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap},
+  ops::Bound,
+};
+
+/// A sorted set data structure implementing the Entity trait.
+///
+/// Members are ordered by score, members with the same score by their
+/// bytes. Scores are never NaN.
+#[derive(Clone, Debug, Default)]
+pub struct KvSortedSet {
+  /// Members grouped by score, in order
+  by_score: BTreeMap<OrderedFloat<f64>, BTreeSet<String>>,
+  /// Score of every member
+  scores: HashMap<String, f64>,
+}
+
+impl KvSortedSet {
+  /// Creates a new empty sorted set.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Parses a score, accepting `inf`, `+inf` and `-inf` but not NaN.
+  ///
+  /// # Returns
+  ///
+  /// * `Some(f64)` - The score
+  /// * `None` - If `s` is not a valid score
+  pub fn parse_score(s: &str) -> Option<f64> {
+    s.parse::<f64>().ok().filter(|score| !score.is_nan())
+  }
+
+  /// Returns the number of members.
+  pub fn len(&self) -> usize {
+    self.scores.len()
+  }
+
+  /// Returns true if the set has no members.
+  pub fn is_empty(&self) -> bool {
+    self.scores.is_empty()
+  }
+
+  /// Adds a member, or moves it to a new score if it already exists.
+  ///
+  /// # Arguments
+  ///
+  /// * `member` - The member to add
+  /// * `score` - The member's score, must not be NaN
+  ///
+  /// # Returns
+  ///
+  /// * `true` - The member is new
+  /// * `false` - The member existed, only its score was updated
+  pub fn insert(&mut self, member: String, score: f64) -> bool {
+    let existed = self.remove(&member).is_some();
+    self
+      .by_score
+      .entry(OrderedFloat(score))
+      .or_default()
+      .insert(member.clone());
+    self.scores.insert(member, score);
+    !existed
+  }
+
+  /// Removes a member.
+  ///
+  /// # Returns
+  ///
+  /// * `Some(f64)` - The removed member's score
+  /// * `None` - If the member wasn't in the set
+  pub fn remove(&mut self, member: &str) -> Option<f64> {
+    let score = self.scores.remove(member)?;
+    if let Some(members) = self.by_score.get_mut(&OrderedFloat(score)) {
+      members.remove(member);
+      if members.is_empty() {
+        self.by_score.remove(&OrderedFloat(score));
+      }
+    }
+    Some(score)
+  }
+
+  /// Returns the score of a member, if it is in the set.
+  pub fn score(&self, member: &str) -> Option<f64> {
+    self.scores.get(member).copied()
+  }
+
+  /// Returns the 0-based position of a member in score order, if it is in
+  /// the set.
+  pub fn rank(&self, member: &str) -> Option<usize> {
+    let score = OrderedFloat(self.score(member)?);
+    let below: usize = self
+      .by_score
+      .range(..score)
+      .map(|(_, members)| members.len())
+      .sum();
+    let tied = self.by_score[&score]
+      .iter()
+      .take_while(|tied| tied.as_str() < member)
+      .count();
+    Some(below + tied)
+  }
+
+  /// Iterates over the members whose score lies between two bounds, in
+  /// order. Only the scores within the bounds are visited.
+  ///
+  /// # Arguments
+  ///
+  /// * `min` - Lowest score to include
+  /// * `max` - Highest score to include
+  pub fn range_by_score(
+    &self,
+    min: Bound<f64>,
+    max: Bound<f64>,
+  ) -> impl Iterator<Item = (&String, f64)> {
+    // BTreeMap::range panics on reversed bounds instead of returning nothing
+    let empty = match (min, max) {
+      (Bound::Included(min), Bound::Included(max)) => min > max,
+      (
+        Bound::Included(min) | Bound::Excluded(min),
+        Bound::Included(max) | Bound::Excluded(max),
+      ) => min >= max,
+      _ => false,
+    };
+    let range = (!empty).then(|| {
+      self
+        .by_score
+        .range((min.map(OrderedFloat), max.map(OrderedFloat)))
+    });
+    range
+      .into_iter()
+      .flatten()
+      .flat_map(|(score, members)| members.iter().map(|member| (member, score.0)))
+  }
+
+  /// Iterates over the members and their scores in order.
+  pub fn iter(&self) -> impl Iterator<Item = (&String, f64)> {
+    self
+      .by_score
+      .iter()
+      .flat_map(|(score, members)| members.iter().map(|member| (member, score.0)))
+  }
+}
+
+impl Entity for KvSortedSet {
+  /// Returns the type name of this entity.
+  fn entity_type(&self) -> &'static str {
+    "zset"
+  }
+
+  /// Gets the score of a member.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The member to look up
+  ///
+  /// # Returns
+  ///
+  /// * `Some(Value::Double)` - The member's score
+  /// * `None` - If the member isn't in the set
+  fn get(&self, key: &str) -> Option<Value> {
+    self.score(key).map(Value::Double)
+  }
+
+  /// Adds a member with the given score.
+  ///
+  /// Values other than a non-NaN Double are ignored.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The member to add
+  /// * `value` - The member's score
+  fn set(&mut self, key: &str, value: Value) {
+    if let Value::Double(score) = value
+      && !score.is_nan()
+    {
+      self.insert(key.to_string(), score);
+    }
+  }
+
+  /// Removes a member.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The member to remove
+  ///
+  /// # Returns
+  ///
+  /// * `Some(Value::Double)` - The removed member's score
+  /// * `None` - If the member wasn't in the set
+  fn delete(&mut self, key: &str) -> Option<Value> {
+    self.remove(key).map(Value::Double)
+  }
+}
@@ -0,0 +1,14 @@
+//! RustyKV, a Redis-like key-value store speaking the RESP protocol.
+//!
+//! The server binary is a thin layer over this library, which also lets the
+//! integration tests in `tests/` drive connections without opening sockets.
+
+// Guards of std locks block the worker thread, they must never be held
+// across an await
+#![deny(clippy::await_holding_lock)]
+
+pub mod commands;
+pub mod ds;
+pub mod resp;
+pub mod storage;
+pub mod utils;
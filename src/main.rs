@@ -4,43 +4,187 @@
 //! It supports basic Redis commands and authentication, with data stored in memory
 //! and user credentials persisted in SQLite.
 
+// Guards of std locks block the worker thread, they must never be held
+// across an await
+#![deny(clippy::await_holding_lock)]
+
 // External dependencies
+use std::{
+  sync::{Arc, RwLock},
+  time::{Duration, Instant},
+};
+
 use log::{error, info, warn};
-use tokio::net::TcpListener;
+use tokio::{signal, sync::mpsc, task::JoinSet};
 
 // Local dependencies
-mod commands;
-mod ds;
-mod resp;
-mod storage;
-mod utils;
+use rusty_kv_store::commands::{executor::CommandExecutor, stats};
+use rusty_kv_store::storage::aof;
+use rusty_kv_store::storage::db::InternalDB;
+use rusty_kv_store::storage::kdb;
+use rusty_kv_store::storage::memory::MemoryStore;
+use rusty_kv_store::storage::notify::Notifier;
+use rusty_kv_store::storage::pubsub::PubSub;
+use rusty_kv_store::utils::{
+  client::{Client, ClientRegistry},
+  listener::Listener,
+  logger::Logger,
+  metrics,
+  network::NetworkUtils,
+  settings::{Settings, SharedSettings},
+  shutdown::{ShutdownMode, ShutdownTrigger},
+  tls,
+};
+
+/// Configuration file read at startup and again on SIGHUP.
+const CONFIG_FILE: &str = "config.toml";
 
-use storage::db::InternalDB;
-use storage::memory::{MemoryStore, Store};
-use utils::{logger::Logger, network::NetworkUtils, settings::Settings};
+/// How long shutdown waits for connections to finish their current command.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
 
 /// Main entry point function.
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
+  // Load configuration first, it decides how logging is set up
+  let (settings, load_error) = match Settings::load(CONFIG_FILE) {
+    Ok(settings) => (settings, None),
+    Err(e) => (Settings::default(), Some(e)),
+  };
+
   // Set up logging
-  Logger::setup();
+  Logger::setup(&settings.server.logging);
 
   info!("Initializing RustyKV server...");
+  match load_error {
+    None => info!("Loaded settings from {}", CONFIG_FILE),
+    Some(e) => error!("{:#}, using the default settings", e),
+  }
 
-  // Load configuration
-  let settings = Settings::new(Some("config.toml"));
-  info!("Loaded settings from config.toml");
+  // Start logging commands slower than the configured threshold
+  stats::configure_slowlog(&settings.server.slowlog);
+
+  // Connections share a copy of the settings CONFIG SET can change
+  let shared_settings: SharedSettings = Arc::new(RwLock::new(settings.clone()));
 
   warn!("Starting RustyKV server...");
 
-  // Initialize the global memory store
-  let memory_store = MemoryStore::new();
+  // Initialize the pub/sub channel registry shared by all connections
+  let pubsub = PubSub::new();
+
+  // Initialize the global memory store, with a background task dropping
+  // large values removed by UNLINK. Keyspace notifications go to pub/sub
+  let (reclaim_queue, mut reclaimed) = mpsc::unbounded_channel();
+  let notifier = Notifier::new(pubsub.clone(), settings.server.notify.keyspace_events);
+  let memory_store = MemoryStore::new(settings.server.network.databases)
+    .with_reclaim_queue(reclaim_queue)
+    .with_quota(settings.server.network.quota())
+    .with_eviction(settings.server.network.eviction())
+    .with_notifier(notifier);
+  tokio::spawn(async move {
+    while let Some(value) = reclaimed.recv().await {
+      drop(value);
+    }
+  });
   info!("Initialized global memory store");
 
+  // Re-read the configuration file whenever the process gets SIGHUP
+  #[cfg(unix)]
+  tokio::spawn(reload_on_sighup(
+    shared_settings.clone(),
+    memory_store.clone(),
+  ));
+
+  // Initialize the registry of live connections, used by CLIENT LIST and KILL
+  let clients = ClientRegistry::new();
+
+  // Initialize the trigger SHUTDOWN uses to stop the server
+  let shutdown_trigger = ShutdownTrigger::new();
+
   // Initialize the internal database for persistence
   warn!("Initializing internal database...");
   let internal_db = InternalDB::new(&settings);
 
+  // Restore the memory store from the KDB file before accepting connections,
+  // unless the AOF has entries, it then holds every write since it was created
+  let replay_aof = settings.server.aof.enabled && aof::has_entries(&settings.server.aof.path);
+  if settings.server.kdb.persistence && !replay_aof {
+    warn!("Loading KDB file...");
+    // Refuse to start rather than overwrite the file with an empty snapshot
+    if let Err(e) = kdb::load(&memory_store, &settings) {
+      error!("Failed to load KDB file: {:#}", e);
+      std::process::exit(1);
+    }
+  }
+
+  // Rebuild the memory store from the AOF, then log every write to it
+  if settings.server.aof.enabled {
+    if replay_aof {
+      warn!("Replaying AOF...");
+      // Replay runs as root, so it may run every command the file holds
+      let mut client = Client::new("aof".to_string(), ClientRegistry::new());
+      client.set_root(true);
+      let executor = CommandExecutor::new(
+        memory_store.clone(),
+        internal_db.clone(),
+        shared_settings.clone(),
+        pubsub.clone(),
+        mpsc::channel(1).0,
+        client,
+        shutdown_trigger.clone(),
+      );
+      if let Err(e) = aof::replay(&settings.server.aof.path, executor).await {
+        error!("Failed to replay AOF: {:#}", e);
+        std::process::exit(1);
+      }
+    }
+    if let Err(e) = aof::open(&memory_store, &settings.server.aof) {
+      error!("Failed to open AOF: {:#}", e);
+      std::process::exit(1);
+    }
+
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(Duration::from_secs(1));
+      loop {
+        interval.tick().await;
+        aof::tick();
+      }
+    });
+  }
+
+  // Periodically persist the memory store to the KDB file
+  if settings.server.kdb.persistence {
+    let backup_interval = settings.server.kdb.backup_interval.max(1);
+    let kdb_store = memory_store.clone();
+    let kdb_settings = settings.clone();
+
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(Duration::from_secs(backup_interval));
+      // The first tick completes immediately, skip it
+      interval.tick().await;
+
+      loop {
+        interval.tick().await;
+        // Saving blocks, keep it off the runtime's worker threads
+        if let Err(e) = kdb::background_save(kdb_store.clone(), kdb_settings.clone()) {
+          info!("Skipping periodic save: {:#}", e);
+        }
+      }
+    });
+    info!(
+      "KDB persistence enabled, saving every {} seconds",
+      backup_interval
+    );
+  }
+
+  // Save in the background once enough changes piled up, like Redis' `save`
+  if settings.server.kdb.persistence && !settings.server.kdb.save_points.is_empty() {
+    kdb::spawn_save_points(memory_store.clone(), settings.clone());
+    info!(
+      "Saving after {:?} (seconds, changes)",
+      settings.server.kdb.save_points
+    );
+  }
+
   // Get network configuration
   let kv_host = settings
     .get::<String>("server.network.host")
@@ -55,39 +199,184 @@ async fn main() {
       6379
     });
 
-  // Bind to the specified address and port
-  let listener = TcpListener::bind(format!("{}:{}", kv_host, kv_port))
-    .await
-    .unwrap();
+  // Listen on every configured address, or only on host and port if none are
+  let addresses = if settings.server.network.listeners.is_empty() {
+    vec![format!("{}:{}", kv_host, kv_port)]
+  } else {
+    settings.server.network.listeners.clone()
+  };
+
+  if !settings.server.network.requireauth {
+    if settings.server.network.protected_mode {
+      warn!(
+        "Authentication is disabled, loopback connections start authenticated as '{}' \
+         and protected mode refuses the others",
+        settings.server.network.user
+      );
+    } else {
+      warn!(
+        "Authentication and protected mode are disabled, every connection starts \
+         authenticated as '{}'",
+        settings.server.network.user
+      );
+    }
+  }
+
+  // Load the certificate once, a broken TLS setup should stop the server
+  // rather than fall back to plaintext
+  let tls = match tls::load_acceptor(&settings.server.network.tls) {
+    Ok(tls) => tls,
+    Err(e) => {
+      error!("{:#}", e);
+      std::process::exit(1);
+    }
+  };
+
+  let mut listeners = Vec::new();
+  for address in &addresses {
+    match Listener::bind(address, tls.clone()).await {
+      Ok(listener) => {
+        warn!("Bound to {}", listener.local_addr());
+        listeners.push(listener);
+      }
+      Err(e) => {
+        error!("{:#}", e);
+        // Remove the socket files bound so far before giving up
+        drop(listeners);
+        std::process::exit(1);
+      }
+    }
+  }
 
-  warn!(
-    "Bound to TCP - {:?}",
-    listener.local_addr().unwrap_or_else(|e| {
-      error!("Failed to get local address, {e}");
-      std::net::SocketAddr::new("127.0.0.1".parse().unwrap(), 0)
-    })
-  );
+  // Serve Prometheus metrics, if a port is configured for them
+  let metrics_listener = match settings.server.metrics.port {
+    Some(port) => match metrics::bind(&kv_host, port).await {
+      Ok(listener) => {
+        warn!("Serving metrics on {}:{}", kv_host, port);
+        Some(listener)
+      }
+      Err(e) => {
+        error!("{:#}", e);
+        drop(listeners);
+        std::process::exit(1);
+      }
+    },
+    None => None,
+  };
 
   info!("Listening for incoming connections...");
 
-  // Main server loop
+  // Each listener accepts connections on its own task, all feeding the same store
+  let mut accept_loops = JoinSet::new();
+  for listener in listeners {
+    accept_loops.spawn(serve(
+      listener,
+      memory_store.clone(),
+      internal_db.clone(),
+      shared_settings.clone(),
+      pubsub.clone(),
+      clients.clone(),
+      shutdown_trigger.clone(),
+    ));
+  }
+  if let Some(listener) = metrics_listener {
+    accept_loops.spawn(metrics::serve(listener, memory_store.clone()));
+  }
+
+  // Serve connections until the process is asked to terminate
+  let shutdown_mode = tokio::select! {
+    _ = shutdown_signal() => ShutdownMode::Default,
+    mode = shutdown_trigger.requested() => mode,
+  };
+
+  warn!("Shutting down, no longer accepting connections");
+  // Stopping the accept loops drops their listeners, removing socket files
+  accept_loops.shutdown().await;
+
+  // Let connections finish the command they are running, so their writes
+  // make it into the final snapshot
+  let signalled = clients.kill_all();
+  info!("Waiting for {} connections to close...", signalled);
+  let deadline = Instant::now() + SHUTDOWN_GRACE;
+  while NetworkUtils::connected_clients() > 0 && Instant::now() < deadline {
+    tokio::time::sleep(Duration::from_millis(50)).await;
+  }
+  if NetworkUtils::connected_clients() > 0 {
+    warn!(
+      "{} connections still open after {:?}, closing anyway",
+      NetworkUtils::connected_clients(),
+      SHUTDOWN_GRACE
+    );
+  }
+
+  // Whatever the fsync policy, logged writes are on disk once stopped
+  aof::sync();
+
+  let save = match shutdown_mode {
+    ShutdownMode::Default => settings.server.kdb.persistence,
+    ShutdownMode::Save => true,
+    ShutdownMode::NoSave => false,
+  };
+  if save {
+    warn!("Saving final KDB snapshot...");
+    match kdb::save(&memory_store, &settings) {
+      Ok(()) => info!("Saved KDB snapshot"),
+      Err(e) => error!("Failed to save KDB file: {:#}", e),
+    }
+  }
+
+  warn!("RustyKV server stopped");
+}
+
+/// Accepts connections on a listener, serving each one on its own task.
+///
+/// Runs until the task is aborted at shutdown.
+async fn serve(
+  listener: Listener,
+  store: MemoryStore,
+  db: InternalDB,
+  settings: SharedSettings,
+  pubsub: PubSub,
+  clients: ClientRegistry,
+  shutdown: ShutdownTrigger,
+) {
   loop {
-    let stream = listener.accept().await;
-    match stream {
-      Ok((stream, addr)) => {
+    match listener.accept().await {
+      Ok(incoming) => {
+        let peer = incoming.peer();
+
         // Clone the store and db references for each connection
-        let connection_store = memory_store.clone();
-        let connection_db = internal_db.clone();
+        let connection_store = store.clone();
+        let connection_db = db.clone();
+        let connection_settings = settings.clone();
+        let connection_pubsub = pubsub.clone();
+        let connection_clients = clients.clone();
+        let connection_shutdown = shutdown.clone();
 
         // Spawn a new task to handle the connection
         tokio::spawn(async move {
-          if let Err(e) =
-            NetworkUtils::accept_connection(stream, connection_store, connection_db).await
+          let stream = match incoming.establish().await {
+            Ok(stream) => stream,
+            Err(e) => {
+              error!("TLS handshake failed: {}", e);
+              return;
+            }
+          };
+          if let Err(e) = NetworkUtils::accept_connection(
+            stream,
+            connection_store,
+            connection_db,
+            connection_settings,
+            connection_pubsub,
+            connection_clients,
+            connection_shutdown,
+          )
+          .await
           {
             error!("Error handling connection: {}", e);
           }
         });
-        info!("Accepted a new connection from {}", addr);
+        info!("Accepted a new connection from {}", peer);
       }
       Err(e) => {
         error!("Error accepting connection: {}", e);
@@ -95,3 +384,86 @@ async fn main() {
     }
   }
 }
+
+/// Reloads the configuration file every time the process receives SIGHUP.
+///
+/// Parameters that can change at runtime are applied to the shared
+/// settings and the memory store, connections and data are kept. Anything
+/// else that changed is logged as requiring a restart.
+#[cfg(unix)]
+async fn reload_on_sighup(settings: SharedSettings, store: MemoryStore) {
+  let mut sighup = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+    Ok(sighup) => sighup,
+    Err(e) => {
+      error!("Failed to listen for SIGHUP: {}", e);
+      return;
+    }
+  };
+
+  while sighup.recv().await.is_some() {
+    warn!("Received SIGHUP, reloading {}", CONFIG_FILE);
+    // A broken file must not reset everything to the defaults
+    let fresh = match Settings::load(CONFIG_FILE) {
+      Ok(fresh) => fresh,
+      Err(e) => {
+        error!("Keeping the current settings: {:#}", e);
+        continue;
+      }
+    };
+
+    let reload = {
+      let mut settings = settings.write().unwrap();
+      let reload = settings.reload(&fresh);
+      store.set_quota(settings.server.network.quota());
+      store.set_eviction(settings.server.network.eviction());
+      store
+        .notifier()
+        .set_events(settings.server.notify.keyspace_events);
+      reload
+    };
+
+    for (name, before, after) in &reload.applied {
+      info!("Changed {} from {} to {}", name, before, after);
+    }
+    for path in &reload.requires_restart {
+      warn!("Changing {} requires restart, skipped", path);
+    }
+    warn!(
+      "Reloaded {}: {} changed, {} skipped",
+      CONFIG_FILE,
+      reload.applied.len(),
+      reload.requires_restart.len()
+    );
+  }
+}
+
+/// Waits until the process is asked to terminate with Ctrl-C (SIGINT) or,
+/// on Unix, SIGTERM.
+async fn shutdown_signal() {
+  let ctrl_c = async {
+    if let Err(e) = signal::ctrl_c().await {
+      error!("Failed to listen for Ctrl-C: {}", e);
+      std::future::pending::<()>().await;
+    }
+  };
+
+  #[cfg(unix)]
+  let terminate = async {
+    match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+      Ok(mut sigterm) => {
+        sigterm.recv().await;
+      }
+      Err(e) => {
+        error!("Failed to listen for SIGTERM: {}", e);
+        std::future::pending::<()>().await;
+      }
+    }
+  };
+  #[cfg(not(unix))]
+  let terminate = std::future::pending::<()>();
+
+  tokio::select! {
+    _ = ctrl_c => warn!("Received Ctrl-C"),
+    _ = terminate => warn!("Received SIGTERM"),
+  }
+}
@@ -0,0 +1,99 @@
+//! Typed RESP error replies.
+//!
+//! Redis starts every error reply with a code clients can match on, like
+//! `WRONGTYPE` or `NOAUTH`, followed by a message for people. Commands
+//! fail with [`CommandError`] to choose the code, any other error is
+//! replied as a generic `ERR`.
+
+use std::{borrow::Cow, fmt};
+
+use crate::resp::value::Value;
+
+/// Code an error reply starts with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+  /// Generic error
+  Err,
+  /// The key holds a value of another kind
+  WrongType,
+  /// The connection is not authenticated
+  NoAuth,
+  /// The credentials given to AUTH are wrong
+  WrongPass,
+  /// The user may not run the command
+  NoPerm,
+  /// The target key already exists
+  BusyKey,
+  /// The write doesn't fit in the memory limits
+  Oom,
+  /// HELLO asked for a protocol version the server doesn't speak
+  NoProto,
+  /// The connection is refused by protected mode
+  Denied,
+}
+
+impl ErrorCode {
+  /// Returns the code as it is written in replies.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ErrorCode::Err => "ERR",
+      ErrorCode::WrongType => "WRONGTYPE",
+      ErrorCode::NoAuth => "NOAUTH",
+      ErrorCode::WrongPass => "WRONGPASS",
+      ErrorCode::NoPerm => "NOPERM",
+      ErrorCode::BusyKey => "BUSYKEY",
+      ErrorCode::Oom => "OOM",
+      ErrorCode::NoProto => "NOPROTO",
+      ErrorCode::Denied => "DENIED",
+    }
+  }
+}
+
+/// An error replied with a specific [`ErrorCode`].
+///
+/// Converts into [`anyhow::Error`], so commands return it with `bail!` like
+/// any other error. Its `Display` includes the code.
+///
+/// # Example
+///
+/// ```
+/// bail!(CommandError::new(ErrorCode::NoPerm, "this user has no permissions"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct CommandError {
+  /// Code the reply starts with
+  pub code: ErrorCode,
+  /// Message following the code
+  pub message: Cow<'static, str>,
+}
+
+impl CommandError {
+  /// Creates an error replied as `-<code> <message>`.
+  pub fn new(code: ErrorCode, message: impl Into<Cow<'static, str>>) -> Self {
+    Self {
+      code,
+      message: message.into(),
+    }
+  }
+
+  /// Builds the reply for an error returned by a command.
+  ///
+  /// # Returns
+  ///
+  /// * `Value::Error` - `-<code> <message>` for a [`CommandError`], even if
+  ///   context was added to it, `-ERR <message>` for any other error
+  pub fn reply(error: &anyhow::Error) -> Value {
+    match error.downcast_ref::<CommandError>() {
+      Some(error) => Value::Error(error.to_string()),
+      None => Value::Error(format!("{} {}", ErrorCode::Err.as_str(), error)),
+    }
+  }
+}
+
+impl fmt::Display for CommandError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} {}", self.code.as_str(), self.message)
+  }
+}
+
+impl std::error::Error for CommandError {}
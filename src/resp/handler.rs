@@ -1,46 +1,82 @@
-//! TCP stream handler for RESP protocol.
+//! Stream handler for RESP protocol.
 //!
-//! Provides functionality to read and write RESP values from/to a TCP stream.
+//! Provides functionality to read and write RESP values from/to a TCP or
+//! Unix socket stream. Written values are buffered until [`RespHandler::flush`],
+//! so the replies to a pipeline of commands can go out in a single write.
+//! Large values are written out as they are serialized, so a huge reply
+//! never sits in memory twice and waits for a slow client to read it.
 
 use crate::resp::value::Value;
 use anyhow::Result;
 use bytes::{Buf, BytesMut};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
 
-use super::parser::RespParser;
+use super::parser::{ParserLimits, RespParser};
 
-/// Handles reading and writing RESP values from/to a TCP stream.
-pub struct RespHandler {
-  /// The TCP stream to read from and write to
-  stream: TcpStream,
+/// Buffered output past which [`RespHandler::write_value`] writes right away,
+/// so a long pipeline or a large reply doesn't pile up in memory.
+const MAX_BUFFERED_OUTPUT: usize = 64 * 1024;
+
+/// Handles reading and writing RESP values from/to a stream.
+pub struct RespHandler<S> {
+  /// The stream to read from, and to write to through the output buffer
+  stream: BufWriter<S>,
   /// Buffer for incoming data
   buffer: BytesMut,
+  /// Parser turning buffered data into values
+  parser: RespParser,
+  /// Value parsed by [`RespHandler::has_buffered_value`], not returned yet
+  next: Option<Value>,
+  /// RESP version values are written in, RESP2 until HELLO switches it
+  protocol: u8,
 }
 
-impl RespHandler {
-  /// Creates a new RESP handler for a TCP stream.
+impl<S: AsyncRead + AsyncWrite + Unpin> RespHandler<S> {
+  /// Creates a new RESP handler for a stream.
   ///
   /// # Arguments
   ///
-  /// * `stream` - The TCP stream to handle
-  pub fn new(stream: TcpStream) -> Self {
+  /// * `stream` - The stream to handle
+  /// * `limits` - Limits incoming values must stay within
+  pub fn new(stream: S, limits: ParserLimits) -> Self {
     Self {
-      stream,
+      stream: BufWriter::with_capacity(MAX_BUFFERED_OUTPUT, stream),
       buffer: BytesMut::with_capacity(1024),
+      parser: RespParser::new(limits),
+      next: None,
+      protocol: 2,
     }
   }
 
+  /// Sets the RESP version later values are written in.
+  ///
+  /// Below 3, values only RESP3 has are written as their RESP2
+  /// equivalent, see [`Value::serialize_resp2_to`].
+  pub fn set_protocol(&mut self, protocol: u8) {
+    self.protocol = protocol;
+  }
+
   /// Reads a RESP value from the stream.
   ///
+  /// Values already received are returned first, the stream is only read
+  /// once none is complete. Cancelling the returned future loses no data.
+  ///
   /// # Returns
   ///
   /// * `Ok(Some(Value))` - Successfully read a value
   /// * `Ok(None)` - Connection closed with no data
   /// * `Err(...)` - Error reading or parsing data
   pub async fn read_value(&mut self) -> Result<Option<Value>> {
+    if let Some(value) = self.next.take() {
+      return Ok(Some(value));
+    }
+
     loop {
-      // Read data into the buffer
+      if let Some(value) = self.parse_buffered()? {
+        return Ok(Some(value));
+      }
+
+      // Not enough data, read more into the buffer
       let bytes_read = self.stream.read_buf(&mut self.buffer).await?;
       if bytes_read == 0 {
         if self.buffer.is_empty() {
@@ -49,20 +85,41 @@ impl RespHandler {
           return Err(anyhow::anyhow!("Connection closed unexpectedly"));
         }
       }
+    }
+  }
 
-      // Try to parse a RESP message from the buffer
-      match RespParser::parse_message(&mut self.buffer) {
-        Ok(Some((val, consumed))) => {
-          self.buffer.advance(consumed);
-          return Ok(Some(val));
-        }
-        Ok(None) => continue, // Not enough data, read more
-        Err(e) => return Err(e),
+  /// Checks whether a complete value was already received, without reading
+  /// from the stream.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(true)` - The next [`RespHandler::read_value`] returns without waiting
+  /// * `Ok(false)` - No complete value is buffered
+  /// * `Err(...)` - The buffered data can't be parsed
+  pub fn has_buffered_value(&mut self) -> Result<bool> {
+    if self.next.is_none() {
+      self.next = self.parse_buffered()?;
+    }
+    Ok(self.next.is_some())
+  }
+
+  /// Parses the next value out of the data received so far.
+  fn parse_buffered(&mut self) -> Result<Option<Value>> {
+    match self.parser.parse_message(&self.buffer)? {
+      Some((value, consumed)) => {
+        self.buffer.advance(consumed);
+        Ok(Some(value))
       }
+      None => Ok(None),
     }
   }
 
-  /// Writes a RESP value to the stream.
+  /// Queues a RESP value to be written to the stream.
+  ///
+  /// The value is only written by the next [`RespHandler::flush`], or once
+  /// enough output is queued. Arrays are serialized one element at a time,
+  /// and writing waits while the output buffer is full, so however large
+  /// the value, only the buffer's worth of it is held serialized.
   ///
   /// # Arguments
   ///
@@ -70,11 +127,50 @@ impl RespHandler {
   ///
   /// # Returns
   ///
-  /// * `Ok(())` - Value was successfully written
-  /// * `Err(...)` - Error writing to the stream
+  /// * `Ok(())` - Value was successfully queued
+  /// * `Err(...)` - Error writing queued output to the stream
   pub async fn write_value(&mut self, value: Value) -> Result<()> {
-    let data = value.serialize();
-    self.stream.write_all(data.as_bytes()).await?;
+    let mut chunk = Vec::new();
+    // Elements of the arrays being written, innermost last
+    let mut arrays = vec![std::slice::from_ref(&value).iter()];
+    while let Some(elements) = arrays.last_mut() {
+      let Some(element) = elements.next() else {
+        arrays.pop();
+        continue;
+      };
+      chunk.clear();
+      match element {
+        Value::Array(nested) => {
+          Value::serialize_array_header(&mut chunk, nested.len())?;
+          arrays.push(nested.iter());
+        }
+        Value::Push(nested) => {
+          if self.protocol < 3 {
+            Value::serialize_array_header(&mut chunk, nested.len())?;
+          } else {
+            Value::serialize_push_header(&mut chunk, nested.len())?;
+          }
+          arrays.push(nested.iter());
+        }
+        element if self.protocol < 3 => element.serialize_resp2_to(&mut chunk)?,
+        element => element.serialize_to(&mut chunk)?,
+      }
+      self.stream.write_all(&chunk).await?;
+    }
+    Ok(())
+  }
+
+  /// Writes every queued value to the stream.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - Everything queued was written
+  /// * `Err(...)` - Error writing to the stream
+  pub async fn flush(&mut self) -> Result<()> {
+    // Flushes the inner stream too, even if nothing is buffered: writes
+    // larger than the buffer go straight through, and TLS streams hold on
+    // to data until flushed
+    self.stream.flush().await?;
     Ok(())
   }
 }
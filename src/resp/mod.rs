@@ -1,3 +1,5 @@
+pub mod error;
 pub mod handler;
 pub mod parser;
+pub mod serde;
 pub mod value;
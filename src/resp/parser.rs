@@ -1,23 +1,95 @@
 //! Parser for the RESP (Redis Serialization Protocol).
 //!
 //! Provides functionality to parse RESP-formatted byte streams into Value objects.
+//! Inline commands, as sent by telnet or nc, are parsed into the same arrays
+//! a RESP client would send.
 
-use anyhow::Result;
-use bytes::BytesMut;
+use anyhow::{Result, bail};
 
 use super::value::Value;
 
+/// Longest line accepted, for inline commands and type headers, in bytes.
+const MAX_LINE_LEN: usize = 64 * 1024;
+
+/// First bytes of the RESP types the parser understands.
+const TYPE_SIGILS: &[u8] = b"+-:$*#,=(>";
+
+/// Limits protecting the parser from clients declaring huge lengths.
+#[derive(Clone, Copy, Debug)]
+pub struct ParserLimits {
+  /// Maximum number of elements in an array
+  pub max_array_len: usize,
+  /// Maximum length of a bulk string in bytes
+  pub max_bulk_len: usize,
+}
+
+impl Default for ParserLimits {
+  fn default() -> Self {
+    Self {
+      max_array_len: 1024 * 1024,
+      max_bulk_len: 512 * 1024 * 1024,
+    }
+  }
+}
+
+/// Most elements reserved up front for an array, whatever length it
+/// declares, so a client can't make the parser allocate memory it never sends.
+const MAX_PREALLOCATED: usize = 1024;
+
+/// Most arrays a value may be nested in. Commands are flat arrays, and
+/// deeper values couldn't be dropped or formatted without overflowing the
+/// stack.
+const MAX_NESTING: usize = 128;
+
 /// Parser for RESP-formatted data.
-pub struct RespParser;
+///
+/// Keeps the arrays it has started parsing between calls, so a value
+/// arriving over several reads is parsed exactly once.
+pub struct RespParser {
+  /// Limits the parsed input must stay within
+  limits: ParserLimits,
+  /// Progress on the incomplete value at the start of the buffer, if any
+  partial: Option<Partial>,
+}
+
+/// Progress on a value not received in full yet.
+#[derive(Default)]
+struct Partial {
+  /// Arrays being filled, innermost last
+  arrays: Vec<PartialArray>,
+  /// Position parsing resumes at
+  pos: usize,
+}
+
+/// An array or push whose elements are still being parsed.
+struct PartialArray {
+  /// Elements parsed so far
+  values: Vec<Value>,
+  /// Number of elements still to parse
+  remaining: usize,
+  /// Whether it is a RESP3 push rather than an array
+  push: bool,
+}
 
 impl RespParser {
   /// Creates a new RESP parser.
-  pub fn new() -> Self {
-    Self
+  ///
+  /// # Arguments
+  ///
+  /// * `limits` - Limits the parsed input must stay within
+  pub fn new(limits: ParserLimits) -> Self {
+    Self {
+      limits,
+      partial: None,
+    }
   }
 
   /// Parses RESP data from a buffer.
   ///
+  /// An incomplete value is picked up where it was left off by the next
+  /// call, so between calls the buffer may only grow, and must be advanced
+  /// by the bytes consumed once a value is returned.
+  ///
   /// # Arguments
   ///
   /// * `buf` - Buffer containing RESP-formatted data
@@ -26,138 +98,348 @@ impl RespParser {
   ///
   /// * `Ok(Some((Value, usize)))` - Parsed value and number of bytes consumed
   /// * `Ok(None)` - Not enough data to parse a complete value
+  /// * `Err(...)` - Error during parsing, including input exceeding the limits
+  pub fn parse_message(&mut self, buf: &[u8]) -> Result<Option<(Value, usize)>> {
+    if self.partial.is_none() {
+      match buf.first() {
+        None => return Ok(None),
+        Some(first) if TYPE_SIGILS.contains(first) => {}
+        // Anything else is an inline command, as typed into telnet or nc
+        Some(_) => return self.parse_inline(buf),
+      }
+    }
+
+    let Partial {
+      mut arrays,
+      mut pos,
+    } = self.partial.take().unwrap_or_default();
+    loop {
+      let parsed = match buf.get(pos) {
+        Some(&sigil @ (b'*' | b'>')) => match self.parse_array_header(buf, pos)? {
+          Some((Some(count), end)) if count > 0 => {
+            if arrays.len() >= MAX_NESTING {
+              bail!("too deeply nested request");
+            }
+            // Its elements are the values that follow
+            arrays.push(PartialArray {
+              values: Vec::with_capacity(count.min(MAX_PREALLOCATED)),
+              remaining: count,
+              push: sigil == b'>',
+            });
+            pos = end;
+            continue;
+          }
+          Some((Some(_), end)) if sigil == b'>' => Some((Value::Push(Vec::new()), end)),
+          Some((Some(_), end)) => Some((Value::Array(Vec::new()), end)),
+          Some((None, end)) => Some((Value::Null, end)),
+          None => None,
+        },
+        _ => self.parse_value(buf, pos)?,
+      };
+      let Some((mut value, end)) = parsed else {
+        self.partial = Some(Partial { arrays, pos });
+        return Ok(None);
+      };
+      pos = end;
+
+      // Hand the value to the array it belongs to, closing every array it
+      // completes
+      loop {
+        let Some(array) = arrays.last_mut() else {
+          return Ok(Some((value, pos)));
+        };
+        array.values.push(value);
+        array.remaining -= 1;
+        if array.remaining > 0 {
+          break;
+        }
+        let array = arrays.pop().unwrap();
+        value = if array.push {
+          Value::Push(array.values)
+        } else {
+          Value::Array(array.values)
+        };
+      }
+    }
+  }
+
+  /// Parses the value starting at `start`, other than an array.
+  ///
+  /// Arrays are assembled by [`RespParser::parse_message`] from the values
+  /// following their header, so nested values are never copied, and no
+  /// recursion is needed however deep they are.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some((Value, usize)))` - Parsed value and the position right after it
+  /// * `Ok(None)` - The value is not complete yet
   /// * `Err(...)` - Error during parsing
-  pub fn parse_message(buf: &mut BytesMut) -> Result<Option<(Value, usize)>> {
-    if buf.is_empty() {
+  fn parse_value(&self, buf: &[u8], start: usize) -> Result<Option<(Value, usize)>> {
+    let Some(&sigil) = buf.get(start) else {
       return Ok(None);
+    };
+
+    // Parse based on the first byte (RESP type indicator)
+    match sigil {
+      b'+' => self.parse_simple_string(buf, start),
+      b'-' => self.parse_error(buf, start),
+      b':' => self.parse_integer(buf, start),
+      b'$' => self.parse_bulk_string(buf, start),
+      b'#' => self.parse_boolean(buf, start),
+      b',' => self.parse_double(buf, start),
+      b'=' => self.parse_verbatim_string(buf, start),
+      b'(' => self.parse_big_number(buf, start),
+      other => bail!("Unknown RESP type: {:?}", other as char),
     }
+  }
 
-    let parser = Self::new();
+  /// Parses an inline command ("SET foo bar\r\n").
+  ///
+  /// The line is split on whitespace into an array of bulk strings, like a
+  /// regular RESP command. Arguments can be wrapped in double quotes, which
+  /// support backslash escapes, or single quotes to include spaces.
+  fn parse_inline(&self, buf: &[u8]) -> Result<Option<(Value, usize)>> {
+    let Some(end) = buf.iter().position(|&b| b == b'\n') else {
+      if buf.len() > MAX_LINE_LEN {
+        bail!("too big inline request");
+      }
+      return Ok(None);
+    };
 
-    // Parse based on the first byte (RESP type indicator)
-    match buf[0] as char {
-      '+' => parser.parse_simple_string(buf),
-      '-' => parser.parse_error(buf),
-      ':' => parser.parse_integer(buf),
-      '$' => parser.parse_bulk_string(buf),
-      '*' => parser.parse_array(buf),
-      '#' => parser.parse_boolean(buf),
-      _ => Err(anyhow::anyhow!(
-        "Unknown RESP type: {:?}",
-        std::str::from_utf8(&buf[..]).ok()
-      )),
+    // Accept both CRLF and a bare LF as line terminator
+    let line = buf[..end].strip_suffix(b"\r").unwrap_or(&buf[..end]);
+    let args = self.split_inline(std::str::from_utf8(line)?)?;
+
+    let values = args.into_iter().map(Value::BulkString).collect();
+    Ok(Some((Value::Array(values), end + 1)))
+  }
+
+  /// Splits an inline command line into its arguments.
+  fn split_inline(&self, line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+      // Skip the whitespace separating arguments
+      while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+      let Some(&first) = chars.peek() else {
+        break;
+      };
+
+      let mut arg = String::new();
+      if first == '"' || first == '\'' {
+        chars.next();
+        let mut closed = false;
+        while let Some(c) = chars.next() {
+          match c {
+            c if c == first => {
+              closed = true;
+              break;
+            }
+            '\\' if first == '"' => match chars.next() {
+              Some('n') => arg.push('\n'),
+              Some('r') => arg.push('\r'),
+              Some('t') => arg.push('\t'),
+              Some(escaped) => arg.push(escaped),
+              None => break,
+            },
+            '\\' if chars.peek() == Some(&'\'') => {
+              chars.next();
+              arg.push('\'');
+            }
+            c => arg.push(c),
+          }
+        }
+
+        // A closing quote must end the argument
+        if !closed || chars.peek().is_some_and(|c| !c.is_ascii_whitespace()) {
+          return Err(anyhow::anyhow!("unbalanced quotes in request"));
+        }
+      } else {
+        while let Some(c) = chars.next_if(|c| !c.is_ascii_whitespace()) {
+          arg.push(c);
+        }
+      }
+      args.push(arg);
     }
+
+    Ok(args)
   }
 
   /// Parses a RESP simple string ("+...").
-  fn parse_simple_string(&self, buf: &BytesMut) -> Result<Option<(Value, usize)>> {
-    self
-      .parse_line(buf, 1)
-      .map(|(line, len)| Some((Value::SimpleString(line), len)))
+  fn parse_simple_string(&self, buf: &[u8], start: usize) -> Result<Option<(Value, usize)>> {
+    Ok(
+      self
+        .parse_line(buf, start + 1)?
+        .map(|(line, end)| (Value::SimpleString(line), end)),
+    )
   }
 
   /// Parses a RESP error ("-...").
-  fn parse_error(&self, buf: &BytesMut) -> Result<Option<(Value, usize)>> {
-    self
-      .parse_line(buf, 1)
-      .map(|(line, len)| Some((Value::Error(line), len)))
+  fn parse_error(&self, buf: &[u8], start: usize) -> Result<Option<(Value, usize)>> {
+    Ok(
+      self
+        .parse_line(buf, start + 1)?
+        .map(|(line, end)| (Value::Error(line), end)),
+    )
   }
 
   /// Parses a RESP integer (":...").
-  fn parse_integer(&self, buf: &BytesMut) -> Result<Option<(Value, usize)>> {
-    self
-      .parse_line(buf, 1)
-      .and_then(|(line, len)| Ok(Some((Value::Integer(line.parse::<i64>()?), len))))
+  fn parse_integer(&self, buf: &[u8], start: usize) -> Result<Option<(Value, usize)>> {
+    let Some((line, end)) = self.read_line(buf, start + 1)? else {
+      return Ok(None);
+    };
+    Ok(Some((Value::Integer(self.parse_int(line)?), end)))
   }
 
   /// Parses a RESP bulk string ("$...").
-  fn parse_bulk_string(&self, buf: &BytesMut) -> Result<Option<(Value, usize)>> {
-    let (len_str, prefix_len) = self
-      .read_until_crlf(&buf[1..])
-      .ok_or_else(|| anyhow::anyhow!("Invalid bulk string length"))?;
+  fn parse_bulk_string(&self, buf: &[u8], start: usize) -> Result<Option<(Value, usize)>> {
+    let Some((len_str, data_start)) = self.read_line(buf, start + 1)? else {
+      return Ok(None);
+    };
     let len = self.parse_int(len_str)?;
 
     // Handle null strings ($-1\r\n)
     if len == -1 {
-      return Ok(Some((Value::Null, 1 + prefix_len)));
+      return Ok(Some((Value::Null, data_start)));
     }
+    let len = self.check_len(len, self.limits.max_bulk_len, "invalid bulk length")?;
 
-    let total_len = 1 + prefix_len + len as usize + 2;
-    if buf.len() < total_len {
+    let data_end = data_start + len;
+    if buf.len() < data_end + 2 {
       return Ok(None);
     }
-    let data = buf[1 + prefix_len..1 + prefix_len + len as usize].to_vec();
-    let string = String::from_utf8(data)?;
-    Ok(Some((Value::BulkString(string), total_len)))
+    if &buf[data_end..data_end + 2] != b"\r\n" {
+      bail!("Expected CRLF after bulk string");
+    }
+    // Payloads are binary-safe, only valid UTF-8 is turned into a string
+    let value = Value::from_bytes(buf[data_start..data_end].to_vec());
+    Ok(Some((value, data_end + 2)))
   }
 
-  /// Parses a RESP array ("*...").
-  fn parse_array(&self, buf: &BytesMut) -> Result<Option<(Value, usize)>> {
-    let (len_str, prefix_len) = self
-      .read_until_crlf(&buf[1..])
-      .ok_or_else(|| anyhow::anyhow!("Invalid array header"))?;
-    let count = self.parse_int(len_str)?;
+  /// Parses a RESP3 verbatim string ("=...").
+  ///
+  /// The payload is a three character format, a colon and the text, which
+  /// has to be valid UTF-8.
+  fn parse_verbatim_string(&self, buf: &[u8], start: usize) -> Result<Option<(Value, usize)>> {
+    let Some((len_str, data_start)) = self.read_line(buf, start + 1)? else {
+      return Ok(None);
+    };
+    let len = self.parse_int(len_str)?;
+    let len = self.check_len(len, self.limits.max_bulk_len, "invalid bulk length")?;
 
-    // Handle null arrays (*-1\r\n)
-    if count == -1 {
-      return Ok(Some((Value::Null, 1 + prefix_len)));
+    let data_end = data_start + len;
+    if buf.len() < data_end + 2 {
+      return Ok(None);
     }
+    if &buf[data_end..data_end + 2] != b"\r\n" {
+      bail!("Expected CRLF after verbatim string");
+    }
+    let payload = &buf[data_start..data_end];
+    if payload.len() < 4 || payload[3] != b':' {
+      bail!("Invalid verbatim string format");
+    }
+    let format = String::from_utf8(payload[..3].to_vec())?;
+    let text = String::from_utf8(payload[4..].to_vec())?;
+    Ok(Some((Value::VerbatimString(format, text), data_end + 2)))
+  }
 
-    let mut total_len = 1 + prefix_len;
-    let mut values = Vec::new();
-
-    // Parse each array element
-    for _ in 0..count {
-      let (v, len) = Self::parse_message(&mut BytesMut::from(&buf[total_len..]))?
-        .ok_or_else(|| anyhow::anyhow!("Incomplete array element"))?;
-      values.push(v);
-      total_len += len;
+  /// Parses a RESP3 big number ("(...").
+  fn parse_big_number(&self, buf: &[u8], start: usize) -> Result<Option<(Value, usize)>> {
+    let Some((line, end)) = self.parse_line(buf, start + 1)? else {
+      return Ok(None);
+    };
+    let digits = line.strip_prefix('-').unwrap_or(&line);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+      bail!("Invalid big number value");
     }
+    Ok(Some((Value::BigNumber(line), end)))
+  }
 
-    Ok(Some((Value::Array(values), total_len)))
+  /// Parses the header of a RESP array ("*...") or RESP3 push (">...").
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some((Some(count), usize)))` - Declared element count and the position after the header
+  /// * `Ok(Some((None, usize)))` - A null array (*-1\r\n)
+  /// * `Ok(None)` - The header is not complete yet
+  /// * `Err(...)` - Invalid or too large length
+  fn parse_array_header(&self, buf: &[u8], start: usize) -> Result<Option<(Option<usize>, usize)>> {
+    let Some((len_str, pos)) = self.read_line(buf, start + 1)? else {
+      return Ok(None);
+    };
+    let count = self.parse_int(len_str)?;
+    if count == -1 {
+      return Ok(Some((None, pos)));
+    }
+    let count = self.check_len(count, self.limits.max_array_len, "invalid multibulk length")?;
+    Ok(Some((Some(count), pos)))
   }
 
   /// Parses a RESP boolean ("#...").
-  fn parse_boolean(&self, buf: &BytesMut) -> Result<Option<(Value, usize)>> {
-    if buf.len() < 4 {
+  fn parse_boolean(&self, buf: &[u8], start: usize) -> Result<Option<(Value, usize)>> {
+    if buf.len() < start + 4 {
       return Ok(None);
     }
-    let val = match buf[1] as char {
-      't' => true,
-      'f' => false,
-      _ => return Err(anyhow::anyhow!("Invalid boolean value")),
+    let val = match buf[start + 1] {
+      b't' => true,
+      b'f' => false,
+      _ => bail!("Invalid boolean value"),
     };
-    if &buf[2..4] != b"\r\n" {
-      return Err(anyhow::anyhow!("Expected CRLF after boolean"));
+    if &buf[start + 2..start + 4] != b"\r\n" {
+      bail!("Expected CRLF after boolean");
     }
-    Ok(Some((Value::Boolean(val), 4)))
+    Ok(Some((Value::Boolean(val), start + 4)))
   }
 
-  /// Parses a line until CR-LF.
-  fn parse_line(&self, buf: &BytesMut, start: usize) -> Result<(String, usize)> {
-    self
-      .read_until_crlf(&buf[start..])
-      .ok_or_else(|| anyhow::anyhow!("CRLF not found"))
-      .and_then(|(line, len)| Ok((String::from_utf8(line.to_vec())?, start + len)))
+  /// Parses a double (",1.5\r\n").
+  fn parse_double(&self, buf: &[u8], start: usize) -> Result<Option<(Value, usize)>> {
+    let Some((line, end)) = self.parse_line(buf, start + 1)? else {
+      return Ok(None);
+    };
+    let double = line
+      .parse::<f64>()
+      .map_err(|_| anyhow::anyhow!("Invalid double value"))?;
+    Ok(Some((Value::Double(double), end)))
+  }
+
+  /// Parses a line until CR-LF as a string.
+  fn parse_line(&self, buf: &[u8], start: usize) -> Result<Option<(String, usize)>> {
+    let Some((line, end)) = self.read_line(buf, start)? else {
+      return Ok(None);
+    };
+    Ok(Some((String::from_utf8(line.to_vec())?, end)))
+  }
+
+  /// Validates a declared length against its limit.
+  ///
+  /// Negative lengths are rejected, the null sentinel (-1) has to be
+  /// handled by the caller beforehand.
+  fn check_len(&self, len: i64, max: usize, error: &str) -> Result<usize> {
+    match usize::try_from(len) {
+      Ok(len) if len <= max => Ok(len),
+      _ => bail!("{}", error),
+    }
   }
 
   /// Parses a string as an integer.
   fn parse_int(&self, buf: &[u8]) -> Result<i64> {
-    Ok(String::from_utf8(buf.to_vec())?.parse::<i64>()?)
+    Ok(std::str::from_utf8(buf)?.parse::<i64>()?)
   }
 
-  /// Reads from a buffer until CR-LF is found.
+  /// Reads from `start` until CR-LF is found.
   ///
   /// # Returns
   ///
-  /// * `Some((&[u8], usize))` - Content before CR-LF and total length including CR-LF
-  /// * `None` - CR-LF not found
-  fn read_until_crlf<'a>(&self, buffer: &'a [u8]) -> Option<(&'a [u8], usize)> {
-    for i in 1..buffer.len() {
-      if buffer[i - 1] == b'\r' && buffer[i] == b'\n' {
-        return Some((&buffer[0..i - 1], i + 1));
-      }
+  /// * `Ok(Some((&[u8], usize)))` - Content before CR-LF and the position after it
+  /// * `Ok(None)` - CR-LF not found yet
+  /// * `Err(...)` - The line is longer than any valid line could be
+  fn read_line<'a>(&self, buf: &'a [u8], start: usize) -> Result<Option<(&'a [u8], usize)>> {
+    let rest = buf.get(start..).unwrap_or_default();
+    match rest.windows(2).position(|window| window == b"\r\n") {
+      Some(i) => Ok(Some((&rest[..i], start + i + 2))),
+      None if rest.len() > MAX_LINE_LEN => bail!("too big request line"),
+      None => Ok(None),
     }
-    None
   }
 }
@@ -0,0 +1,267 @@
+//! Compact binary encoding of RESP values.
+//!
+//! Used by KDB snapshots for the values they store, and by `DUMP` and
+//! `RESTORE` to move a single value between servers as an opaque blob.
+//!
+//! # Format
+//!
+//! All integers are big-endian and strings are a `u32` length followed by
+//! the bytes. A value is a type tag followed by its payload:
+//!
+//! ```text
+//! 0 null          |
+//! 1 simple string | str
+//! 2 bulk string   | str
+//! 3 integer       | i64
+//! 4 boolean       | u8
+//! 5 error         | str
+//! 6 array         | count: u32 | value*
+//! 7 bulk bytes    | str
+//! 8 double        | f64
+//! 9 verbatim      | format: str | str
+//! 10 big number   | str
+//! 11 push         | count: u32 | value*
+//! ```
+//!
+//! A dump is a value followed by the format version as a `u16` and the
+//! first 8 bytes of the SHA3-256 digest of everything before them.
+
+use anyhow::{Result, anyhow, bail};
+use bytes::{Buf, BufMut};
+use sha3::{Digest, Sha3_256};
+
+use crate::resp::value::Value;
+
+/// Value type tags.
+const VALUE_NULL: u8 = 0;
+const VALUE_SIMPLE_STRING: u8 = 1;
+const VALUE_BULK_STRING: u8 = 2;
+const VALUE_INTEGER: u8 = 3;
+const VALUE_BOOLEAN: u8 = 4;
+const VALUE_ERROR: u8 = 5;
+const VALUE_ARRAY: u8 = 6;
+const VALUE_BULK_BYTES: u8 = 7;
+const VALUE_DOUBLE: u8 = 8;
+const VALUE_VERBATIM_STRING: u8 = 9;
+const VALUE_BIG_NUMBER: u8 = 10;
+const VALUE_PUSH: u8 = 11;
+
+/// Most arrays a decoded value may be nested in, so a crafted payload can't
+/// overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+/// Version written after a dumped value.
+const DUMP_VERSION: u16 = 1;
+
+/// Length of the checksum ending a dump.
+const CHECKSUM_LEN: usize = 8;
+
+/// Error `RESTORE` replies with for a blob it can't read.
+const BAD_FORMAT: &str = "Bad data format";
+
+/// Serializes a value for `DUMP`.
+///
+/// # Arguments
+///
+/// * `value` - Value to serialize
+///
+/// # Returns
+///
+/// The encoded value followed by the format version and its checksum.
+pub fn dump(value: &Value) -> Vec<u8> {
+  let mut buf = Vec::new();
+  encode_value(&mut buf, value);
+  buf.put_u16(DUMP_VERSION);
+  let checksum = checksum(&buf);
+  buf.put_slice(&checksum);
+  buf
+}
+
+/// Reads a value serialized by [`dump`].
+///
+/// # Arguments
+///
+/// * `blob` - Payload received by `RESTORE`
+///
+/// # Returns
+///
+/// * `Ok(Value)` - The serialized value
+/// * `Err` - If the checksum or version doesn't match, or the payload is malformed
+pub fn restore(blob: &[u8]) -> Result<Value> {
+  if blob.len() < 2 + CHECKSUM_LEN {
+    bail!(BAD_FORMAT);
+  }
+  let (payload, expected) = blob.split_at(blob.len() - CHECKSUM_LEN);
+  if checksum(payload) != expected {
+    bail!(BAD_FORMAT);
+  }
+
+  let (mut value, mut version) = payload.split_at(payload.len() - 2);
+  if version.get_u16() != DUMP_VERSION {
+    bail!(BAD_FORMAT);
+  }
+  let decoded = decode_value(&mut value).map_err(|_| anyhow!(BAD_FORMAT))?;
+  if !value.is_empty() {
+    bail!(BAD_FORMAT);
+  }
+  Ok(decoded)
+}
+
+/// Computes the checksum ending a dump.
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+  let digest = Sha3_256::digest(payload);
+  let mut checksum = [0; CHECKSUM_LEN];
+  checksum.copy_from_slice(&digest[..CHECKSUM_LEN]);
+  checksum
+}
+
+/// Writes a length-prefixed string.
+pub fn put_str(buf: &mut Vec<u8>, s: &str) {
+  put_bytes(buf, s.as_bytes());
+}
+
+/// Writes a length-prefixed byte string.
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+  buf.put_u32(bytes.len() as u32);
+  buf.put_slice(bytes);
+}
+
+/// Writes a value as a type tag followed by its payload.
+///
+/// Strings and errors are length-prefixed, integers are `i64`, doubles
+/// `f64`, booleans a single byte and arrays and pushes a `u32` element
+/// count followed by each element.
+pub fn encode_value(buf: &mut Vec<u8>, value: &Value) {
+  match value {
+    Value::Null => buf.put_u8(VALUE_NULL),
+    Value::SimpleString(s) => {
+      buf.put_u8(VALUE_SIMPLE_STRING);
+      put_str(buf, s);
+    }
+    Value::BulkString(s) => {
+      buf.put_u8(VALUE_BULK_STRING);
+      put_str(buf, s);
+    }
+    Value::BulkBytes(bytes) => {
+      buf.put_u8(VALUE_BULK_BYTES);
+      put_bytes(buf, bytes);
+    }
+    Value::Integer(i) => {
+      buf.put_u8(VALUE_INTEGER);
+      buf.put_i64(*i);
+    }
+    Value::Boolean(b) => {
+      buf.put_u8(VALUE_BOOLEAN);
+      buf.put_u8(*b as u8);
+    }
+    Value::Double(d) => {
+      buf.put_u8(VALUE_DOUBLE);
+      buf.put_f64(*d);
+    }
+    Value::Error(s) => {
+      buf.put_u8(VALUE_ERROR);
+      put_str(buf, s);
+    }
+    Value::VerbatimString(format, text) => {
+      buf.put_u8(VALUE_VERBATIM_STRING);
+      put_str(buf, format);
+      put_str(buf, text);
+    }
+    Value::BigNumber(digits) => {
+      buf.put_u8(VALUE_BIG_NUMBER);
+      put_str(buf, digits);
+    }
+    Value::Array(values) => {
+      buf.put_u8(VALUE_ARRAY);
+      buf.put_u32(values.len() as u32);
+      for v in values {
+        encode_value(buf, v);
+      }
+    }
+    Value::Push(values) => {
+      buf.put_u8(VALUE_PUSH);
+      buf.put_u32(values.len() as u32);
+      for v in values {
+        encode_value(buf, v);
+      }
+    }
+  }
+}
+
+/// Reads a value written by [`encode_value`].
+pub fn decode_value(buf: &mut &[u8]) -> Result<Value> {
+  decode_nested(buf, 0)
+}
+
+/// Reads a value nested in `depth` arrays.
+fn decode_nested(buf: &mut &[u8], depth: usize) -> Result<Value> {
+  let value = match get_u8(buf)? {
+    VALUE_NULL => Value::Null,
+    VALUE_SIMPLE_STRING => Value::SimpleString(get_str(buf)?),
+    VALUE_BULK_STRING => Value::BulkString(get_str(buf)?),
+    VALUE_BULK_BYTES => Value::BulkBytes(get_bytes(buf)?),
+    VALUE_INTEGER => Value::Integer(get_u64(buf)? as i64),
+    VALUE_BOOLEAN => Value::Boolean(get_u8(buf)? != 0),
+    VALUE_DOUBLE => Value::Double(f64::from_bits(get_u64(buf)?)),
+    VALUE_ERROR => Value::Error(get_str(buf)?),
+    VALUE_VERBATIM_STRING => Value::VerbatimString(get_str(buf)?, get_str(buf)?),
+    VALUE_BIG_NUMBER => Value::BigNumber(get_str(buf)?),
+    tag @ (VALUE_ARRAY | VALUE_PUSH) => {
+      if depth >= MAX_DEPTH {
+        bail!("Values nested deeper than {} arrays", MAX_DEPTH);
+      }
+      let len = get_u32(buf)?;
+      let mut values = Vec::new();
+      for _ in 0..len {
+        values.push(decode_nested(buf, depth + 1)?);
+      }
+      if tag == VALUE_PUSH {
+        Value::Push(values)
+      } else {
+        Value::Array(values)
+      }
+    }
+    tag => bail!("Unknown value tag {}", tag),
+  };
+  Ok(value)
+}
+
+/// Fails if fewer than `len` bytes are left to read.
+fn ensure(buf: &[u8], len: usize) -> Result<()> {
+  if buf.remaining() < len {
+    bail!("Unexpected end of data");
+  }
+  Ok(())
+}
+
+/// Reads a single byte.
+pub fn get_u8(buf: &mut &[u8]) -> Result<u8> {
+  ensure(buf, 1)?;
+  Ok(buf.get_u8())
+}
+
+/// Reads a big-endian `u32`.
+pub fn get_u32(buf: &mut &[u8]) -> Result<u32> {
+  ensure(buf, 4)?;
+  Ok(buf.get_u32())
+}
+
+/// Reads a big-endian `u64`.
+pub fn get_u64(buf: &mut &[u8]) -> Result<u64> {
+  ensure(buf, 8)?;
+  Ok(buf.get_u64())
+}
+
+/// Reads a length-prefixed string.
+pub fn get_str(buf: &mut &[u8]) -> Result<String> {
+  Ok(String::from_utf8(get_bytes(buf)?)?)
+}
+
+/// Reads a length-prefixed byte string.
+fn get_bytes(buf: &mut &[u8]) -> Result<Vec<u8>> {
+  let len = get_u32(buf)? as usize;
+  ensure(buf, len)?;
+  let bytes = buf[..len].to_vec();
+  buf.advance(len);
+  Ok(bytes)
+}
@@ -3,6 +3,10 @@
 //! Defines the different value types that can be serialized and deserialized
 //! according to the RESP specification.
 
+use std::io::{self, Write};
+
+use anyhow::{Result, bail};
+
 /// Enum representing the different RESP value types.
 #[derive(Clone, Debug)]
 pub enum Value {
@@ -15,6 +19,9 @@ pub enum Value {
   /// Bulk string (represented as "${length}\r\n{string}\r\n" in RESP)
   BulkString(String),
 
+  /// Bulk string whose payload is not valid UTF-8, kept as raw bytes
+  BulkBytes(Vec<u8>),
+
   /// Array of values (represented as "*{length}\r\n{values...}" in RESP)
   Array(Vec<Value>),
 
@@ -26,137 +33,273 @@ pub enum Value {
 
   /// Boolean (represented as "#{t|f}\r\n" in RESP)
   Boolean(bool),
+
+  /// Floating point number (represented as ",{double}\r\n" in RESP)
+  Double(f64),
+
+  /// Text with a three character format such as `txt` or `mkd`
+  /// (represented as "={length}\r\n{format}:{text}\r\n" in RESP3)
+  VerbatimString(String, String),
+
+  /// Integer of any size, as its decimal digits (represented as
+  /// "({digits}\r\n" in RESP3)
+  BigNumber(String),
+
+  /// Out-of-band message such as a published message, not a reply to a
+  /// command (represented as ">{length}\r\n{values...}" in RESP3)
+  Push(Vec<Value>),
 }
 
 impl Value {
-  /// Serializes the value to a RESP-compatible string.
+  /// Creates a bulk string from raw bytes.
   ///
   /// # Returns
   ///
-  /// A string containing the RESP-encoded representation of the value.
-  pub fn serialize(&self) -> String {
+  /// * `Value::BulkString` - If the bytes are valid UTF-8
+  /// * `Value::BulkBytes` - Otherwise
+  pub fn from_bytes(bytes: Vec<u8>) -> Value {
+    match String::from_utf8(bytes) {
+      Ok(string) => Value::BulkString(string),
+      Err(e) => Value::BulkBytes(e.into_bytes()),
+    }
+  }
+
+  /// Returns the bytes of a string value.
+  ///
+  /// Numbers count as strings holding their decimal representation.
+  ///
+  /// # Returns
+  ///
+  /// * `Some(Vec<u8>)` - The value's bytes
+  /// * `None` - If the value is not a string
+  pub fn to_bytes(&self) -> Option<Vec<u8>> {
     match self {
-      Value::Null => "$-1\r\n".to_string(),
-      Value::SimpleString(s) => format!("+{}\r\n", s),
-      Value::BulkString(s) => format!("${}\r\n{}\r\n", s.len(), s),
-      Value::Integer(i) => format!(":{}\r\n", i),
-      Value::Error(s) => format!("-{}\r\n", s),
-      Value::Boolean(b) => format!("#{}\r\n", if *b { "t" } else { "f" }),
+      Value::SimpleString(s) | Value::BulkString(s) | Value::VerbatimString(_, s) => {
+        Some(s.clone().into_bytes())
+      }
+      Value::BigNumber(digits) => Some(digits.clone().into_bytes()),
+      Value::BulkBytes(bytes) => Some(bytes.clone()),
+      Value::Integer(i) => Some(i.to_string().into_bytes()),
+      Value::Double(d) => Some(format_double(*d).into_bytes()),
+      _ => None,
+    }
+  }
+
+  /// Serializes the value to RESP-encoded bytes.
+  ///
+  /// # Returns
+  ///
+  /// The RESP-encoded representation of the value. Bulk strings are
+  /// written as-is, so the output is not necessarily valid UTF-8.
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut buf = Vec::new();
+    self
+      .serialize_to(&mut buf)
+      .expect("Writing to a Vec never fails");
+    buf
+  }
+
+  /// Writes the value RESP-encoded to a writer.
+  ///
+  /// Nothing is buffered or allocated, arrays write their elements one
+  /// after another, so wrap unbuffered writers in a [`std::io::BufWriter`].
+  ///
+  /// # Arguments
+  ///
+  /// * `w` - Writer the encoded value is written to
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - The whole value was written
+  /// * `Err` - Error returned by the writer
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// let mut buf = Vec::new();
+  /// Value::Integer(42).serialize_to(&mut buf)?;
+  /// assert_eq!(buf, b":42\r\n");
+  /// ```
+  pub fn serialize_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    match self {
+      Value::Null => w.write_all(b"$-1\r\n"),
+      Value::SimpleString(s) => write!(w, "+{}\r\n", s),
+      Value::BulkString(s) => Self::serialize_bulk(w, s.as_bytes()),
+      Value::BulkBytes(bytes) => Self::serialize_bulk(w, bytes),
+      Value::Integer(i) => write!(w, ":{}\r\n", i),
+      Value::Error(s) => write!(w, "-{}\r\n", s),
+      Value::Boolean(b) => w.write_all(if *b { b"#t\r\n" } else { b"#f\r\n" }),
+      // As format_double writes it, without allocating a string
+      Value::Double(d) if d.is_nan() => w.write_all(b",nan\r\n"),
+      Value::Double(d) => write!(w, ",{}\r\n", d),
+      Value::VerbatimString(format, text) => {
+        write!(w, "={}\r\n{}:", format.len() + 1 + text.len(), format)?;
+        w.write_all(text.as_bytes())?;
+        w.write_all(b"\r\n")
+      }
+      Value::BigNumber(digits) => write!(w, "({}\r\n", digits),
       Value::Array(arr) => {
-        let mut s = format!("*{}\r\n", arr.len());
-        for v in arr {
-          s.push_str(&v.serialize());
-        }
-        s
+        Self::serialize_array_header(w, arr.len())?;
+        arr.iter().try_for_each(|v| v.serialize_to(w))
+      }
+      Value::Push(values) => {
+        Self::serialize_push_header(w, values.len())?;
+        values.iter().try_for_each(|v| v.serialize_to(w))
       }
     }
   }
 
+  /// Writes the value the way a RESP2 client expects it.
+  ///
+  /// Verbatim strings, big numbers, doubles, booleans and pushes only exist
+  /// in RESP3. The first three are written as bulk strings of their text,
+  /// booleans as the integers 1 and 0, and pushes as arrays, like Redis
+  /// does. Every other value is written as [`Value::serialize_to`] writes
+  /// it.
+  ///
+  /// # Arguments
+  ///
+  /// * `w` - Writer the encoded value is written to
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - The whole value was written
+  /// * `Err` - Error returned by the writer
+  pub fn serialize_resp2_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    match self {
+      Value::VerbatimString(_, text) => Self::serialize_bulk(w, text.as_bytes()),
+      Value::BigNumber(digits) => Self::serialize_bulk(w, digits.as_bytes()),
+      Value::Double(d) => Self::serialize_bulk(w, format_double(*d).as_bytes()),
+      Value::Boolean(b) => w.write_all(if *b { b":1\r\n" } else { b":0\r\n" }),
+      Value::Array(values) | Value::Push(values) => {
+        Self::serialize_array_header(w, values.len())?;
+        values.iter().try_for_each(|v| v.serialize_resp2_to(w))
+      }
+      value => value.serialize_to(w),
+    }
+  }
+
+  /// Returns the length of the value RESP-encoded, without encoding it in
+  /// memory.
+  pub fn serialized_len(&self) -> usize {
+    let mut counter = ByteCounter(0);
+    self
+      .serialize_to(&mut counter)
+      .expect("Counting bytes never fails");
+    counter.0
+  }
+
+  /// Writes the header of an array of `len` elements, which must be
+  /// followed by the elements themselves.
+  pub fn serialize_array_header<W: Write>(w: &mut W, len: usize) -> io::Result<()> {
+    write!(w, "*{}\r\n", len)
+  }
+
+  /// Writes the header of a push of `len` elements, which must be followed
+  /// by the elements themselves.
+  pub fn serialize_push_header<W: Write>(w: &mut W, len: usize) -> io::Result<()> {
+    write!(w, ">{}\r\n", len)
+  }
+
+  /// Writes a bulk string payload with its length header.
+  fn serialize_bulk<W: Write>(w: &mut W, payload: &[u8]) -> io::Result<()> {
+    write!(w, "${}\r\n", payload.len())?;
+    w.write_all(payload)?;
+    w.write_all(b"\r\n")
+  }
+
   /// Converts a RESP value to a command and arguments.
   ///
   /// Expects an array where the first element is the command name
-  /// and subsequent elements are arguments.
+  /// and subsequent elements are arguments. The parser already decoded
+  /// every element, so arguments are passed on as they are, including
+  /// nested arrays.
+  ///
+  /// An empty array or a null is valid RESP that holds no command, Redis
+  /// ignores those without replying. Anything else is malformed.
   ///
   /// # Returns
   ///
-  /// * `Some((String, Vec<String>))` - Command name (uppercase) and argument list
-  /// * `None` - If the value is not a valid command format
-  pub fn to_command(&self) -> Option<(String, Vec<Value>)> {
-    if let Value::Array(elements) = self {
-      if elements.is_empty() {
-        return None;
-      }
+  /// * `Ok(Some((String, Vec<Value>)))` - Command name (uppercase) and argument list
+  /// * `Ok(None)` - The value holds no command
+  /// * `Err` - The value is not a valid command format
+  pub fn to_command(&self) -> Result<Option<(String, Vec<Value>)>> {
+    let elements = match self {
+      Value::Null => return Ok(None),
+      Value::Array(elements) => elements,
+      _ => bail!("invalid command format"),
+    };
+    let Some((command, args)) = elements.split_first() else {
+      return Ok(None);
+    };
+    let command = match command {
+      Value::BulkString(s) | Value::SimpleString(s) => s.to_uppercase(),
+      _ => bail!("invalid command format"),
+    };
+    Ok(Some((command, args.to_vec())))
+  }
+}
 
-      // Extract the actual command from possibly embedded RESP format
-      let command = match &elements[0] {
-        Value::BulkString(s) => {
-          // Check if the string is in RESP format ($3\r\nset\r\n)
-          if s.starts_with('$') && s.contains("\r\n") {
-            let parts: Vec<&str> = s.split("\r\n").collect(); // Extract actual command from embedded RESP format
-            if parts.len() >= 2 {
-              parts[1].to_string().to_uppercase()
-            } else {
-              s.clone().to_uppercase()
-            }
-          } else {
-            s.clone().to_uppercase()
-          }
-        }
-        Value::SimpleString(s) => s.clone().to_uppercase(),
-        _ => return None,
-      };
-
-      // Extract arguments, preserving their original types
-      let args = elements[1..]
-        .iter()
-        .map(|v| match v {
-          Value::BulkString(s) => {
-            // Check if the string is in RESP format
-            if s.starts_with('$') && s.contains("\r\n") {
-              // Extract actual value from embedded RESP format
-              let parts: Vec<&str> = s.split("\r\n").collect();
-              if parts.len() >= 2 {
-                Value::BulkString(parts[1].to_string())
-              } else {
-                v.clone()
-              }
-            } else if s.starts_with(':') {
-              // Handle numeric values encoded as :100\r\n
-              let num_str = s.trim_start_matches(':').trim_end_matches("\r\n");
-              if let Ok(num) = num_str.parse::<i64>() {
-                Value::Integer(num)
-              } else {
-                v.clone()
-              }
-            } else if s.starts_with("#") {
-              let bool_str = s.trim_start_matches('#').trim_end_matches("\r\n"); // Handle boolean values encoded as #t\r\n or #f\r\n
-              if bool_str == "t" {
-                Value::Boolean(true)
-              } else if bool_str == "f" {
-                Value::Boolean(false)
-              } else {
-                v.clone()
-              }
-            } else if s.starts_with("*") {
-              let mut lines = s.split("\r\n"); // Handle array values encoded as *3\r\n$1\r\n1\r\n$1\r\n2\r\n$1\r\n3\r\n
-
-              // Extract the array header, e.g., "*3"
-              let arr_header = lines.next().unwrap_or("");
-              let arr_length: usize = if arr_header.starts_with('*') {
-                arr_header[1..].parse().unwrap_or(0)
-              } else {
-                0
-              };
-
-              let mut array_values = Vec::with_capacity(arr_length);
-              let mut i = 0;
-
-              while i < arr_length {
-                if let Some(length_str) = lines.next() {
-                  // Skip the length indicator (like $1)
-                  if length_str.starts_with('$') {
-                    if let Some(value) = lines.next() {
-                      array_values.push(Value::BulkString(value.to_string()));
-                      i += 1;
-                    }
-                  }
-                } else {
-                  break;
-                }
-              }
-
-              Value::Array(array_values)
-            } else {
-              v.clone()
-            }
-          }
-          _ => v.clone(),
-        })
-        .collect();
-
-      Some((command, args))
-    } else {
-      None
-    }
+/// Writer that only counts the bytes written to it.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.0 += buf.len();
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+/// Formats a double the way RESP writes it.
+///
+/// Finite numbers use their shortest exact representation, without a
+/// fractional part if it is zero. Infinities are `inf` and `-inf`, NaN is
+/// `nan`.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(format_double(1.5), "1.5");
+/// assert_eq!(format_double(3.0), "3");
+/// assert_eq!(format_double(f64::NEG_INFINITY), "-inf");
+/// ```
+pub fn format_double(d: f64) -> String {
+  if d.is_nan() {
+    "nan".to_string()
+  } else {
+    d.to_string()
   }
 }
+
+/// Estimates the memory a value takes up, in bytes.
+///
+/// Counts the value itself plus its heap payload, recursing into arrays.
+/// Allocator overhead and spare capacity are left out, so the result is an
+/// approximation.
+///
+/// # Arguments
+///
+/// * `value` - The value to measure
+///
+/// # Example
+///
+/// ```
+/// let short = size_of_value(&Value::BulkString("hi".to_string()));
+/// let array = size_of_value(&Value::Array(vec![Value::Integer(1); 100]));
+/// assert!(array > short);
+/// ```
+pub fn size_of_value(value: &Value) -> usize {
+  let payload = match value {
+    Value::SimpleString(s) | Value::BulkString(s) | Value::Error(s) => s.len(),
+    Value::VerbatimString(format, text) => format.len() + text.len(),
+    Value::BigNumber(digits) => digits.len(),
+    Value::BulkBytes(bytes) => bytes.len(),
+    Value::Array(values) | Value::Push(values) => values.iter().map(size_of_value).sum(),
+    Value::Integer(_) | Value::Boolean(_) | Value::Double(_) | Value::Null => 0,
+  };
+  size_of::<Value>() + payload
+}
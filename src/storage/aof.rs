@@ -0,0 +1,470 @@
+//! Append-only file persistence.
+//!
+//! Every command that changes the keyspace is appended to the AOF once it
+//! succeeds, and the file is replayed through a command executor at
+//! startup to rebuild the data. Reads are never logged, the commands logged
+//! are the ones flagged `write` in the command registry.
+//!
+//! # File format
+//!
+//! The file is a sequence of RESP arrays, each holding a command name and
+//! its arguments as they were received. Two entries set the context of the
+//! entries following them, and are only written when it changes:
+//!
+//! ```text
+//! @USER <user id>    the user whose keyspace the commands run against
+//! SELECT <db>        the database they run in
+//! ```
+//!
+//! `@USER` is not a command, replay handles it itself. Commands whose
+//! effect depends on when they run are logged as commands that always do
+//! the same:
+//!
+//! - `SPOP` as `SREM` of the members it popped
+//! - `BLPOP` / `BRPOP` as `LPOP` / `RPOP` of the key that was served
+//! - `MIGRATE` as `DEL` of the moved key, unless `COPY` was given
+//! - `SET`, `GETEX` and `RESTORE` are followed by a `PEXPIREAT` holding the
+//!   key's deadline, so relative expiries don't restart on replay
+//!
+//! Keys evicted to stay within the memory limits are not logged.
+
+use std::{
+  fs::{self, File, OpenOptions},
+  io::{BufWriter, ErrorKind, Write},
+  path::Path,
+  sync::{Mutex, OnceLock},
+  time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use log::{error, info, warn};
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
+
+use super::{
+  entities::Entities,
+  memory::{KeyExpiry, MemoryStore, Store, deadline, is_live},
+};
+use crate::{
+  commands::{executor::CommandExecutor, registry},
+  resp::{
+    parser::{ParserLimits, RespParser},
+    value::{Value, format_double},
+  },
+  utils::settings::{AofFsync, AofSettings},
+};
+
+/// Name of the entry switching the user later entries run as.
+const USER_ENTRY: &str = "@USER";
+
+/// The open AOF, set once at startup if the AOF is enabled.
+static AOF: OnceLock<Aof> = OnceLock::new();
+
+/// Held by write commands while they run and are logged, so the file holds
+/// them in the order they were applied.
+static WRITE_ORDER: AsyncMutex<()> = AsyncMutex::const_new(());
+
+/// An open append-only file.
+struct Aof {
+  /// Buffered writer and the context of the last entry
+  file: Mutex<AofFile>,
+  /// When the file is flushed to disk
+  fsync: AofFsync,
+}
+
+/// Writer of the AOF and the context entries are appended in.
+struct AofFile {
+  /// Buffered writer of the file
+  writer: BufWriter<File>,
+  /// User of the last logged command
+  user: Option<String>,
+  /// Database of the last logged command
+  db: usize,
+}
+
+impl AofFile {
+  /// Appends a command, switching the user and database first if they
+  /// changed since the last one.
+  fn write(&mut self, user: &str, db: usize, command: Vec<Value>) -> Result<()> {
+    if self.user.as_deref() != Some(user) {
+      self.write_entry(vec![bulk(USER_ENTRY), bulk(user)])?;
+      self.user = Some(user.to_string());
+      // Databases are per user, the next command always selects one
+      self.db = usize::MAX;
+    }
+    if self.db != db {
+      self.write_entry(vec![bulk("SELECT"), bulk(db.to_string())])?;
+      self.db = db;
+    }
+    self.write_entry(command)
+  }
+
+  /// Appends a single RESP array.
+  fn write_entry(&mut self, entry: Vec<Value>) -> Result<()> {
+    Value::Array(entry).serialize_to(&mut self.writer)?;
+    Ok(())
+  }
+
+  /// Writes buffered entries to the file, and to disk if `sync` is set.
+  fn flush(&mut self, sync: bool) -> Result<()> {
+    self.writer.flush()?;
+    if sync {
+      self.writer.get_ref().sync_data()?;
+    }
+    Ok(())
+  }
+}
+
+/// Opens the AOF for appending, creating it if needed.
+///
+/// Commands are only logged once this was called, so the file is replayed
+/// first.
+///
+/// # Arguments
+///
+/// * `store` - The memory store, its data is written to a new file first
+/// * `settings` - Settings holding the path and fsync policy
+///
+/// # Returns
+///
+/// * `Ok(())` - The file is open
+/// * `Err` - Error creating or writing the file
+pub fn open(store: &MemoryStore, settings: &AofSettings) -> Result<()> {
+  let path = Path::new(&settings.path);
+  if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+  }
+  let file = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(path)
+    .with_context(|| format!("Failed to open {}", path.display()))?;
+
+  let mut file = AofFile {
+    writer: BufWriter::new(file),
+    user: None,
+    db: 0,
+  };
+  // Data loaded from the KDB file only survives a restart if the AOF holds it
+  if file.writer.get_ref().metadata()?.len() == 0 {
+    let written = write_dataset(&mut file, store)?;
+    if written > 0 {
+      info!(
+        "Wrote {} keys loaded at startup to {}",
+        written,
+        path.display()
+      );
+    }
+  }
+  file.flush(true)?;
+
+  let aof = Aof {
+    file: Mutex::new(file),
+    fsync: settings.fsync,
+  };
+  if AOF.set(aof).is_err() {
+    warn!("AOF already open, ignoring {}", path.display());
+  }
+  info!("Appending write commands to {}", path.display());
+  Ok(())
+}
+
+/// Returns true if `command` is logged, i.e. the AOF is open and the
+/// command may change the keyspace.
+pub fn logs(command: &str) -> bool {
+  AOF.get().is_some() && registry::lookup(command).is_some_and(|spec| spec.write)
+}
+
+/// Waits until no other write command is running, if `command` is logged.
+///
+/// The returned guard is held while the command runs and is logged.
+/// Blocking pops are left out, they wait for other writes to run. So is
+/// MIGRATE, which waits on the target instance and orders its local delete
+/// itself with [`write_order`].
+pub async fn ordered(command: &str) -> Option<AsyncMutexGuard<'static, ()>> {
+  if !logs(command) || matches!(command, "BLPOP" | "BRPOP" | "MIGRATE") {
+    return None;
+  }
+  write_order().await
+}
+
+/// Waits until no other write command is running, if the AOF is open.
+///
+/// For commands that append their own entries, so they only hold up other
+/// writes while they change the keyspace and log it.
+pub async fn write_order() -> Option<AsyncMutexGuard<'static, ()>> {
+  AOF.get()?;
+  Some(WRITE_ORDER.lock().await)
+}
+
+/// Appends a command that succeeded to the AOF.
+///
+/// Failing to write only logs an error, the command already ran.
+///
+/// # Arguments
+///
+/// * `store` - The connection's store, giving the user and database
+/// * `command` - Upper-case command name
+/// * `args` - Arguments as they were received
+/// * `replies` - What the command replied
+pub fn append(store: &MemoryStore, command: &str, args: &[Value], replies: &[Value]) {
+  let Some(aof) = AOF.get() else {
+    return;
+  };
+  let Some(user) = store.get_current_user() else {
+    return;
+  };
+
+  let entries = logged_commands(store, command, args, replies);
+  let mut file = aof.file.lock().unwrap();
+  let written = entries
+    .into_iter()
+    .try_for_each(|entry| file.write(&user, store.selected_db(), entry))
+    .and_then(|()| match aof.fsync {
+      AofFsync::Always => file.flush(true),
+      AofFsync::Everysec | AofFsync::No => Ok(()),
+    });
+  if let Err(e) = written {
+    error!("Failed to append '{}' to the AOF: {:#}", command, e);
+  }
+}
+
+/// Flushes buffered entries, called every second.
+///
+/// With the `everysec` policy the file is also synced to disk, with `no`
+/// the operating system decides when it is.
+pub fn tick() {
+  let Some(aof) = AOF.get() else {
+    return;
+  };
+  let sync = aof.fsync == AofFsync::Everysec;
+  if let Err(e) = aof.file.lock().unwrap().flush(sync) {
+    error!("Failed to flush the AOF: {:#}", e);
+  }
+}
+
+/// Flushes buffered entries and syncs the file to disk, at shutdown.
+pub fn sync() {
+  let Some(aof) = AOF.get() else {
+    return;
+  };
+  if let Err(e) = aof.file.lock().unwrap().flush(true) {
+    error!("Failed to flush the AOF: {:#}", e);
+  }
+}
+
+/// Returns true if the file at `path` has entries to replay.
+pub fn has_entries(path: &str) -> bool {
+  fs::metadata(path).is_ok_and(|metadata| metadata.len() > 0)
+}
+
+/// Rebuilds the keyspace by running every command in the AOF.
+///
+/// A command the file ends in the middle of, as left by a crash, is
+/// removed from the file. Commands that fail are logged and skipped.
+///
+/// # Arguments
+///
+/// * `path` - Path of the AOF
+/// * `executor` - Executor the commands run through, it must be allowed to
+///   run any command
+///
+/// # Returns
+///
+/// * `Ok(usize)` - Number of commands replayed
+/// * `Err` - Error reading the file, or the file is not valid RESP
+pub async fn replay(path: &str, mut executor: CommandExecutor) -> Result<usize> {
+  let data = match fs::read(path) {
+    Ok(data) => data,
+    Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+    Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path)),
+  };
+
+  let mut parser = RespParser::new(ParserLimits {
+    max_array_len: usize::MAX,
+    max_bulk_len: usize::MAX,
+  });
+  let mut buf = data.as_slice();
+  let mut replayed = 0;
+  while !buf.is_empty() {
+    let Some((entry, consumed)) = parser
+      .parse_message(buf)
+      .with_context(|| format!("Failed to parse {}", path))?
+    else {
+      // Cut it off, so commands appended later don't follow a partial one
+      warn!("{} ends with an incomplete command, removing it", path);
+      let valid = (data.len() - buf.len()) as u64;
+      OpenOptions::new()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_len(valid))
+        .with_context(|| format!("Failed to truncate {}", path))?;
+      break;
+    };
+    buf = &buf[consumed..];
+
+    let (command, args) = match entry.to_command() {
+      Ok(Some(command)) => command,
+      Ok(None) => continue,
+      Err(e) => {
+        warn!("Skipping AOF entry: {:#}", e);
+        continue;
+      }
+    };
+    if command == USER_ENTRY {
+      match args.first().and_then(Value::to_bytes) {
+        Some(user) => executor.impersonate(String::from_utf8_lossy(&user).into_owned()),
+        None => warn!("Skipping {} entry without a user", USER_ENTRY),
+      }
+      continue;
+    }
+    if let Err(e) = executor.execute(&command, args).await {
+      warn!("Failed to replay '{}': {:#}", command, e);
+      continue;
+    }
+    replayed += 1;
+  }
+
+  info!("Replayed {} commands from {}", replayed, path);
+  Ok(replayed)
+}
+
+/// Returns the commands logged for a command that succeeded.
+fn logged_commands(
+  store: &MemoryStore,
+  command: &str,
+  args: &[Value],
+  replies: &[Value],
+) -> Vec<Vec<Value>> {
+  let as_received = || {
+    let mut entry = vec![bulk(command)];
+    entry.extend_from_slice(args);
+    entry
+  };
+  let reply = replies.first();
+
+  match command {
+    "SPOP" => {
+      let members = match reply {
+        Some(Value::Array(members)) => members.clone(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(member) => vec![member.clone()],
+      };
+      if members.is_empty() {
+        return Vec::new();
+      }
+      let mut entry = vec![bulk("SREM"), args[0].clone()];
+      entry.extend(members);
+      vec![entry]
+    }
+    "BLPOP" | "BRPOP" => match reply {
+      Some(Value::Array(popped)) if !popped.is_empty() => {
+        let pop = if command == "BLPOP" { "LPOP" } else { "RPOP" };
+        vec![vec![bulk(pop), popped[0].clone()]]
+      }
+      _ => Vec::new(),
+    },
+    // MIGRATE appends its local delete itself, see `write_order`
+    "MIGRATE" => Vec::new(),
+    "SET" | "GETEX" | "RESTORE" => {
+      let mut entries = vec![as_received()];
+      let key = String::from_utf8_lossy(&args[0].to_bytes().unwrap_or_default()).into_owned();
+      if let Ok(KeyExpiry::At(at)) = store.expire_time(&key) {
+        entries.push(vec![
+          bulk("PEXPIREAT"),
+          args[0].clone(),
+          bulk(unix_millis(at).to_string()),
+        ]);
+      }
+      entries
+    }
+    _ => vec![as_received()],
+  }
+}
+
+/// Writes every user's data as commands recreating it.
+///
+/// # Returns
+///
+/// * `Ok(usize)` - Number of keys written
+/// * `Err` - Error writing the file
+fn write_dataset(file: &mut AofFile, store: &MemoryStore) -> Result<usize> {
+  let mut written = 0;
+  for user_store in store.auth_stores.iter() {
+    let databases = user_store.databases.lock().unwrap();
+    for (db, entities) in databases.iter().enumerate() {
+      for (name, entity) in entities.iter() {
+        let key = bulk(name.as_str());
+        let (keys, commands) = match entity {
+          Entities::HashMap(map) if name == "default" => {
+            let map = map.lock().unwrap();
+            let mut commands = Vec::new();
+            let live = map.iter().filter(|(_, entry)| is_live(entry));
+            for (key, entry) in live {
+              let (value, inserted_at, args, _) = entry;
+              commands.push(vec![bulk("SET"), bulk(key.as_str()), value.clone()]);
+              if let Some(at) = deadline(inserted_at, args) {
+                commands.push(vec![
+                  bulk("PEXPIREAT"),
+                  bulk(key.as_str()),
+                  bulk(unix_millis(at).to_string()),
+                ]);
+              }
+            }
+            (map.len(), commands)
+          }
+          Entities::HashMap(map) => {
+            let mut command = vec![bulk("HSET"), key];
+            for (field, (value, ..)) in map.lock().unwrap().iter() {
+              command.extend([bulk(field.as_str()), value.clone()]);
+            }
+            (1, vec![command])
+          }
+          Entities::Set(set) => {
+            let mut command = vec![bulk("SADD"), key];
+            command.extend(
+              set
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|member| bulk(member.as_str())),
+            );
+            (1, vec![command])
+          }
+          Entities::LinkedList(list) => {
+            let mut command = vec![bulk("RPUSH"), key];
+            command.extend(list.lock().unwrap().iter().map(|item| bulk(item.as_str())));
+            (1, vec![command])
+          }
+          Entities::SortedSet(set) => {
+            let mut command = vec![bulk("ZADD"), key];
+            for (member, score) in set.lock().unwrap().iter() {
+              command.extend([bulk(format_double(score)), bulk(member.as_str())]);
+            }
+            (1, vec![command])
+          }
+          // Placeholder entities hold no data yet
+          Entities::_HashSet | Entities::_List | Entities::_Queue => (0, Vec::new()),
+        };
+        written += keys;
+        // Empty collections have nothing to recreate
+        for command in commands.into_iter().filter(|command| command.len() > 2) {
+          file.write(user_store.key(), db, command)?;
+        }
+      }
+    }
+  }
+  Ok(written)
+}
+
+/// Wraps a string as a bulk string argument.
+fn bulk(s: impl Into<String>) -> Value {
+  Value::BulkString(s.into())
+}
+
+/// Converts a time to milliseconds since the Unix epoch.
+fn unix_millis(time: SystemTime) -> u64 {
+  time
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
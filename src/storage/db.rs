@@ -9,10 +9,9 @@ use chrono::{DateTime, Utc};
 use log::{error, info, warn};
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::params;
-use sha3::{Digest, Keccak256};
 use uuid::Uuid;
 
-use crate::utils::settings::Settings;
+use crate::utils::{password::hash_password, settings::Settings};
 
 /// Internal database for persistent storage.
 ///
@@ -142,10 +141,7 @@ impl InternalDB {
       });
 
     // Hash the root user password to store in the database
-    let mut hasher = Keccak256::new();
-    hasher.update(root_password.as_bytes());
-    let root_password_hash = hasher.finalize();
-    let root_password_hash = format!("{:x}", root_password_hash);
+    let root_password_hash = hash_password(&root_password).expect("Failed to hash password");
 
     let time_stamp: DateTime<Utc> = SystemTime::now().into();
     let time_stamp = time_stamp.to_rfc3339();
@@ -177,11 +173,7 @@ impl InternalDB {
     });
 
     // Hash the user password to store in the database
-    let mut hasher = Keccak256::new();
-    hasher.reset();
-    hasher.update(password.as_bytes());
-    let password_hash = hasher.finalize();
-    let password_hash = format!("{:x}", password_hash);
+    let password_hash = hash_password(&password).expect("Failed to hash password");
 
     // Create the regular user
     match conn.execute(
@@ -4,10 +4,12 @@
 //! in the key-value server.
 
 use std::collections::{HashMap, HashSet, LinkedList};
+use std::ops::{Deref, DerefMut};
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 
 use crate::commands::general::set::Options;
+use crate::ds::zset::KvSortedSet;
 use crate::resp::value::Value;
 
 /// @NOTE Helper type aliases
@@ -15,9 +17,11 @@ use crate::resp::value::Value;
 
 /// Helper type for storing key-value pairs with optional modifiers.
 pub type KvMapArgs = HashMap<Options, u64>;
-/// Represents a the Value as the first element and arguments map as the last element
-/// and the SystemTime as the second element to store the time of insertion.
-pub type KvMapPair = (Value, SystemTime, KvMapArgs);
+/// Represents a the Value as the first element, the SystemTime it was stored
+/// at as the second element, its arguments map as the third element and the
+/// SystemTime of its last access as the last element. EX and PX expiries
+/// count from the time the value was stored.
+pub type KvMapPair = (Value, SystemTime, KvMapArgs, SystemTime);
 
 /// -------------------------------------------------------------------
 
@@ -30,17 +34,72 @@ pub type KvHashMap = HashMap<String, KvMapPair>;
 /// A linked list of string values.
 pub type KvLinkedList = LinkedList<String>;
 
+/// A collection along with the time its key was created and last accessed.
+///
+/// Dereferences to the collection, so it can be used in its place. A
+/// database's default map keeps these times for every string instead, its
+/// own are left unused.
+#[derive(Clone, Debug)]
+pub struct Tracked<T> {
+  /// The collection itself
+  data: T,
+  /// Time the key was created
+  pub inserted_at: SystemTime,
+  /// Time the key was last read or written
+  pub accessed_at: SystemTime,
+}
+
+impl<T> Tracked<T> {
+  /// Wraps a collection whose key is created now.
+  pub fn new(data: T) -> Self {
+    let now = SystemTime::now();
+    Self {
+      data,
+      inserted_at: now,
+      accessed_at: now,
+    }
+  }
+
+  /// Records that the key was just read or written.
+  pub fn touch(&mut self) {
+    self.accessed_at = SystemTime::now();
+  }
+}
+
+impl<T: Default> Default for Tracked<T> {
+  fn default() -> Self {
+    Self::new(T::default())
+  }
+}
+
+impl<T> Deref for Tracked<T> {
+  type Target = T;
+
+  fn deref(&self) -> &T {
+    &self.data
+  }
+}
+
+impl<T> DerefMut for Tracked<T> {
+  fn deref_mut(&mut self) -> &mut T {
+    &mut self.data
+  }
+}
+
 /// Enum representing different types of data structures for storage.
 #[derive(Debug)]
 pub enum Entities {
   /// A set of unique string values.
-  _Set(Arc<Mutex<KvSet>>),
+  Set(Arc<Mutex<Tracked<KvSet>>>),
 
   /// A map of string keys to RESP values.
-  HashMap(Arc<Mutex<KvHashMap>>),
+  HashMap(Arc<Mutex<Tracked<KvHashMap>>>),
 
   /// A linked list of string values.
-  _LinkedList(Arc<Mutex<KvLinkedList>>),
+  LinkedList(Arc<Mutex<Tracked<KvLinkedList>>>),
+
+  /// A set of unique string values ordered by score.
+  SortedSet(Arc<Mutex<Tracked<KvSortedSet>>>),
 
   /// A hash set (placeholder for future implementation).
   _HashSet,
@@ -51,3 +110,69 @@ pub enum Entities {
   /// A queue (placeholder for future implementation).
   _Queue,
 }
+
+impl Entities {
+  /// Returns the kind of value the entity holds, as commands see it.
+  pub fn kind(&self) -> EntityKind {
+    match self {
+      Entities::HashMap(_) => EntityKind::Hash,
+      Entities::Set(_) | Entities::_HashSet => EntityKind::Set,
+      Entities::LinkedList(_) | Entities::_List | Entities::_Queue => EntityKind::List,
+      Entities::SortedSet(_) => EntityKind::SortedSet,
+    }
+  }
+
+  /// Returns the time the entity's key was created and the time it was
+  /// last read or written, in that order.
+  ///
+  /// Placeholders have none. The times of a database's default map mean
+  /// nothing, its strings keep their own.
+  pub fn times(&self) -> Option<(SystemTime, SystemTime)> {
+    fn of<T>(tracked: &Mutex<Tracked<T>>) -> (SystemTime, SystemTime) {
+      let tracked = tracked.lock().unwrap();
+      (tracked.inserted_at, tracked.accessed_at)
+    }
+    match self {
+      Entities::Set(set) => Some(of(set)),
+      Entities::HashMap(hash) => Some(of(hash)),
+      Entities::LinkedList(list) => Some(of(list)),
+      Entities::SortedSet(zset) => Some(of(zset)),
+      Entities::_HashSet | Entities::_List | Entities::_Queue => None,
+    }
+  }
+
+  /// Records that the entity's key was just read or written.
+  ///
+  /// # Returns
+  ///
+  /// * `true` - The access was recorded
+  /// * `false` - The entity is a placeholder, which tracks no times
+  pub fn touch(&self) -> bool {
+    match self {
+      Entities::Set(set) => set.lock().unwrap().touch(),
+      Entities::HashMap(hash) => hash.lock().unwrap().touch(),
+      Entities::LinkedList(list) => list.lock().unwrap().touch(),
+      Entities::SortedSet(zset) => zset.lock().unwrap().touch(),
+      Entities::_HashSet | Entities::_List | Entities::_Queue => return false,
+    }
+    true
+  }
+}
+
+/// Kind of value a key holds.
+///
+/// Strings live in a database's default map, every other kind is an entity
+/// named after its key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKind {
+  /// A string, kept in the default map
+  String,
+  /// A list
+  List,
+  /// A set
+  Set,
+  /// A sorted set
+  SortedSet,
+  /// A hash
+  Hash,
+}
@@ -0,0 +1,157 @@
+//! Key eviction under memory pressure.
+//!
+//! When a write would take a user over `maxmemory`, keys are evicted from
+//! the user's databases according to the configured [`EvictionPolicy`]
+//! until the new data fits.
+
+use std::{
+  borrow::Cow,
+  collections::HashMap,
+  time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+  entities::{Entities, KvMapArgs},
+  memory::{default_map, is_expired},
+};
+use crate::{
+  commands::general::set::Options,
+  resp::error::{CommandError, ErrorCode},
+};
+
+/// Error returned when a write doesn't fit in `maxmemory` and nothing can be evicted.
+pub const OOM: CommandError = CommandError {
+  code: ErrorCode::Oom,
+  message: Cow::Borrowed("command not allowed when used memory > 'maxmemory'"),
+};
+
+/// Which keys are evicted when a user is over `maxmemory`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvictionPolicy {
+  /// Nothing is evicted, writes that don't fit fail instead
+  #[default]
+  #[serde(rename = "noeviction")]
+  NoEviction,
+  /// The least recently used key is evicted
+  #[serde(rename = "allkeys-lru")]
+  AllKeysLru,
+  /// The key closest to expiring is evicted, keys without an expiry are kept
+  #[serde(rename = "volatile-ttl")]
+  VolatileTtl,
+}
+
+impl EvictionPolicy {
+  /// Returns the policy's name as used in the configuration.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::NoEviction => "noeviction",
+      Self::AllKeysLru => "allkeys-lru",
+      Self::VolatileTtl => "volatile-ttl",
+    }
+  }
+
+  /// Looks up a policy by its name as used in the configuration.
+  pub fn from_name(name: &str) -> Option<Self> {
+    [Self::NoEviction, Self::AllKeysLru, Self::VolatileTtl]
+      .into_iter()
+      .find(|policy| policy.as_str().eq_ignore_ascii_case(name))
+  }
+}
+
+/// Per-user memory budget and what happens once it is reached.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Eviction {
+  /// Approximate bytes a user may store before keys are evicted, 0 for unlimited
+  pub maxmemory: usize,
+  /// Which keys are evicted
+  pub policy: EvictionPolicy,
+}
+
+/// Picks the next key to evict from a user's databases.
+///
+/// Keys that already expired are picked before any other, under every
+/// policy. Every key is looked at, so this is linear in the number of keys
+/// the user holds.
+///
+/// # Arguments
+///
+/// * `policy` - Policy deciding which keys may be evicted first
+/// * `databases` - The user's databases
+/// * `keep` - Database index and names of the keys being written, never picked
+///
+/// # Returns
+///
+/// * `Some((db, key))` - The key to evict and the index of its database
+/// * `None` - If no key may be evicted
+pub(super) fn pick_victim(
+  policy: EvictionPolicy,
+  databases: &[HashMap<String, Entities>],
+  keep: (usize, &[&str]),
+) -> Option<(usize, String)> {
+  let mut victim: Option<((bool, SystemTime), usize, String)> = None;
+
+  for (db, entities) in databases.iter().enumerate() {
+    // Collections never expire, only LRU evicts them
+    if policy == EvictionPolicy::AllKeysLru {
+      for (key, entity) in entities {
+        if key == "default" || db == keep.0 && keep.1.contains(&key.as_str()) {
+          continue;
+        }
+        let Some((_, accessed_at)) = entity.times() else {
+          continue;
+        };
+        let rank = (true, accessed_at);
+        if victim.as_ref().is_none_or(|(best, ..)| rank < *best) {
+          victim = Some((rank, db, key.clone()));
+        }
+      }
+    }
+
+    let Some(map) = default_map(entities) else {
+      continue;
+    };
+    let map = map.lock().unwrap();
+
+    for (key, (_, stamp, args, accessed_at)) in map.iter() {
+      if db == keep.0 && keep.1.contains(&key.as_str()) {
+        continue;
+      }
+
+      // Expired keys sort first, then by the policy's own order
+      let rank = if is_expired(stamp, args) {
+        (false, SystemTime::UNIX_EPOCH)
+      } else {
+        match policy {
+          EvictionPolicy::NoEviction => continue,
+          EvictionPolicy::AllKeysLru => (true, *accessed_at),
+          EvictionPolicy::VolatileTtl => match deadline(stamp, args) {
+            Some(deadline) => (true, deadline),
+            None => continue,
+          },
+        }
+      };
+
+      if victim.as_ref().is_none_or(|(best, ..)| rank < *best) {
+        victim = Some((rank, db, key.clone()));
+      }
+    }
+  }
+
+  victim.map(|(_, db, key)| (db, key))
+}
+
+/// Returns the moment an entry stored at `stamp` with `args` expires, if it does.
+fn deadline(stamp: &SystemTime, args: &KvMapArgs) -> Option<SystemTime> {
+  let ex = args
+    .get(&Options::Ex)
+    .map(|&secs| Duration::from_secs(secs));
+  let px = args.get(&Options::Px).map(|&ms| Duration::from_millis(ms));
+
+  let expiry = match (ex, px) {
+    (Some(ex), Some(px)) => ex.min(px),
+    (ex, px) => ex.or(px)?,
+  };
+  Some(*stamp + expiry)
+}
@@ -1,6 +1,623 @@
-/// KDB storage configuration
-pub struct KDB {
-  file_name: String,
-  persistence: bool,
-  backup_interval: u64,
+//! KDB snapshot persistence.
+//!
+//! Serializes every authenticated user's entities into a single KDB file so
+//! the in-memory data can survive a restart, and loads it back on startup.
+//!
+//! # File format
+//!
+//! All integers are big-endian and strings are a `u32` length followed by
+//! the UTF-8 bytes.
+//!
+//! ```text
+//! "RKDB" | version: u8
+//! record* :
+//!   0x01 | user id: str | db index: u32 | entity name: str | entity type: u8 | key: str
+//!        | value | inserted at (unix millis): u64 | expires at (unix millis, 0 if never): u64
+//!        | arg count: u8 | (option: u8 | value: u64)*
+//! 0xFF
+//! ```
+//!
+//! A value is a type tag followed by its payload (see [`crate::resp::serde`]).
+//! Set and list entities store each member as the key of a record with a
+//! null value, in iteration order. Sorted sets store each member as the key
+//! of a record with its score as a double value. Version 1 files predate numbered
+//! databases and have no db index, their records are loaded into database 0.
+//! Files written before users had ids hold each user's legacy credential
+//! hash in place of the id, the data is moved over when the user next
+//! authenticates.
+//!
+//! Expiries are written as absolute deadlines, so a key keeps the time it
+//! had left minus the time the server was down. Versions 1 and 2 have no
+//! deadline field and keep `EX`/`PX` among the args instead, counted from
+//! the insertion time.
+
+use std::{
+  collections::HashMap,
+  fs,
+  io::{ErrorKind, Write},
+  path::{Path, PathBuf},
+  sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering},
+  },
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use bytes::{Buf, BufMut};
+use log::{debug, error, info, warn};
+use tokio::task::JoinHandle;
+
+use super::{
+  entities::{Entities, KvMapArgs, Tracked},
+  memory::{MemoryStore, UserStore, deadline, is_expired},
+};
+use crate::{
+  commands::general::set::Options,
+  resp::{
+    serde::{decode_value, encode_value, get_str, get_u8, get_u32, get_u64, put_str},
+    value::Value,
+  },
+  utils::settings::Settings,
+};
+
+/// Magic bytes at the start of every KDB file.
+const MAGIC: &[u8; 4] = b"RKDB";
+/// Current version of the file format.
+const VERSION: u8 = 3;
+/// Last version without a db index in its records.
+const VERSION_SINGLE_DB: u8 = 1;
+/// Last version storing expiries as `EX`/`PX` args instead of deadlines.
+const VERSION_RELATIVE_EXPIRY: u8 = 2;
+
+/// Record opcode for a single stored entry.
+const OP_ENTRY: u8 = 0x01;
+/// Opcode marking the end of the file.
+const OP_EOF: u8 = 0xFF;
+
+/// How long a save point waits after starting a save before starting another.
+const SAVE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Entity type tags.
+const ENTITY_HASHMAP: u8 = 0;
+const ENTITY_SET: u8 = 1;
+const ENTITY_LIST: u8 = 2;
+const ENTITY_SORTED_SET: u8 = 3;
+
+/// Progress of the saves made from a memory store, shared by all its
+/// handles.
+#[derive(Debug, Default)]
+pub(super) struct SaveState {
+  /// Held for a whole save, from taking the snapshot to renaming the file
+  /// into place, so concurrent saves never write to the same temporary file
+  /// and an older snapshot never replaces a newer one
+  lock: Mutex<()>,
+  /// Set while a background save is running
+  bgsave_in_progress: AtomicBool,
+  /// How long the last successful save took, in microseconds
+  last_save_duration: AtomicU64,
+  /// Unix time of the last successful save, in seconds, 0 if there was none
+  last_save: AtomicI64,
+}
+
+/// A point-in-time copy of every user's databases, keyed by user id.
+type Snapshot = HashMap<String, Vec<HashMap<String, Entities>>>;
+
+/// Returns the full path of the KDB file from the settings.
+pub fn file_path(settings: &Settings) -> PathBuf {
+  Path::new(&settings.server.kdb.path).join(&settings.server.kdb.file_name)
+}
+
+/// Saves every user's entities to the configured KDB file.
+///
+/// The snapshot is written to a temporary file first and then renamed over
+/// the old one, so a crash mid-write never leaves a corrupted KDB file.
+///
+/// # Arguments
+///
+/// * `store` - The memory store to snapshot
+/// * `settings` - Settings holding the KDB path and file name
+///
+/// # Returns
+///
+/// * `Ok(())` - The snapshot was written
+/// * `Err` - Error creating or writing the file
+pub fn save(store: &MemoryStore, settings: &Settings) -> Result<()> {
+  let saves = store.saves();
+  let _guard = saves.lock.lock().unwrap();
+
+  let started = Instant::now();
+  let path = file_path(settings);
+  // Changes made while the file is written are not in it, they stay counted
+  let changes = store.changes_since_last_save();
+  let data = encode(&snapshot(store));
+
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+  }
+
+  let tmp_path = path.with_extension("tmp");
+  let mut file = fs::File::create(&tmp_path)
+    .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+  file.write_all(&data)?;
+  file.sync_all()?;
+  fs::rename(&tmp_path, &path)
+    .with_context(|| format!("Failed to move snapshot to {}", path.display()))?;
+
+  let elapsed = u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+  saves.last_save_duration.store(elapsed, Ordering::Relaxed);
+  saves.last_save.store(unix_time(), Ordering::Relaxed);
+  store.mark_saved(changes);
+
+  info!("Saved {} bytes to KDB file {}", data.len(), path.display());
+  Ok(())
+}
+
+/// Returns true while a background save of `store` is running.
+pub fn bgsave_in_progress(store: &MemoryStore) -> bool {
+  store.saves().bgsave_in_progress.load(Ordering::Acquire)
+}
+
+/// Returns how long the last successful save of `store` took, in
+/// microseconds, or 0 if it wasn't saved since startup.
+pub fn last_save_duration(store: &MemoryStore) -> u64 {
+  store.saves().last_save_duration.load(Ordering::Relaxed)
+}
+
+/// Returns the Unix time of the last successful save of `store`, in
+/// seconds, or 0 if it wasn't saved since startup.
+pub fn last_save(store: &MemoryStore) -> i64 {
+  store.saves().last_save.load(Ordering::Relaxed)
+}
+
+/// Returns the first save point that is reached, if any.
+///
+/// # Arguments
+///
+/// * `save_points` - `(seconds, changes)` pairs from the settings
+/// * `changes` - Changes made since the last save
+/// * `elapsed` - Seconds since the last save, or since startup
+pub fn save_point_reached(
+  save_points: &[(u64, u64)],
+  changes: u64,
+  elapsed: u64,
+) -> Option<(u64, u64)> {
+  save_points
+    .iter()
+    .copied()
+    .find(|&(seconds, min_changes)| changes >= min_changes.max(1) && elapsed >= seconds)
+}
+
+/// Starts saving the memory store to the KDB file in the background.
+///
+/// Only one background save runs at a time. Since the caller has already
+/// moved on by the time the save finishes, failures are only logged.
+///
+/// # Arguments
+///
+/// * `store` - The memory store to snapshot
+/// * `settings` - Settings holding the KDB path and file name
+///
+/// # Returns
+///
+/// * `Ok(())` - The background save was started
+/// * `Err` - A background save is already in progress
+pub fn background_save(store: MemoryStore, settings: Settings) -> Result<()> {
+  if store
+    .saves()
+    .bgsave_in_progress
+    .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+    .is_err()
+  {
+    bail!("Background save already in progress");
+  }
+
+  tokio::task::spawn_blocking(move || {
+    match save(&store, &settings) {
+      Ok(()) => info!("Background saving terminated with success"),
+      Err(e) => error!("Background saving failed: {:#}", e),
+    }
+    store
+      .saves()
+      .bgsave_in_progress
+      .store(false, Ordering::Release);
+  });
+  Ok(())
+}
+
+/// Starts a task saving the memory store in the background whenever one of
+/// the configured save points is reached, like Redis' `save` directive.
+///
+/// The save points are checked once per second. After starting a save, the
+/// task waits [`SAVE_RETRY_DELAY`] before trying again, so a failing save is
+/// not retried in a tight loop.
+///
+/// # Arguments
+///
+/// * `store` - The memory store to snapshot
+/// * `settings` - Settings holding the save points and the KDB file
+///
+/// # Returns
+///
+/// * `JoinHandle<()>` - Handle of the task, which runs until it is aborted
+pub fn spawn_save_points(store: MemoryStore, settings: Settings) -> JoinHandle<()> {
+  tokio::spawn(async move {
+    let started = Instant::now();
+    let mut last_try: Option<Instant> = None;
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+      interval.tick().await;
+      // Give a failing save some time before trying again
+      if last_try.is_some_and(|at| at.elapsed() < SAVE_RETRY_DELAY) {
+        continue;
+      }
+
+      let since_save = match last_save(&store) {
+        0 => started.elapsed().as_secs(),
+        at => (unix_time() - at).max(0) as u64,
+      };
+      let changes = store.changes_since_last_save();
+      let Some((seconds, min_changes)) =
+        save_point_reached(&settings.server.kdb.save_points, changes, since_save)
+      else {
+        continue;
+      };
+
+      info!(
+        "{} changes in {} seconds, saving (save point {} {})",
+        changes, since_save, seconds, min_changes
+      );
+      last_try = Some(Instant::now());
+      if let Err(e) = background_save(store.clone(), settings.clone()) {
+        info!("Skipping save point: {:#}", e);
+      }
+    }
+  })
+}
+
+/// Loads the configured KDB file into the memory store.
+///
+/// A missing or empty file is not an error, the store simply starts empty.
+/// Keys whose expiry already passed are dropped while loading. The file is
+/// decoded completely before anything is added to the store, so a corrupted
+/// file, or one using more databases than the store is configured with,
+/// leaves the store untouched.
+///
+/// # Arguments
+///
+/// * `store` - The memory store to populate
+/// * `settings` - Settings holding the KDB path and file name
+///
+/// # Returns
+///
+/// * `Ok(usize)` - Number of records loaded
+/// * `Err` - Error reading or decoding the file
+pub fn load(store: &MemoryStore, settings: &Settings) -> Result<usize> {
+  let path = file_path(settings);
+  let data = match fs::read(&path) {
+    Ok(data) => data,
+    Err(e) if e.kind() == ErrorKind::NotFound => {
+      warn!("No KDB file found at {}, starting empty", path.display());
+      return Ok(0);
+    }
+    Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+  };
+
+  if data.is_empty() {
+    warn!("KDB file {} is empty, starting empty", path.display());
+    return Ok(0);
+  }
+
+  let (users, loaded) = decode(&data, store.databases())
+    .with_context(|| format!("Failed to decode {}", path.display()))?;
+
+  for (user_id, user_store) in users {
+    store.auth_stores.insert(user_id, user_store);
+  }
+
+  info!("Loaded {} records from KDB file {}", loaded, path.display());
+  Ok(loaded)
+}
+
+/// Copies every user's entities out of the store.
+///
+/// Locks are only held while copying, so the slow encoding and writing
+/// never blocks other connections.
+fn snapshot(store: &MemoryStore) -> Snapshot {
+  store
+    .auth_stores
+    .iter()
+    .map(|user_store| {
+      let databases = user_store.databases.lock().unwrap();
+      let copy = databases.iter().map(copy_entities).collect();
+      (user_store.key().clone(), copy)
+    })
+    .collect()
+}
+
+/// Deep-copies the entities of a single database.
+fn copy_entities(entities: &HashMap<String, Entities>) -> HashMap<String, Entities> {
+  entities
+    .iter()
+    .filter_map(|(name, entity)| {
+      let entity = match entity {
+        Entities::HashMap(map) => {
+          Entities::HashMap(Arc::new(Mutex::new(map.lock().unwrap().clone())))
+        }
+        Entities::Set(set) => Entities::Set(Arc::new(Mutex::new(set.lock().unwrap().clone()))),
+        Entities::LinkedList(list) => {
+          Entities::LinkedList(Arc::new(Mutex::new(list.lock().unwrap().clone())))
+        }
+        Entities::SortedSet(set) => {
+          Entities::SortedSet(Arc::new(Mutex::new(set.lock().unwrap().clone())))
+        }
+        // Placeholder entities hold no data yet
+        Entities::_HashSet | Entities::_List | Entities::_Queue => return None,
+      };
+      Some((name.clone(), entity))
+    })
+    .collect()
+}
+
+/// Encodes a snapshot into the KDB format.
+fn encode(snapshot: &Snapshot) -> Vec<u8> {
+  let mut buf = Vec::new();
+  buf.put_slice(MAGIC);
+  buf.put_u8(VERSION);
+
+  for (user_id, databases) in snapshot.iter() {
+    for (db, entities) in databases.iter().enumerate() {
+      for (name, entity) in entities.iter() {
+        let header = EntryHeader {
+          user_id,
+          db: db as u32,
+          entity: name,
+        };
+        match entity {
+          Entities::HashMap(map) => {
+            let map = map.lock().unwrap();
+            for (key, (value, inserted_at, args, _)) in map.iter() {
+              put_entry_header(&mut buf, &header, ENTITY_HASHMAP, key);
+              encode_value(&mut buf, value);
+              put_entry_meta(&mut buf, *inserted_at, args);
+            }
+          }
+          Entities::Set(set) => {
+            let set = set.lock().unwrap();
+            for member in set.iter() {
+              put_entry_header(&mut buf, &header, ENTITY_SET, member);
+              encode_value(&mut buf, &Value::Null);
+              put_entry_meta(&mut buf, SystemTime::now(), &KvMapArgs::new());
+            }
+          }
+          Entities::LinkedList(list) => {
+            let list = list.lock().unwrap();
+            for item in list.iter() {
+              put_entry_header(&mut buf, &header, ENTITY_LIST, item);
+              encode_value(&mut buf, &Value::Null);
+              put_entry_meta(&mut buf, SystemTime::now(), &KvMapArgs::new());
+            }
+          }
+          Entities::SortedSet(set) => {
+            let set = set.lock().unwrap();
+            for (member, score) in set.iter() {
+              put_entry_header(&mut buf, &header, ENTITY_SORTED_SET, member);
+              encode_value(&mut buf, &Value::Double(score));
+              put_entry_meta(&mut buf, SystemTime::now(), &KvMapArgs::new());
+            }
+          }
+          // Placeholder entities hold no data yet
+          Entities::_HashSet | Entities::_List | Entities::_Queue => {}
+        }
+      }
+    }
+  }
+
+  buf.put_u8(OP_EOF);
+  buf
+}
+
+/// Identifies the entity a record belongs to.
+struct EntryHeader<'a> {
+  /// Id of the owning user
+  user_id: &'a str,
+  /// Index of the database holding the entity
+  db: u32,
+  /// Name of the entity
+  entity: &'a str,
+}
+
+/// Writes the fields identifying a record.
+fn put_entry_header(buf: &mut Vec<u8>, header: &EntryHeader, entity_type: u8, key: &str) {
+  buf.put_u8(OP_ENTRY);
+  put_str(buf, header.user_id);
+  buf.put_u32(header.db);
+  put_str(buf, header.entity);
+  buf.put_u8(entity_type);
+  put_str(buf, key);
+}
+
+/// Writes the insertion time, expiry deadline and remaining arguments of
+/// a record.
+fn put_entry_meta(buf: &mut Vec<u8>, inserted_at: SystemTime, args: &KvMapArgs) {
+  buf.put_u64(unix_millis(inserted_at));
+  buf.put_u64(deadline(&inserted_at, args).map_or(0, unix_millis));
+
+  let args: Vec<_> = args
+    .iter()
+    .filter(|(option, _)| !matches!(option, Options::Ex | Options::Px))
+    .collect();
+  buf.put_u8(args.len() as u8);
+  for (option, value) in args {
+    buf.put_u8(option_tag(option));
+    buf.put_u64(*value);
+  }
+}
+
+/// Converts a time to milliseconds since the Unix epoch, 0 if it is before.
+fn unix_millis(time: SystemTime) -> u64 {
+  time
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+/// Returns the current Unix time in seconds.
+fn unix_time() -> i64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |since| since.as_secs() as i64)
+}
+
+/// Maps a SET option to its tag in the file.
+fn option_tag(option: &Options) -> u8 {
+  match option {
+    Options::Ex => 0,
+    Options::Px => 1,
+    Options::Nx => 2,
+    Options::Xx => 3,
+    Options::Get => 4,
+    Options::KeepTtl => 5,
+  }
+}
+
+/// Decodes a KDB file into user stores keyed by user id.
+///
+/// # Arguments
+///
+/// * `buf` - Contents of the KDB file
+/// * `databases` - Number of databases every user store is created with
+///
+/// # Returns
+///
+/// The decoded user stores and the number of records kept.
+fn decode(mut buf: &[u8], databases: usize) -> Result<(HashMap<String, UserStore>, usize)> {
+  if buf.len() < MAGIC.len() + 1 || &buf[..MAGIC.len()] != MAGIC {
+    bail!("Not a KDB file");
+  }
+  buf.advance(MAGIC.len());
+
+  let version = buf.get_u8();
+  if !(VERSION_SINGLE_DB..=VERSION).contains(&version) {
+    bail!("Unsupported KDB version {}", version);
+  }
+
+  let mut users: HashMap<String, Vec<HashMap<String, Entities>>> = HashMap::new();
+  let mut loaded = 0;
+
+  loop {
+    match get_u8(&mut buf)? {
+      OP_EOF => break,
+      OP_ENTRY => {}
+      op => bail!("Unknown record opcode {:#x}", op),
+    }
+
+    let user_id = get_str(&mut buf)?;
+    let db = match version {
+      VERSION_SINGLE_DB => 0,
+      _ => get_u32(&mut buf)? as usize,
+    };
+    if db >= databases {
+      bail!(
+        "Record in database {} but only {} databases are configured",
+        db,
+        databases
+      );
+    }
+    let entity = get_str(&mut buf)?;
+    let entity_type = get_u8(&mut buf)?;
+    let key = get_str(&mut buf)?;
+    let value = decode_value(&mut buf)?;
+
+    let inserted_millis = get_u64(&mut buf)?;
+    let inserted_at = UNIX_EPOCH + Duration::from_millis(inserted_millis);
+    let expires_at = match version {
+      VERSION_SINGLE_DB | VERSION_RELATIVE_EXPIRY => 0,
+      _ => get_u64(&mut buf)?,
+    };
+    let mut args = KvMapArgs::new();
+    for _ in 0..get_u8(&mut buf)? {
+      let option = option_from_tag(get_u8(&mut buf)?)?;
+      args.insert(option, get_u64(&mut buf)?);
+    }
+    // Stored in memory as PX counted from the insertion time, so the key
+    // expires at the same deadline
+    if expires_at != 0 {
+      args.insert(Options::Px, expires_at.saturating_sub(inserted_millis));
+    }
+
+    let entities = &mut users
+      .entry(user_id)
+      .or_insert_with(|| (0..databases).map(|_| HashMap::new()).collect())[db];
+    match entity_type {
+      ENTITY_HASHMAP => {
+        if is_expired(&inserted_at, &args) {
+          debug!("Dropping expired key '{}' while loading", key);
+          continue;
+        }
+        let entry = entities
+          .entry(entity)
+          .or_insert_with(|| Entities::HashMap(Arc::new(Mutex::new(Tracked::default()))));
+        let Entities::HashMap(map) = entry else {
+          bail!("Entity type mismatch for '{}'", key);
+        };
+        map
+          .lock()
+          .unwrap()
+          .insert(key, (value, inserted_at, args, inserted_at));
+      }
+      ENTITY_SET => {
+        let entry = entities
+          .entry(entity)
+          .or_insert_with(|| Entities::Set(Arc::new(Mutex::new(Tracked::default()))));
+        let Entities::Set(set) = entry else {
+          bail!("Entity type mismatch for '{}'", key);
+        };
+        set.lock().unwrap().insert(key);
+      }
+      ENTITY_LIST => {
+        let entry = entities
+          .entry(entity)
+          .or_insert_with(|| Entities::LinkedList(Arc::new(Mutex::new(Tracked::default()))));
+        let Entities::LinkedList(list) = entry else {
+          bail!("Entity type mismatch for '{}'", key);
+        };
+        list.lock().unwrap().push_back(key);
+      }
+      ENTITY_SORTED_SET => {
+        let Value::Double(score) = value else {
+          bail!("Sorted set member '{}' has no score", key);
+        };
+        let entry = entities
+          .entry(entity)
+          .or_insert_with(|| Entities::SortedSet(Arc::new(Mutex::new(Tracked::default()))));
+        let Entities::SortedSet(set) = entry else {
+          bail!("Entity type mismatch for '{}'", key);
+        };
+        set.lock().unwrap().insert(key, score);
+      }
+      other => bail!("Unknown entity type {}", other),
+    }
+    loaded += 1;
+  }
+
+  let users = users
+    .into_iter()
+    .map(|(hash, databases)| (hash, UserStore::from_databases(databases)))
+    .collect();
+  Ok((users, loaded))
+}
+
+/// Maps a tag from the file back to its SET option.
+fn option_from_tag(tag: u8) -> Result<Options> {
+  match tag {
+    0 => Ok(Options::Ex),
+    1 => Ok(Options::Px),
+    2 => Ok(Options::Nx),
+    3 => Ok(Options::Xx),
+    4 => Ok(Options::Get),
+    5 => Ok(Options::KeepTtl),
+    _ => Err(anyhow!("Unknown option tag {}", tag)),
+  }
 }
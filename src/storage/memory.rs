@@ -2,67 +2,3054 @@
 //!
 //! Provides a thread-safe, multi-user in-memory data store with support
 //! for different entity types (HashMaps, Sets) and authentication.
+//! Every user's data is split into a fixed number of numbered logical
+//! databases, selected per connection with `SELECT`.
 
 use std::{
-  collections::HashMap,
-  sync::{Arc, Mutex, RwLock},
-  time::SystemTime,
+  borrow::Cow,
+  collections::{HashMap, HashSet, VecDeque},
+  sync::{
+    Arc, Mutex, RwLock,
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+  },
+  time::{Duration, SystemTime},
 };
 
+use anyhow::{Result, anyhow, bail};
+use dashmap::{DashMap, mapref::entry::Entry};
 use log::{debug, info};
+use rand::seq::{IndexedRandom, IteratorRandom};
+use tokio::sync::{mpsc, oneshot};
 
-use super::entities::{Entities, KvHashMap};
-use crate::{commands::general::set::Options, resp::value::Value};
+use super::{
+  entities::{Entities, EntityKind, KvHashMap, KvLinkedList, KvMapArgs, KvMapPair, KvSet, Tracked},
+  eviction::{self, Eviction},
+  kdb::SaveState,
+  notify::{EventClass, Notifier},
+};
+use crate::{
+  commands::general::set::Options,
+  ds::zset::KvSortedSet,
+  resp::{
+    error::{CommandError, ErrorCode},
+    value::{Value, size_of_value},
+  },
+  utils::glob,
+};
+
+/// Number of logical databases per user when not configured otherwise.
+pub const DEFAULT_DATABASES: usize = 16;
+
+/// Values made of at least this many elements are freed in the background
+/// by [`MemoryStore::lazy_free`]. Smaller values are cheaper to drop right
+/// away than to hand over to another task.
+pub const LAZY_FREE_THRESHOLD: usize = 64;
+
+/// Error returned when a command needs an authenticated connection.
+pub const AUTH_REQUIRED: CommandError = CommandError {
+  code: ErrorCode::NoAuth,
+  message: Cow::Borrowed("Authentication required."),
+};
+
+/// Error returned when a command meets a value of a type it can't handle.
+pub const WRONG_TYPE: CommandError = CommandError {
+  code: ErrorCode::WrongType,
+  message: Cow::Borrowed("Operation against a key holding the wrong kind of value"),
+};
+
+/// Error returned when a write would take a user over their quota.
+pub const QUOTA_EXCEEDED: CommandError = CommandError {
+  code: ErrorCode::Oom,
+  message: Cow::Borrowed("user quota exceeded"),
+};
+
+/// Error returned when a key would shadow a database's default map.
+pub const RESERVED_KEY: &str = "the key name 'default' is reserved";
+
+/// Sending half of the queue of values waiting to be dropped.
+pub type ReclaimQueue = mpsc::UnboundedSender<Value>;
+
+/// Limits on how much data a single user may store, 0 meaning unlimited.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Quota {
+  /// Maximum number of keys across all of the user's databases
+  pub max_keys: usize,
+  /// Maximum approximate size of the user's keys and values in bytes
+  pub max_bytes: usize,
+}
+
+/// How several sets are combined by [`MemoryStore::set_combine`].
+#[derive(Clone, Copy, Debug)]
+pub enum SetOp {
+  /// Members found in every set
+  Inter,
+  /// Members found in any set
+  Union,
+  /// Members of the first set found in none of the others
+  Diff,
+}
+
+impl SetOp {
+  /// Returns the keyspace event published when the result is stored.
+  fn store_event(self) -> &'static str {
+    match self {
+      Self::Inter => "sinterstore",
+      Self::Union => "sunionstore",
+      Self::Diff => "sdiffstore",
+    }
+  }
+}
+
+/// How [`MemoryStore::get_ex`] changes the expiry of the key it reads.
+#[derive(Clone, Copy, Debug)]
+pub enum ExpiryUpdate {
+  /// Leave the expiry as it is
+  Keep,
+  /// Remove the expiry
+  Persist,
+  /// Expire the key at the given time
+  At(SystemTime),
+}
+
+/// When a key expires, as returned by [`MemoryStore::expire_time`].
+#[derive(Clone, Copy, Debug)]
+pub enum KeyExpiry {
+  /// The key doesn't exist or has expired
+  Missing,
+  /// The key never expires
+  Persistent,
+  /// The key expires at the given time
+  At(SystemTime),
+}
+
+/// How a key is stored, as returned by [`MemoryStore::debug_object`].
+#[derive(Clone, Debug)]
+pub struct ObjectDebug {
+  /// Variant of [`Entities`] holding the key, strings live in the default map
+  pub entity: &'static str,
+  /// Variant of the stored [`Value`], only strings keep a single value
+  pub value: Option<&'static str>,
+  /// Length of the value encoded as RESP
+  pub serialized_length: usize,
+  /// Whether the key has an EX or PX expiry
+  pub expires: bool,
+  /// When the key was created, reads and later writes don't move it
+  pub inserted_at: Option<SystemTime>,
+}
+
+/// Outcome of [`MemoryStore::list_pop_or_block`].
+#[derive(Debug)]
+pub enum ListPop {
+  /// A value was popped right away, from the list at the given key
+  Popped(String, String),
+  /// Every list was empty. The receiver gets the key and value once a push
+  /// serves this client, drop it to stop waiting
+  Blocked(oneshot::Receiver<(String, String)>),
+}
+
+/// A client blocked popping from one or more lists.
+///
+/// The client is queued under every key it waits on, sharing one reply
+/// channel. Whichever list is pushed to first takes the channel, the
+/// entries left under the other keys are skipped.
+#[derive(Clone, Debug)]
+struct BlockedPop {
+  /// Pop from the head of the list instead of its tail
+  front: bool,
+  /// Channel the popped key and value are sent through, None once served
+  reply: Arc<Mutex<Option<PopSender>>>,
+}
+
+/// Sends a blocked client the key and value popped for it.
+type PopSender = oneshot::Sender<(String, String)>;
+
+/// Clients blocked popping from lists, in the order they blocked, keyed by
+/// database index and list key.
+type BlockedPops = HashMap<(usize, String), VecDeque<BlockedPop>>;
+
+/// Clients blocked on a user's keys.
+#[derive(Debug, Default)]
+struct Blocked {
+  /// Clients waiting to pop from a list
+  pops: BlockedPops,
+  /// Clients waiting for a key to exist, keyed by database index and key.
+  /// Woken up by whichever write creates the key
+  keys: HashMap<(usize, String), Vec<oneshot::Sender<()>>>,
+}
+
+/// Main in-memory storage structure.
+///
+/// Provides authenticated access to user-specific data stores.
+/// Clones share the underlying data but each keeps its own selected
+/// database and authenticated user, so every connection can `SELECT` and
+/// `AUTH` independently.
+#[derive(Clone)]
+pub struct MemoryStore {
+  /// Store for authenticated users, keyed by user id. Sharded, so users
+  /// never contend on a single lock to reach their own store
+  pub(super) auth_stores: Arc<DashMap<String, UserStore>>,
+  /// Id of the user authenticated on this handle (if any)
+  current_user: Option<String>,
+  /// Number of logical databases in every user store
+  databases: usize,
+  /// Index of the database key operations are routed to
+  selected_db: usize,
+  /// Queue large removed values are dropped through, if set up
+  reclaim: Option<ReclaimQueue>,
+  /// Limits every user's store is held to, shared by all handles
+  quota: Arc<RwLock<Quota>>,
+  /// Memory budget past which keys are evicted, shared by all handles
+  eviction: Arc<RwLock<Eviction>>,
+  /// Publishes keyspace notifications, shared by all handles
+  notifier: Notifier,
+  /// Changes made to any user's keys since the last successful save,
+  /// shared by all handles
+  changes: Arc<AtomicU64>,
+  /// Progress of the saves made from the store, shared by all handles
+  saves: Arc<SaveState>,
+}
+
+/// Represents a single user's data store.
+///
+/// Contains all entities (HashMaps, Sets, etc.) owned by a specific user,
+/// split into numbered logical databases.
+#[derive(Clone, Debug)]
+pub struct UserStore {
+  /// Stores entity references for various data types, one map per database
+  /// Key is entity name, value is the entity (HashMap, Set, etc)
+  pub(super) databases: Arc<Mutex<Vec<HashMap<String, Entities>>>>,
+  /// Approximate amount of data held across all databases
+  usage: Arc<Usage>,
+  /// Clients waiting for a value to be pushed onto a list. Always locked
+  /// after `databases`
+  blocked: Arc<Mutex<Blocked>>,
+}
+
+/// Approximate amount of data held by a user store.
+///
+/// Updated as keys are written and removed. Keys that expired but were not
+/// removed yet still count.
+#[derive(Debug, Default)]
+struct Usage {
+  /// Number of keys
+  keys: AtomicUsize,
+  /// Size of the keys and their values in bytes
+  bytes: AtomicUsize,
+}
+
+impl Usage {
+  /// Measures the keys and values held in `databases`, strings and other
+  /// entities alike.
+  fn measure(databases: &[HashMap<String, Entities>]) -> Self {
+    let usage = Self::default();
+    for (name, entity) in databases.iter().flatten() {
+      match entity {
+        Entities::HashMap(map) if name == "default" => {
+          for (key, (value, ..)) in map.lock().unwrap().iter() {
+            usage.replace(entry_size(key, value), None);
+          }
+        }
+        entity => usage.replace(entity_size(name, entity), None),
+      }
+    }
+    usage
+  }
+
+  /// Fails if storing an entry of `size` bytes in place of one of `old`
+  /// bytes, or in a new key if None, would exceed the quota.
+  fn check(&self, quota: Quota, size: usize, old: Option<usize>) -> Result<()> {
+    let keys = self.keys.load(Ordering::Relaxed) + usize::from(old.is_none());
+    let bytes = (self.bytes.load(Ordering::Relaxed) + size).saturating_sub(old.unwrap_or(0));
+
+    // Shrinking an existing entry is always allowed
+    let grows = old.is_none_or(|old| size > old);
+    if (quota.max_keys > 0 && old.is_none() && keys > quota.max_keys)
+      || (quota.max_bytes > 0 && grows && bytes > quota.max_bytes)
+    {
+      bail!(QUOTA_EXCEEDED);
+    }
+    Ok(())
+  }
+
+  /// Records an entry of `size` bytes stored in place of one of `old`
+  /// bytes, or in a new key if None.
+  fn replace(&self, size: usize, old: Option<usize>) {
+    match old {
+      Some(old) => {
+        self.bytes.fetch_sub(old, Ordering::Relaxed);
+      }
+      None => {
+        self.keys.fetch_add(1, Ordering::Relaxed);
+      }
+    }
+    self.bytes.fetch_add(size, Ordering::Relaxed);
+  }
+
+  /// Records an entry of `size` bytes being removed.
+  fn remove(&self, size: usize) {
+    self.keys.fetch_sub(1, Ordering::Relaxed);
+    self.bytes.fetch_sub(size, Ordering::Relaxed);
+  }
+
+  /// Records elements of `size` bytes being removed from a collection
+  /// that keeps its key.
+  fn shrink(&self, size: usize) {
+    self.bytes.fetch_sub(size, Ordering::Relaxed);
+  }
+
+  /// Returns the key count and size in bytes.
+  fn get(&self) -> (usize, usize) {
+    (
+      self.keys.load(Ordering::Relaxed),
+      self.bytes.load(Ordering::Relaxed),
+    )
+  }
+}
+
+impl UserStore {
+  /// Creates a new UserStore with `databases` empty databases.
+  pub(super) fn new(databases: usize) -> Self {
+    Self::from_databases((0..databases).map(|_| HashMap::new()).collect())
+  }
+
+  /// Creates a UserStore holding the given databases.
+  pub(super) fn from_databases(databases: Vec<HashMap<String, Entities>>) -> Self {
+    Self {
+      usage: Arc::new(Usage::measure(&databases)),
+      databases: Arc::new(Mutex::new(databases)),
+      blocked: Arc::new(Mutex::new(Blocked::default())),
+    }
+  }
+}
+
+impl MemoryStore {
+  /// Creates a new empty MemoryStore whose users get `databases` logical
+  /// databases each.
+  ///
+  /// # Arguments
+  ///
+  /// * `databases` - Number of databases per user, at least one is always created
+  pub fn new(databases: usize) -> Self {
+    info!("Initializing memory store for authenticated users only");
+    Self {
+      auth_stores: Arc::new(DashMap::new()),
+      current_user: None,
+      databases: databases.max(1),
+      selected_db: 0,
+      reclaim: None,
+      quota: Arc::new(RwLock::new(Quota::default())),
+      eviction: Arc::new(RwLock::new(Eviction::default())),
+      notifier: Notifier::default(),
+      changes: Arc::new(AtomicU64::new(0)),
+      saves: Arc::default(),
+    }
+  }
+
+  /// Sets the memory budget past which writes evict keys.
+  pub fn with_eviction(self, eviction: Eviction) -> Self {
+    self.set_eviction(eviction);
+    self
+  }
+
+  /// Sets the limits every user's store is held to.
+  pub fn with_quota(self, quota: Quota) -> Self {
+    self.set_quota(quota);
+    self
+  }
+
+  /// Sets where keyspace notifications are published, and which ones.
+  pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+    self.notifier = notifier;
+    self
+  }
+
+  /// Sets the queue large values are sent to by [`MemoryStore::lazy_free`].
+  ///
+  /// Whoever owns the receiving half is responsible for dropping them.
+  pub fn with_reclaim_queue(mut self, queue: ReclaimQueue) -> Self {
+    self.reclaim = Some(queue);
+    self
+  }
+
+  /// Drops a value that was removed from the store.
+  ///
+  /// Large arrays are sent to the reclaim queue so freeing them doesn't
+  /// stall the caller. Everything else, or every value when no queue is
+  /// set up, is dropped immediately.
+  pub fn lazy_free(&self, value: Value) {
+    let Some(queue) = &self.reclaim else {
+      return;
+    };
+    if element_count(&value) >= LAZY_FREE_THRESHOLD {
+      // If the drainer is gone the value comes back and is dropped here
+      let _ = queue.send(value);
+    }
+  }
+
+  /// Returns the number of logical databases per user.
+  pub fn databases(&self) -> usize {
+    self.databases
+  }
+
+  /// Returns the index of the database key operations are routed to.
+  pub fn selected_db(&self) -> usize {
+    self.selected_db
+  }
+
+  /// Selects the database that key operations on this handle are routed to.
+  ///
+  /// # Arguments
+  ///
+  /// * `index` - Index of the database to select
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - The database was selected
+  /// * `Err` - The index is out of range
+  pub fn select(&mut self, index: usize) -> Result<()> {
+    self.check_db_index(index)?;
+    self.selected_db = index;
+    Ok(())
+  }
+
+  /// Swaps the contents of two of the current user's databases.
+  ///
+  /// Connections only hold a database index, so every connection of the
+  /// user that has either database selected sees the swapped data.
+  ///
+  /// # Arguments
+  ///
+  /// * `first` - Index of the first database
+  /// * `second` - Index of the second database
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - The databases were swapped
+  /// * `Err` - Not authenticated, or an index is out of range
+  pub fn swap_db(&self, first: usize, second: usize) -> Result<()> {
+    self.check_db_index(first)?;
+    self.check_db_index(second)?;
+
+    self
+      .with_databases(|databases| databases.swap(first, second))
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+      .inspect(|()| self.record_change())
+  }
+
+  /// Moves a key from the selected database to another one.
+  ///
+  /// The value keeps its insertion time and expiry options. Both databases
+  /// are locked for the whole move, so the key is never visible in both.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key to move
+  /// * `db` - Index of the destination database
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(true)` - The key was moved
+  /// * `Ok(false)` - The key is missing from the source or already exists in the destination
+  /// * `Err` - Not authenticated, or the destination is invalid
+  pub fn move_key(&self, key: &str, db: usize) -> Result<bool> {
+    self.check_db_index(db)?;
+    if db == self.selected_db {
+      bail!("source and destination objects are the same");
+    }
+
+    self
+      .with_databases(|databases| {
+        let Some(source) = default_map(&databases[self.selected_db]) else {
+          return false;
+        };
+        let Some(destination) = default_map_or_create(&mut databases[db]) else {
+          return false;
+        };
+        let mut source = source.lock().unwrap();
+        let mut destination = destination.lock().unwrap();
+
+        if !source.get(key).is_some_and(is_live)
+          || destination.get(key).is_some_and(is_live)
+          || holds_entity(&databases[db], key)
+        {
+          return false;
+        }
+
+        let entry = source.remove(key).unwrap();
+        destination.insert(key.to_string(), entry);
+        true
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+      .inspect(|&moved| {
+        if moved {
+          self.notify(EventClass::Generic, "move_from", key);
+          self
+            .notifier
+            .notify(EventClass::Generic, "move_to", db, key);
+        }
+      })
+  }
+
+  /// Copies a key's value to another key, optionally in another database.
+  ///
+  /// The copy gets a fresh insertion time, its expiry options are adjusted
+  /// so it still expires at the same moment as the source. The source read
+  /// and destination write happen under a single lock.
+  ///
+  /// # Arguments
+  ///
+  /// * `source` - The key to copy
+  /// * `destination` - The key to copy to
+  /// * `db` - Index of the destination database, the selected one if None
+  /// * `replace` - Whether an existing destination key may be overwritten
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(true)` - The key was copied
+  /// * `Ok(false)` - The source is missing or the destination exists
+  /// * `Err` - Not authenticated, the database is invalid, source and destination are the same,
+  ///   or the copy would exceed the user's quota
+  pub fn copy_key(
+    &self,
+    source: &str,
+    destination: &str,
+    db: Option<usize>,
+    replace: bool,
+  ) -> Result<bool> {
+    let db = db.unwrap_or(self.selected_db);
+    self.check_db_index(db)?;
+    if db == self.selected_db && source == destination {
+      bail!("source and destination objects are the same");
+    }
+
+    self
+      .with_user_store(|databases, usage| {
+        let Some(source_map) = default_map(&databases[self.selected_db]) else {
+          return Ok(false);
+        };
+        let copy = match source_map.lock().unwrap().get_mut(source) {
+          Some(entry) if is_live(entry) => {
+            let (value, inserted_at, args, _) = &*entry;
+            let copy = new_entry(value.clone(), rebase_expiry(inserted_at, args));
+            touch_entry(entry);
+            copy
+          }
+          _ => return Ok(false),
+        };
+
+        // Only strings are copied, a destination of another kind is replaced like one
+        let replaced_entity = match databases[db].get(destination) {
+          Some(_) if destination == "default" => None,
+          Some(_) if !replace => return Ok(false),
+          entity => entity.map(|entity| entity_size(destination, entity)),
+        };
+
+        let Some(destination_map) = default_map_or_create(&mut databases[db]) else {
+          return Ok(false);
+        };
+        let mut destination_map = destination_map.lock().unwrap();
+        if !replace && destination_map.get(destination).is_some_and(is_live) {
+          return Ok(false);
+        }
+
+        let size = entry_size(destination, &copy.0);
+        let old = replaced_entity.or_else(|| {
+          destination_map
+            .get(destination)
+            .map(|(value, ..)| entry_size(destination, value))
+        });
+        usage.check(self.quota(), size, old)?;
+        usage.replace(size, old);
+
+        if replaced_entity.is_some() {
+          databases[db].remove(destination);
+        }
+        destination_map.insert(destination.to_string(), copy);
+        Ok(true)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|&copied| {
+        if copied {
+          self
+            .notifier
+            .notify(EventClass::Generic, "copy_to", db, destination);
+        }
+      })
+  }
+
+  /// Reads and modifies a string value under a single lock.
+  ///
+  /// A missing or expired key is passed to `f` as an empty string and is
+  /// only stored if `f` leaves it non-empty. An existing key keeps its expiry.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the string
+  /// * `f` - Function modifying the string's bytes in place
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(R)` - The result of `f`
+  /// * `Err` - Not authenticated, the key holds a value that is not a string,
+  ///   or the new value would exceed the user's quota
+  pub fn update_bytes<R>(&self, key: &str, f: impl FnOnce(&mut Vec<u8>) -> R) -> Result<R> {
+    self
+      .with_db_usage(|entities, usage| {
+        if holds_entity(entities, key) {
+          bail!(WRONG_TYPE);
+        }
+        let map =
+          default_map_or_create(entities).ok_or_else(|| anyhow!("Default map corrupted"))?;
+        let mut map = map.lock().unwrap();
+        let old = map.get(key).map(|(value, ..)| entry_size(key, value));
+
+        match map.get_mut(key) {
+          Some(entry) if is_live(entry) => {
+            let mut bytes = entry.0.to_bytes().ok_or_else(|| anyhow!(WRONG_TYPE))?;
+            let result = f(&mut bytes);
+            let value = Value::from_bytes(bytes);
+            let size = entry_size(key, &value);
+            usage.check(self.quota(), size, old)?;
+            usage.replace(size, old);
+            entry.0 = value;
+            touch_entry(entry);
+            Ok(result)
+          }
+          _ => {
+            let mut bytes = Vec::new();
+            let result = f(&mut bytes);
+            if !bytes.is_empty() {
+              let entry = new_entry(Value::from_bytes(bytes), HashMap::new());
+              let size = entry_size(key, &entry.0);
+              usage.check(self.quota(), size, old)?;
+              usage.replace(size, old);
+              map.insert(key.to_string(), entry);
+            }
+            Ok(result)
+          }
+        }
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Makes a key expire at an absolute point in time.
+  ///
+  /// The deadline is stored as a PX expiry counted from the entry's time,
+  /// which is moved so the two add up to exactly the deadline. A deadline
+  /// that already passed deletes the key right away.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key to expire
+  /// * `deadline` - When the key expires
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(true)` - The expiry was set, or the key deleted
+  /// * `Ok(false)` - The key doesn't exist or has expired
+  /// * `Err` - Not authenticated
+  pub fn expire_at(&self, key: &str, deadline: SystemTime) -> Result<bool> {
+    self
+      .with_db_usage(|entities, usage| {
+        let map = default_map(entities)?;
+        let mut map = map.lock().unwrap();
+        let entry = map.get_mut(key).filter(|entry| is_live(entry))?;
+
+        if !set_deadline(entry, deadline) {
+          let (value, ..) = map.remove(key).unwrap();
+          usage.remove(entry_size(key, &value));
+          self.lazy_free(value);
+          return Some("del");
+        }
+        Some("expire")
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+      .map(|event| {
+        if let Some(event) = event {
+          self.notify(EventClass::Generic, event, key);
+        }
+        event.is_some()
+      })
+  }
+
+  /// Reads a string and changes its expiry in a single step.
+  ///
+  /// The read counts as an access, like `GET`. A deadline that already
+  /// passed deletes the key, the value is still returned.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key to read
+  /// * `update` - How to change the key's expiry
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(Value))` - The value the key held
+  /// * `Ok(None)` - The key doesn't exist or has expired
+  /// * `Err` - Not authenticated
+  pub fn get_ex(&self, key: &str, update: ExpiryUpdate) -> Result<Option<Value>> {
+    self
+      .with_db_usage(|entities, usage| {
+        let map = default_map(entities)?;
+        let mut map = map.lock().unwrap();
+        let entry = map.get_mut(key).filter(|entry| is_live(entry))?;
+        touch_entry(entry);
+
+        let event = match update {
+          ExpiryUpdate::Keep => None,
+          ExpiryUpdate::Persist => {
+            entry.2.remove(&Options::Ex);
+            entry.2.remove(&Options::Px);
+            Some("persist")
+          }
+          ExpiryUpdate::At(deadline) => {
+            if !set_deadline(entry, deadline) {
+              let (value, ..) = map.remove(key).unwrap();
+              usage.remove(entry_size(key, &value));
+              return Some((value, Some("del")));
+            }
+            Some("expire")
+          }
+        };
+        Some((entry.0.clone(), event))
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+      .map(|read| {
+        read.map(|(value, event)| {
+          if let Some(event) = event {
+            self.notify(EventClass::Generic, event, key);
+          }
+          value
+        })
+      })
+  }
+
+  /// Deletes a string only if it holds `expected`, in a single step.
+  ///
+  /// Values are compared by their bytes, so a value stored as a number
+  /// matches its decimal representation.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key to delete
+  /// * `expected` - The bytes the key must hold
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(true)` - The key held `expected` and was deleted
+  /// * `Ok(false)` - The key doesn't exist or holds another value
+  /// * `Err` - Not authenticated, or the key holds a value that is not a string
+  pub fn compare_and_delete(&self, key: &str, expected: &[u8]) -> Result<bool> {
+    self
+      .with_db_usage(|entities, usage| {
+        if holds_entity(entities, key) {
+          bail!(WRONG_TYPE);
+        }
+        let Some(map) = default_map(entities) else {
+          return Ok(false);
+        };
+        let mut map = map.lock().unwrap();
+        let matches = map
+          .get(key)
+          .filter(|entry| is_live(entry))
+          .is_some_and(|(value, ..)| value.to_bytes().as_deref() == Some(expected));
+        if matches {
+          let (value, ..) = map.remove(key).unwrap();
+          usage.remove(entry_size(key, &value));
+        }
+        Ok(matches)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|&deleted| {
+        if deleted {
+          self.notify(EventClass::Generic, "del", key);
+        }
+      })
+  }
+
+  /// Writes a string only if the key holds `expected`, in a single step.
+  ///
+  /// Values are compared by their bytes, like
+  /// [`MemoryStore::compare_and_delete`]. The write counts against the
+  /// quota and may evict keys, like `SET`.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key to write
+  /// * `expected` - The bytes the key must hold, or None if it must not exist
+  /// * `value` - The value to store
+  /// * `keep_ttl` - Keep the key's expiry instead of removing it
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(true)` - The key held `expected` and now holds `value`
+  /// * `Ok(false)` - The key holds something else, nothing was written
+  /// * `Err` - Not authenticated, the key holds a value that is not a string,
+  ///   or the value doesn't fit in the user's quota
+  pub fn compare_and_set(
+    &self,
+    key: &str,
+    expected: Option<&[u8]>,
+    value: Value,
+    keep_ttl: bool,
+  ) -> Result<bool> {
+    self
+      .with_user_store(|databases, usage| {
+        if holds_entity(&databases[self.selected_db], key) {
+          bail!(WRONG_TYPE);
+        }
+        let map = default_map_or_create(&mut databases[self.selected_db])
+          .ok_or_else(|| anyhow!("Default map corrupted"))?;
+
+        let size = entry_size(key, &value);
+        let (old, kept_expiry) = {
+          let map = map.lock().unwrap();
+          let entry = map.get(key);
+          let live = entry.filter(|entry| is_live(entry));
+          let matches = match (live, expected) {
+            (None, None) => true,
+            (Some((current, ..)), Some(expected)) => {
+              current.to_bytes().as_deref() == Some(expected)
+            }
+            _ => false,
+          };
+          if !matches {
+            return Ok(false);
+          }
+          (
+            entry.map(|(value, ..)| entry_size(key, value)),
+            live
+              .filter(|_| keep_ttl)
+              .map(|(_, inserted_at, args, _)| rebase_expiry(inserted_at, args)),
+          )
+        };
+
+        self.make_room(databases, usage, size, old, &[key])?;
+
+        // The user's databases stay locked, so the key still holds `expected`
+        let mut map = map.lock().unwrap();
+        usage.check(self.quota(), size, old)?;
+        usage.replace(size, old);
+        let args = kept_expiry.unwrap_or_default();
+        map.insert(key.to_string(), new_entry(value, args));
+        Ok(true)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|&stored| {
+        if stored {
+          self.notify(EventClass::String, "set", key);
+        }
+      })
+  }
+
+  /// Stores several strings only if none of their keys exist, in a single
+  /// step.
+  ///
+  /// Either every pair is stored or none is: if the quota runs out part way
+  /// through, the pairs already stored are removed again. A key given twice
+  /// keeps its last value.
+  ///
+  /// # Arguments
+  ///
+  /// * `pairs` - The keys and the values to store in them
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(true)` - No key existed and every pair was stored
+  /// * `Ok(false)` - A key already exists, nothing was stored
+  /// * `Err` - Not authenticated, or the values don't fit in the user's quota
+  pub fn set_all_if_absent(&self, pairs: &[(String, Value)]) -> Result<bool> {
+    self
+      .with_user_store(|databases, usage| {
+        let entities = &databases[self.selected_db];
+        if pairs
+          .iter()
+          .any(|(key, _)| kind_of(entities, key).is_some())
+        {
+          return Ok(false);
+        }
+        let map = default_map_or_create(&mut databases[self.selected_db])
+          .ok_or_else(|| anyhow!("Default map corrupted"))?;
+
+        let mut stored = Vec::new();
+        let outcome = pairs.iter().try_for_each(|(key, value)| {
+          let size = entry_size(key, value);
+          // Expired entries still take up room until they are replaced
+          let old = map
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|(value, ..)| entry_size(key, value));
+          self.make_room(databases, usage, size, old, &[key])?;
+
+          let mut map = map.lock().unwrap();
+          usage.check(self.quota(), size, old)?;
+          usage.replace(size, old);
+          map.insert(key.clone(), new_entry(value.clone(), KvMapArgs::new()));
+          stored.push(key);
+          Ok(())
+        });
+
+        if let Err(e) = outcome {
+          let mut map = map.lock().unwrap();
+          for key in stored {
+            if let Some((value, ..)) = map.remove(key) {
+              usage.remove(entry_size(key, &value));
+            }
+          }
+          return Err(e);
+        }
+        Ok(true)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|&stored| {
+        if stored {
+          for (key, _) in pairs {
+            self.notify(EventClass::String, "set", key);
+          }
+        }
+      })
+  }
+
+  /// Marks keys as accessed without reading them.
+  ///
+  /// # Arguments
+  ///
+  /// * `keys` - The keys to touch, a key given twice counts twice
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(count)` - Number of keys that exist, expired keys are skipped
+  /// * `Err` - Not authenticated
+  pub fn touch(&self, keys: &[String]) -> Result<usize> {
+    self
+      .with_db(|entities| {
+        let map = default_map(entities);
+        let mut map = map.as_ref().map(|map| map.lock().unwrap());
+        keys
+          .iter()
+          .filter(|key| {
+            if holds_entity(entities, key) {
+              return entities[key.as_str()].touch();
+            }
+            match map.as_mut().and_then(|map| map.get_mut(key.as_str())) {
+              Some(entry) if is_live(entry) => {
+                touch_entry(entry);
+                true
+              }
+              _ => false,
+            }
+          })
+          .count()
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+  }
+
+  /// Returns how long ago a key was last read or written.
+  ///
+  /// Looking the key up doesn't count as an access.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(idle))` - Time since the key was last accessed
+  /// * `Ok(None)` - The key doesn't exist or has expired
+  /// * `Err` - Not authenticated
+  pub fn idle_time(&self, key: &str) -> Result<Option<Duration>> {
+    self
+      .with_db(|entities| {
+        let accessed_at = if holds_entity(entities, key) {
+          entities[key].times()?.1
+        } else {
+          let map = default_map(entities)?;
+          let map = map.lock().unwrap();
+          map.get(key).filter(|entry| is_live(entry))?.3
+        };
+        Some(SystemTime::elapsed(&accessed_at).unwrap_or_default())
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+  }
+
+  /// Returns the time left before a key expires.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(ttl))` - Time until the key expires
+  /// * `Ok(None)` - The key doesn't exist, has expired or has no expiry
+  /// * `Err` - Not authenticated
+  pub fn time_to_live(&self, key: &str) -> Result<Option<Duration>> {
+    self
+      .with_db(|entities| {
+        let map = default_map(entities)?;
+        let map = map.lock().unwrap();
+        let (_, stamp, args, _) = map.get(key).filter(|entry| is_live(entry))?;
+        let px = rebase_expiry(stamp, args).get(&Options::Px).copied()?;
+        Some(Duration::from_millis(px))
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+  }
+
+  /// Returns a string's value and the time it has left to live, read in a
+  /// single step without counting as an access.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some((value, ttl)))` - The value, and its time to live if it expires
+  /// * `Ok(None)` - The key doesn't exist or has expired
+  /// * `Err` - Not authenticated, or the key holds a value that is not a string
+  pub fn get_with_ttl(&self, key: &str) -> Result<Option<(Value, Option<Duration>)>> {
+    self
+      .with_db(|entities| {
+        if holds_entity(entities, key) {
+          bail!(WRONG_TYPE);
+        }
+        let Some(map) = default_map(entities) else {
+          return Ok(None);
+        };
+        let map = map.lock().unwrap();
+        Ok(
+          map
+            .get(key)
+            .filter(|entry| is_live(entry))
+            .map(|(value, stamp, args, _)| {
+              let ttl = rebase_expiry(stamp, args).get(&Options::Px).copied();
+              (value.clone(), ttl.map(Duration::from_millis))
+            }),
+        )
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Returns the time at which a key expires.
+  ///
+  /// Only strings can have an expiry, keys of other kinds are persistent.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(KeyExpiry)` - Whether and when the key expires
+  /// * `Err` - Not authenticated
+  pub fn expire_time(&self, key: &str) -> Result<KeyExpiry> {
+    self
+      .with_db(|entities| {
+        if holds_entity(entities, key) {
+          return KeyExpiry::Persistent;
+        }
+        let Some(map) = default_map(entities) else {
+          return KeyExpiry::Missing;
+        };
+        let map = map.lock().unwrap();
+        match map.get(key).filter(|entry| is_live(entry)) {
+          None => KeyExpiry::Missing,
+          Some((_, stamp, args, _)) => {
+            deadline(stamp, args).map_or(KeyExpiry::Persistent, KeyExpiry::At)
+          }
+        }
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+  }
+
+  /// Returns the kind of value a key holds in the selected database.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(EntityKind))` - The kind of the key's value
+  /// * `Ok(None)` - The key doesn't exist or has expired
+  /// * `Err` - Not authenticated
+  pub fn entity_kind(&self, key: &str) -> Result<Option<EntityKind>> {
+    self
+      .with_db(|entities| kind_of(entities, key))
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+  }
+
+  /// Returns the internal details of how a key is stored.
+  ///
+  /// Entities are serialized as the array their elements would be replied
+  /// as, so this is slow on large values.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(ObjectDebug))` - The key's details
+  /// * `Ok(None)` - The key doesn't exist or has expired
+  /// * `Err` - Not authenticated
+  pub fn debug_object(&self, key: &str) -> Result<Option<ObjectDebug>> {
+    self
+      .with_db(|entities| {
+        if let Some(entity) = entities.get(key).filter(|_| key != "default") {
+          let (variant, elements) = match entity {
+            Entities::HashMap(map) => (
+              "HashMap",
+              map
+                .lock()
+                .unwrap()
+                .iter()
+                .flat_map(|(field, (value, ..))| [Value::BulkString(field.clone()), value.clone()])
+                .collect(),
+            ),
+            Entities::Set(set) => (
+              "Set",
+              set
+                .lock()
+                .unwrap()
+                .iter()
+                .cloned()
+                .map(Value::BulkString)
+                .collect(),
+            ),
+            Entities::LinkedList(list) => (
+              "LinkedList",
+              list
+                .lock()
+                .unwrap()
+                .iter()
+                .cloned()
+                .map(Value::BulkString)
+                .collect(),
+            ),
+            Entities::SortedSet(zset) => (
+              "SortedSet",
+              zset
+                .lock()
+                .unwrap()
+                .iter()
+                .flat_map(|(member, score)| {
+                  [Value::BulkString(member.clone()), Value::Double(score)]
+                })
+                .collect(),
+            ),
+            Entities::_HashSet => ("_HashSet", Vec::new()),
+            Entities::_List => ("_List", Vec::new()),
+            Entities::_Queue => ("_Queue", Vec::new()),
+          };
+          return Some(ObjectDebug {
+            entity: variant,
+            value: None,
+            serialized_length: Value::Array(elements).serialized_len(),
+            expires: false,
+            inserted_at: entity.times().map(|(inserted_at, _)| inserted_at),
+          });
+        }
+
+        let map = default_map(entities)?;
+        let map = map.lock().unwrap();
+        let (value, stamp, args, _) = map.get(key).filter(|entry| is_live(entry))?;
+        Some(ObjectDebug {
+          entity: "HashMap",
+          value: Some(variant_name(value)),
+          serialized_length: value.serialized_len(),
+          expires: args.contains_key(&Options::Ex) || args.contains_key(&Options::Px),
+          inserted_at: Some(*stamp),
+        })
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+  }
+
+  /// Returns the selected database's keys matching a glob, expired strings
+  /// left out.
+  ///
+  /// The keyspace is walked once under the lock, so this is linear in the
+  /// number of keys.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Vec<String>)` - The matching keys, in no particular order
+  /// * `Err` - Not authenticated
+  pub fn keys_matching(&self, pattern: &str) -> Result<Vec<String>> {
+    self
+      .with_db(|entities| {
+        let strings = default_map(entities);
+        let strings = strings.as_ref().map(|map| map.lock().unwrap());
+        let live_strings = strings
+          .iter()
+          .flat_map(|map| map.iter())
+          .filter(|(_, entry)| is_live(entry))
+          .map(|(key, _)| key);
+        let others = entities.keys().filter(|key| *key != "default");
+        live_strings
+          .chain(others)
+          .filter(|key| glob::matches(pattern, key))
+          .cloned()
+          .collect()
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+  }
+
+  /// Fails with [`WRONG_TYPE`] if a key holds a value of another kind.
+  ///
+  /// Commands call this before operating on a key, a missing key passes.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key about to be operated on
+  /// * `kind` - The kind of value the command works with
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - The key is missing or holds a value of `kind`
+  /// * `Err` - Not authenticated, or the key holds another kind of value
+  pub fn expect_kind(&self, key: &str, kind: EntityKind) -> Result<()> {
+    match self.entity_kind(key)? {
+      Some(found) if found != kind => bail!(WRONG_TYPE),
+      _ => Ok(()),
+    }
+  }
+
+  /// Estimates the memory a key and its value take up, as counted against
+  /// the user's quota.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(usize))` - Approximate size in bytes
+  /// * `Ok(None)` - The key doesn't exist or has expired
+  /// * `Err` - Not authenticated
+  pub fn memory_usage(&self, key: &str) -> Result<Option<usize>> {
+    self
+      .with_db(|entities| {
+        if holds_entity(entities, key) {
+          return Some(entity_size(key, &entities[key]));
+        }
+        let map = default_map(entities)?;
+        let map = map.lock().unwrap();
+        map
+          .get(key)
+          .filter(|entry| is_live(entry))
+          .map(|(value, ..)| entry_size(key, value))
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+  }
+
+  /// Runs `f` on a key's value without counting it as an access.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(R))` - The result of `f`
+  /// * `Ok(None)` - The key doesn't exist or has expired
+  /// * `Err` - Not authenticated
+  pub fn inspect<R>(&self, key: &str, f: impl FnOnce(&Value) -> R) -> Result<Option<R>> {
+    self
+      .with_db(|entities| {
+        let map = default_map(entities)?;
+        let map = map.lock().unwrap();
+        map
+          .get(key)
+          .filter(|entry| is_live(entry))
+          .map(|(value, ..)| f(value))
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+  }
+
+  /// Adds members to the set stored at a key, creating it if needed.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the set
+  /// * `members` - The members to add
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(count)` - Number of members that were not in the set yet
+  /// * `Err` - Not authenticated, the key holds a value that is not a set,
+  ///   or the new members don't fit in the user's quota
+  pub fn set_add(&self, key: &str, members: &[String]) -> Result<usize> {
+    self
+      .with_user_store(|databases, usage| {
+        let existing = entity_of::<KvSet>(&databases[self.selected_db], key)?;
+        let added: KvSet = {
+          let set = existing.as_ref().map(|set| set.lock().unwrap());
+          members
+            .iter()
+            .filter(|member| set.as_ref().is_none_or(|set| !set.contains(*member)))
+            .cloned()
+            .collect()
+        };
+        if added.is_empty() {
+          return Ok(0);
+        }
+
+        let size = added.iter().map(|member| member_size(member)).sum();
+        self.charge_collection(databases, usage, key, size, 0)?;
+        let set = entity_or_create::<KvSet>(&mut databases[self.selected_db], key)?;
+        let count = added.len();
+        set.lock().unwrap().extend(added);
+        Ok(count)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|&added| {
+        if added > 0 {
+          self.notify(EventClass::Set, "sadd", key);
+        }
+      })
+  }
+
+  /// Removes members from the set stored at a key. A set left empty is
+  /// deleted.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the set
+  /// * `members` - The members to remove
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(count)` - Number of members that were removed
+  /// * `Err` - Not authenticated, or the key holds a value that is not a set
+  pub fn set_remove(&self, key: &str, members: &[String]) -> Result<usize> {
+    self
+      .with_db_usage(|entities, usage| {
+        let Some(set) = entity_of::<KvSet>(entities, key)? else {
+          return Ok(0);
+        };
+        let mut set = set.lock().unwrap();
+        let removed: Vec<&String> = members
+          .iter()
+          .filter(|member| set.remove(*member))
+          .collect();
+        let size = removed.iter().map(|member| member_size(member)).sum();
+        release_elements(entities, usage, key, size, set.is_empty());
+        Ok(removed.len())
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|&removed| {
+        if removed > 0 {
+          self.notify(EventClass::Set, "srem", key);
+        }
+      })
+  }
+
+  /// Checks whether a member is in the set stored at a key.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(true)` - The member is in the set
+  /// * `Ok(false)` - The member or the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a set
+  pub fn set_contains(&self, key: &str, member: &str) -> Result<bool> {
+    self
+      .with_db(|entities| {
+        let set = entity_of::<KvSet>(entities, key)?;
+        Ok(set.is_some_and(|set| set.lock().unwrap().contains(member)))
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Returns the number of members of the set stored at a key.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(count)` - Number of members, 0 if the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a set
+  pub fn set_len(&self, key: &str) -> Result<usize> {
+    self
+      .with_db(|entities| {
+        let set = entity_of::<KvSet>(entities, key)?;
+        Ok(set.map_or(0, |set| set.lock().unwrap().len()))
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Removes random members from the set stored at a key. A set left
+  /// empty is deleted.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the set
+  /// * `count` - Number of members to remove, the whole set if it has fewer
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(members)` - The removed members, empty if the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a set
+  pub fn set_pop(&self, key: &str, count: usize) -> Result<Vec<String>> {
+    self
+      .with_db_usage(|entities, usage| {
+        let Some(set) = entity_of::<KvSet>(entities, key)? else {
+          return Ok(Vec::new());
+        };
+        let mut set = set.lock().unwrap();
+        let popped: Vec<String> = if count >= set.len() {
+          set.drain().collect()
+        } else {
+          let popped: Vec<String> = set
+            .iter()
+            .choose_multiple(&mut rand::rng(), count)
+            .into_iter()
+            .cloned()
+            .collect();
+          for member in &popped {
+            set.remove(member);
+          }
+          popped
+        };
+        let size = popped.iter().map(|member| member_size(member)).sum();
+        release_elements(entities, usage, key, size, set.is_empty());
+        Ok(popped)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|popped| {
+        if !popped.is_empty() {
+          self.notify(EventClass::Set, "spop", key);
+        }
+      })
+  }
+
+  /// Picks random members of the set stored at a key, without removing
+  /// them.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the set
+  /// * `count` - Number of members to pick
+  /// * `distinct` - Whether every member is picked at most once, which
+  ///   limits the result to the size of the set
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(members)` - The picked members, empty if the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a set
+  pub fn set_random(&self, key: &str, count: usize, distinct: bool) -> Result<Vec<String>> {
+    self
+      .with_db(|entities| {
+        let Some(set) = entity_of::<KvSet>(entities, key)? else {
+          return Ok(Vec::new());
+        };
+        let set = set.lock().unwrap();
+        let mut rng = rand::rng();
+        if distinct {
+          let picked = set.iter().choose_multiple(&mut rng, count);
+          return Ok(picked.into_iter().cloned().collect());
+        }
+
+        let members: Vec<&String> = set.iter().collect();
+        Ok(
+          (0..count)
+            .filter_map(|_| members.choose(&mut rng).map(|member| member.to_string()))
+            .collect(),
+        )
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Combines the sets stored at several keys. Missing keys count as
+  /// empty sets.
+  ///
+  /// # Arguments
+  ///
+  /// * `op` - How to combine the sets
+  /// * `keys` - The keys holding the sets, the first one is the one others
+  ///   are subtracted from by [`SetOp::Diff`]
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(KvSet)` - The resulting set
+  /// * `Err` - Not authenticated, or a key holds a value that is not a set
+  pub fn set_combine(&self, op: SetOp, keys: &[String]) -> Result<KvSet> {
+    self
+      .with_db(|entities| combine_sets(entities, op, keys))
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Combines the sets stored at several keys and stores the result at
+  /// `destination`, replacing whatever it held. An empty result deletes
+  /// `destination`.
+  ///
+  /// # Arguments
+  ///
+  /// * `op` - How to combine the sets
+  /// * `destination` - The key to store the result at, may be one of `keys`
+  /// * `keys` - The keys holding the sets
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(count)` - Number of members of the resulting set
+  /// * `Err` - Not authenticated, a key holds a value that is not a set, or
+  ///   the result doesn't fit in the user's quota
+  pub fn set_combine_store(&self, op: SetOp, destination: &str, keys: &[String]) -> Result<usize> {
+    self
+      .with_user_store(|databases, usage| {
+        let entities = &databases[self.selected_db];
+        let result = combine_sets(entities, op, keys)?;
+        if destination == "default" && !result.is_empty() {
+          bail!(RESERVED_KEY);
+        }
+
+        // The destination is replaced whatever kind of value it holds
+        let old = if holds_entity(entities, destination) {
+          Some(entity_size(destination, &entities[destination]))
+        } else {
+          default_map(entities).and_then(|map| {
+            map
+              .lock()
+              .unwrap()
+              .get(destination)
+              .map(|(value, ..)| entry_size(destination, value))
+          })
+        };
+        let count = result.len();
+        if count > 0 {
+          let size = key_size(destination)
+            + result
+              .iter()
+              .map(|member| member_size(member))
+              .sum::<usize>();
+          self.make_room(databases, usage, size, old, &[destination])?;
+          usage.check(self.quota(), size, old)?;
+          usage.replace(size, old);
+        } else if let Some(old) = old {
+          usage.remove(old);
+        }
+
+        let entities = &mut databases[self.selected_db];
+        if holds_entity(entities, destination) {
+          entities.remove(destination);
+        } else if let Some(map) = default_map(entities)
+          && let Some((value, ..)) = map.lock().unwrap().remove(destination)
+        {
+          self.lazy_free(value);
+        }
+
+        if count > 0 {
+          entities.insert(
+            destination.to_string(),
+            Entities::Set(Arc::new(Mutex::new(Tracked::new(result)))),
+          );
+        }
+        Ok(count)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|&count| {
+        if count > 0 {
+          self.notify(EventClass::Set, op.store_event(), destination);
+        }
+      })
+  }
+
+  /// Sets fields of the hash stored at a key, creating it if needed.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the hash
+  /// * `fields` - Field names and the values to set them to
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(count)` - Number of fields that didn't exist yet
+  /// * `Err` - Not authenticated, the key holds a value that is not a hash,
+  ///   or the fields don't fit in the user's quota
+  pub fn hash_set(&self, key: &str, fields: Vec<(String, Value)>) -> Result<usize> {
+    self
+      .with_user_store(|databases, usage| {
+        let existing = entity_of::<KvHashMap>(&databases[self.selected_db], key)?;
+        // A field given twice keeps its last value
+        let fields: HashMap<String, Value> = fields.into_iter().collect();
+        let size = fields
+          .iter()
+          .map(|(field, value)| entry_size(field, value))
+          .sum();
+        let (old, replaced) = existing.as_ref().map_or((0, 0), |hash| {
+          let hash = hash.lock().unwrap();
+          fields
+            .keys()
+            .filter_map(|field| hash.get_key_value(field))
+            .fold((0, 0), |(old, replaced), (field, (value, ..))| {
+              (old + entry_size(field, value), replaced + 1)
+            })
+        });
+
+        self.charge_collection(databases, usage, key, size, old)?;
+        let hash = entity_or_create::<KvHashMap>(&mut databases[self.selected_db], key)?;
+        let mut hash = hash.lock().unwrap();
+        let added = fields.len() - replaced;
+        for (field, value) in fields {
+          hash.insert(field, new_entry(value, KvMapArgs::new()));
+        }
+        Ok(added)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|_| self.notify(EventClass::Hash, "hset", key))
+  }
+
+  /// Returns the value of a field of the hash stored at a key.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(Value))` - The field's value
+  /// * `Ok(None)` - The field or the key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a hash
+  pub fn hash_get(&self, key: &str, field: &str) -> Result<Option<Value>> {
+    let value = self.inspect_hash(key, |hash| hash.get(field).map(|(value, ..)| value.clone()))?;
+    Ok(value.flatten())
+  }
+
+  /// Runs `f` on the hash stored at a key, under a single lock.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(R))` - The result of `f`
+  /// * `Ok(None)` - The key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a hash
+  pub fn inspect_hash<R>(&self, key: &str, f: impl FnOnce(&KvHashMap) -> R) -> Result<Option<R>> {
+    self
+      .with_db(|entities| {
+        let hash = entity_of::<KvHashMap>(entities, key)?;
+        Ok(hash.map(|hash| f(&hash.lock().unwrap())))
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Removes fields from the hash stored at a key. A hash left empty is
+  /// deleted.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the hash
+  /// * `fields` - The fields to remove
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(count)` - Number of fields that were removed
+  /// * `Err` - Not authenticated, or the key holds a value that is not a hash
+  pub fn hash_delete(&self, key: &str, fields: &[String]) -> Result<usize> {
+    self
+      .with_db_usage(|entities, usage| {
+        let Some(hash) = entity_of::<KvHashMap>(entities, key)? else {
+          return Ok(0);
+        };
+        let mut hash = hash.lock().unwrap();
+        let (removed, size) = fields
+          .iter()
+          .filter_map(|field| {
+            let (value, ..) = hash.remove(field)?;
+            Some(entry_size(field, &value))
+          })
+          .fold((0, 0), |(removed, size), entry| (removed + 1, size + entry));
+        release_elements(entities, usage, key, size, hash.is_empty());
+        Ok(removed)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|&removed| {
+        if removed > 0 {
+          self.notify(EventClass::Hash, "hdel", key);
+        }
+      })
+  }
+
+  /// Reads and replaces the value of a hash field under a single lock.
+  ///
+  /// The hash is created if needed. Nothing is stored if `f` fails.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the hash
+  /// * `field` - The field to update
+  /// * `f` - Function turning the field's value, None if it doesn't exist,
+  ///   into its new value
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Value)` - The field's new value
+  /// * `Err` - Not authenticated, the key holds a value that is not a hash,
+  ///   `f` failed, or the new value doesn't fit in the user's quota
+  pub fn hash_update(
+    &self,
+    key: &str,
+    field: &str,
+    f: impl FnOnce(Option<&Value>) -> Result<Value>,
+  ) -> Result<Value> {
+    self
+      .with_user_store(|databases, usage| {
+        // Don't create an empty hash if `f` fails
+        let existing = entity_of::<KvHashMap>(&databases[self.selected_db], key)?;
+        let (value, old) = match &existing {
+          Some(hash) => {
+            let hash = hash.lock().unwrap();
+            let current = hash.get(field).map(|(value, ..)| value);
+            (
+              f(current)?,
+              current.map_or(0, |value| entry_size(field, value)),
+            )
+          }
+          None => (f(None)?, 0),
+        };
+
+        self.charge_collection(databases, usage, key, entry_size(field, &value), old)?;
+        let hash = match existing {
+          Some(hash) => hash,
+          None => entity_or_create::<KvHashMap>(&mut databases[self.selected_db], key)?,
+        };
+
+        let entry = new_entry(value.clone(), KvMapArgs::new());
+        hash.lock().unwrap().insert(field.to_string(), entry);
+        Ok(value)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Adds members to the sorted set stored at a key, creating it if
+  /// needed. Members already in the set move to their new score.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the sorted set
+  /// * `members` - The members to add and their scores, which must not be NaN
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(count)` - Number of members that were not in the set yet
+  /// * `Err` - Not authenticated, the key holds a value that is not a sorted
+  ///   set, or the new members don't fit in the user's quota
+  pub fn zset_add(&self, key: &str, members: Vec<(String, f64)>) -> Result<usize> {
+    self
+      .with_user_store(|databases, usage| {
+        let existing = entity_of::<KvSortedSet>(&databases[self.selected_db], key)?;
+        // Members already in the set only change their score
+        let size = {
+          let set = existing.as_ref().map(|set| set.lock().unwrap());
+          let mut added = HashSet::new();
+          members
+            .iter()
+            .filter(|(member, _)| {
+              set.as_ref().is_none_or(|set| set.score(member).is_none())
+                && added.insert(member.as_str())
+            })
+            .map(|(member, _)| scored_member_size(member))
+            .sum()
+        };
+
+        self.charge_collection(databases, usage, key, size, 0)?;
+        let set = entity_or_create::<KvSortedSet>(&mut databases[self.selected_db], key)?;
+        let mut set = set.lock().unwrap();
+        let mut added = 0;
+        for (member, score) in members {
+          if set.insert(member, score) {
+            added += 1;
+          }
+        }
+        Ok(added)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|_| self.notify(EventClass::SortedSet, "zadd", key))
+  }
+
+  /// Adds to the score of a sorted set member under a single lock.
+  ///
+  /// The set and the member are created if needed, a new member starts at
+  /// a score of 0. Nothing is stored if the new score would be NaN.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the sorted set
+  /// * `member` - The member whose score to change
+  /// * `delta` - Amount to add to the score
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(f64)` - The member's new score
+  /// * `Err` - Not authenticated, the key holds a value that is not a sorted
+  ///   set, the new score would be NaN, or a new member doesn't fit in the
+  ///   user's quota
+  pub fn zset_incr(&self, key: &str, member: &str, delta: f64) -> Result<f64> {
+    self
+      .with_user_store(|databases, usage| {
+        let existing = entity_of::<KvSortedSet>(&databases[self.selected_db], key)?;
+        let current = existing
+          .as_ref()
+          .and_then(|set| set.lock().unwrap().score(member));
+        let score = current.unwrap_or(0.0) + delta;
+        if score.is_nan() {
+          bail!("resulting score is not a number (NaN)");
+        }
+
+        let size = match current {
+          Some(_) => 0,
+          None => scored_member_size(member),
+        };
+        self.charge_collection(databases, usage, key, size, 0)?;
+        let set = match existing {
+          Some(set) => set,
+          None => entity_or_create::<KvSortedSet>(&mut databases[self.selected_db], key)?,
+        };
+        set.lock().unwrap().insert(member.to_string(), score);
+        Ok(score)
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|_| self.notify(EventClass::SortedSet, "zincr", key))
+  }
+
+  /// Runs `f` on the sorted set stored at a key, under a single lock.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(R))` - The result of `f`
+  /// * `Ok(None)` - The key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a sorted set
+  pub fn inspect_zset<R>(&self, key: &str, f: impl FnOnce(&KvSortedSet) -> R) -> Result<Option<R>> {
+    self
+      .with_db(|entities| {
+        let set = entity_of::<KvSortedSet>(entities, key)?;
+        Ok(set.map(|set| f(&set.lock().unwrap())))
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Pushes values onto the list stored at a key, creating it if needed.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the list
+  /// * `values` - The values to push, in order
+  /// * `front` - Push onto the head of the list instead of its tail. Each
+  ///   value goes in front of the previous one, so they end up reversed
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(len)` - Length of the list after the push
+  /// * `Err` - Not authenticated, the key holds a value that is not a list,
+  ///   or the values don't fit in the user's quota
+  pub fn list_push(&self, key: &str, values: &[String], front: bool) -> Result<usize> {
+    self
+      .with_user_store_blocked(|databases, usage, blocked| {
+        let db = self.selected_db;
+        entity_of::<KvLinkedList>(&databases[db], key)?;
+        let size = values.iter().map(|value| member_size(value)).sum();
+        self.charge_collection(databases, usage, key, size, 0)?;
+
+        let entities = &mut databases[db];
+        let list = entity_or_create::<KvLinkedList>(entities, key)?;
+        let len = {
+          let mut list = list.lock().unwrap();
+          for value in values {
+            if front {
+              list.push_front(value.clone());
+            } else {
+              list.push_back(value.clone());
+            }
+          }
+          list.len()
+        };
+        let served = serve_blocked(entities, usage, &mut blocked.pops, db, key);
+        Ok((len, served))
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .map(|(len, served)| {
+        self.notify(EventClass::List, if front { "lpush" } else { "rpush" }, key);
+        for event in served {
+          self.notify(EventClass::List, event, key);
+        }
+        len
+      })
+  }
+
+  /// Pops a value from the first non-empty list among `keys`, or queues the
+  /// client to be served by the next push onto any of them.
+  ///
+  /// Checking the lists and queuing happen under a single lock, so no push
+  /// can slip in between. Blocked clients are served in the order they
+  /// blocked.
+  ///
+  /// # Arguments
+  ///
+  /// * `keys` - The keys holding the lists, checked in order
+  /// * `front` - Pop from the head of the lists instead of their tail
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(ListPop)` - The popped value, or the channel it will be sent through
+  /// * `Err` - Not authenticated, or a key holds a value that is not a list
+  pub fn list_pop_or_block(&self, keys: &[String], front: bool) -> Result<ListPop> {
+    self
+      .with_db_blocked(|entities, usage, blocked, db| {
+        for key in keys {
+          let Some(list) = entity_of::<KvLinkedList>(entities, key)? else {
+            continue;
+          };
+          let mut list = list.lock().unwrap();
+          let value = if front {
+            list.pop_front()
+          } else {
+            list.pop_back()
+          };
+          if let Some(value) = value {
+            release_elements(entities, usage, key, member_size(&value), list.is_empty());
+            return Ok(ListPop::Popped(key.clone(), value));
+          }
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let waiter = BlockedPop {
+          front,
+          reply: Arc::new(Mutex::new(Some(sender))),
+        };
+        for key in keys {
+          let queue = blocked.pops.entry((db, key.clone())).or_default();
+          // Drop clients that stopped waiting, so the queue doesn't grow
+          queue.retain(|waiter| {
+            waiter
+              .reply
+              .lock()
+              .unwrap()
+              .as_ref()
+              .is_some_and(|reply| !reply.is_closed())
+          });
+          queue.push_back(waiter.clone());
+        }
+        Ok(ListPop::Blocked(receiver))
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .inspect(|pop| {
+        if let ListPop::Popped(key, _) = pop {
+          self.notify(EventClass::List, if front { "lpop" } else { "rpop" }, key);
+        }
+      })
+  }
+
+  /// Pops a value from one list and pushes it onto another, under a single
+  /// lock. A source list left empty is deleted, the destination is created
+  /// if needed.
+  ///
+  /// Only one list is locked at a time. The user's databases stay locked,
+  /// so other commands see the value in exactly one of the lists. When
+  /// `source` and `destination` are the same key, the list is rotated.
+  ///
+  /// # Arguments
+  ///
+  /// * `source` - The key holding the list to pop from
+  /// * `destination` - The key holding the list to push onto
+  /// * `from_front` - Pop from the head of the source instead of its tail
+  /// * `to_front` - Push onto the head of the destination instead of its tail
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(value))` - The moved value
+  /// * `Ok(None)` - The source doesn't exist, nothing was moved
+  /// * `Err` - Not authenticated, either key holds a value that is not a
+  ///   list, or a new destination doesn't fit in the user's quota
+  pub fn list_move(
+    &self,
+    source: &str,
+    destination: &str,
+    from_front: bool,
+    to_front: bool,
+  ) -> Result<Option<String>> {
+    self
+      .with_user_store_blocked(|databases, usage, blocked| {
+        let db = self.selected_db;
+        let Some(from) = entity_of::<KvLinkedList>(&databases[db], source)? else {
+          return Ok(None);
+        };
+        // Lists left empty are deleted, so the source has a value to move.
+        // Getting the destination first leaves the source intact if it fails
+        if entity_of::<KvLinkedList>(&databases[db], destination)?.is_none() {
+          if destination == "default" {
+            bail!(RESERVED_KEY);
+          }
+          // The value only changes lists, so only the new key is counted.
+          // The source is about to be popped from and must not be evicted
+          self.make_room(
+            databases,
+            usage,
+            key_size(destination),
+            None,
+            &[source, destination],
+          )?;
+          self.charge_collection(databases, usage, destination, 0, 0)?;
+        }
+        let entities = &mut databases[db];
+        let to = entity_or_create::<KvLinkedList>(entities, destination)?;
+
+        let value = {
+          let mut from = from.lock().unwrap();
+          let value = if from_front {
+            from.pop_front()
+          } else {
+            from.pop_back()
+          };
+          if from.is_empty() && source != destination {
+            release_elements(entities, usage, source, 0, true);
+          }
+          value
+        };
+        let Some(value) = value else {
+          return Ok(None);
+        };
+
+        {
+          let mut to = to.lock().unwrap();
+          if to_front {
+            to.push_front(value.clone());
+          } else {
+            to.push_back(value.clone());
+          }
+        }
+        let served = serve_blocked(entities, usage, &mut blocked.pops, db, destination);
+        Ok(Some((value, served)))
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+      .map(|moved| {
+        moved.map(|(value, served)| {
+          self.notify(
+            EventClass::List,
+            if from_front { "lpop" } else { "rpop" },
+            source,
+          );
+          self.notify(
+            EventClass::List,
+            if to_front { "lpush" } else { "rpush" },
+            destination,
+          );
+          for event in served {
+            self.notify(EventClass::List, event, destination);
+          }
+          value
+        })
+      })
+  }
+
+  /// Checks whether a key exists, or queues the client to be woken up by
+  /// the write that creates it.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(None)` - The key exists
+  /// * `Ok(Some(receiver))` - The key doesn't exist. The receiver gets a
+  ///   message once it does, drop it to stop waiting
+  /// * `Err` - Not authenticated
+  pub fn wait_for_key(&self, key: &str) -> Result<Option<oneshot::Receiver<()>>> {
+    self
+      .with_db_blocked(|entities, _, blocked, db| {
+        if kind_of(entities, key).is_some() {
+          return None;
+        }
+        let (sender, receiver) = oneshot::channel();
+        blocked
+          .keys
+          .entry((db, key.to_string()))
+          .or_default()
+          .push(sender);
+        Some(receiver)
+      })
+      .ok_or_else(|| anyhow!(AUTH_REQUIRED))
+  }
+
+  /// Pops up to `count` values from the list stored at a key. A list left
+  /// empty is deleted.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the list
+  /// * `count` - The most values to pop
+  /// * `front` - Pop from the head of the list instead of its tail
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(values))` - The popped values, in the order they were popped
+  /// * `Ok(None)` - The key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a list
+  pub fn list_pop(&self, key: &str, count: usize, front: bool) -> Result<Option<Vec<String>>> {
+    self
+      .with_db_usage(|entities, usage| {
+        let Some(list) = entity_of::<KvLinkedList>(entities, key)? else {
+          return Ok(None);
+        };
+        let mut list = list.lock().unwrap();
+        let mut popped = Vec::new();
+        while popped.len() < count
+          && let Some(value) = if front {
+            list.pop_front()
+          } else {
+            list.pop_back()
+          }
+        {
+          popped.push(value);
+        }
+        let size = popped.iter().map(|value| member_size(value)).sum();
+        release_elements(entities, usage, key, size, list.is_empty());
+        Ok(Some(popped))
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Runs `f` on the list stored at a key, under a single lock. A list
+  /// left empty is deleted.
+  ///
+  /// The list is measured before and after `f` runs to account for what it
+  /// changed, so this takes time linear in the length of the list.
+  ///
+  /// # Arguments
+  ///
+  /// * `key` - The key holding the list
+  /// * `added` - The value `f` may add to the list, if any, checked against
+  ///   the user's quota before it runs
+  /// * `f` - Function modifying the list
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(R))` - The result of `f`
+  /// * `Ok(None)` - The key doesn't exist
+  /// * `Err` - Not authenticated, the key holds a value that is not a list,
+  ///   or `added` doesn't fit in the user's quota
+  pub fn update_list<R>(
+    &self,
+    key: &str,
+    added: Option<&str>,
+    f: impl FnOnce(&mut KvLinkedList) -> R,
+  ) -> Result<Option<R>> {
+    self
+      .with_user_store(|databases, usage| {
+        let Some(list) = entity_of::<KvLinkedList>(&databases[self.selected_db], key)? else {
+          return Ok(None);
+        };
+        if let Some(added) = added {
+          let size = member_size(added);
+          self.make_room(databases, usage, size, Some(0), &[key])?;
+          usage.check(self.quota(), size, Some(0))?;
+        }
+
+        let mut list = list.lock().unwrap();
+        let old = list_size(&list);
+        let result = f(&mut list);
+        usage.replace(list_size(&list), Some(old));
+        if list.is_empty() {
+          release_elements(&mut databases[self.selected_db], usage, key, 0, true);
+        }
+        Ok(Some(result))
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Runs `f` on the list stored at a key, under a single lock.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Some(R))` - The result of `f`
+  /// * `Ok(None)` - The key doesn't exist
+  /// * `Err` - Not authenticated, or the key holds a value that is not a list
+  pub fn inspect_list<R>(
+    &self,
+    key: &str,
+    f: impl FnOnce(&KvLinkedList) -> R,
+  ) -> Result<Option<R>> {
+    self
+      .with_db(|entities| {
+        let list = entity_of::<KvLinkedList>(entities, key)?;
+        Ok(list.map(|list| f(&list.lock().unwrap())))
+      })
+      .unwrap_or_else(|| Err(anyhow!(AUTH_REQUIRED)))
+  }
+
+  /// Moves a user's data from one store key to another.
+  ///
+  /// Used to carry data saved under a legacy credential hash over to the
+  /// user's id. Does nothing if no store exists under the old key, or if
+  /// one already exists under the new key.
+  ///
+  /// # Arguments
+  ///
+  /// * `old_key` - Key the data is stored under
+  /// * `new_key` - Key to store the data under from now on
+  pub fn rekey_user(&self, old_key: &str, new_key: &str) {
+    if self.auth_stores.contains_key(new_key) {
+      return;
+    }
+    let Some((old_key, user_store)) = self.auth_stores.remove(old_key) else {
+      return;
+    };
+    // A store may have been created under the new key in the meantime. The
+    // entry locks its shard, so it must be gone before putting the data back
+    let unmoved = match self.auth_stores.entry(new_key.to_string()) {
+      Entry::Vacant(entry) => {
+        entry.insert(user_store);
+        None
+      }
+      Entry::Occupied(_) => Some(user_store),
+    };
+    if let Some(user_store) = unmoved {
+      self.auth_stores.insert(old_key, user_store);
+    }
+  }
+
+  /// Returns the number of users with a store, their total key count and
+  /// the approximate size of their keys and values in bytes.
+  ///
+  /// Expired keys that were not removed yet are counted too, so the
+  /// counts are only approximate.
+  pub fn usage(&self) -> (usize, usize, usize) {
+    let (users, keys, bytes) = self
+      .auth_stores
+      .iter()
+      .map(|user_store| user_store.usage.get())
+      .fold((0, 0, 0), |(users, keys, bytes), (k, b)| {
+        (users + 1, keys + k, bytes + b)
+      });
+    (users, keys, bytes)
+  }
+
+  /// Returns the key count and approximate size in bytes of every user's
+  /// store, sorted by user id.
+  pub fn usage_by_user(&self) -> Vec<(String, usize, usize)> {
+    let mut usage: Vec<_> = self
+      .auth_stores
+      .iter()
+      .map(|user_store| {
+        let (keys, bytes) = user_store.usage.get();
+        (user_store.key().clone(), keys, bytes)
+      })
+      .collect();
+    usage.sort();
+    usage
+  }
+
+  /// Returns the current user's key count and approximate size in bytes.
+  ///
+  /// # Returns
+  ///
+  /// * `Some((keys, bytes))` - The user's usage
+  /// * `None` - If no user is authenticated
+  pub fn user_usage(&self) -> Option<(usize, usize)> {
+    let user_id = self.get_current_user()?;
+    self
+      .auth_stores
+      .get(&user_id)
+      .map(|user_store| user_store.usage.get())
+  }
+
+  /// Returns the limits every user's store is held to.
+  pub fn quota(&self) -> Quota {
+    *self.quota.read().unwrap()
+  }
+
+  /// Changes the limits every user's store is held to, for all handles.
+  ///
+  /// Users already over the new limits keep their data, only writes that
+  /// would grow it further fail.
+  pub fn set_quota(&self, quota: Quota) {
+    *self.quota.write().unwrap() = quota;
+  }
+
+  /// Returns the memory budget past which keys are evicted.
+  pub fn eviction(&self) -> Eviction {
+    *self.eviction.read().unwrap()
+  }
+
+  /// Changes the memory budget past which keys are evicted, for all handles.
+  ///
+  /// Nothing is evicted right away, the next write that needs room does it.
+  pub fn set_eviction(&self, eviction: Eviction) {
+    *self.eviction.write().unwrap() = eviction;
+  }
+
+  /// Returns the key and expiring key counts of each of the current
+  /// user's databases.
+  ///
+  /// # Returns
+  ///
+  /// * `Some(sizes)` - `(keys, expires)` for every database, by index
+  /// * `None` - If no user is authenticated
+  pub fn keyspace(&self) -> Option<Vec<(usize, usize)>> {
+    self.with_databases(|databases| databases.iter().map(db_size).collect())
+  }
+
+  /// Evicts keys until an entry of `size` bytes, stored in place of one of
+  /// `old` bytes or in a new key if None, fits in `maxmemory`.
+  ///
+  /// Must be called without any of the user's maps locked.
+  ///
+  /// # Arguments
+  ///
+  /// * `keep` - Keys of the selected database the write is about, never evicted
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - The entry fits
+  /// * `Err` - Nothing more may be evicted and the entry still doesn't fit
+  fn make_room(
+    &self,
+    databases: &mut [HashMap<String, Entities>],
+    usage: &Usage,
+    size: usize,
+    old: Option<usize>,
+    keep: &[&str],
+  ) -> Result<()> {
+    let Eviction { maxmemory, policy } = self.eviction();
+    // Shrinking an existing entry never needs room
+    if maxmemory == 0 || old.is_some_and(|old| size <= old) {
+      return Ok(());
+    }
+    // Don't evict anything for an entry that can never fit
+    if size > maxmemory {
+      bail!(eviction::OOM);
+    }
+
+    while usage.get().1 + size - old.unwrap_or(0) > maxmemory {
+      let Some((db, victim)) = eviction::pick_victim(policy, databases, (self.selected_db, keep))
+      else {
+        bail!(eviction::OOM);
+      };
+      if holds_entity(&databases[db], &victim) {
+        let entity = databases[db].remove(&victim).unwrap();
+        usage.remove(entity_size(&victim, &entity));
+        debug!("Evicted key '{}' from database {}", victim, db);
+        self.record_change();
+        self
+          .notifier
+          .notify(EventClass::Evicted, "evicted", db, &victim);
+        continue;
+      }
+      let Some(map) = default_map(&databases[db]) else {
+        bail!(eviction::OOM);
+      };
+      let entry = map.lock().unwrap().remove(&victim);
+      if let Some(entry) = entry {
+        // Keys past their expiry were only waiting to be noticed
+        let (class, event) = if is_live(&entry) {
+          (EventClass::Evicted, "evicted")
+        } else {
+          (EventClass::Expired, "expired")
+        };
+        let (value, ..) = entry;
+        usage.remove(entry_size(&victim, &value));
+        debug!("Evicted key '{}' from database {}", victim, db);
+        self.lazy_free(value);
+        self.record_change();
+        self.notifier.notify(class, event, db, &victim);
+      }
+    }
+    Ok(())
+  }
 
-/// Main in-memory storage structure.
+  /// Makes room for elements of `size` bytes replacing elements of `old`
+  /// bytes in the collection at `key` of the selected database, and counts
+  /// them against the user's quota.
+  ///
+  /// A key that doesn't exist yet is counted as a new key, along with its
+  /// name. Must be called without any of the user's maps locked.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - The elements were counted and may be stored
+  /// * `Err` - The key is reserved, or the elements don't fit in the quota
+  ///   or in `maxmemory`
+  fn charge_collection(
+    &self,
+    databases: &mut [HashMap<String, Entities>],
+    usage: &Usage,
+    key: &str,
+    size: usize,
+    old: usize,
+  ) -> Result<()> {
+    let entities = &databases[self.selected_db];
+    let (size, old) = if holds_entity(entities, key) {
+      (size, Some(old))
+    } else {
+      if key == "default" {
+        bail!(RESERVED_KEY);
+      }
+      // An expired string may still be waiting to be removed under the key
+      if let Some(map) = default_map(entities)
+        && let Some((value, ..)) = map.lock().unwrap().remove(key)
+      {
+        usage.remove(entry_size(key, &value));
+        self.lazy_free(value);
+      }
+      (key_size(key) + size, None)
+    };
+
+    self.make_room(databases, usage, size, old, &[key])?;
+    usage.check(self.quota(), size, old)?;
+    usage.replace(size, old);
+    Ok(())
+  }
+
+  /// Publishes a keyspace notification about a key of the selected
+  /// database, if its class is enabled.
+  ///
+  /// # Arguments
+  ///
+  /// * `class` - Kind of change the event reports
+  /// * `event` - Name of the event, usually the command's in lower case
+  /// * `key` - The key the event is about
+  pub fn notify(&self, class: EventClass, event: &str, key: &str) {
+    // Every change to a key is notified, so it is counted here
+    self.record_change();
+    self.notifier.notify(class, event, self.selected_db, key);
+  }
+
+  /// Returns the number of changes made to any user's keys since the last
+  /// successful save.
+  pub fn changes_since_last_save(&self) -> u64 {
+    self.changes.load(Ordering::Relaxed)
+  }
+
+  /// Takes the changes a successful save started with off the counter.
+  ///
+  /// Changes made while the save ran are not in it, so they stay counted.
+  ///
+  /// # Arguments
+  ///
+  /// * `saved` - [`MemoryStore::changes_since_last_save`] when the save started
+  pub fn mark_saved(&self, saved: u64) {
+    self.changes.fetch_sub(saved, Ordering::Relaxed);
+  }
+
+  /// Returns the progress of the saves made from the store.
+  pub(super) fn saves(&self) -> &SaveState {
+    &self.saves
+  }
+
+  /// Counts a change to a key, for [`MemoryStore::changes_since_last_save`].
+  fn record_change(&self) {
+    self.changes.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// Returns the notifier keyspace notifications are published through.
+  pub fn notifier(&self) -> &Notifier {
+    &self.notifier
+  }
+
+  /// Fails if `index` does not name one of the configured databases.
+  fn check_db_index(&self, index: usize) -> Result<()> {
+    if index >= self.databases {
+      bail!("DB index is out of range");
+    }
+    Ok(())
+  }
+
+  /// Runs `f` on all of the current user's databases under a single lock.
+  ///
+  /// # Returns
+  ///
+  /// * `Some(R)` - The result of `f`
+  /// * `None` - If no user is authenticated
+  fn with_databases<R>(
+    &self,
+    f: impl FnOnce(&mut Vec<HashMap<String, Entities>>) -> R,
+  ) -> Option<R> {
+    self.with_user_store(|databases, _| f(databases))
+  }
+
+  /// Runs `f` on the entities of the current user's selected database,
+  /// along with the user's usage, the clients blocked on the user's keys
+  /// and the index of the selected database.
+  ///
+  /// # Returns
+  ///
+  /// * `Some(R)` - The result of `f`
+  /// * `None` - If no user is authenticated
+  fn with_db_blocked<R>(
+    &self,
+    f: impl FnOnce(&mut HashMap<String, Entities>, &Usage, &mut Blocked, usize) -> R,
+  ) -> Option<R> {
+    self.with_user_store_blocked(|databases, usage, blocked| {
+      f(
+        &mut databases[self.selected_db],
+        usage,
+        blocked,
+        self.selected_db,
+      )
+    })
+  }
+
+  /// Runs `f` on the entities of the current user's selected database,
+  /// along with the user's usage so writes can be accounted for.
+  ///
+  /// # Returns
+  ///
+  /// * `Some(R)` - The result of `f`
+  /// * `None` - If no user is authenticated
+  fn with_db_usage<R>(
+    &self,
+    f: impl FnOnce(&mut HashMap<String, Entities>, &Usage) -> R,
+  ) -> Option<R> {
+    self.with_user_store(|databases, usage| f(&mut databases[self.selected_db], usage))
+  }
+
+  /// Runs `f` on all of the current user's databases and their usage,
+  /// under a single lock.
+  fn with_user_store<R>(
+    &self,
+    f: impl FnOnce(&mut Vec<HashMap<String, Entities>>, &Usage) -> R,
+  ) -> Option<R> {
+    self.with_user_store_blocked(|databases, usage, _| f(databases, usage))
+  }
+
+  /// Runs `f` on all of the current user's databases, their usage and the
+  /// clients blocked on them, under a single lock.
+  ///
+  /// Every write goes through here, so clients waiting for a key that
+  /// exists once `f` returns are woken up before the lock is released.
+  fn with_user_store_blocked<R>(
+    &self,
+    f: impl FnOnce(&mut Vec<HashMap<String, Entities>>, &Usage, &mut Blocked) -> R,
+  ) -> Option<R> {
+    let user_id = self.get_current_user()?;
+    // Clone the handle out, so the map's shard isn't locked while `f` runs
+    let user_store = self.auth_stores.get(&user_id)?.clone();
+    let mut databases = user_store.databases.lock().unwrap();
+    let mut blocked = user_store.blocked.lock().unwrap();
+    let result = f(&mut databases, &user_store.usage, &mut blocked);
+    wake_key_waiters(&databases, &mut blocked.keys);
+    Some(result)
+  }
+
+  /// Runs `f` on the entities of the current user's selected database.
+  ///
+  /// # Returns
+  ///
+  /// * `Some(R)` - The result of `f`
+  /// * `None` - If no user is authenticated
+  fn with_db<R>(&self, f: impl FnOnce(&mut HashMap<String, Entities>) -> R) -> Option<R> {
+    self.with_databases(|databases| f(&mut databases[self.selected_db]))
+  }
+}
+
+/// Names the variant of a stored value, without its contents.
+fn variant_name(value: &Value) -> &'static str {
+  match value {
+    Value::Null => "Null",
+    Value::SimpleString(_) => "SimpleString",
+    Value::BulkString(_) => "BulkString",
+    Value::BulkBytes(_) => "BulkBytes",
+    Value::Integer(_) => "Integer",
+    Value::Error(_) => "Error",
+    Value::Boolean(_) => "Boolean",
+    Value::Double(_) => "Double",
+    Value::VerbatimString(..) => "VerbatimString",
+    Value::BigNumber(_) => "BigNumber",
+    Value::Push(_) => "Push",
+    Value::Array(_) => "Array",
+  }
+}
+
+/// Checks whether a stored value has outlived its EX/PX expiry.
 ///
-/// Provides authenticated access to user-specific data stores.
-#[derive(Clone)]
-pub struct MemoryStore {
-  /// Store for authenticated users, keyed by user credential hash
-  auth_stores: Arc<RwLock<HashMap<String, UserStore>>>,
-  /// Current user's credential hash (if authenticated)
-  current_user: Arc<RwLock<Option<String>>>,
+/// # Arguments
+///
+/// * `inserted_at` - Time the value was stored
+/// * `args` - Options the value was stored with
+pub(super) fn is_expired(inserted_at: &SystemTime, args: &KvMapArgs) -> bool {
+  let elapsed = SystemTime::elapsed(inserted_at).unwrap_or_default();
+
+  // Check for expiration if Ex option is set (in seconds)
+  let ex_expired = args
+    .get(&Options::Ex)
+    .is_some_and(|&expiry| elapsed.as_secs() >= expiry);
+
+  // Check for expiration if Px option is set (in milliseconds)
+  let px_expired = args
+    .get(&Options::Px)
+    .is_some_and(|&expiry| elapsed.as_millis() >= expiry as u128);
+
+  ex_expired || px_expired
 }
 
-/// Represents a single user's data store.
+/// Returns the time a value stored with EX/PX options expires, if it does.
 ///
-/// Contains all entities (HashMaps, Sets, etc.) owned by a specific user.
-#[derive(Clone, Debug)]
-pub struct UserStore {
-  /// Stores entity references for various data types
-  /// Key is entity name, value is the entity (HashMap, Set, etc)
-  entities: Arc<Mutex<HashMap<String, Entities>>>,
+/// # Arguments
+///
+/// * `inserted_at` - Time the value was stored
+/// * `args` - Options the value was stored with
+pub(super) fn deadline(inserted_at: &SystemTime, args: &KvMapArgs) -> Option<SystemTime> {
+  let ex = args
+    .get(&Options::Ex)
+    .map(|&secs| *inserted_at + Duration::from_secs(secs));
+  let px = args
+    .get(&Options::Px)
+    .map(|&millis| *inserted_at + Duration::from_millis(millis));
+  // Whichever comes first expires the value, like in `is_expired`
+  ex.into_iter().chain(px).min()
 }
 
-impl UserStore {
-  /// Creates a new empty UserStore.
-  fn new() -> Self {
-    Self {
-      entities: Arc::new(Mutex::new(HashMap::new())),
+/// Counts the elements of a value, including those of nested arrays.
+///
+/// Counting stops early once the lazy free threshold is reached.
+fn element_count(value: &Value) -> usize {
+  match value {
+    Value::Array(values) => values.iter().fold(0, |count, value| {
+      if count >= LAZY_FREE_THRESHOLD {
+        count
+      } else {
+        count + 1 + element_count(value)
+      }
+    }),
+    _ => 0,
+  }
+}
+
+/// Estimates the memory an entry takes up: its key plus its value, see
+/// [`size_of_value`].
+fn entry_size(key: &str, value: &Value) -> usize {
+  key_size(key) + size_of_value(value)
+}
+
+/// Estimates the memory a key's name takes up.
+fn key_size(key: &str) -> usize {
+  size_of::<String>() + key.len()
+}
+
+/// Estimates the memory a member of a set or a list takes up.
+fn member_size(member: &str) -> usize {
+  size_of::<String>() + member.len()
+}
+
+/// Estimates the memory a member of a sorted set takes up, with its score.
+fn scored_member_size(member: &str) -> usize {
+  member_size(member) + size_of::<f64>()
+}
+
+/// Estimates the memory the values of a list take up.
+fn list_size(list: &KvLinkedList) -> usize {
+  list.iter().map(|value| member_size(value)).sum()
+}
+
+/// Estimates the memory an entity other than a default map takes up: its
+/// key plus every element it holds.
+///
+/// Locks the entity, so it must not be held by the caller.
+fn entity_size(key: &str, entity: &Entities) -> usize {
+  let elements = match entity {
+    Entities::HashMap(hash) => hash
+      .lock()
+      .unwrap()
+      .iter()
+      .map(|(field, (value, ..))| entry_size(field, value))
+      .sum(),
+    Entities::Set(set) => set
+      .lock()
+      .unwrap()
+      .iter()
+      .map(|member| member_size(member))
+      .sum(),
+    Entities::LinkedList(list) => list_size(&list.lock().unwrap()),
+    Entities::SortedSet(zset) => zset
+      .lock()
+      .unwrap()
+      .iter()
+      .map(|(member, _)| scored_member_size(member))
+      .sum(),
+    Entities::_HashSet | Entities::_List | Entities::_Queue => 0,
+  };
+  key_size(key) + elements
+}
+
+/// Takes elements of `size` bytes removed from the collection at `key` off
+/// a user's usage, and deletes the collection if they left it `empty`.
+fn release_elements(
+  entities: &mut HashMap<String, Entities>,
+  usage: &Usage,
+  key: &str,
+  size: usize,
+  empty: bool,
+) {
+  usage.shrink(size);
+  if empty {
+    entities.remove(key);
+    usage.remove(key_size(key));
+  }
+}
+
+/// Counts the keys of a database and how many of them have an expiry.
+///
+/// Every entry of the default map is a key, other entities count as one
+/// key each.
+fn db_size(entities: &HashMap<String, Entities>) -> (usize, usize) {
+  entities
+    .iter()
+    .fold((0, 0), |(keys, expires), (name, entity)| match entity {
+      Entities::HashMap(map) if name == "default" => {
+        let map = map.lock().unwrap();
+        let expiring = map
+          .values()
+          .filter(|(_, _, args, _)| {
+            args.contains_key(&Options::Ex) || args.contains_key(&Options::Px)
+          })
+          .count();
+        (keys + map.len(), expires + expiring)
+      }
+      _ => (keys + 1, expires),
+    })
+}
+
+/// Rewrites expiry options relative to the current time.
+///
+/// EX and PX count from the insertion time, so an entry stored again now
+/// needs the time already elapsed taken off. The result always uses PX.
+fn rebase_expiry(inserted_at: &SystemTime, args: &KvMapArgs) -> KvMapArgs {
+  let elapsed = SystemTime::elapsed(inserted_at)
+    .unwrap_or_default()
+    .as_millis() as u64;
+  shift_expiry(args, elapsed)
+}
+
+/// Takes `elapsed` milliseconds off an entry's expiry options, as PX.
+fn shift_expiry(args: &KvMapArgs, elapsed: u64) -> KvMapArgs {
+  let mut args = args.clone();
+  let ex = args
+    .remove(&Options::Ex)
+    .map(|secs| secs.saturating_mul(1000));
+  let px = args.remove(&Options::Px);
+
+  let expiry = match (ex, px) {
+    (Some(ex), Some(px)) => Some(ex.min(px)),
+    (ex, px) => ex.or(px),
+  };
+  if let Some(expiry) = expiry {
+    args.insert(Options::Px, expiry.saturating_sub(elapsed));
+  }
+  args
+}
+
+/// Makes an entry expire at `deadline`.
+///
+/// The deadline is stored as a PX expiry counted from the entry's
+/// insertion time, rounded up to the next millisecond.
+///
+/// # Returns
+///
+/// * `true` - The expiry was set
+/// * `false` - The deadline already passed, the entry should be removed
+fn set_deadline((_, inserted_at, args, _): &mut KvMapPair, deadline: SystemTime) -> bool {
+  if deadline <= SystemTime::now() {
+    return false;
+  }
+  let expiry = deadline.duration_since(*inserted_at).unwrap_or_default();
+  args.remove(&Options::Ex);
+  args.insert(Options::Px, expiry.as_nanos().div_ceil(1_000_000) as u64);
+  true
+}
+
+/// Records that an entry was just accessed, for LRU eviction and idle times.
+fn touch_entry((.., accessed_at): &mut KvMapPair) {
+  *accessed_at = SystemTime::now();
+}
+
+/// Creates an entry for a value stored now.
+fn new_entry(value: Value, args: KvMapArgs) -> KvMapPair {
+  let now = SystemTime::now();
+  (value, now, args, now)
+}
+
+/// Checks whether a stored entry has not expired yet.
+pub(super) fn is_live((_, inserted_at, args, _): &KvMapPair) -> bool {
+  !is_expired(inserted_at, args)
+}
+
+/// Returns the default map of a database, if it has one.
+pub(super) fn default_map(
+  entities: &HashMap<String, Entities>,
+) -> Option<Arc<Mutex<Tracked<KvHashMap>>>> {
+  match entities.get("default") {
+    Some(Entities::HashMap(map)) => Some(map.clone()),
+    _ => None,
+  }
+}
+
+/// Returns true if `key` names an entity of its own rather than a string
+/// in the default map.
+///
+/// Unlike [`kind_of`], this leaves the default map unlocked, so it can be
+/// called by code already holding it.
+fn holds_entity(entities: &HashMap<String, Entities>, key: &str) -> bool {
+  key != "default" && entities.contains_key(key)
+}
+
+/// Returns the kind of value `key` holds in a database, if it exists.
+///
+/// Locks the default map, so it must not be held by the caller.
+fn kind_of(entities: &HashMap<String, Entities>, key: &str) -> Option<EntityKind> {
+  if let Some(entity) = entities.get(key).filter(|_| holds_entity(entities, key)) {
+    return Some(entity.kind());
+  }
+
+  let map = default_map(entities)?;
+  let map = map.lock().unwrap();
+  map
+    .get(key)
+    .filter(|entry| is_live(entry))
+    .map(|_| EntityKind::String)
+}
+
+/// A collection kept in an entity of its own, see [`entity_of`].
+trait Collection: Default {
+  /// Returns the collection an entity holds, if it is of this kind.
+  fn of(entity: &Entities) -> Option<&Arc<Mutex<Tracked<Self>>>>;
+
+  /// Wraps a collection into an entity.
+  fn into_entity(collection: Arc<Mutex<Tracked<Self>>>) -> Entities;
+}
+
+impl Collection for KvSet {
+  fn of(entity: &Entities) -> Option<&Arc<Mutex<Tracked<Self>>>> {
+    match entity {
+      Entities::Set(set) => Some(set),
+      _ => None,
+    }
+  }
+
+  fn into_entity(collection: Arc<Mutex<Tracked<Self>>>) -> Entities {
+    Entities::Set(collection)
+  }
+}
+
+impl Collection for KvHashMap {
+  fn of(entity: &Entities) -> Option<&Arc<Mutex<Tracked<Self>>>> {
+    match entity {
+      Entities::HashMap(hash) => Some(hash),
+      _ => None,
+    }
+  }
+
+  fn into_entity(collection: Arc<Mutex<Tracked<Self>>>) -> Entities {
+    Entities::HashMap(collection)
+  }
+}
+
+impl Collection for KvSortedSet {
+  fn of(entity: &Entities) -> Option<&Arc<Mutex<Tracked<Self>>>> {
+    match entity {
+      Entities::SortedSet(set) => Some(set),
+      _ => None,
+    }
+  }
+
+  fn into_entity(collection: Arc<Mutex<Tracked<Self>>>) -> Entities {
+    Entities::SortedSet(collection)
+  }
+}
+
+impl Collection for KvLinkedList {
+  fn of(entity: &Entities) -> Option<&Arc<Mutex<Tracked<Self>>>> {
+    match entity {
+      Entities::LinkedList(list) => Some(list),
+      _ => None,
+    }
+  }
+
+  fn into_entity(collection: Arc<Mutex<Tracked<Self>>>) -> Entities {
+    Entities::LinkedList(collection)
+  }
+}
+
+/// Returns the collection of kind `T` stored at `key` in a database, if
+/// it exists.
+///
+/// Counts as an access to the key. Locks the default map, so it must not
+/// be held by the caller.
+///
+/// # Returns
+///
+/// * `Ok(Some(collection))` - The collection
+/// * `Ok(None)` - The key doesn't exist
+/// * `Err` - The key holds a value of another kind
+fn entity_of<T: Collection>(
+  entities: &HashMap<String, Entities>,
+  key: &str,
+) -> Result<Option<Arc<Mutex<Tracked<T>>>>> {
+  if holds_entity(entities, key) {
+    let collection = T::of(&entities[key]).ok_or_else(|| anyhow!(WRONG_TYPE))?;
+    collection.lock().unwrap().touch();
+    return Ok(Some(collection.clone()));
+  }
+  match kind_of(entities, key) {
+    Some(_) => bail!(WRONG_TYPE),
+    None => Ok(None),
+  }
+}
+
+/// Returns the collection of kind `T` stored at `key` in a database,
+/// creating an empty one if the key doesn't exist.
+///
+/// # Returns
+///
+/// * `Ok(collection)` - The collection
+/// * `Err` - The key holds a value of another kind, or is reserved
+fn entity_or_create<T: Collection>(
+  entities: &mut HashMap<String, Entities>,
+  key: &str,
+) -> Result<Arc<Mutex<Tracked<T>>>> {
+  if let Some(collection) = entity_of(entities, key)? {
+    return Ok(collection);
+  }
+  if key == "default" {
+    bail!(RESERVED_KEY);
+  }
+  let collection = Arc::new(Mutex::new(Tracked::default()));
+  entities.insert(key.to_string(), T::into_entity(collection.clone()));
+  Ok(collection)
+}
+
+/// Wakes up the clients waiting for keys that now exist, and forgets the
+/// ones that stopped waiting.
+fn wake_key_waiters(
+  databases: &[HashMap<String, Entities>],
+  waiters: &mut HashMap<(usize, String), Vec<oneshot::Sender<()>>>,
+) {
+  waiters.retain(|(db, key), senders| {
+    if kind_of(&databases[*db], key).is_some() {
+      for sender in senders.drain(..) {
+        let _ = sender.send(());
+      }
+      return false;
+    }
+    senders.retain(|sender| !sender.is_closed());
+    !senders.is_empty()
+  });
+}
+
+/// Hands values of the list stored at `key` to the clients blocked on it,
+/// oldest first, until either runs out. A list left empty is deleted.
+///
+/// Clients that were already served through another key, or stopped
+/// waiting, are skipped and their value stays in the list.
+///
+/// Returns the pop event of every client served, for the caller to notify
+/// once the lock is released.
+fn serve_blocked(
+  entities: &mut HashMap<String, Entities>,
+  usage: &Usage,
+  blocked: &mut BlockedPops,
+  db: usize,
+  key: &str,
+) -> Vec<&'static str> {
+  let mut served = Vec::new();
+  let slot = (db, key.to_string());
+  let Some(queue) = blocked.get_mut(&slot) else {
+    return served;
+  };
+  let Ok(Some(list)) = entity_of::<KvLinkedList>(entities, key) else {
+    return served;
+  };
+
+  let mut list = list.lock().unwrap();
+  let mut size = 0;
+  while !list.is_empty()
+    && let Some(waiter) = queue.pop_front()
+  {
+    let Some(reply) = waiter.reply.lock().unwrap().take() else {
+      continue;
+    };
+    let value = if waiter.front {
+      list.pop_front()
+    } else {
+      list.pop_back()
+    };
+    let Some(value) = value else {
+      break;
+    };
+    let value_size = member_size(&value);
+    match reply.send((key.to_string(), value)) {
+      Ok(()) => {
+        size += value_size;
+        served.push(if waiter.front { "lpop" } else { "rpop" });
+      }
+      // The client stopped waiting, put the value back where it was
+      Err((_, value)) => {
+        if waiter.front {
+          list.push_front(value);
+        } else {
+          list.push_back(value);
+        }
+      }
+    }
+  }
+
+  if queue.is_empty() {
+    blocked.remove(&slot);
+  }
+  release_elements(entities, usage, key, size, list.is_empty());
+  served
+}
+
+/// Combines the sets stored at `keys` in a database, see
+/// [`MemoryStore::set_combine`].
+///
+/// Only one set is locked at a time. The user's databases stay locked, so
+/// none of them can change while the result is computed.
+fn combine_sets(entities: &HashMap<String, Entities>, op: SetOp, keys: &[String]) -> Result<KvSet> {
+  let sets = keys
+    .iter()
+    .map(|key| entity_of::<KvSet>(entities, key))
+    .collect::<Result<Vec<_>>>()?;
+  let Some((first, others)) = sets.split_first() else {
+    return Ok(KvSet::new());
+  };
+
+  let mut result = first
+    .as_ref()
+    .map(|set| KvSet::clone(&set.lock().unwrap()))
+    .unwrap_or_default();
+  for set in others {
+    match (op, set) {
+      (SetOp::Inter, None) => result.clear(),
+      (SetOp::Inter, Some(set)) => {
+        let set = set.lock().unwrap();
+        result.retain(|member| set.contains(member));
+      }
+      (SetOp::Union, Some(set)) => result.extend(set.lock().unwrap().iter().cloned()),
+      (SetOp::Diff, Some(set)) => {
+        let set = set.lock().unwrap();
+        result.retain(|member| !set.contains(member));
+      }
+      (SetOp::Union | SetOp::Diff, None) => {}
+    }
+    if result.is_empty() && !matches!(op, SetOp::Union) {
+      break;
     }
   }
+  Ok(result)
+}
+
+/// Returns the default map of a database, creating it if it doesn't exist.
+///
+/// # Returns
+///
+/// * `Some(map)` - The default map
+/// * `None` - If the "default" entity is not a map
+fn default_map_or_create(
+  entities: &mut HashMap<String, Entities>,
+) -> Option<Arc<Mutex<Tracked<KvHashMap>>>> {
+  let entity = entities
+    .entry("default".to_string())
+    .or_insert_with(|| Entities::HashMap(Arc::new(Mutex::new(Tracked::default()))));
+
+  match entity {
+    Entities::HashMap(map) => Some(map.clone()),
+    _ => None,
+  }
+}
+
+/// Result of [`Store::set`].
+#[derive(Debug)]
+pub struct SetOutcome {
+  /// Whether the value was stored, NX and XX can prevent it
+  pub stored: bool,
+  /// The value the key held before, only filled in when GET was given
+  pub previous: Option<Value>,
 }
 
 /// Interface for storage operations.
 ///
 /// Defines the standard operations that all storage implementations must provide.
+// The futures are only awaited on the connection's own task, so they needn't
+// be declared `Send`
+#[allow(async_fn_in_trait)]
 pub trait Store {
-  /// Creates a new store instance.
-  fn new() -> Self;
-
   /// Sets a key-value pair in the store.
   ///
   /// # Arguments
   ///
   /// * `key` - The key to set
   /// * `value` - The value to store
+  /// * `options` - Expiry options to store with the value, and the NX, XX,
+  ///   GET and KEEPTTL flags, which are applied and then dropped. KEEPTTL
+  ///   carries over the expiry of the value being replaced
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(SetOutcome)` - Whether the value was stored and, for GET, the previous value
+  /// * `Err` - Not authenticated, out of memory, or GET met a value that is not a string
   async fn set(
     &self,
     key: &str,
     value: Value,
     options: HashMap<Options, u64>,
-  ) -> anyhow::Result<()>;
+  ) -> anyhow::Result<SetOutcome>;
 
   /// Gets a value from the store by key.
   ///
@@ -84,22 +3071,23 @@ pub trait Store {
   ///
   /// # Returns
   ///
-  /// * `Some(Value)` - The deleted value if found
-  /// * `None` - If the key didn't exist
+  /// * `Some(Value)` - The deleted value if found, Null for keys that are
+  ///   not strings
+  /// * `None` - If the key didn't exist or had already expired
   async fn delete(&self, key: &str) -> Option<Value>;
 
   /// Sets the current authenticated user.
   ///
   /// # Arguments
   ///
-  /// * `user_hash` - Credential hash for the authenticated user, or None to clear
-  fn set_current_user(&self, user_hash: Option<String>);
+  /// * `user_id` - Id of the authenticated user, or None to clear
+  fn set_current_user(&mut self, user_id: Option<String>);
 
-  /// Gets the current authenticated user's credential hash.
+  /// Gets the current authenticated user's id.
   ///
   /// # Returns
   ///
-  /// * `Some(String)` - Credential hash if a user is authenticated
+  /// * `Some(String)` - User id if a user is authenticated
   /// * `None` - If no user is authenticated
   fn get_current_user(&self) -> Option<String>;
 
@@ -113,55 +3101,55 @@ pub trait Store {
 }
 
 impl Store for MemoryStore {
-  /// Creates a new empty MemoryStore instance.
-  fn new() -> Self {
-    info!("Initializing memory store for authenticated users only");
-    Self {
-      auth_stores: Arc::new(RwLock::new(HashMap::new())),
-      current_user: Arc::new(RwLock::new(None)),
-    }
-  }
-
   /// Sets the current authenticated user and initializes their store if needed.
   ///
   /// # Arguments
   ///
-  /// * `user_hash` - Credential hash for the user, or None to clear authentication
-  fn set_current_user(&self, user_hash: Option<String>) {
-    let mut current_user = self.current_user.write().unwrap();
-    *current_user = user_hash;
+  /// * `user_id` - Id of the user, or None to clear authentication
+  fn set_current_user(&mut self, user_id: Option<String>) {
+    self.current_user = user_id;
 
     // Initialize user store if it doesn't exist
-    if let Some(hash) = current_user.clone() {
-      let mut stores = self.auth_stores.write().unwrap();
-      if !stores.contains_key(&hash) {
-        info!("Creating new store for user with hash: {}", hash);
-        stores.insert(hash, UserStore::new());
-      }
+    if let Some(id) = self.current_user.clone()
+      && let Entry::Vacant(entry) = self.auth_stores.entry(id)
+    {
+      info!("Creating new store for user with id: {}", entry.key());
+      entry.insert(UserStore::new(self.databases));
     }
   }
 
-  /// Gets the current authenticated user's credential hash.
+  /// Gets the current authenticated user's id.
   fn get_current_user(&self) -> Option<String> {
-    self.current_user.read().unwrap().clone()
+    self.current_user.clone()
   }
 
   /// Checks if a user is currently authenticated.
   fn is_authenticated(&self) -> bool {
-    self.current_user.read().unwrap().is_some()
+    self.current_user.is_some()
   }
 
   /// Sets a key-value pair in the store.
   ///
   /// If the key contains a dot, it's treated as an entity operation.
   /// Otherwise, it's stored in the default HashMap.
-  async fn set(&self, key: &str, value: Value, args: HashMap<Options, u64>) -> anyhow::Result<()> {
+  async fn set(
+    &self,
+    key: &str,
+    value: Value,
+    mut args: HashMap<Options, u64>,
+  ) -> anyhow::Result<SetOutcome> {
     if !self.is_authenticated() {
-      return Err(anyhow::anyhow!("Authentication required"));
+      return Err(anyhow::anyhow!(AUTH_REQUIRED));
     }
 
     debug!("Got extra options: {:?}", args);
 
+    // Conditions only apply to this write, they are not stored with the value
+    let nx = args.remove(&Options::Nx).is_some();
+    let xx = args.remove(&Options::Xx).is_some();
+    let get = args.remove(&Options::Get).is_some();
+    let keep_ttl = args.remove(&Options::KeepTtl).is_some();
+
     // @TODO: handle where user would want to divider their data into different entities like this
     // @TODO: `SET admin.foo bar` would set a value in the "admin" entity with key "foo"
     // // Check if this is an entity operation (key contains ".")
@@ -177,28 +3165,79 @@ impl Store for MemoryStore {
     // }
 
     // For regular key-value operation, wrap in a HashMap entity
-    let user_hash = self.get_current_user().unwrap();
-    let mut stores = self.auth_stores.write().unwrap();
-    let user_store = stores.get_mut(&user_hash).unwrap();
-
-    let mut entities = user_store.entities.lock().unwrap();
-
-    // Create a "default" map if it doesn't exist
-    if !entities.contains_key("default") {
-      entities.insert(
-        "default".to_string(),
-        Entities::HashMap(Arc::new(Mutex::new(KvHashMap::new()))),
-      );
-    }
+    self
+      .with_user_store(|databases, usage| {
+        // SET replaces a key of any kind, but GET can only return strings
+        let replaces_entity = holds_entity(&databases[self.selected_db], key);
+        if replaces_entity && get {
+          bail!(WRONG_TYPE);
+        }
 
-    // Insert the key-value pair into the default HashMap
-    if let Some(Entities::HashMap(map)) = entities.get("default") {
-      let mut map = map.lock().unwrap();
-      map.insert(key.to_string(), (value, SystemTime::now(), args));
-      Ok(())
-    } else {
-      Err(anyhow::anyhow!("Default map corrupted"))
-    }
+        // Create a "default" map if it doesn't exist
+        let map = default_map_or_create(&mut databases[self.selected_db])
+          .ok_or_else(|| anyhow!("Default map corrupted"))?;
+
+        // Overwriting a key only counts the change in size against the quota
+        let size = entry_size(key, &value);
+        let (old, exists, previous, kept_expiry) = {
+          let map = map.lock().unwrap();
+          let entry = map.get(key);
+          let live = entry.filter(|entry| is_live(entry));
+          let previous = live.filter(|_| get).map(|(value, ..)| value.clone());
+          let kept_expiry = live
+            .filter(|_| keep_ttl)
+            .map(|(_, inserted_at, args, _)| rebase_expiry(inserted_at, args));
+          if previous
+            .as_ref()
+            .is_some_and(|value| value.to_bytes().is_none())
+          {
+            bail!(WRONG_TYPE);
+          }
+          (
+            entry.map(|(value, ..)| entry_size(key, value)),
+            live.is_some() || replaces_entity,
+            previous,
+            kept_expiry,
+          )
+        };
+
+        if (nx && exists) || (xx && !exists) {
+          return Ok(SetOutcome {
+            stored: false,
+            previous,
+          });
+        }
+        // Replacing another kind of value takes its place in the quota
+        let old = if replaces_entity {
+          Some(entity_size(key, &databases[self.selected_db][key]))
+        } else {
+          old
+        };
+
+        self.make_room(databases, usage, size, old, &[key])?;
+        if replaces_entity {
+          databases[self.selected_db].remove(key);
+        }
+
+        // The user's databases stay locked, so nothing changed since `old` was read
+        let mut map = map.lock().unwrap();
+        usage.check(self.quota(), size, old)?;
+        usage.replace(size, old);
+
+        // Insert the key-value pair into the default HashMap
+        let args = kept_expiry.unwrap_or(args);
+        map.insert(key.to_string(), new_entry(value, args));
+        Ok(SetOutcome {
+          stored: true,
+          previous,
+        })
+      })
+      .unwrap_or_else(|| Err(anyhow::anyhow!(AUTH_REQUIRED)))
+      .inspect(|outcome| {
+        if outcome.stored {
+          self.notify(EventClass::String, "set", key);
+        }
+      })
   }
 
   /// Gets a value from the store by key.
@@ -228,43 +3267,29 @@ impl Store for MemoryStore {
     // }
 
     // For regular key-value operation, retrieve from default HashMap
-    let user_hash = self.get_current_user().unwrap();
-    let stores = self.auth_stores.read().unwrap();
-
-    if let Some(user_store) = stores.get(&user_hash) {
-      let entities = user_store.entities.lock().unwrap();
+    self
+      .with_db(|entities| {
+        let Some(Entities::HashMap(map)) = entities.get("default") else {
+          return None;
+        };
 
-      if let Some(Entities::HashMap(map)) = entities.get("default") {
         // Get the map and check for the key
-        let map = map.lock().unwrap();
+        let mut map = map.lock().unwrap();
         // Get the value tuple for the key
-        let val_tuple = map.get(key);
-
-        if let Some((value, _time, args)) = val_tuple {
-          // Check for expiration if Ex option is set (in seconds)
-          if let Some(&expiry_ms) = args.get(&Options::Ex) {
-            let elapsed = SystemTime::elapsed(_time).unwrap();
-            if elapsed.as_secs() >= expiry_ms as u64 {
-              debug!("Key '{}' has expired", key);
-              return None; // Key has expired
-            }
-          }
+        let val_tuple = map.get_mut(key);
 
-          // Check for expiration if Px option is set (in milliseconds)
-          if let Some(&expiry_ms) = args.get(&Options::Px) {
-            let elapsed = SystemTime::elapsed(_time).unwrap();
-            if elapsed.as_millis() >= expiry_ms as u128 {
-              debug!("Key '{}' has expired", key);
-              return None; // Key has expired
-            }
+        if let Some(entry) = val_tuple {
+          if !is_live(entry) {
+            debug!("Key '{}' has expired", key);
+            return None; // Key has expired
           }
-          return Some(value.clone()); // Return the value if not expired
+          touch_entry(entry);
+          return Some(entry.0.clone()); // Return the value if not expired
         };
         debug!("Key '{}' not found in default HashMap", key);
-      }
-    }
-
-    None
+        None
+      })
+      .flatten()
   }
 
   /// Deletes a key-value pair from the store.
@@ -294,18 +3319,25 @@ impl Store for MemoryStore {
     // }
 
     // For regular key-value operation
-    let user_hash = self.get_current_user().unwrap();
-    let stores = self.auth_stores.read().unwrap();
-
-    if let Some(user_store) = stores.get(&user_hash) {
-      let entities = user_store.entities.lock().unwrap();
-
-      if let Some(Entities::HashMap(map)) = entities.get("default") {
-        let mut map = map.lock().unwrap();
-        return map.remove(key).map(|(value, _time, _args)| value);
-      }
-    }
+    self
+      .with_db_usage(|entities, usage| match entities.get("default") {
+        // Other kinds have no single value to hand back
+        _ if holds_entity(entities, key) => {
+          let entity = entities.remove(key)?;
+          usage.remove(entity_size(key, &entity));
+          Some(Value::Null)
+        }
+        Some(Entities::HashMap(map)) => {
+          let mut map = map.lock().unwrap();
+          let entry = map.remove(key)?;
+          usage.remove(entry_size(key, &entry.0));
 
-    None
+          // Expired keys are removed too, but don't count as deleted
+          Some(entry).filter(is_live).map(|(value, ..)| value)
+        }
+        _ => None,
+      })
+      .flatten()
+      .inspect(|_| self.notify(EventClass::Generic, "del", key))
   }
 }
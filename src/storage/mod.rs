@@ -1,4 +1,8 @@
+pub mod aof;
 pub mod db;
 pub mod entities;
-pub mod memory;
+pub mod eviction;
 pub mod kdb;
+pub mod memory;
+pub mod notify;
+pub mod pubsub;
@@ -0,0 +1,209 @@
+//! Keyspace notifications.
+//!
+//! When enabled, writes publish what they did to pub/sub channels, like
+//! Redis does. For an event `del` on key `k` in database 0:
+//! - `__keyspace@0__:k` receives `del` (class `K`)
+//! - `__keyevent@0__:del` receives `k` (class `E`)
+//!
+//! Which events are published is chosen with the same flags as Redis'
+//! `notify-keyspace-events`, see [`KeyspaceEvents`]. Channels are shared by
+//! every user, so key names are visible to anyone subscribed.
+
+use std::{
+  fmt,
+  sync::{Arc, RwLock},
+};
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use super::pubsub::PubSub;
+use crate::resp::value::Value;
+
+/// Kind of change an event reports, each enabled by its own flag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventClass {
+  /// Commands working on any kind of key, like DEL or EXPIRE (`g`)
+  Generic,
+  /// String commands (`$`)
+  String,
+  /// List commands (`l`)
+  List,
+  /// Set commands (`s`)
+  Set,
+  /// Hash commands (`h`)
+  Hash,
+  /// Sorted set commands (`z`)
+  SortedSet,
+  /// Keys removed because they expired (`x`)
+  Expired,
+  /// Keys removed to stay within `maxmemory` (`e`)
+  Evicted,
+}
+
+impl EventClass {
+  /// Every class, in the order their flags are listed.
+  const ALL: [Self; 8] = [
+    Self::Generic,
+    Self::String,
+    Self::List,
+    Self::Set,
+    Self::Hash,
+    Self::SortedSet,
+    Self::Expired,
+    Self::Evicted,
+  ];
+
+  /// Returns the flag enabling the class.
+  fn flag(self) -> char {
+    match self {
+      Self::Generic => 'g',
+      Self::String => '$',
+      Self::List => 'l',
+      Self::Set => 's',
+      Self::Hash => 'h',
+      Self::SortedSet => 'z',
+      Self::Expired => 'x',
+      Self::Evicted => 'e',
+    }
+  }
+
+  /// Returns the bit the class takes in [`KeyspaceEvents`].
+  fn bit(self) -> u16 {
+    1 << (self as u16 + 2)
+  }
+}
+
+/// Which keyspace notifications are published.
+///
+/// Parsed from Redis' flags: `K` and `E` choose the keyspace and keyevent
+/// channels, the other flags the [`EventClass`]es, and `A` stands for every
+/// class. Nothing is published unless `K` or `E` and at least one class
+/// are given. The empty string, the default, disables notifications.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyspaceEvents(u16);
+
+impl KeyspaceEvents {
+  /// Publish to `__keyspace@<db>__:<key>` channels.
+  const KEYSPACE: u16 = 1;
+  /// Publish to `__keyevent@<db>__:<event>` channels.
+  const KEYEVENT: u16 = 1 << 1;
+
+  /// Parses notification flags, like `KEA` or `Elg`.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(KeyspaceEvents)` - The flags
+  /// * `Err` - If a flag is unknown
+  pub fn parse(flags: &str) -> Result<Self> {
+    let mut events = 0;
+    for flag in flags.chars() {
+      events |= match flag {
+        'K' => Self::KEYSPACE,
+        'E' => Self::KEYEVENT,
+        'A' => EventClass::ALL.iter().map(|class| class.bit()).sum(),
+        _ => match EventClass::ALL.iter().find(|class| class.flag() == flag) {
+          Some(class) => class.bit(),
+          None => bail!("Unknown keyspace event flag '{}'", flag),
+        },
+      };
+    }
+    Ok(Self(events))
+  }
+
+  /// Returns true if events of `class` are published to any channel.
+  pub fn enabled(&self, class: EventClass) -> bool {
+    self.0 & class.bit() != 0 && self.0 & (Self::KEYSPACE | Self::KEYEVENT) != 0
+  }
+}
+
+impl fmt::Display for KeyspaceEvents {
+  /// Writes the flags back, with `A` in place of every class flag.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let all = EventClass::ALL
+      .iter()
+      .all(|class| self.0 & class.bit() != 0);
+    if all {
+      f.write_str("A")?;
+    } else {
+      for class in EventClass::ALL {
+        if self.0 & class.bit() != 0 {
+          write!(f, "{}", class.flag())?;
+        }
+      }
+    }
+    if self.0 & Self::KEYSPACE != 0 {
+      f.write_str("K")?;
+    }
+    if self.0 & Self::KEYEVENT != 0 {
+      f.write_str("E")?;
+    }
+    Ok(())
+  }
+}
+
+impl TryFrom<String> for KeyspaceEvents {
+  type Error = anyhow::Error;
+
+  fn try_from(flags: String) -> Result<Self> {
+    Self::parse(&flags)
+  }
+}
+
+impl From<KeyspaceEvents> for String {
+  fn from(events: KeyspaceEvents) -> Self {
+    events.to_string()
+  }
+}
+
+/// Publishes keyspace notifications, shared by every store handle.
+#[derive(Clone, Debug, Default)]
+pub struct Notifier {
+  /// Registry the notifications are published to, None to never publish
+  pubsub: Option<PubSub>,
+  /// Notifications currently enabled
+  events: Arc<RwLock<KeyspaceEvents>>,
+}
+
+impl Notifier {
+  /// Creates a notifier publishing the enabled events to `pubsub`.
+  pub fn new(pubsub: PubSub, events: KeyspaceEvents) -> Self {
+    Self {
+      pubsub: Some(pubsub),
+      events: Arc::new(RwLock::new(events)),
+    }
+  }
+
+  /// Changes which notifications are published, for every handle.
+  pub fn set_events(&self, events: KeyspaceEvents) {
+    *self.events.write().unwrap() = events;
+  }
+
+  /// Publishes an event about a key, if its class is enabled.
+  ///
+  /// # Arguments
+  ///
+  /// * `class` - Kind of change the event reports
+  /// * `event` - Name of the event, usually the command's in lower case
+  /// * `db` - Index of the database holding the key
+  /// * `key` - The key the event is about
+  pub fn notify(&self, class: EventClass, event: &str, db: usize, key: &str) {
+    let Some(pubsub) = &self.pubsub else {
+      return;
+    };
+    let events = *self.events.read().unwrap();
+    if !events.enabled(class) {
+      return;
+    }
+
+    if events.0 & KeyspaceEvents::KEYSPACE != 0 {
+      let channel = format!("__keyspace@{}__:{}", db, key);
+      pubsub.publish(&channel, Value::BulkString(event.to_string()));
+    }
+    if events.0 & KeyspaceEvents::KEYEVENT != 0 {
+      let channel = format!("__keyevent@{}__:{}", db, event);
+      pubsub.publish(&channel, Value::BulkString(key.to_string()));
+    }
+  }
+}
@@ -0,0 +1,159 @@
+//! Publish/subscribe channel registry.
+//!
+//! Keeps track of which connections are subscribed to which channels and
+//! fans published messages out to them. The registry is shared by every
+//! connection, while each connection owns its own [`Subscription`].
+
+use std::{
+  collections::{HashMap, HashSet},
+  sync::{Arc, RwLock},
+};
+
+use log::debug;
+use tokio::sync::mpsc;
+
+use crate::resp::value::Value;
+
+/// Sending half of a subscriber's message queue.
+pub type Subscriber = mpsc::Sender<Value>;
+
+/// Number of undelivered messages a subscriber can buffer before new
+/// messages for it are dropped.
+pub const SUBSCRIBER_BUFFER: usize = 1024;
+
+/// Shared registry of channel subscriptions.
+#[derive(Clone, Debug, Default)]
+pub struct PubSub {
+  /// Subscribers for each channel, keyed by channel name
+  channels: Arc<RwLock<HashMap<String, Vec<Subscriber>>>>,
+}
+
+/// Per-connection subscription state.
+///
+/// Holds the connection's message queue and the channels it listens on.
+#[derive(Debug)]
+pub struct Subscription {
+  /// Queue used to deliver published messages to this connection
+  sender: Subscriber,
+  /// Channels this connection is currently subscribed to
+  channels: HashSet<String>,
+}
+
+impl Subscription {
+  /// Creates an empty subscription delivering messages to `sender`.
+  pub fn new(sender: Subscriber) -> Self {
+    Self {
+      sender,
+      channels: HashSet::new(),
+    }
+  }
+
+  /// Returns true if the connection listens on at least one channel.
+  pub fn is_active(&self) -> bool {
+    !self.channels.is_empty()
+  }
+
+  /// Returns the number of channels the connection is subscribed to.
+  pub fn count(&self) -> usize {
+    self.channels.len()
+  }
+
+  /// Returns the names of all subscribed channels.
+  pub fn channels(&self) -> Vec<String> {
+    self.channels.iter().cloned().collect()
+  }
+}
+
+impl PubSub {
+  /// Creates an empty registry.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Subscribes a connection to a channel.
+  ///
+  /// Subscribing to a channel the connection already listens on is a no-op.
+  pub fn subscribe(&self, subscription: &mut Subscription, channel: &str) {
+    if !subscription.channels.insert(channel.to_string()) {
+      return;
+    }
+
+    let mut channels = self.channels.write().unwrap();
+    channels
+      .entry(channel.to_string())
+      .or_default()
+      .push(subscription.sender.clone());
+  }
+
+  /// Unsubscribes a connection from a channel.
+  ///
+  /// # Returns
+  ///
+  /// * `true` - The connection was subscribed to the channel
+  /// * `false` - The connection was not subscribed to the channel
+  pub fn unsubscribe(&self, subscription: &mut Subscription, channel: &str) -> bool {
+    if !subscription.channels.remove(channel) {
+      return false;
+    }
+
+    let mut channels = self.channels.write().unwrap();
+    if let Some(subscribers) = channels.get_mut(channel) {
+      subscribers.retain(|s| !s.same_channel(&subscription.sender));
+      if subscribers.is_empty() {
+        channels.remove(channel);
+      }
+    }
+    true
+  }
+
+  /// Removes a connection from every channel it is subscribed to.
+  ///
+  /// Called when a connection closes so no dead senders are left behind.
+  pub fn unsubscribe_all(&self, subscription: &mut Subscription) {
+    for channel in subscription.channels() {
+      self.unsubscribe(subscription, &channel);
+    }
+  }
+
+  /// Publishes a message to every subscriber of a channel.
+  ///
+  /// Subscribers whose connection has gone away are pruned along the way.
+  /// Delivery never blocks the publisher: if a subscriber's queue is full
+  /// the message is dropped for that subscriber.
+  ///
+  /// # Returns
+  ///
+  /// The number of subscribers that received the message.
+  pub fn publish(&self, channel: &str, message: Value) -> usize {
+    // A push, which the connection's handler writes as an array to RESP2
+    // subscribers
+    let frame = Value::Push(vec![
+      Value::BulkString("message".to_string()),
+      Value::BulkString(channel.to_string()),
+      message,
+    ]);
+
+    let mut channels = self.channels.write().unwrap();
+    let Some(subscribers) = channels.get_mut(channel) else {
+      return 0;
+    };
+
+    let mut received = 0;
+    subscribers.retain(|subscriber| match subscriber.try_send(frame.clone()) {
+      Ok(()) => {
+        received += 1;
+        true
+      }
+      Err(mpsc::error::TrySendError::Full(_)) => {
+        debug!("Subscriber queue full, dropping message on '{}'", channel);
+        true
+      }
+      Err(mpsc::error::TrySendError::Closed(_)) => false,
+    });
+
+    if subscribers.is_empty() {
+      channels.remove(channel);
+    }
+    received
+  }
+}
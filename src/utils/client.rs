@@ -0,0 +1,276 @@
+//! Per-connection client state.
+//!
+//! Every accepted connection is described by a [`Client`] carrying a
+//! unique id, which commands like CLIENT use to identify the connection.
+//! Live clients are tracked in a [`ClientRegistry`] shared by all
+//! connections, so operators can list them and disconnect them.
+
+use std::{
+  collections::HashMap,
+  sync::{
+    Arc, Mutex,
+    atomic::{AtomicU64, Ordering},
+  },
+  time::{Duration, Instant},
+};
+
+use tokio::sync::Notify;
+
+/// Protocol version connections speak until HELLO switches it.
+pub const DEFAULT_PROTOCOL: u8 = 2;
+
+/// Id handed out to the next accepted connection.
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// What the registry knows about a live connection.
+#[derive(Debug)]
+struct ClientHandle {
+  /// Address of the peer
+  addr: String,
+  /// Name set with `CLIENT SETNAME`, if any
+  name: Option<String>,
+  /// When the connection was accepted
+  connected_at: Instant,
+  /// When the connection last ran a command, shared with the [`Client`]
+  last_command: Arc<LastCommand>,
+  /// Wakes the connection up when it should close
+  kill: Arc<Notify>,
+}
+
+/// When a connection last ran a command, in milliseconds since it was
+/// accepted.
+///
+/// The connection updates it without locking the registry, so busy
+/// connections don't contend on the registry lock.
+#[derive(Debug)]
+struct LastCommand {
+  /// When the connection was accepted
+  connected_at: Instant,
+  /// Milliseconds from `connected_at` to the last command
+  millis: AtomicU64,
+}
+
+impl LastCommand {
+  /// Starts counting from now.
+  fn new() -> Self {
+    Self {
+      connected_at: Instant::now(),
+      millis: AtomicU64::new(0),
+    }
+  }
+
+  /// Records a command run now.
+  fn touch(&self) {
+    let millis = u64::try_from(self.connected_at.elapsed().as_millis()).unwrap_or(u64::MAX);
+    self.millis.store(millis, Ordering::Relaxed);
+  }
+
+  /// Returns how long ago the last command ran.
+  fn idle(&self) -> Duration {
+    let at = Duration::from_millis(self.millis.load(Ordering::Relaxed));
+    self.connected_at.elapsed().saturating_sub(at)
+  }
+}
+
+/// Shared registry of every live connection, keyed by client id.
+#[derive(Clone, Debug, Default)]
+pub struct ClientRegistry {
+  clients: Arc<Mutex<HashMap<u64, ClientHandle>>>,
+}
+
+/// A single line of `CLIENT LIST`.
+#[derive(Debug)]
+pub struct ClientSummary {
+  /// Unique client id
+  pub id: u64,
+  /// Address of the peer
+  pub addr: String,
+  /// Name of the connection, empty if unset
+  pub name: String,
+  /// Seconds since the connection was accepted
+  pub age: u64,
+  /// Seconds since the connection last ran a command
+  pub idle: u64,
+}
+
+impl ClientRegistry {
+  /// Creates an empty registry.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Describes every live connection, ordered by id.
+  pub fn list(&self) -> Vec<ClientSummary> {
+    let clients = self.clients.lock().unwrap();
+    let mut summaries: Vec<ClientSummary> = clients
+      .iter()
+      .map(|(&id, handle)| ClientSummary {
+        id,
+        addr: handle.addr.clone(),
+        name: handle.name.clone().unwrap_or_default(),
+        age: handle.connected_at.elapsed().as_secs(),
+        idle: handle.last_command.idle().as_secs(),
+      })
+      .collect();
+    summaries.sort_by_key(|summary| summary.id);
+    summaries
+  }
+
+  /// Asks a connection to close.
+  ///
+  /// The connection finishes the command it is running, if any, and then
+  /// disconnects.
+  ///
+  /// # Returns
+  ///
+  /// * `true` - A connection with the id was found
+  /// * `false` - No live connection has the id
+  pub fn kill(&self, id: u64) -> bool {
+    let clients = self.clients.lock().unwrap();
+    match clients.get(&id) {
+      Some(handle) => {
+        // notify_one keeps the wake-up even if the connection isn't waiting yet
+        handle.kill.notify_one();
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Asks every live connection to close once its current command is done.
+  ///
+  /// # Returns
+  ///
+  /// The number of connections signalled.
+  pub fn kill_all(&self) -> usize {
+    let clients = self.clients.lock().unwrap();
+    for handle in clients.values() {
+      handle.kill.notify_one();
+    }
+    clients.len()
+  }
+
+  /// Runs `f` on a connection's registry entry, if it is still registered.
+  fn update(&self, id: u64, f: impl FnOnce(&mut ClientHandle)) {
+    if let Some(handle) = self.clients.lock().unwrap().get_mut(&id) {
+      f(handle);
+    }
+  }
+}
+
+/// State owned by a single client connection.
+///
+/// Creating a client registers it, dropping it removes it from the
+/// registry again.
+#[derive(Debug)]
+pub struct Client {
+  /// Unique id, increasing in the order connections were accepted
+  pub id: u64,
+  /// Address of the peer
+  addr: String,
+  /// Name set with `CLIENT SETNAME`, if any
+  name: Option<String>,
+  /// Whether the connection authenticated as a root user
+  root: bool,
+  /// RESP version negotiated with HELLO
+  protocol: u8,
+  /// Registry the client is listed in
+  registry: ClientRegistry,
+  /// Signalled when the connection is killed
+  kill: Arc<Notify>,
+  /// When the connection last ran a command, shared with the registry
+  last_command: Arc<LastCommand>,
+}
+
+impl Client {
+  /// Registers a newly accepted connection with the next free id.
+  ///
+  /// # Arguments
+  ///
+  /// * `addr` - Address of the peer
+  /// * `registry` - Registry of live connections to join
+  pub fn new(addr: String, registry: ClientRegistry) -> Self {
+    let id = NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed);
+    let kill = Arc::new(Notify::new());
+    let last_command = Arc::new(LastCommand::new());
+
+    registry.clients.lock().unwrap().insert(
+      id,
+      ClientHandle {
+        addr: addr.clone(),
+        name: None,
+        connected_at: last_command.connected_at,
+        last_command: last_command.clone(),
+        kill: kill.clone(),
+      },
+    );
+
+    Self {
+      id,
+      addr,
+      name: None,
+      root: false,
+      protocol: DEFAULT_PROTOCOL,
+      registry,
+      kill,
+      last_command,
+    }
+  }
+
+  /// Returns the address of the peer.
+  pub fn addr(&self) -> &str {
+    &self.addr
+  }
+
+  /// Returns the connection's name, if one was set.
+  pub fn name(&self) -> Option<&str> {
+    self.name.as_deref()
+  }
+
+  /// Sets or, with None, clears the connection's name.
+  pub fn set_name(&mut self, name: Option<String>) {
+    self.name = name.clone();
+    self.registry.update(self.id, |handle| handle.name = name);
+  }
+
+  /// Returns true if the connection authenticated as a root user.
+  pub fn is_root(&self) -> bool {
+    self.root
+  }
+
+  /// Records whether the user the connection authenticated as is root.
+  pub fn set_root(&mut self, root: bool) {
+    self.root = root;
+  }
+
+  /// Returns the RESP version replies are written in.
+  pub fn protocol(&self) -> u8 {
+    self.protocol
+  }
+
+  /// Switches the RESP version replies are written in.
+  pub fn set_protocol(&mut self, protocol: u8) {
+    self.protocol = protocol;
+  }
+
+  /// Records that the connection just ran a command.
+  pub fn touch(&self) {
+    self.last_command.touch();
+  }
+
+  /// Returns the registry of all live connections.
+  pub fn registry(&self) -> &ClientRegistry {
+    &self.registry
+  }
+
+  /// Returns the signal raised when the connection is killed.
+  pub fn kill_signal(&self) -> Arc<Notify> {
+    self.kill.clone()
+  }
+}
+
+impl Drop for Client {
+  fn drop(&mut self) {
+    self.registry.clients.lock().unwrap().remove(&self.id);
+  }
+}
@@ -0,0 +1,103 @@
+//! Glob-style pattern matching.
+//!
+//! Implements the patterns Redis accepts for commands like `CONFIG GET`:
+//!
+//! * `*` matches any sequence of characters, including none
+//! * `?` matches a single character
+//! * `[abc]`, `[a-z]` and `[^abc]` match one character from, or not from, a set
+//! * `\` escapes the next character so it matches literally
+
+/// Checks whether `text` matches the glob `pattern`.
+///
+/// Matching works on bytes and is case-sensitive.
+///
+/// # Arguments
+///
+/// * `pattern` - The glob pattern
+/// * `text` - The text to match against it
+///
+/// # Example
+///
+/// ```
+/// assert!(matches("max*", "maxmemory"));
+/// assert!(!matches("max?", "maxmemory"));
+/// ```
+pub fn matches(pattern: &str, text: &str) -> bool {
+  matches_bytes(pattern.as_bytes(), text.as_bytes())
+}
+
+/// Byte-level matcher behind [`matches`].
+///
+/// A `*` remembers where it was seen, so a later mismatch retries from
+/// there with one more character swallowed instead of recursing.
+fn matches_bytes(pattern: &[u8], text: &[u8]) -> bool {
+  let (mut p, mut t) = (0, 0);
+  // Pattern position after the last `*`, and the text position it resumes at
+  let mut backtrack: Option<(usize, usize)> = None;
+
+  while t < text.len() {
+    let step = match pattern.get(p) {
+      Some(b'*') => {
+        backtrack = Some((p + 1, t));
+        p += 1;
+        continue;
+      }
+      Some(b'?') => Some(p + 1),
+      Some(b'[') => match_class(pattern, p, text[t]),
+      Some(b'\\') if p + 1 < pattern.len() => (pattern[p + 1] == text[t]).then_some(p + 2),
+      Some(&c) => (c == text[t]).then_some(p + 1),
+      None => None,
+    };
+
+    match (step, backtrack) {
+      (Some(next), _) => {
+        p = next;
+        t += 1;
+      }
+      (None, Some((star, resume))) => {
+        backtrack = Some((star, resume + 1));
+        p = star;
+        t = resume + 1;
+      }
+      (None, None) => return false,
+    }
+  }
+
+  // Only trailing stars may be left once the text is used up
+  pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Matches `c` against the `[...]` class starting at `pattern[start]`.
+///
+/// # Returns
+///
+/// * `Some(next)` - `c` is in the class, `next` is the position after it
+/// * `None` - `c` isn't in the class
+fn match_class(pattern: &[u8], start: usize, c: u8) -> Option<usize> {
+  let mut p = start + 1;
+  let negated = pattern.get(p) == Some(&b'^');
+  if negated {
+    p += 1;
+  }
+
+  let mut found = false;
+  // An unterminated class runs to the end of the pattern, as in Redis
+  while p < pattern.len() && pattern[p] != b']' {
+    if pattern[p] == b'\\' && p + 1 < pattern.len() {
+      found |= pattern[p + 1] == c;
+      p += 2;
+    } else if p + 2 < pattern.len() && pattern[p + 1] == b'-' && pattern[p + 2] != b']' {
+      let (low, high) = (
+        pattern[p].min(pattern[p + 2]),
+        pattern[p].max(pattern[p + 2]),
+      );
+      found |= (low..=high).contains(&c);
+      p += 3;
+    } else {
+      found |= pattern[p] == c;
+      p += 1;
+    }
+  }
+
+  (found != negated).then_some((p + 1).min(pattern.len()))
+}
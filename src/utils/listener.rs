@@ -0,0 +1,278 @@
+//! Listeners accepting client connections.
+//!
+//! The server can accept connections on several TCP addresses and Unix
+//! domain sockets at once, as configured by `server.network.listeners`.
+//! Every accepted stream implements [`Connection`], so the same connection
+//! handling serves all of them, with or without TLS.
+
+use std::io;
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use log::warn;
+use socket2::SockRef;
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::{
+  io::{AsyncRead, AsyncWrite, DuplexStream},
+  net::{TcpListener, TcpStream},
+};
+use tokio_rustls::{TlsAcceptor, server::TlsStream};
+
+/// Prefix marking a listener address as a Unix domain socket path.
+pub const UNIX_PREFIX: &str = "unix:";
+
+/// A stream a client connection is served over.
+pub trait Connection: AsyncRead + AsyncWrite + Unpin + Send + 'static {
+  /// Describes the peer, as shown in logs and `CLIENT LIST`.
+  fn peer(&self) -> String;
+
+  /// Returns true if the peer connected over the loopback interface or a
+  /// Unix socket, the only peers protected mode serves.
+  fn is_local(&self) -> bool;
+
+  /// Applies socket options before the connection is served.
+  fn configure(&self) {}
+}
+
+impl Connection for TcpStream {
+  fn peer(&self) -> String {
+    self
+      .peer_addr()
+      .map(|addr| addr.to_string())
+      .unwrap_or_else(|_| "unknown".to_string())
+  }
+
+  fn is_local(&self) -> bool {
+    // Dual-stack sockets see IPv4 peers as IPv4-mapped IPv6 addresses
+    self
+      .peer_addr()
+      .is_ok_and(|addr| addr.ip().to_canonical().is_loopback())
+  }
+
+  fn configure(&self) {
+    // Let the OS detect peers that went away without closing the socket
+    if let Err(e) = SockRef::from(self).set_keepalive(true) {
+      warn!("Failed to enable TCP keep-alive for {}: {}", self.peer(), e);
+    }
+  }
+}
+
+#[cfg(unix)]
+impl Connection for UnixStream {
+  fn peer(&self) -> String {
+    // Clients of a Unix socket are unnamed, so name the socket instead, as Redis does
+    let path = self
+      .local_addr()
+      .ok()
+      .and_then(|addr| addr.as_pathname().map(|path| path.display().to_string()));
+    format!("{}:0", path.unwrap_or_default())
+  }
+
+  fn is_local(&self) -> bool {
+    true
+  }
+}
+
+/// In-process connections, as the integration tests open.
+impl Connection for DuplexStream {
+  fn peer(&self) -> String {
+    "duplex:0".to_string()
+  }
+
+  fn is_local(&self) -> bool {
+    true
+  }
+}
+
+impl Connection for TlsStream<Box<dyn Connection>> {
+  fn peer(&self) -> String {
+    self.get_ref().0.peer()
+  }
+
+  fn is_local(&self) -> bool {
+    self.get_ref().0.is_local()
+  }
+
+  fn configure(&self) {
+    self.get_ref().0.configure()
+  }
+}
+
+impl Connection for Box<dyn Connection> {
+  fn peer(&self) -> String {
+    (**self).peer()
+  }
+
+  fn is_local(&self) -> bool {
+    (**self).is_local()
+  }
+
+  fn configure(&self) {
+    (**self).configure()
+  }
+}
+
+/// A bound listener, accepting connections until it is dropped.
+///
+/// Dropping a Unix socket listener removes its socket file.
+pub struct Listener {
+  /// Socket connections are accepted on
+  socket: Socket,
+  /// Acceptor completing the TLS handshake, for TCP listeners with TLS enabled
+  tls: Option<TlsAcceptor>,
+}
+
+/// The socket underneath a [`Listener`].
+enum Socket {
+  /// Listens on a TCP address
+  Tcp(TcpListener),
+  /// Listens on a Unix domain socket at `path`
+  #[cfg(unix)]
+  Unix {
+    listener: UnixListener,
+    path: PathBuf,
+  },
+}
+
+impl Listener {
+  /// Binds a listener to an address from the configuration.
+  ///
+  /// # Arguments
+  ///
+  /// * `address` - `host:port` for TCP, or `unix:` followed by a socket path
+  /// * `tls` - Acceptor for TLS connections, ignored for Unix sockets
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Listener)` - The bound listener
+  /// * `Err` - If the address can't be bound
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// let tcp = Listener::bind("0.0.0.0:6379", None).await?;
+  /// let unix = Listener::bind("unix:/tmp/rustykv.sock", None).await?;
+  /// ```
+  pub async fn bind(address: &str, tls: Option<TlsAcceptor>) -> Result<Self> {
+    if let Some(path) = address.strip_prefix(UNIX_PREFIX) {
+      // Unix sockets are only reachable locally, so they stay plaintext
+      return Ok(Self {
+        socket: Socket::bind_unix(path)?,
+        tls: None,
+      });
+    }
+
+    let listener = TcpListener::bind(address)
+      .await
+      .with_context(|| format!("Failed to bind to {}", address))?;
+    Ok(Self {
+      socket: Socket::Tcp(listener),
+      tls,
+    })
+  }
+
+  /// Waits for the next connection.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Incoming)` - The accepted connection, still to be established
+  /// * `Err` - If accepting failed, the listener keeps working
+  pub async fn accept(&self) -> io::Result<Incoming> {
+    let stream: Box<dyn Connection> = match &self.socket {
+      Socket::Tcp(listener) => Box::new(listener.accept().await?.0),
+      #[cfg(unix)]
+      Socket::Unix { listener, .. } => Box::new(listener.accept().await?.0),
+    };
+    Ok(Incoming {
+      stream,
+      tls: self.tls.clone(),
+    })
+  }
+
+  /// Describes where the listener accepts connections.
+  pub fn local_addr(&self) -> String {
+    let addr = match &self.socket {
+      Socket::Tcp(listener) => listener
+        .local_addr()
+        .map(|addr| format!("TCP - {}", addr))
+        .unwrap_or_else(|e| format!("TCP - unknown address, {}", e)),
+      #[cfg(unix)]
+      Socket::Unix { path, .. } => format!("Unix socket - {}", path.display()),
+    };
+    match self.tls {
+      Some(_) => format!("{} (TLS)", addr),
+      None => addr,
+    }
+  }
+}
+
+impl Socket {
+  /// Binds a Unix domain socket, replacing a socket file left behind by an
+  /// earlier run.
+  #[cfg(unix)]
+  fn bind_unix(path: &str) -> Result<Self> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // Only ever remove sockets, so a typo can't delete a regular file
+    if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+      std::fs::remove_file(path)
+        .with_context(|| format!("Failed to remove stale socket {}", path))?;
+    }
+
+    let listener =
+      UnixListener::bind(path).with_context(|| format!("Failed to bind to {}", path))?;
+    Ok(Self::Unix {
+      listener,
+      path: PathBuf::from(path),
+    })
+  }
+
+  #[cfg(not(unix))]
+  fn bind_unix(path: &str) -> Result<Self> {
+    anyhow::bail!("Unix sockets are not supported on this platform: {}", path)
+  }
+}
+
+#[cfg(unix)]
+impl Drop for Socket {
+  fn drop(&mut self) {
+    if let Self::Unix { path, .. } = self
+      && let Err(e) = std::fs::remove_file(&*path)
+    {
+      warn!("Failed to remove socket {}: {}", path.display(), e);
+    }
+  }
+}
+
+/// A connection accepted by a [`Listener`].
+///
+/// The TLS handshake is left to [`Incoming::establish`], so a slow client
+/// doesn't hold up the listener.
+pub struct Incoming {
+  /// The accepted stream
+  stream: Box<dyn Connection>,
+  /// Acceptor to complete the handshake with, if the listener uses TLS
+  tls: Option<TlsAcceptor>,
+}
+
+impl Incoming {
+  /// Describes the peer, as shown in logs and `CLIENT LIST`.
+  pub fn peer(&self) -> String {
+    self.stream.peer()
+  }
+
+  /// Completes the TLS handshake, if the listener uses TLS.
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Box<dyn Connection>)` - The stream to serve the connection over
+  /// * `Err` - If the TLS handshake failed
+  pub async fn establish(self) -> io::Result<Box<dyn Connection>> {
+    match self.tls {
+      Some(acceptor) => Ok(Box::new(acceptor.accept(self.stream).await?)),
+      None => Ok(self.stream),
+    }
+  }
+}
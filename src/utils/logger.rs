@@ -1,27 +1,84 @@
 //! Logging utilities for the server.
 //!
 //! Provides a simple and standardized logging setup for the entire application.
+//! Lines are written as colored text by default, or as JSON objects for log
+//! aggregation.
 
-use log::info;
+use std::{io::Write, str::FromStr};
+
+use chrono::{SecondsFormat, Utc};
+use log::{LevelFilter, Log, Metadata, Record, info, warn};
 use simple_logger::SimpleLogger;
 
+use crate::utils::settings::{LogFormat, LoggingSettings};
+
 /// Logging configuration utility.
 pub struct Logger;
 
 impl Logger {
-  /// Sets up the default logger for the application.
+  /// Sets up the logger for the application.
+  ///
+  /// Configures a SimpleLogger with colored output and timestamps in
+  /// ISO 8601 format, or a [`JsonLogger`] writing one object per line.
+  /// An unknown level falls back to trace-level logging.
+  ///
+  /// # Arguments
   ///
-  /// Configures a SimpleLogger with colored output, trace-level logging,
-  /// and timestamps in ISO 8601 format.
-  pub fn setup() {
-    SimpleLogger::new()
-      .with_colors(true)
-      .with_level(log::LevelFilter::Trace)
-      .with_timestamp_format(time::macros::format_description!(
-        "[year]-[month]-[day] [hour]:[minute]:[second]"
-      ))
-      .init()
-      .unwrap();
+  /// * `settings` - The `server.logging` settings
+  pub fn setup(settings: &LoggingSettings) {
+    let level = LevelFilter::from_str(&settings.level).ok();
+    let max_level = level.unwrap_or(LevelFilter::Trace);
+
+    match settings.format {
+      LogFormat::Text => SimpleLogger::new()
+        .with_colors(true)
+        .with_level(max_level)
+        .with_timestamp_format(time::macros::format_description!(
+          "[year]-[month]-[day] [hour]:[minute]:[second]"
+        ))
+        .init()
+        .unwrap(),
+      LogFormat::Json => {
+        log::set_boxed_logger(Box::new(JsonLogger { level: max_level })).unwrap();
+        log::set_max_level(max_level);
+      }
+    }
+
+    if level.is_none() {
+      warn!("Unknown log level '{}', logging everything", settings.level);
+    }
     info!("Setting up default logger !")
   }
 }
+
+/// Logger writing every record to stdout as a single-line JSON object with
+/// `timestamp`, `level`, `target` and `message` fields.
+struct JsonLogger {
+  /// Most verbose level written
+  level: LevelFilter,
+}
+
+impl Log for JsonLogger {
+  fn enabled(&self, metadata: &Metadata) -> bool {
+    metadata.level() <= self.level
+  }
+
+  fn log(&self, record: &Record) {
+    if !self.enabled(record.metadata()) {
+      return;
+    }
+
+    let line = serde_json::json!({
+      "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+      "level": record.level().as_str(),
+      "target": record.target(),
+      "message": record.args().to_string(),
+    });
+    // Nowhere is left to report a failed write to
+    let _ = writeln!(std::io::stdout().lock(), "{}", line);
+  }
+
+  fn flush(&self) {
+    let _ = std::io::stdout().flush();
+  }
+}
@@ -0,0 +1,207 @@
+//! Prometheus metrics endpoint.
+//!
+//! When `server.metrics.port` is set, the counters INFO reports are also
+//! served over HTTP at `/metrics`, in the Prometheus text format, so the
+//! server can be scraped by standard monitoring stacks.
+
+use std::{convert::Infallible, fmt::Write};
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{
+  Method, Request, Response, StatusCode, body::Incoming, header::CONTENT_TYPE, server::conn::http1,
+  service::service_fn,
+};
+use hyper_util::rt::TokioIo;
+use log::{debug, error};
+use tokio::net::TcpListener;
+
+use crate::{
+  commands::stats,
+  storage::{kdb, memory::MemoryStore},
+  utils::network::NetworkUtils,
+};
+
+/// Content type of the Prometheus text exposition format.
+const CONTENT_TYPE_PROMETHEUS: &str = "text/plain; version=0.0.4";
+
+/// Binds the metrics endpoint.
+///
+/// # Arguments
+///
+/// * `host` - Address to listen on, the same as for client connections
+/// * `port` - Port from `server.metrics.port`
+///
+/// # Returns
+///
+/// * `Ok(TcpListener)` - The bound listener
+/// * `Err` - If the address can't be bound
+pub async fn bind(host: &str, port: u16) -> Result<TcpListener> {
+  TcpListener::bind((host, port))
+    .await
+    .with_context(|| format!("Failed to bind metrics endpoint to {}:{}", host, port))
+}
+
+/// Serves metrics requests until the task is cancelled.
+///
+/// Every connection is handled on its own task, so a slow scraper doesn't
+/// hold up the others.
+///
+/// # Arguments
+///
+/// * `listener` - Listener from [`bind`]
+/// * `store` - Memory store the keyspace metrics are read from
+pub async fn serve(listener: TcpListener, store: MemoryStore) {
+  loop {
+    let stream = match listener.accept().await {
+      Ok((stream, _)) => stream,
+      Err(e) => {
+        error!("Failed to accept metrics connection: {}", e);
+        continue;
+      }
+    };
+
+    let store = store.clone();
+    tokio::spawn(async move {
+      let service = service_fn(move |request| {
+        let response = respond(&request, &store);
+        async move { Ok::<_, Infallible>(response) }
+      });
+      if let Err(e) = http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .await
+      {
+        debug!("Metrics connection failed: {}", e);
+      }
+    });
+  }
+}
+
+/// Answers a single HTTP request.
+fn respond(request: &Request<Incoming>, store: &MemoryStore) -> Response<Full<Bytes>> {
+  let (status, body) = match (request.method(), request.uri().path()) {
+    (&Method::GET, "/metrics") => (StatusCode::OK, render(store)),
+    (_, "/metrics") => (StatusCode::METHOD_NOT_ALLOWED, String::new()),
+    _ => (StatusCode::NOT_FOUND, String::new()),
+  };
+
+  let mut response = Response::new(Full::new(Bytes::from(body)));
+  *response.status_mut() = status;
+  response
+    .headers_mut()
+    .insert(CONTENT_TYPE, CONTENT_TYPE_PROMETHEUS.parse().unwrap());
+  response
+}
+
+/// Renders every metric in the Prometheus text format.
+fn render(store: &MemoryStore) -> String {
+  let mut out = String::new();
+
+  metric(
+    &mut out,
+    "rustykv_connections_received_total",
+    "counter",
+    "Connections accepted since startup.",
+  );
+  let _ = writeln!(
+    out,
+    "rustykv_connections_received_total {}",
+    NetworkUtils::total_connections()
+  );
+
+  metric(
+    &mut out,
+    "rustykv_connected_clients",
+    "gauge",
+    "Clients currently connected.",
+  );
+  let _ = writeln!(
+    out,
+    "rustykv_connected_clients {}",
+    NetworkUtils::connected_clients()
+  );
+
+  let commands = stats::snapshot();
+  metric(
+    &mut out,
+    "rustykv_commands_total",
+    "counter",
+    "Commands processed, by command.",
+  );
+  for (command, stat) in &commands {
+    let _ = writeln!(
+      out,
+      "rustykv_commands_total{{command=\"{}\"}} {}",
+      label(&command.to_lowercase()),
+      stat.calls
+    );
+  }
+  metric(
+    &mut out,
+    "rustykv_commands_duration_seconds_total",
+    "counter",
+    "Time spent running commands, by command.",
+  );
+  for (command, stat) in &commands {
+    let _ = writeln!(
+      out,
+      "rustykv_commands_duration_seconds_total{{command=\"{}\"}} {}",
+      label(&command.to_lowercase()),
+      stat.usec as f64 / 1e6
+    );
+  }
+
+  let users = store.usage_by_user();
+  metric(
+    &mut out,
+    "rustykv_keys",
+    "gauge",
+    "Keys stored, by user id.",
+  );
+  for (user, keys, _) in &users {
+    let _ = writeln!(out, "rustykv_keys{{user=\"{}\"}} {}", label(user), keys);
+  }
+  metric(
+    &mut out,
+    "rustykv_used_bytes",
+    "gauge",
+    "Approximate size of the stored keys and values, by user id.",
+  );
+  for (user, _, bytes) in &users {
+    let _ = writeln!(
+      out,
+      "rustykv_used_bytes{{user=\"{}\"}} {}",
+      label(user),
+      bytes
+    );
+  }
+
+  metric(
+    &mut out,
+    "rustykv_kdb_last_save_duration_seconds",
+    "gauge",
+    "Time the last successful KDB save took, 0 if none ran yet.",
+  );
+  let _ = writeln!(
+    out,
+    "rustykv_kdb_last_save_duration_seconds {}",
+    kdb::last_save_duration(store) as f64 / 1e6
+  );
+
+  out
+}
+
+/// Writes the `HELP` and `TYPE` lines introducing a metric.
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+  let _ = writeln!(out, "# HELP {} {}", name, help);
+  let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Escapes a label value, as the text format requires.
+fn label(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n")
+}
@@ -1,3 +1,10 @@
+pub mod client;
+pub mod glob;
+pub mod listener;
 pub mod logger;
+pub mod metrics;
 pub mod network;
+pub mod password;
 pub mod settings;
+pub mod shutdown;
+pub mod tls;
@@ -1,74 +1,243 @@
-//! Network handling utilities for TCP connections.
+//! Network handling utilities for client connections.
 //!
-//! Provides functionality for accepting and handling TCP connections,
+//! Provides functionality for handling TCP and Unix socket connections,
 //! processing RESP protocol commands, and routing them to the appropriate handlers.
 
+use std::{
+  sync::atomic::{AtomicUsize, Ordering},
+  time::Duration,
+};
+
 use crate::{
   commands::executor::CommandExecutor,
-  resp::{handler::RespHandler, value::Value},
-  storage::{db::InternalDB, memory::MemoryStore},
+  resp::{
+    error::{CommandError, ErrorCode},
+    handler::RespHandler,
+    parser::ParserLimits,
+    value::Value,
+  },
+  storage::{
+    db::InternalDB,
+    memory::MemoryStore,
+    pubsub::{PubSub, SUBSCRIBER_BUFFER},
+  },
+  utils::{
+    client::{Client, ClientRegistry},
+    listener::Connection,
+    settings::SharedSettings,
+    shutdown::ShutdownTrigger,
+  },
 };
 
 use anyhow::Result;
-use log::{debug, error, info};
-use tokio::net::TcpStream;
+use log::{debug, error, info, warn};
+use tokio::{sync::mpsc, time};
+
+/// Reply sent to connections protected mode refuses, before closing them.
+const PROTECTED_MODE_DENIED: &str = "rusty-kv is running in protected mode because requireauth is off. In this mode \
+   connections are only accepted from the loopback interface. To accept others, turn \
+   server.network.requireauth back on, or turn server.network.protected_mode off if \
+   every client that can reach the server may be trusted.";
+
+/// Number of clients currently connected.
+static CONNECTED_CLIENTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of connections accepted since startup.
+static TOTAL_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts a connection in [`CONNECTED_CLIENTS`] for as long as it lives.
+struct ConnectionGuard;
+
+impl ConnectionGuard {
+  /// Registers a new connection.
+  fn new() -> Self {
+    CONNECTED_CLIENTS.fetch_add(1, Ordering::Relaxed);
+    TOTAL_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+    Self
+  }
+}
+
+impl Drop for ConnectionGuard {
+  fn drop(&mut self) {
+    CONNECTED_CLIENTS.fetch_sub(1, Ordering::Relaxed);
+  }
+}
 
 /// Utilities for handling network operations.
 pub struct NetworkUtils;
 
 impl NetworkUtils {
-  /// Handles a TCP connection by processing RESP commands.
+  /// Returns the number of clients currently connected.
+  pub fn connected_clients() -> usize {
+    CONNECTED_CLIENTS.load(Ordering::Relaxed)
+  }
+
+  /// Returns the number of connections accepted since startup.
+  pub fn total_connections() -> usize {
+    TOTAL_CONNECTIONS.load(Ordering::Relaxed)
+  }
+
+  /// Handles a client connection by processing RESP commands.
   ///
-  /// This function processes incoming RESP protocol commands from a TCP stream,
+  /// This function processes incoming RESP protocol commands from a stream,
   /// executes them using the command executor, and sends back responses.
+  /// While the connection is subscribed to channels, published messages are
+  /// forwarded to the stream as they arrive.
+  ///
+  /// Connections that send no command for `idle_timeout_secs` are closed,
+  /// unless they are subscribed to channels and only waiting for messages.
+  ///
+  /// When `requireauth` is off, connections start authenticated as the
+  /// default user, and with `protected_mode` on, those not coming over the
+  /// loopback interface or a Unix socket are refused.
   ///
   /// # Arguments
   ///
-  /// * `stream` - The TCP stream to read from and write to
+  /// * `stream` - The TCP or Unix socket stream to read from and write to
   /// * `store` - The memory store for data storage and retrieval
   /// * `db` - The internal database for persisting data
+  /// * `settings` - The server settings
+  /// * `pubsub` - The shared pub/sub channel registry
+  /// * `clients` - The shared registry of live connections
+  /// * `shutdown` - Trigger used to stop the server
   ///
   /// # Returns
   ///
   /// * `Ok(())` - Connection was handled successfully
   /// * `Err(...)` - An error occurred during connection handling
-  pub async fn accept_connection(
-    stream: TcpStream,
+  pub async fn accept_connection<S: Connection>(
+    stream: S,
     store: MemoryStore,
     db: InternalDB,
+    settings: SharedSettings,
+    pubsub: PubSub,
+    clients: ClientRegistry,
+    shutdown: ShutdownTrigger,
   ) -> Result<()> {
-    let peer_addr = stream.peer_addr()?;
+    let _guard = ConnectionGuard::new();
+    let peer_addr = stream.peer();
     info!("Handling connection from: {}", peer_addr);
+    stream.configure();
+    let is_local = stream.is_local();
 
     debug!("Initializing RESP handler");
-    let mut handler = RespHandler::new(stream);
+    let limits = {
+      let settings = settings.read().unwrap();
+      ParserLimits {
+        max_array_len: settings.server.network.max_array_len,
+        max_bulk_len: settings.server.network.max_bulk_len,
+      }
+    };
+    let mut handler = RespHandler::new(stream, limits);
+
+    let (requireauth, protected_mode) = {
+      let network = &settings.read().unwrap().server.network;
+      (network.requireauth, network.protected_mode)
+    };
+    if !requireauth && protected_mode && !is_local {
+      warn!(
+        "Refused connection from {}: protected mode only accepts loopback connections \
+         while requireauth is off",
+        peer_addr
+      );
+      let denied = CommandError::new(ErrorCode::Denied, PROTECTED_MODE_DENIED);
+      handler
+        .write_value(Value::Error(denied.to_string()))
+        .await?;
+      handler.flush().await?;
+      return Ok(());
+    }
 
     debug!("Initializing executor for incoming commands");
-    let executor = CommandExecutor::new(store, db);
+    let (subscriber, mut messages) = mpsc::channel(SUBSCRIBER_BUFFER);
+    let client = Client::new(peer_addr.clone(), clients);
+    let client_id = client.id;
+    let kill = client.kill_signal();
+    debug!("Assigned client id {} to {}", client_id, peer_addr);
+    let mut executor = CommandExecutor::new(
+      store,
+      db,
+      settings.clone(),
+      pubsub,
+      subscriber,
+      client,
+      shutdown,
+    );
+    if !requireauth && let Err(e) = executor.login_default_user() {
+      error!(
+        "Failed to authenticate {} as the default user: {:#}",
+        peer_addr, e
+      );
+    }
 
     // Main command processing loop
-    while let Some(value) = handler.read_value().await? {
-      debug!("Received: {:?}", value);
+    loop {
+      // Answer every command already received before waiting for more, so
+      // the replies to a pipeline go out in a single write
+      if !handler.has_buffered_value().unwrap_or(false) {
+        handler.flush().await?;
+      }
 
-      if let Some((cmd, args)) = value.to_command() {
-        info!("Command: {} with args: {:?}", cmd, args);
+      // Read on every command, so CONFIG SET applies to open connections too
+      let idle_timeout =
+        Duration::from_secs(settings.read().unwrap().server.network.idle_timeout_secs);
+      let value = tokio::select! {
+        value = handler.read_value() => match value {
+          Ok(value) => value,
+          Err(e) => {
+            // The stream can't be resynchronized after a protocol error,
+            // so report it and close the connection
+            let error_msg = format!("ERR Protocol error: {}", e);
+            let _ = handler.write_value(Value::Error(error_msg)).await;
+            let _ = handler.flush().await;
+            return Err(e);
+          }
+        },
+        // Forward published messages while subscribed to any channel
+        Some(message) = messages.recv(), if executor.is_subscribed() => {
+          handler.write_value(message).await?;
+          continue;
+        }
+        _ = time::sleep(idle_timeout), if !idle_timeout.is_zero() && !executor.is_subscribed() => {
+          info!("Closing idle connection: {}", peer_addr);
+          break;
+        }
+        _ = kill.notified() => {
+          info!("Client {} ({}) killed", client_id, peer_addr);
+          break;
+        }
+      };
+      let Some(value) = value else {
+        break;
+      };
 
-        // Execute the command and handle the result
-        let result = executor.execute(&cmd, args).await;
-        match result {
-          Ok(response) => {
+      let (cmd, args) = match value.to_command() {
+        Ok(Some(command)) => command,
+        // An empty array or a null holds no command, Redis ignores it
+        // without replying, which keeps pipelined replies in step
+        Ok(None) => continue,
+        Err(e) => {
+          error!("Error handling command, invalid format: {:#}", e);
+          handler.write_value(CommandError::reply(&e)).await?;
+          continue;
+        }
+      };
+      // Execute the command and handle the result
+      let result = executor.execute(&cmd, args).await;
+      // HELLO may have switched protocols, its own reply already uses the new one
+      handler.set_protocol(executor.protocol());
+      match result {
+        Ok(responses) => {
+          for response in responses {
             handler.write_value(response).await?;
           }
-          Err(e) => {
-            let error_msg = format!("ERR {}", e);
-            handler.write_value(Value::Error(error_msg)).await?;
-          }
         }
-      } else {
-        error!("Error handling command, invalid format - {:?}", value);
-        handler
-          .write_value(Value::Error("ERR invalid command format".to_string()))
-          .await?;
+        Err(e) => handler.write_value(CommandError::reply(&e)).await?,
+      }
+      // Commands pipelined after QUIT are dropped unanswered
+      if executor.is_quitting() {
+        handler.flush().await?;
+        break;
       }
     }
 
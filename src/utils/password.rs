@@ -0,0 +1,82 @@
+//! Password hashing for the users table.
+//!
+//! Passwords are stored as Argon2 PHC strings with a random per-user salt.
+//! Rows written before Argon2 was introduced hold an unsalted Keccak256
+//! hex digest instead; they still verify, and are upgraded the next time
+//! the user authenticates (see [`needs_rehash`]).
+//!
+//! A user's in-memory data used to be keyed by a credential hash derived
+//! from the stored password. It is keyed by the user id now, and
+//! [`credential_hash`] only remains to find data saved under the old key.
+
+use anyhow::{Result, anyhow};
+use argon2::{
+  Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+  password_hash::{SaltString, rand_core::OsRng},
+};
+use sha3::{Digest, Keccak256};
+
+/// Hashes a password with Argon2 and a fresh random salt.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The PHC string to store in the users table
+/// * `Err` - Error if hashing fails
+pub fn hash_password(password: &str) -> Result<String> {
+  let salt = SaltString::generate(&mut OsRng);
+  let hash = Argon2::default()
+    .hash_password(password.as_bytes(), &salt)
+    .map_err(|e| anyhow!("Failed to hash password: {}", e))?;
+  Ok(hash.to_string())
+}
+
+/// Checks a password against a hash from the users table.
+///
+/// # Arguments
+///
+/// * `password` - The password to check
+/// * `stored` - Argon2 PHC string or legacy Keccak256 digest
+///
+/// # Returns
+///
+/// * `true` - The password matches
+/// * `false` - It doesn't, or the stored hash is malformed
+pub fn verify_password(password: &str, stored: &str) -> bool {
+  if needs_rehash(stored) {
+    return keccak_hex(password) == stored;
+  }
+  PasswordHash::new(stored).is_ok_and(|hash| {
+    Argon2::default()
+      .verify_password(password.as_bytes(), &hash)
+      .is_ok()
+  })
+}
+
+/// Returns true if a stored hash predates Argon2 and should be replaced.
+pub fn needs_rehash(stored: &str) -> bool {
+  !stored.starts_with("$argon2")
+}
+
+/// Derives the legacy credential hash a user's data was stored under.
+///
+/// # Arguments
+///
+/// * `username` - The user's name
+/// * `stored` - The user's password hash from the users table
+pub fn credential_hash(username: &str, stored: &str) -> String {
+  let secret = match PasswordHash::new(stored) {
+    Ok(PasswordHash {
+      salt: Some(salt), ..
+    }) => salt.as_str().to_string(),
+    // Legacy digests keep the key they have always had
+    _ => stored.to_string(),
+  };
+  keccak_hex(&format!("{}:{}", username, secret))
+}
+
+/// Hashes a string with Keccak256, as lowercase hex.
+fn keccak_hex(input: &str) -> String {
+  let mut hasher = Keccak256::new();
+  hasher.update(input.as_bytes());
+  format!("{:x}", hasher.finalize())
+}
@@ -2,11 +2,47 @@
 //!
 //! This module provides functionality to load, parse, and access server configuration
 //! from TOML files, with sensible defaults when configuration is missing.
+//! Some parameters can also be read and changed at runtime with `CONFIG`.
 
+use std::{
+  collections::BTreeMap,
+  str::FromStr,
+  sync::{Arc, RwLock},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
 use config::{self, Config, File};
-use log::error;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+  resp::parser::ParserLimits,
+  storage::{
+    eviction::{Eviction, EvictionPolicy},
+    memory::{DEFAULT_DATABASES, Quota},
+    notify::KeyspaceEvents,
+  },
+};
+
+/// Settings shared by every connection, changed at runtime by `CONFIG SET`.
+pub type SharedSettings = Arc<RwLock<Settings>>;
+
+/// Parameters `CONFIG GET` reports, in the order they are listed.
+///
+/// Credentials are left out, so they can't be read back over the network.
+pub const PARAMETERS: [&str; 11] = [
+  "host",
+  "port",
+  "databases",
+  "max_array_len",
+  "max_bulk_len",
+  "idle_timeout_secs",
+  "max_keys_per_user",
+  "max_bytes_per_user",
+  "maxmemory",
+  "maxmemory_policy",
+  "notify_keyspace_events",
+];
+
 /// Main configuration structure for the server.
 ///
 /// Contains all server settings including network configuration and database settings.
@@ -32,6 +68,21 @@ pub struct Server {
   pub db: Database,
   /// RDB persistence settings
   pub kdb: KDBSettings,
+  /// Log output settings
+  #[serde(default)]
+  pub logging: LoggingSettings,
+  /// Slow command log settings
+  #[serde(default)]
+  pub slowlog: SlowlogSettings,
+  /// Prometheus metrics endpoint settings
+  #[serde(default)]
+  pub metrics: MetricsSettings,
+  /// Keyspace notification settings
+  #[serde(default)]
+  pub notify: NotifySettings,
+  /// Append-only file persistence settings
+  #[serde(default)]
+  pub aof: AofSettings,
 }
 
 /// Network configuration settings.
@@ -43,6 +94,10 @@ pub struct Network {
   pub host: String,
   /// Port number to listen on
   pub port: u16,
+  /// Addresses to listen on, `host:port` for TCP or `unix:<path>` for a Unix
+  /// socket. When empty, the server only listens on `host` and `port`
+  #[serde(default)]
+  pub listeners: Vec<String>,
   /// Username for root access
   pub root_user: String,
   /// Password for root access
@@ -51,6 +106,96 @@ pub struct Network {
   pub user: String,
   /// Password for regular access
   pub password: String,
+  /// Whether connections must authenticate before running commands. When
+  /// off, they start authenticated as the regular user
+  #[serde(default = "default_true")]
+  pub requireauth: bool,
+  /// Whether connections not coming over the loopback interface are
+  /// refused while `requireauth` is off
+  #[serde(default = "default_true")]
+  pub protected_mode: bool,
+  /// Number of logical databases per user, selectable with `SELECT`
+  #[serde(default = "default_databases")]
+  pub databases: usize,
+  /// Maximum number of elements in a request array
+  #[serde(default = "default_max_array_len")]
+  pub max_array_len: usize,
+  /// Maximum length of a request bulk string in bytes
+  #[serde(default = "default_max_bulk_len")]
+  pub max_bulk_len: usize,
+  /// Seconds a client may stay idle before it is disconnected, 0 to never time out
+  #[serde(default)]
+  pub idle_timeout_secs: u64,
+  /// Maximum number of keys a single user may store, 0 for unlimited
+  #[serde(default)]
+  pub max_keys_per_user: usize,
+  /// Maximum approximate size of a single user's data in bytes, 0 for unlimited
+  #[serde(default)]
+  pub max_bytes_per_user: usize,
+  /// Approximate bytes a single user may store before writes evict keys, 0 for unlimited
+  #[serde(default)]
+  pub maxmemory: usize,
+  /// Which keys are evicted once a user reaches `maxmemory`
+  #[serde(default)]
+  pub maxmemory_policy: EvictionPolicy,
+  /// TLS settings for TCP listeners
+  #[serde(default)]
+  pub tls: TlsSettings,
+}
+
+/// TLS configuration settings.
+///
+/// When enabled, connections accepted on TCP listeners must complete a TLS
+/// handshake before sending commands. Unix sockets stay plaintext.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TlsSettings {
+  /// Whether TCP connections are served over TLS
+  #[serde(default)]
+  pub enabled: bool,
+  /// Path to the PEM encoded certificate chain
+  #[serde(default)]
+  pub cert_path: String,
+  /// Path to the PEM encoded private key
+  #[serde(default)]
+  pub key_path: String,
+}
+
+impl Network {
+  /// Returns the per-user limits configured for the memory store.
+  pub fn quota(&self) -> Quota {
+    Quota {
+      max_keys: self.max_keys_per_user,
+      max_bytes: self.max_bytes_per_user,
+    }
+  }
+
+  /// Returns the memory budget configured for the memory store.
+  pub fn eviction(&self) -> Eviction {
+    Eviction {
+      maxmemory: self.maxmemory,
+      policy: self.maxmemory_policy,
+    }
+  }
+}
+
+/// Default of the switches that are on unless configured otherwise.
+fn default_true() -> bool {
+  true
+}
+
+/// Default number of logical databases when `server.network.databases` is missing.
+fn default_databases() -> usize {
+  DEFAULT_DATABASES
+}
+
+/// Default request array limit when `server.network.max_array_len` is missing.
+fn default_max_array_len() -> usize {
+  ParserLimits::default().max_array_len
+}
+
+/// Default bulk string limit when `server.network.max_bulk_len` is missing.
+fn default_max_bulk_len() -> usize {
+  ParserLimits::default().max_bulk_len
 }
 
 /// Database configuration settings.
@@ -86,26 +231,147 @@ pub struct KDBSettings {
   pub persistence: bool,
   /// Interval for RDB backups in seconds
   pub backup_interval: u64,
+  /// `(seconds, changes)` pairs, like Redis' `save` directive: a background
+  /// save starts once at least `changes` changes were made and `seconds`
+  /// passed since the last save. Empty to only save every `backup_interval`
+  #[serde(default)]
+  pub save_points: Vec<(u64, u64)>,
 }
 
-impl Settings {
-  /// Creates a new Settings instance.
-  ///
-  /// Attempts to load settings from the specified configuration file.
-  /// Falls back to default settings if the file cannot be read or parsed.
-  ///
-  /// # Arguments
-  ///
-  /// * `filename` - Optional name of the configuration file to load
-  ///
-  /// # Returns
-  ///
-  /// A new Settings instance with either loaded or default configuration
-  pub fn new<'a>(filename: impl Into<Option<&'a str>>) -> Self {
-    let filename = filename.into();
+/// How log lines are written.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+  /// Colored lines for people reading the terminal
+  #[default]
+  Text,
+  /// One JSON object per line for log aggregation
+  Json,
+}
+
+/// Logging configuration settings.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoggingSettings {
+  /// Format of every log line
+  #[serde(default)]
+  pub format: LogFormat,
+  /// Most verbose level written: off, error, warn, info, debug or trace
+  #[serde(default = "default_log_level")]
+  pub level: String,
+}
 
-    // Create a default configuration
-    let default_settings = Settings {
+impl Default for LoggingSettings {
+  fn default() -> Self {
+    Self {
+      format: LogFormat::default(),
+      level: default_log_level(),
+    }
+  }
+}
+
+/// Default log level when `server.logging.level` is missing.
+fn default_log_level() -> String {
+  "trace".to_string()
+}
+
+/// Slow log configuration settings.
+///
+/// Read once at startup, changing them takes a restart.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SlowlogSettings {
+  /// Commands running at least this many microseconds are logged, 0 logs every command
+  #[serde(default = "default_slowlog_threshold")]
+  pub threshold_micros: u64,
+  /// Number of entries kept before the oldest is dropped, 0 disables the slow log
+  #[serde(default = "default_slowlog_max_len")]
+  pub max_len: usize,
+}
+
+impl Default for SlowlogSettings {
+  fn default() -> Self {
+    Self {
+      threshold_micros: default_slowlog_threshold(),
+      max_len: default_slowlog_max_len(),
+    }
+  }
+}
+
+/// Default threshold when `server.slowlog.threshold_micros` is missing.
+fn default_slowlog_threshold() -> u64 {
+  10_000
+}
+
+/// Default length when `server.slowlog.max_len` is missing.
+fn default_slowlog_max_len() -> usize {
+  128
+}
+
+/// Prometheus metrics endpoint settings.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MetricsSettings {
+  /// Port the HTTP endpoint listens on, on `server.network.host`. The
+  /// endpoint is disabled when unset
+  #[serde(default)]
+  pub port: Option<u16>,
+}
+
+/// Keyspace notification settings.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NotifySettings {
+  /// Flags choosing which notifications are published, like Redis'
+  /// `notify-keyspace-events`. Empty to publish none
+  #[serde(default)]
+  pub keyspace_events: KeyspaceEvents,
+}
+
+/// When the append-only file is flushed to disk.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AofFsync {
+  /// After every logged command, the safest and slowest
+  Always,
+  /// Once per second, losing at most the last second of writes on a crash
+  #[default]
+  Everysec,
+  /// Whenever the operating system decides
+  No,
+}
+
+/// Append-only file settings.
+///
+/// Read once at startup, changing them takes a restart.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AofSettings {
+  /// Whether commands changing the keyspace are logged and replayed at startup
+  #[serde(default)]
+  pub enabled: bool,
+  /// Path of the file commands are appended to
+  #[serde(default = "default_aof_path")]
+  pub path: String,
+  /// When the file is flushed to disk
+  #[serde(default)]
+  pub fsync: AofFsync,
+}
+
+impl Default for AofSettings {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      path: default_aof_path(),
+      fsync: AofFsync::default(),
+    }
+  }
+}
+
+/// Default path when `server.aof.path` is missing.
+fn default_aof_path() -> String {
+  "appendonly.aof".to_string()
+}
+
+impl Default for Settings {
+  /// Settings used when the configuration file can't be loaded.
+  fn default() -> Self {
+    Settings {
       server: Server {
         name: "Default Server".into(),
         version: "1.0".into(),
@@ -113,10 +379,22 @@ impl Settings {
         network: Network {
           host: "127.0.0.1".into(),
           port: 8080,
+          listeners: Vec::new(),
           root_user: "root".into(),
           root_password: "rootpassword".into(),
           user: "admin".into(),
           password: "securepassword".into(),
+          requireauth: true,
+          protected_mode: true,
+          databases: DEFAULT_DATABASES,
+          max_array_len: default_max_array_len(),
+          max_bulk_len: default_max_bulk_len(),
+          idle_timeout_secs: 0,
+          max_keys_per_user: 0,
+          max_bytes_per_user: 0,
+          maxmemory: 0,
+          maxmemory_policy: EvictionPolicy::default(),
+          tls: TlsSettings::default(),
         },
         db: Database {
           path: "db.sqlite".into(),
@@ -131,30 +409,38 @@ impl Settings {
           file_name: "backup.rdb".to_string(),
           persistence: false,
           backup_interval: 3600, // Default backup interval (in seconds)
+          save_points: Vec::new(),
         },
+        logging: LoggingSettings::default(),
+        slowlog: SlowlogSettings::default(),
+        metrics: MetricsSettings::default(),
+        notify: NotifySettings::default(),
+        aof: AofSettings::default(),
       },
-    };
-
-    // Determine which config file to load
-    let config_file = filename.unwrap_or("config.toml");
+    }
+  }
+}
 
-    // Try to load configuration from file
-    match Config::builder()
-      .add_source(File::with_name(config_file).required(false))
+impl Settings {
+  /// Loads settings from a configuration file, without falling back to
+  /// defaults.
+  ///
+  /// # Arguments
+  ///
+  /// * `filename` - Name of the configuration file to load
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(Settings)` - The settings in the file
+  /// * `Err` - If the file can't be read or doesn't hold valid settings
+  pub fn load(filename: &str) -> Result<Self> {
+    let config = Config::builder()
+      .add_source(File::with_name(filename).required(false))
       .build()
-    {
-      Ok(config) => match config.try_deserialize::<Settings>() {
-        Ok(settings) => settings,
-        Err(e) => {
-          error!("Failed to parse config file {}: {}", config_file, e);
-          default_settings
-        }
-      },
-      Err(e) => {
-        error!("Failed to load config file {}: {}", config_file, e);
-        default_settings
-      }
-    }
+      .with_context(|| format!("Failed to load config file {}", filename))?;
+    config
+      .try_deserialize::<Settings>()
+      .with_context(|| format!("Failed to parse config file {}", filename))
   }
 
   /// Gets a typed configuration value from a dot-notation path.
@@ -193,4 +479,157 @@ impl Settings {
       Err(_) => None,
     }
   }
+
+  /// Returns the current value of a `CONFIG` parameter.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - One of the [`PARAMETERS`]
+  ///
+  /// # Returns
+  ///
+  /// * `Some(String)` - The parameter's value
+  /// * `None` - If there is no such parameter
+  pub fn parameter(&self, name: &str) -> Option<String> {
+    let network = &self.server.network;
+    let value = match name {
+      "host" => network.host.clone(),
+      "port" => network.port.to_string(),
+      "databases" => network.databases.to_string(),
+      "max_array_len" => network.max_array_len.to_string(),
+      "max_bulk_len" => network.max_bulk_len.to_string(),
+      "idle_timeout_secs" => network.idle_timeout_secs.to_string(),
+      "max_keys_per_user" => network.max_keys_per_user.to_string(),
+      "max_bytes_per_user" => network.max_bytes_per_user.to_string(),
+      "maxmemory" => network.maxmemory.to_string(),
+      "maxmemory_policy" => network.maxmemory_policy.as_str().to_string(),
+      "notify_keyspace_events" => self.server.notify.keyspace_events.to_string(),
+      _ => return None,
+    };
+    Some(value)
+  }
+
+  /// Changes a `CONFIG` parameter that can be tuned at runtime.
+  ///
+  /// Only the settings change, callers apply them to whatever was built
+  /// from the old values.
+  ///
+  /// # Arguments
+  ///
+  /// * `name` - One of the [`PARAMETERS`]
+  /// * `value` - The new value
+  ///
+  /// # Returns
+  ///
+  /// * `Ok(())` - The parameter was changed
+  /// * `Err` - Unknown parameter, one only read at startup, or an invalid value
+  pub fn set_parameter(&mut self, name: &str, value: &str) -> Result<()> {
+    let network = &mut self.server.network;
+    match name {
+      "idle_timeout_secs" => network.idle_timeout_secs = parse_parameter(name, value)?,
+      "max_keys_per_user" => network.max_keys_per_user = parse_parameter(name, value)?,
+      "max_bytes_per_user" => network.max_bytes_per_user = parse_parameter(name, value)?,
+      "maxmemory" => network.maxmemory = parse_parameter(name, value)?,
+      "maxmemory_policy" => {
+        network.maxmemory_policy = EvictionPolicy::from_name(value)
+          .ok_or_else(|| anyhow!("Invalid argument '{}' for CONFIG SET '{}'", value, name))?
+      }
+      "notify_keyspace_events" => {
+        self.server.notify.keyspace_events = KeyspaceEvents::parse(value)
+          .map_err(|_| anyhow!("Invalid argument '{}' for CONFIG SET '{}'", value, name))?
+      }
+      _ if PARAMETERS.contains(&name) => {
+        bail!("CONFIG SET failed, '{}' can't be changed at runtime", name)
+      }
+      _ => bail!("Unknown option '{}' for CONFIG SET", name),
+    }
+    Ok(())
+  }
+
+  /// Takes over the parameters of `fresh` that can change at runtime.
+  ///
+  /// Every other difference is only reported, it takes a restart to apply.
+  ///
+  /// # Arguments
+  ///
+  /// * `fresh` - Settings read from the configuration file again
+  ///
+  /// # Returns
+  ///
+  /// What was applied and what was skipped.
+  pub fn reload(&mut self, fresh: &Settings) -> Reload {
+    let mut old = BTreeMap::new();
+    let mut new = BTreeMap::new();
+    flatten(
+      "",
+      &serde_json::to_value(&*self).unwrap_or_default(),
+      &mut old,
+    );
+    flatten(
+      "",
+      &serde_json::to_value(fresh).unwrap_or_default(),
+      &mut new,
+    );
+
+    let mut reload = Reload::default();
+    for (path, value) in new {
+      if old.get(&path) == Some(&value) {
+        continue;
+      }
+
+      // Top-level network fields are named as is, the others after their section
+      let name = match path.strip_prefix("server.network.") {
+        Some(name) => name.to_string(),
+        None => path
+          .strip_prefix("server.")
+          .unwrap_or_default()
+          .replace('.', "_"),
+      };
+      let name = name.as_str();
+      let before = self.parameter(name);
+      let applied = match (&before, fresh.parameter(name)) {
+        (Some(_), Some(after)) => self.set_parameter(name, &after).is_ok().then_some(after),
+        _ => None,
+      };
+      match (before, applied) {
+        (Some(before), Some(after)) => reload.applied.push((name.to_string(), before, after)),
+        _ => reload.requires_restart.push(path),
+      }
+    }
+    reload
+  }
+}
+
+/// Outcome of [`Settings::reload`].
+#[derive(Debug, Default)]
+pub struct Reload {
+  /// Parameters that were changed, with their old and new value
+  pub applied: Vec<(String, String, String)>,
+  /// Settings that changed in the file but are only read at startup
+  pub requires_restart: Vec<String>,
+}
+
+/// Flattens settings serialized to JSON into dot-notation paths and their values.
+fn flatten(prefix: &str, value: &serde_json::Value, out: &mut BTreeMap<String, serde_json::Value>) {
+  match value {
+    serde_json::Value::Object(fields) => {
+      for (key, value) in fields {
+        let path = match prefix {
+          "" => key.clone(),
+          prefix => format!("{}.{}", prefix, key),
+        };
+        flatten(&path, value, out);
+      }
+    }
+    leaf => {
+      out.insert(prefix.to_string(), leaf.clone());
+    }
+  }
+}
+
+/// Parses the value of a numeric `CONFIG` parameter.
+fn parse_parameter<T: FromStr>(name: &str, value: &str) -> Result<T> {
+  value
+    .parse()
+    .map_err(|_| anyhow!("Invalid argument '{}' for CONFIG SET '{}'", value, name))
 }
@@ -0,0 +1,61 @@
+//! Server shutdown requests.
+//!
+//! Commands can't stop the process themselves, since the final save and
+//! the closing of connections happen in `main`. Instead they raise a
+//! [`ShutdownTrigger`] that the main loop waits on alongside OS signals.
+
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+
+/// What to do with the data when the server shuts down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShutdownMode {
+  /// Save a KDB snapshot if persistence is enabled
+  Default,
+  /// Always save a KDB snapshot
+  Save,
+  /// Never save a KDB snapshot
+  NoSave,
+}
+
+/// Shared handle used to ask the server to shut down.
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownTrigger {
+  /// Wakes up the main loop
+  notify: Arc<Notify>,
+  /// Mode of the pending request, if any
+  mode: Arc<Mutex<Option<ShutdownMode>>>,
+}
+
+impl ShutdownTrigger {
+  /// Creates a trigger with no pending request.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Asks the server to shut down.
+  ///
+  /// # Arguments
+  ///
+  /// * `mode` - What to do with the data before exiting
+  pub fn trigger(&self, mode: ShutdownMode) {
+    *self.mode.lock().unwrap() = Some(mode);
+    // notify_one keeps the wake-up even if nobody is waiting yet
+    self.notify.notify_one();
+  }
+
+  /// Waits until a shutdown is requested.
+  ///
+  /// # Returns
+  ///
+  /// The mode the shutdown was requested with.
+  pub async fn requested(&self) -> ShutdownMode {
+    loop {
+      self.notify.notified().await;
+      if let Some(mode) = *self.mode.lock().unwrap() {
+        return mode;
+      }
+    }
+  }
+}
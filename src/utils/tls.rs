@@ -0,0 +1,55 @@
+//! TLS termination for client connections.
+//!
+//! The certificate and key are loaded once at startup. Each connection
+//! accepted on a TCP listener then completes a handshake with the shared
+//! [`TlsAcceptor`] before it is served.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use tokio_rustls::{
+  TlsAcceptor,
+  rustls::{
+    ServerConfig,
+    crypto::ring,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+  },
+};
+
+use crate::utils::settings::TlsSettings;
+
+/// Builds the acceptor for TLS connections from the settings.
+///
+/// # Arguments
+///
+/// * `settings` - The `server.network.tls` settings
+///
+/// # Returns
+///
+/// * `Ok(Some(TlsAcceptor))` - TLS is enabled and the certificate and key are valid
+/// * `Ok(None)` - TLS is disabled, connections stay plaintext
+/// * `Err` - A file is missing or doesn't hold a valid certificate or key
+pub fn load_acceptor(settings: &TlsSettings) -> Result<Option<TlsAcceptor>> {
+  if !settings.enabled {
+    return Ok(None);
+  }
+
+  let certs = CertificateDer::pem_file_iter(&settings.cert_path)
+    .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+    .with_context(|| format!("Failed to read TLS certificate {}", settings.cert_path))?;
+  if certs.is_empty() {
+    bail!("No certificate found in {}", settings.cert_path);
+  }
+
+  let key = PrivateKeyDer::from_pem_file(&settings.key_path)
+    .with_context(|| format!("Failed to read TLS private key {}", settings.key_path))?;
+
+  let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+    .with_safe_default_protocol_versions()
+    .context("Failed to set up TLS")?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)
+    .context("TLS certificate and private key don't match")?;
+
+  Ok(Some(TlsAcceptor::from(Arc::new(config))))
+}
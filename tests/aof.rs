@@ -0,0 +1,128 @@
+//! Append-only file replay and logging.
+//!
+//! The AOF is open for the whole process once opened, so everything runs in
+//! a single test.
+
+mod common;
+
+use std::{fs, time::Duration};
+
+use common::{ROOT, TestServer, USER, bulk, bulks, int, ok};
+use rusty_kv_store::{resp::value::Value, storage::aof};
+use tokio::{net::TcpListener, time};
+
+fn entry(args: &[&str]) -> Vec<u8> {
+  Value::Array(args.iter().map(|arg| bulk(arg)).collect()).serialize()
+}
+
+#[tokio::test]
+async fn replay_then_log_writes() {
+  let server = TestServer::new();
+  let path = server.settings.read().unwrap().server.aof.path.clone();
+  let user = server.user_id(USER.0);
+
+  let mut file = Vec::new();
+  for args in [
+    &["@USER", &user][..],
+    &["SELECT", "1"],
+    &["SET", "key", "value"],
+    &["RPUSH", "list", "a", "b", "c"],
+    &["SADD", "set", "x", "y", "z"],
+    &["SREM", "set", "y"],
+    &["SELECT", "0"],
+    &["SET", "first", "db"],
+    &["NOSUCHCOMMAND"],
+  ] {
+    file.extend(entry(args));
+  }
+  let complete = file.len();
+  // A crash left half a command at the end
+  file.extend_from_slice(b"*3\r\n$3\r\nSET\r\n$4\r\nhalf");
+  fs::write(&path, &file).unwrap();
+
+  assert!(aof::has_entries(&path));
+  assert_eq!(aof::replay(&path, server.executor()).await.unwrap(), 7);
+  assert_eq!(fs::metadata(&path).unwrap().len(), complete as u64);
+
+  let mut client = server.connect_as(USER).await;
+  client.send("GET", &["first"]).await;
+  client.expect(bulk("db")).await;
+  client.send("SELECT", &["1"]).await;
+  client.expect(ok()).await;
+  client.send("GET", &["key"]).await;
+  client.expect(bulk("value")).await;
+  client.send("LRANGE", &["list", "0", "-1"]).await;
+  client.expect(bulks(&["a", "b", "c"])).await;
+  client.send("SISMEMBER", &["set", "y"]).await;
+  client.expect(int(0)).await;
+  client.send("SCARD", &["set"]).await;
+  client.expect(int(2)).await;
+
+  // Once open, writes are appended and reads are not
+  fs::write(&path, b"").unwrap();
+  aof::open(&server.store, &server.settings.read().unwrap().server.aof).unwrap();
+  let written = fs::metadata(&path).unwrap().len();
+  assert!(
+    written > 0,
+    "the loaded data wasn't written to the new file"
+  );
+  client.send("GET", &["key"]).await;
+  client.expect(bulk("value")).await;
+  client.send("SET", &["logged", "yes"]).await;
+  client.expect(ok()).await;
+  aof::sync();
+
+  let data = fs::read(&path).unwrap();
+  let appended = String::from_utf8_lossy(&data[written as usize..]).into_owned();
+  assert!(
+    appended.contains("$3\r\nSET\r\n$6\r\nlogged\r\n$3\r\nyes\r\n"),
+    "{}",
+    appended
+  );
+  assert!(!appended.contains("GET"), "{}", appended);
+
+  // MIGRATE doesn't hold up other writes while it waits on the target
+  let stalled = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let port = stalled.local_addr().unwrap().port().to_string();
+  let mut root = server.connect_as(ROOT).await;
+  root.send("SET", &["moved", "v"]).await;
+  root.expect(ok()).await;
+  root
+    .send("MIGRATE", &["127.0.0.1", &port, "moved", "0", "2000"])
+    .await;
+  time::timeout(Duration::from_millis(1000), async {
+    client.send("SET", &["during", "migrate"]).await;
+    client.expect(ok()).await;
+  })
+  .await
+  .expect("MIGRATE held up other writes");
+  root.expect_error("ERR IOERR ").await;
+
+  // Its local delete is logged once the target stored the key
+  let target = TestServer::new();
+  let port = target.listen().await.to_string();
+  root
+    .send(
+      "MIGRATE",
+      &[
+        "127.0.0.1",
+        &port,
+        "moved",
+        "0",
+        "5000",
+        "AUTH2",
+        USER.0,
+        USER.1,
+      ],
+    )
+    .await;
+  root.expect(ok()).await;
+  aof::sync();
+  let data = fs::read(&path).unwrap();
+  let appended = String::from_utf8_lossy(&data[written as usize..]).into_owned();
+  assert!(
+    appended.contains("$3\r\nDEL\r\n$5\r\nmoved\r\n"),
+    "{}",
+    appended
+  );
+}
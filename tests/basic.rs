@@ -0,0 +1,83 @@
+//! Round trips of the most common commands through a whole connection.
+
+mod common;
+
+use common::{TestServer, USER, bulk, int, ok};
+use rusty_kv_store::resp::value::Value;
+
+#[tokio::test]
+async fn auth_set_get_del() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  client.send("AUTH", &[USER.0, USER.1]).await;
+  client.expect(ok()).await;
+
+  client.send("SET", &["greeting", "hello world"]).await;
+  client.expect(ok()).await;
+  client.send("GET", &["greeting"]).await;
+  client.expect(bulk("hello world")).await;
+
+  client.send("DEL", &["greeting"]).await;
+  client.expect(int(1)).await;
+  client.send("GET", &["greeting"]).await;
+  client
+    .expect(Value::Error("ERR Key greeting not found".to_string()))
+    .await;
+}
+
+#[tokio::test]
+async fn del_counts_only_existing_keys() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["a", "1"]).await;
+  client.expect(ok()).await;
+  client.send("SET", &["b", "2"]).await;
+  client.expect(ok()).await;
+  client.send("DEL", &["a", "missing", "b"]).await;
+  client.expect(int(2)).await;
+}
+
+#[tokio::test]
+async fn set_and_get_binary_values() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client
+    .send_bytes("SET", vec![b"bin".to_vec(), b"\xff\x00\xfe".to_vec()])
+    .await;
+  client.expect(ok()).await;
+  client.send("GET", &["bin"]).await;
+  client
+    .expect(Value::BulkBytes(b"\xff\x00\xfe".to_vec()))
+    .await;
+}
+
+#[tokio::test]
+async fn commands_before_auth_are_refused() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  client.send("GET", &["greeting"]).await;
+  client
+    .expect(Value::Error("NOAUTH Authentication required.".to_string()))
+    .await;
+  client.send("PING", &[]).await;
+  client.expect(Value::SimpleString("PONG".to_string())).await;
+  client.send("ECHO", &["still here"]).await;
+  client.expect(bulk("still here")).await;
+}
+
+#[tokio::test]
+async fn wrong_password_is_refused() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  client.send("AUTH", &[USER.0, "wrong"]).await;
+  client.expect_error("WRONGPASS").await;
+  client.send("AUTH", &["wrong"]).await;
+  client.expect_error("WRONGPASS").await;
+  client.send("AUTH", &[USER.1]).await;
+  client.expect(ok()).await;
+}
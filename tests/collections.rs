@@ -0,0 +1,468 @@
+//! Hashes, lists, sets and sorted sets, and waiting for keys.
+
+mod common;
+
+use std::time::Duration;
+
+use common::{TestClient, TestServer, USER, bulk, bulks, int, ok};
+use rusty_kv_store::resp::value::Value;
+use tokio::time;
+
+/// Sorts an array reply of bulk strings, for sets.
+fn sorted(reply: Value) -> Vec<String> {
+  let Value::Array(values) = reply else {
+    panic!("expected an array, got {:?}", reply);
+  };
+  let mut members: Vec<String> = values
+    .into_iter()
+    .map(|value| match value {
+      Value::BulkString(s) => s,
+      other => panic!("expected a bulk string, got {:?}", other),
+    })
+    .collect();
+  members.sort();
+  members
+}
+
+async fn sadd(client: &mut TestClient, key: &str, members: &[&str]) {
+  let mut args = vec![key];
+  args.extend(members);
+  client.send("SADD", &args).await;
+  client.expect(int(members.len() as i64)).await;
+}
+
+#[tokio::test]
+async fn set_algebra() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  sadd(&mut client, "a", &["1", "2", "3"]).await;
+  sadd(&mut client, "b", &["2", "3", "4"]).await;
+
+  assert_eq!(sorted(client.call("SINTER", &["a", "b"]).await), ["2", "3"]);
+  assert_eq!(
+    sorted(client.call("SUNION", &["a", "b"]).await),
+    ["1", "2", "3", "4"]
+  );
+  assert_eq!(sorted(client.call("SDIFF", &["a", "b"]).await), ["1"]);
+
+  // A missing key is an empty set
+  assert!(sorted(client.call("SINTER", &["a", "missing"]).await).is_empty());
+  assert_eq!(
+    sorted(client.call("SUNION", &["a", "missing"]).await),
+    ["1", "2", "3"]
+  );
+
+  // The destination is replaced, may be a source, and is deleted when empty
+  client.send("SET", &["dest", "string"]).await;
+  client.expect(ok()).await;
+  client.send("SINTERSTORE", &["dest", "a", "b"]).await;
+  client.expect(int(2)).await;
+  assert_eq!(sorted(client.call("SMEMBERS", &["dest"]).await), ["2", "3"]);
+  client.send("SUNIONSTORE", &["a", "a", "b"]).await;
+  client.expect(int(4)).await;
+  client.send("SDIFFSTORE", &["dest", "b", "a"]).await;
+  client.expect(int(0)).await;
+  client.send("SCARD", &["dest"]).await;
+  client.expect(int(0)).await;
+  client.send("EXPIRETIME", &["dest"]).await;
+  client.expect(int(-2)).await;
+
+  client.send("SET", &["string", "v"]).await;
+  client.expect(ok()).await;
+  client.send("SINTER", &["a", "string"]).await;
+  client.expect_error("WRONGTYPE ").await;
+
+  client.send("DEL", &["a"]).await;
+  client.expect(int(1)).await;
+  client.send("SCARD", &["a"]).await;
+  client.expect(int(0)).await;
+
+  // The map plain keys live in can't be replaced by a set
+  client.send("SADD", &["default", "x"]).await;
+  client
+    .expect_error("ERR the key name 'default' is reserved")
+    .await;
+  client.send("SUNIONSTORE", &["default", "b"]).await;
+  client
+    .expect_error("ERR the key name 'default' is reserved")
+    .await;
+}
+
+#[tokio::test]
+async fn spop_and_srandmember_counts() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  sadd(&mut client, "set", &["a", "b", "c"]).await;
+  client.send("SCARD", &["set"]).await;
+  client.expect(int(3)).await;
+
+  assert_eq!(
+    sorted(client.call("SRANDMEMBER", &["set", "10"]).await),
+    ["a", "b", "c"]
+  );
+  let Value::Array(repeated) = client.call("SRANDMEMBER", &["set", "-10"]).await else {
+    panic!("SRANDMEMBER didn't return an array");
+  };
+  assert_eq!(repeated.len(), 10);
+
+  client.send("SPOP", &["set", "-1"]).await;
+  client.expect_error("ERR ").await;
+  assert_eq!(
+    sorted(client.call("SPOP", &["set", "10"]).await),
+    ["a", "b", "c"]
+  );
+  client.send("EXPIRETIME", &["set"]).await;
+  client.expect(int(-2)).await;
+
+  client.send("SPOP", &["set"]).await;
+  client.expect(Value::Null).await;
+  client.send("SPOP", &["set", "2"]).await;
+  client.expect(bulks(&[])).await;
+  client.send("SRANDMEMBER", &["set"]).await;
+  client.expect(Value::Null).await;
+  client.send("SRANDMEMBER", &["set", "2"]).await;
+  client.expect(bulks(&[])).await;
+}
+
+#[tokio::test]
+async fn hincrby_and_hincrbyfloat() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("HINCRBY", &["hash", "count", "5"]).await;
+  client.expect(int(5)).await;
+  client.send("HINCRBY", &["hash", "count", "-2"]).await;
+  client.expect(int(3)).await;
+  client.send("HINCRBYFLOAT", &["hash", "ratio", "0.5"]).await;
+  client.expect(bulk("0.5")).await;
+
+  client.send("HSET", &["hash", "name", "x"]).await;
+  client.expect(int(1)).await;
+  client.send("HINCRBY", &["hash", "name", "1"]).await;
+  client
+    .expect_error("ERR hash value is not an integer")
+    .await;
+  client.send("HINCRBYFLOAT", &["hash", "name", "1"]).await;
+  client.expect_error("ERR hash value is not a float").await;
+  client.send("HGET", &["hash", "name"]).await;
+  client.expect(bulk("x")).await;
+
+  client
+    .send("HSET", &["hash", "big", &i64::MAX.to_string()])
+    .await;
+  client.expect(int(1)).await;
+  client.send("HINCRBY", &["hash", "big", "1"]).await;
+  client.expect_error("ERR ").await;
+  for increment in ["nan", "inf"] {
+    client
+      .send("HINCRBYFLOAT", &["hash", "ratio", increment])
+      .await;
+    client.expect_error("ERR ").await;
+  }
+}
+
+#[tokio::test]
+async fn concurrent_hincrby_loses_no_increments() {
+  let server = TestServer::new();
+  let mut tasks = Vec::new();
+  for _ in 0..8 {
+    let mut client = server.connect_as(USER).await;
+    tasks.push(tokio::spawn(async move {
+      for _ in 0..50 {
+        client.send("HINCRBY", &["hash", "count", "1"]).await;
+        let _ = client.read().await;
+      }
+    }));
+  }
+  for task in tasks {
+    task.await.unwrap();
+  }
+
+  let mut client = server.connect_as(USER).await;
+  client.send("HGET", &["hash", "count"]).await;
+  client.expect(bulk("400")).await;
+}
+
+#[tokio::test]
+async fn hash_reads() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("HMGET", &["missing", "a", "b"]).await;
+  client
+    .expect(Value::Array(vec![Value::Null, Value::Null]))
+    .await;
+  client.send("HKEYS", &["missing"]).await;
+  client.expect(bulks(&[])).await;
+  client.send("HVALS", &["missing"]).await;
+  client.expect(bulks(&[])).await;
+  client.send("HLEN", &["missing"]).await;
+  client.expect(int(0)).await;
+  client.send("HEXISTS", &["missing", "a"]).await;
+  client.expect(int(0)).await;
+
+  client.send("HSET", &["hash", "a", "1", "c", "3"]).await;
+  client.expect(int(2)).await;
+  client.send("HMGET", &["hash", "a", "b", "c"]).await;
+  client
+    .expect(Value::Array(vec![bulk("1"), Value::Null, bulk("3")]))
+    .await;
+  client.send("HLEN", &["hash"]).await;
+  client.expect(int(2)).await;
+  client.send("HEXISTS", &["hash", "c"]).await;
+  client.expect(int(1)).await;
+
+  sadd(&mut client, "set", &["a"]).await;
+  client.send("HMGET", &["set", "a"]).await;
+  client.expect_error("WRONGTYPE ").await;
+}
+
+#[tokio::test]
+async fn sorted_set_ranges() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client
+    .send("ZADD", &["z", "1", "b", "1", "a", "2", "c", "3", "d"])
+    .await;
+  client.expect(int(4)).await;
+
+  // Members with equal scores are ordered lexicographically
+  client.send("ZRANGE", &["z", "0", "-1"]).await;
+  client.expect(bulks(&["a", "b", "c", "d"])).await;
+  client.send("ZRANGE", &["z", "-2", "100"]).await;
+  client.expect(bulks(&["c", "d"])).await;
+  client.send("ZRANGE", &["z", "5", "10"]).await;
+  client.expect(bulks(&[])).await;
+  client.send("ZRANGE", &["z", "0", "1", "WITHSCORES"]).await;
+  client
+    .expect(Value::Array(vec![
+      bulk("a"),
+      bulk("1"),
+      bulk("b"),
+      bulk("1"),
+    ]))
+    .await;
+
+  client.send("ZRANK", &["z", "c"]).await;
+  client.expect(int(2)).await;
+  client.send("ZRANK", &["z", "nobody"]).await;
+  client.expect(Value::Null).await;
+  client.send("ZSCORE", &["z", "nobody"]).await;
+  client.expect(Value::Null).await;
+}
+
+#[tokio::test]
+async fn zrangebyscore_and_zincrby() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client
+    .send("ZADD", &["z", "1", "b", "1", "a", "2", "c", "3", "d"])
+    .await;
+  client.expect(int(4)).await;
+
+  client.send("ZRANGEBYSCORE", &["z", "(1", "3"]).await;
+  client.expect(bulks(&["c", "d"])).await;
+  client.send("ZRANGEBYSCORE", &["z", "-inf", "+inf"]).await;
+  client.expect(bulks(&["a", "b", "c", "d"])).await;
+  client.send("ZRANGEBYSCORE", &["z", "3", "1"]).await;
+  client.expect(bulks(&[])).await;
+  client.send("ZRANGEBYSCORE", &["z", "(1", "(2"]).await;
+  client.expect(bulks(&[])).await;
+  client
+    .send("ZRANGEBYSCORE", &["z", "-inf", "+inf", "LIMIT", "1", "2"])
+    .await;
+  client.expect(bulks(&["b", "c"])).await;
+  client
+    .send("ZRANGEBYSCORE", &["z", "-inf", "+inf", "LIMIT", "1", "-1"])
+    .await;
+  client.expect(bulks(&["b", "c", "d"])).await;
+  client
+    .send("ZRANGEBYSCORE", &["z", "-inf", "+inf", "LIMIT", "-1", "2"])
+    .await;
+  client.expect(bulks(&[])).await;
+
+  client.send("ZINCRBY", &["z", "5", "new"]).await;
+  client.expect(bulk("5")).await;
+  client.send("ZINCRBY", &["z", "nan", "new"]).await;
+  client.expect_error("ERR ").await;
+}
+
+#[tokio::test]
+async fn list_editing() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client
+    .send("LINSERT", &["missing", "BEFORE", "x", "y"])
+    .await;
+  client.expect(int(0)).await;
+  client
+    .send("RPUSH", &["list", "a", "b", "a", "c", "a"])
+    .await;
+  client.expect(int(5)).await;
+  client
+    .send("LINSERT", &["list", "BEFORE", "zzz", "y"])
+    .await;
+  client.expect(int(-1)).await;
+
+  client.send("LINDEX", &["list", "-1"]).await;
+  client.expect(bulk("a")).await;
+  client.send("LINDEX", &["list", "-2"]).await;
+  client.expect(bulk("c")).await;
+  client.send("LINDEX", &["list", "10"]).await;
+  client.expect(Value::Null).await;
+
+  client.send("LREM", &["list", "-1", "a"]).await;
+  client.expect(int(1)).await;
+  client.send("LRANGE", &["list", "0", "-1"]).await;
+  client.expect(bulks(&["a", "b", "a", "c"])).await;
+  client.send("LREM", &["list", "0", "a"]).await;
+  client.expect(int(2)).await;
+  client.send("LRANGE", &["list", "0", "-1"]).await;
+  client.expect(bulks(&["b", "c"])).await;
+
+  client.send("LSET", &["list", "5", "x"]).await;
+  client.expect_error("ERR index out of range").await;
+  client.send("LSET", &["missing", "0", "x"]).await;
+  client.expect_error("ERR no such key").await;
+}
+
+#[tokio::test]
+async fn lmove_and_rpoplpush() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("RPUSH", &["list", "a", "b", "c"]).await;
+  client.expect(int(3)).await;
+  client.send("RPOPLPUSH", &["list", "list"]).await;
+  client.expect(bulk("c")).await;
+  client
+    .send("LMOVE", &["list", "list", "LEFT", "RIGHT"])
+    .await;
+  client.expect(bulk("c")).await;
+  client.send("LRANGE", &["list", "0", "-1"]).await;
+  client.expect(bulks(&["a", "b", "c"])).await;
+
+  client.send("RPUSH", &["single", "x"]).await;
+  client.expect(int(1)).await;
+  client
+    .send("LMOVE", &["single", "single", "RIGHT", "LEFT"])
+    .await;
+  client.expect(bulk("x")).await;
+  client.send("LRANGE", &["single", "0", "-1"]).await;
+  client.expect(bulks(&["x"])).await;
+
+  // Moving the last value away deletes the source
+  client
+    .send("LMOVE", &["single", "other", "LEFT", "LEFT"])
+    .await;
+  client.expect(bulk("x")).await;
+  client.send("EXPIRETIME", &["single"]).await;
+  client.expect(int(-2)).await;
+
+  client
+    .send("LMOVE", &["missing", "created", "LEFT", "LEFT"])
+    .await;
+  client.expect(Value::Null).await;
+  client.send("EXPIRETIME", &["created"]).await;
+  client.expect(int(-2)).await;
+
+  client.send("SET", &["string", "v"]).await;
+  client.expect(ok()).await;
+  client
+    .send("LMOVE", &["list", "string", "LEFT", "LEFT"])
+    .await;
+  client.expect_error("WRONGTYPE ").await;
+  client.send("LLEN", &["list"]).await;
+  client.expect(int(3)).await;
+}
+
+#[tokio::test]
+async fn blpop_waits_for_a_push() {
+  let server = TestServer::new();
+  let mut waiter = server.connect_as(USER).await;
+  let mut pusher = server.connect_as(USER).await;
+
+  waiter.send("BLPOP", &["queue", "5"]).await;
+  waiter.expect_silence(Duration::from_millis(100)).await;
+  pusher.send("LPUSH", &["queue", "job"]).await;
+  pusher.expect(int(1)).await;
+  waiter.expect(bulks(&["queue", "job"])).await;
+
+  // A timeout replies null, and a later push isn't consumed
+  waiter.send("BRPOP", &["queue", "0.1"]).await;
+  waiter.expect(Value::Null).await;
+  pusher.send("LPUSH", &["queue", "later"]).await;
+  pusher.expect(int(1)).await;
+  pusher.send("LLEN", &["queue"]).await;
+  pusher.expect(int(1)).await;
+
+  // The first key holding values is popped from
+  pusher.send("RPUSH", &["second", "s"]).await;
+  pusher.expect(int(1)).await;
+  waiter
+    .send("BLPOP", &["first", "second", "queue", "1"])
+    .await;
+  waiter.expect(bulks(&["second", "s"])).await;
+}
+
+#[tokio::test]
+async fn blocked_clients_are_served_in_order() {
+  let server = TestServer::new();
+  let mut first = server.connect_as(USER).await;
+  let mut second = server.connect_as(USER).await;
+  let mut pusher = server.connect_as(USER).await;
+
+  first.send("BLPOP", &["queue", "5"]).await;
+  first.expect_silence(Duration::from_millis(50)).await;
+  second.send("BLPOP", &["queue", "5"]).await;
+  second.expect_silence(Duration::from_millis(50)).await;
+
+  pusher.send("RPUSH", &["source", "1", "2"]).await;
+  pusher.expect(int(2)).await;
+  pusher
+    .send("LMOVE", &["source", "queue", "LEFT", "RIGHT"])
+    .await;
+  pusher.expect(bulk("1")).await;
+  first.expect(bulks(&["queue", "1"])).await;
+  pusher.send("LPUSH", &["queue", "2"]).await;
+  pusher.expect(int(1)).await;
+  second.expect(bulks(&["queue", "2"])).await;
+}
+
+#[tokio::test]
+async fn waitkey() {
+  let server = TestServer::new();
+  let mut waiter = server.connect_as(USER).await;
+  let mut writer = server.connect_as(USER).await;
+
+  writer.send("SET", &["present", "v"]).await;
+  writer.expect(ok()).await;
+  waiter.send("WAITKEY", &["present", "1"]).await;
+  waiter.expect(int(1)).await;
+
+  waiter.send("WAITKEY", &["string", "5"]).await;
+  waiter.expect_silence(Duration::from_millis(50)).await;
+  writer.send("SET", &["string", "v"]).await;
+  writer.expect(ok()).await;
+  waiter.expect(int(1)).await;
+
+  waiter.send("WAITKEY", &["set", "5"]).await;
+  waiter.expect_silence(Duration::from_millis(50)).await;
+  writer.send("SADD", &["set", "m"]).await;
+  writer.expect(int(1)).await;
+  waiter.expect(int(1)).await;
+
+  // Writes to another database don't wake the waiter
+  waiter.send("WAITKEY", &["elsewhere", "1"]).await;
+  writer.send("SELECT", &["1"]).await;
+  writer.expect(ok()).await;
+  writer.send("SET", &["elsewhere", "v"]).await;
+  writer.expect(ok()).await;
+  time::sleep(Duration::from_millis(50)).await;
+  waiter.expect(int(0)).await;
+}
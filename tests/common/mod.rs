@@ -0,0 +1,343 @@
+//! Test harness driving the server over in-memory connections.
+//!
+//! A [`TestServer`] holds what `main` sets up for every connection, a
+//! memory store and an internal database in a temporary directory among
+//! others, and [`TestServer::connect`] serves a connection over one end of a
+//! [`tokio::io::duplex`] pair exactly like an accepted socket. The
+//! [`TestClient`] on the other end writes commands as RESP arrays and
+//! compares the serialized replies.
+
+#![allow(dead_code)]
+
+use std::{
+  sync::{Arc, RwLock},
+  time::Duration,
+};
+
+use bytes::{Buf, BytesMut};
+use rusty_kv_store::{
+  commands::executor::CommandExecutor,
+  resp::{
+    parser::{ParserLimits, RespParser},
+    value::Value,
+  },
+  storage::{db::InternalDB, memory::MemoryStore, notify::Notifier, pubsub::PubSub},
+  utils::{
+    client::{Client, ClientRegistry},
+    network::NetworkUtils,
+    settings::{Settings, SharedSettings},
+    shutdown::ShutdownTrigger,
+  },
+};
+use tempfile::TempDir;
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt, DuplexStream},
+  net::TcpListener,
+  sync::mpsc,
+  time,
+};
+
+/// Regular user created in every test database.
+pub const USER: (&str, &str) = ("admin", "securepassword");
+
+/// Root user created in every test database.
+pub const ROOT: (&str, &str) = ("root", "rootpassword");
+
+/// How long a reply may take before the test fails.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Capacity of each direction of a duplex connection.
+const DUPLEX_BUFFER: usize = 64 * 1024;
+
+/// Everything connections share, as `main` sets it up.
+pub struct TestServer {
+  /// Memory store all connections work on
+  pub store: MemoryStore,
+  /// Internal database holding the users
+  pub db: InternalDB,
+  /// Settings connections read, CONFIG SET changes them
+  pub settings: SharedSettings,
+  /// Pub/sub channel registry
+  pub pubsub: PubSub,
+  /// Registry of live connections
+  pub clients: ClientRegistry,
+  /// Trigger SHUTDOWN pulls
+  pub shutdown: ShutdownTrigger,
+  /// Directory holding the SQLite, KDB and AOF files, removed when the server is dropped
+  pub dir: TempDir,
+}
+
+impl TestServer {
+  /// Creates a server with the default settings.
+  pub fn new() -> Self {
+    Self::with_settings(|_| {})
+  }
+
+  /// Creates a server with the default settings changed by `configure`.
+  ///
+  /// The file paths are set afterwards, so every server gets its own SQLite,
+  /// KDB and AOF files.
+  pub fn with_settings(configure: impl FnOnce(&mut Settings)) -> Self {
+    let dir = tempfile::tempdir().expect("Failed to create a temporary directory");
+    let mut settings = Settings::default();
+    configure(&mut settings);
+    settings.server.db.path = dir.path().join("db").display().to_string();
+    settings.server.db.backup_path = dir.path().join("backup").display().to_string();
+    settings.server.kdb.path = dir.path().join("kdb").display().to_string();
+    settings.server.aof.path = dir.path().join("appendonly.aof").display().to_string();
+
+    let pubsub = PubSub::new();
+    let notifier = Notifier::new(pubsub.clone(), settings.server.notify.keyspace_events);
+    let store = MemoryStore::new(settings.server.network.databases)
+      .with_quota(settings.server.network.quota())
+      .with_eviction(settings.server.network.eviction())
+      .with_notifier(notifier);
+    let db = InternalDB::new(&settings);
+
+    Self {
+      store,
+      db,
+      settings: Arc::new(RwLock::new(settings)),
+      pubsub,
+      clients: ClientRegistry::new(),
+      shutdown: ShutdownTrigger::new(),
+      dir,
+    }
+  }
+
+  /// Opens a connection, served on its own task like an accepted socket.
+  pub fn connect(&self) -> TestClient {
+    let (client, server) = tokio::io::duplex(DUPLEX_BUFFER);
+    tokio::spawn(NetworkUtils::accept_connection(
+      server,
+      self.store.clone(),
+      self.db.clone(),
+      self.settings.clone(),
+      self.pubsub.clone(),
+      self.clients.clone(),
+      self.shutdown.clone(),
+    ));
+    TestClient::new(client)
+  }
+
+  /// Serves connections on a local TCP port, for commands that connect to
+  /// another instance like MIGRATE.
+  ///
+  /// # Returns
+  ///
+  /// The port the server listens on.
+  pub async fn listen(&self) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0")
+      .await
+      .expect("Failed to bind a local port");
+    let port = listener.local_addr().unwrap().port();
+    let (store, db, settings, pubsub, clients, shutdown) = (
+      self.store.clone(),
+      self.db.clone(),
+      self.settings.clone(),
+      self.pubsub.clone(),
+      self.clients.clone(),
+      self.shutdown.clone(),
+    );
+    tokio::spawn(async move {
+      while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(NetworkUtils::accept_connection(
+          stream,
+          store.clone(),
+          db.clone(),
+          settings.clone(),
+          pubsub.clone(),
+          clients.clone(),
+          shutdown.clone(),
+        ));
+      }
+    });
+    port
+  }
+
+  /// Opens a connection authenticated as `user`.
+  pub async fn connect_as(&self, (username, password): (&str, &str)) -> TestClient {
+    let mut client = self.connect();
+    client.send("AUTH", &[username, password]).await;
+    client.expect(ok()).await;
+    client
+  }
+
+  /// Creates an executor for a connection that never touches a stream, for
+  /// tests calling commands directly.
+  ///
+  /// Like the one replaying the AOF, it runs as root.
+  pub fn executor(&self) -> CommandExecutor {
+    let mut client = Client::new("test".to_string(), self.clients.clone());
+    client.set_root(true);
+    CommandExecutor::new(
+      self.store.clone(),
+      self.db.clone(),
+      self.settings.clone(),
+      self.pubsub.clone(),
+      mpsc::channel(1).0,
+      client,
+      self.shutdown.clone(),
+    )
+  }
+
+  /// Returns the id the data of `username` is stored under.
+  pub fn user_id(&self, username: &str) -> String {
+    let conn = self.db.pool.get().expect("Failed to get a connection");
+    conn
+      .query_row(
+        "SELECT id FROM users WHERE username = ?",
+        [username],
+        |row| row.get(0),
+      )
+      .expect("No such user")
+  }
+}
+
+/// The client end of a connection.
+pub struct TestClient {
+  /// Stream the server end is served over
+  stream: DuplexStream,
+  /// Bytes received but not parsed yet
+  buffer: BytesMut,
+  /// Parser for the replies
+  parser: RespParser,
+}
+
+impl TestClient {
+  /// Wraps the client end of a duplex pair.
+  pub fn new(stream: DuplexStream) -> Self {
+    Self {
+      stream,
+      buffer: BytesMut::new(),
+      parser: RespParser::new(ParserLimits::default()),
+    }
+  }
+
+  /// Sends a command as an array of bulk strings, like any Redis client.
+  pub async fn send(&mut self, command: &str, args: &[&str]) {
+    let args = args.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+    self.send_bytes(command, args).await;
+  }
+
+  /// Sends a command whose arguments may not be UTF-8.
+  pub async fn send_bytes(&mut self, command: &str, args: Vec<Vec<u8>>) {
+    let mut values = vec![Value::BulkString(command.to_string())];
+    values.extend(args.into_iter().map(Value::from_bytes));
+    self.send_value(Value::Array(values)).await;
+  }
+
+  /// Sends any value, commands are arrays.
+  pub async fn send_value(&mut self, value: Value) {
+    self.send_raw(&value.serialize()).await;
+  }
+
+  /// Sends bytes as they are, for malformed or inline input.
+  pub async fn send_raw(&mut self, bytes: &[u8]) {
+    self
+      .stream
+      .write_all(bytes)
+      .await
+      .expect("Failed to write to the connection");
+  }
+
+  /// Reads the next reply.
+  ///
+  /// Panics if none arrives within a few seconds or the connection closed.
+  pub async fn read(&mut self) -> Value {
+    match time::timeout(REPLY_TIMEOUT, self.try_read()).await {
+      Ok(Some(value)) => value,
+      Ok(None) => panic!("Connection closed while waiting for a reply"),
+      Err(_) => panic!("No reply within {:?}", REPLY_TIMEOUT),
+    }
+  }
+
+  /// Reads the next reply, or `None` once the server closed the connection.
+  pub async fn try_read(&mut self) -> Option<Value> {
+    loop {
+      if let Some((value, consumed)) = self
+        .parser
+        .parse_message(&self.buffer)
+        .expect("Failed to parse a reply")
+      {
+        self.buffer.advance(consumed);
+        return Some(value);
+      }
+      let read = self
+        .stream
+        .read_buf(&mut self.buffer)
+        .await
+        .expect("Failed to read from the connection");
+      if read == 0 {
+        return None;
+      }
+    }
+  }
+
+  /// Reads the next reply and checks it serializes to `expected`.
+  pub async fn expect(&mut self, expected: Value) {
+    let reply = self.read().await;
+    assert_eq!(
+      String::from_utf8_lossy(&reply.serialize()),
+      String::from_utf8_lossy(&expected.serialize()),
+    );
+  }
+
+  /// Reads the next reply and checks it is an error starting with `prefix`.
+  pub async fn expect_error(&mut self, prefix: &str) {
+    match self.read().await {
+      Value::Error(message) => assert!(
+        message.starts_with(prefix),
+        "expected an error starting with {:?}, got {:?}",
+        prefix,
+        message
+      ),
+      other => panic!(
+        "expected an error starting with {:?}, got {:?}",
+        prefix, other
+      ),
+    }
+  }
+
+  /// Sends a command and returns its reply.
+  pub async fn call(&mut self, command: &str, args: &[&str]) -> Value {
+    self.send(command, args).await;
+    self.read().await
+  }
+
+  /// Checks no reply arrives within `wait`.
+  pub async fn expect_silence(&mut self, wait: Duration) {
+    if let Ok(reply) = time::timeout(wait, self.try_read()).await {
+      panic!("expected no reply, got {:?}", reply);
+    }
+  }
+
+  /// Checks the server closes the connection within a few seconds.
+  pub async fn expect_closed(&mut self) {
+    match time::timeout(REPLY_TIMEOUT, self.try_read()).await {
+      Ok(None) => {}
+      Ok(Some(reply)) => panic!("expected the connection to close, got {:?}", reply),
+      Err(_) => panic!("Connection still open after {:?}", REPLY_TIMEOUT),
+    }
+  }
+}
+
+/// `+OK`
+pub fn ok() -> Value {
+  Value::SimpleString("OK".to_string())
+}
+
+/// A bulk string reply.
+pub fn bulk(s: &str) -> Value {
+  Value::BulkString(s.to_string())
+}
+
+/// An integer reply.
+pub fn int(i: i64) -> Value {
+  Value::Integer(i)
+}
+
+/// An array reply of bulk strings.
+pub fn bulks(items: &[&str]) -> Value {
+  Value::Array(items.iter().map(|s| bulk(s)).collect())
+}
@@ -0,0 +1,612 @@
+//! Connection handling: framing, authentication, errors and CLIENT.
+
+mod common;
+
+use std::time::Duration;
+
+use common::{ROOT, TestServer, USER, bulk, bulks, int, ok};
+use rusty_kv_store::{
+  commands::registry::{COMMANDS, Handler},
+  resp::value::Value,
+  utils::shutdown::ShutdownMode,
+};
+use tokio::time;
+
+fn pong() -> Value {
+  Value::SimpleString("PONG".to_string())
+}
+
+fn error(message: &str) -> Value {
+  Value::Error(message.to_string())
+}
+
+#[tokio::test]
+async fn unknown_command() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("FROBNICATE", &["x"]).await;
+  client
+    .expect(error("ERR unknown command 'FROBNICATE'"))
+    .await;
+}
+
+#[tokio::test]
+async fn wrong_number_of_arguments() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("GET", &[]).await;
+  client
+    .expect(error("ERR wrong number of arguments for 'get' command"))
+    .await;
+  client.send("SET", &["key"]).await;
+  client
+    .expect(error("ERR wrong number of arguments for 'set' command"))
+    .await;
+  client.send("ECHO", &["one", "two"]).await;
+  client
+    .expect(error("ERR wrong number of arguments for 'echo' command"))
+    .await;
+}
+
+#[tokio::test]
+async fn echo_replies_its_argument() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  client.send("ECHO", &["Hello World!!"]).await;
+  client.expect(bulk("Hello World!!")).await;
+}
+
+#[tokio::test]
+async fn inline_commands() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send_raw(b"SET foo \"hello world\"\r\n").await;
+  client.expect(ok()).await;
+  client.send_raw(b"GET foo\r\n").await;
+  client.expect(bulk("hello world")).await;
+
+  client.send_raw(b"SET foo \"hello\r\n").await;
+  client
+    .expect(error("ERR Protocol error: unbalanced quotes in request"))
+    .await;
+  client.expect_closed().await;
+}
+
+#[tokio::test]
+async fn oversized_lengths_are_protocol_errors() {
+  let server = TestServer::new();
+
+  let mut client = server.connect();
+  client.send_raw(b"*1000000000\r\n").await;
+  client
+    .expect(error("ERR Protocol error: invalid multibulk length"))
+    .await;
+  client.expect_closed().await;
+
+  let mut client = server.connect();
+  client
+    .send_raw(b"*2\r\n$4\r\nECHO\r\n$2000000000\r\n")
+    .await;
+  client
+    .expect(error("ERR Protocol error: invalid bulk length"))
+    .await;
+  client.expect_closed().await;
+
+  let mut client = server.connect();
+  client.send_raw(b"*-5\r\n").await;
+  client
+    .expect(error("ERR Protocol error: invalid multibulk length"))
+    .await;
+  client.expect_closed().await;
+}
+
+#[tokio::test]
+async fn command_sent_one_byte_at_a_time() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  for byte in b"*2\r\n$4\r\nECHO\r\n$5\r\nhello\r\n" {
+    client.send_raw(&[*byte]).await;
+    tokio::task::yield_now().await;
+  }
+  client.expect(bulk("hello")).await;
+}
+
+#[tokio::test]
+async fn pipelined_commands_are_answered_in_order() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  let pings = b"*1\r\n$4\r\nPING\r\n".repeat(1000);
+  client.send_raw(&pings).await;
+  for _ in 0..1000 {
+    client.expect(pong()).await;
+  }
+
+  // The same pipeline in odd-sized chunks
+  for chunk in pings.chunks(7) {
+    client.send_raw(chunk).await;
+  }
+  for _ in 0..1000 {
+    client.expect(pong()).await;
+  }
+}
+
+#[tokio::test]
+async fn command_split_across_writes() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  client.send_raw(b"*2\r\n$4\r\nECHO\r\n$5\r\nhel").await;
+  client.expect_silence(Duration::from_millis(100)).await;
+  client.send_raw(b"lo\r\n").await;
+  client.expect(bulk("hello")).await;
+}
+
+#[tokio::test]
+async fn empty_arrays_get_no_reply() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  client.send_raw(b"*0\r\n*1\r\n$4\r\nPING\r\n").await;
+  client.expect(pong()).await;
+}
+
+#[tokio::test]
+async fn nulls_get_no_reply() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  client.send_raw(b"$-1\r\n").await;
+  client.send_raw(b"*-1\r\n").await;
+  client.send("PING", &[]).await;
+  client.expect(pong()).await;
+}
+
+#[tokio::test]
+async fn idle_connections_are_closed() {
+  let server = TestServer::with_settings(|settings| {
+    settings.server.network.idle_timeout_secs = 1;
+  });
+  let mut client = server.connect();
+
+  client.send("PING", &[]).await;
+  client.expect(pong()).await;
+  client.expect_closed().await;
+}
+
+#[tokio::test]
+async fn only_allow_listed_commands_run_before_auth() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  client.send("GET", &["key"]).await;
+  client
+    .expect(error("NOAUTH Authentication required."))
+    .await;
+  client.send("PING", &[]).await;
+  client.expect(pong()).await;
+  client.send("ECHO", &["hi"]).await;
+  client.expect(bulk("hi")).await;
+
+  let allowed = ["AUTH", "HELLO", "PING", "ECHO", "HELP", "RESET", "QUIT"];
+  for spec in COMMANDS.iter().filter(|spec| spec.no_auth) {
+    assert!(
+      allowed.contains(&spec.name),
+      "{} may run before AUTH",
+      spec.name
+    );
+  }
+}
+
+#[test]
+fn only_connection_state_commands_bypass_the_handlers() {
+  let executor: Vec<&str> = COMMANDS
+    .iter()
+    .filter(|spec| matches!(spec.handler, Handler::Executor))
+    .map(|spec| spec.name)
+    .collect();
+  assert_eq!(
+    executor,
+    [
+      "RESET",
+      "QUIT",
+      "SUBSCRIBE",
+      "UNSUBSCRIBE",
+      "MULTI",
+      "EXEC",
+      "DISCARD",
+      "SHUTDOWN"
+    ]
+  );
+}
+
+#[tokio::test]
+async fn auth_with_only_a_password_uses_the_default_user() {
+  let server = TestServer::new();
+
+  let mut client = server.connect();
+  client.send("AUTH", &[USER.1]).await;
+  client.expect(ok()).await;
+  match client.call("WHOAMI", &[]).await {
+    Value::Array(values) => assert_eq!(values[0].serialize(), bulk(USER.0).serialize()),
+    other => panic!("unexpected WHOAMI reply {:?}", other),
+  }
+
+  let mut client = server.connect();
+  client.send("AUTH", &[USER.0, USER.1]).await;
+  client.expect(ok()).await;
+  client.send("AUTH", &["wrong"]).await;
+  client
+    .expect(error(
+      "WRONGPASS invalid username-password pair or user is disabled.",
+    ))
+    .await;
+  client.send("AUTH", &[USER.0, "wrong"]).await;
+  client.expect_error("WRONGPASS").await;
+}
+
+#[tokio::test]
+async fn error_codes() {
+  let server = TestServer::new();
+
+  let mut client = server.connect();
+  client.send("GET", &["key"]).await;
+  client.expect_error("NOAUTH ").await;
+  client.send("AUTH", &[USER.0, "wrong"]).await;
+  client.expect_error("WRONGPASS ").await;
+
+  let mut client = server.connect_as(USER).await;
+  client.send("LPUSH", &["list", "a"]).await;
+  client.expect(int(1)).await;
+  client.send("GET", &["list"]).await;
+  client.expect_error("WRONGTYPE ").await;
+  client.send("NOSUCHCOMMAND", &[]).await;
+  client.expect_error("ERR unknown command").await;
+  client.send("SHUTDOWN", &[]).await;
+  client.expect_error("NOPERM ").await;
+
+  client.send("SET", &["key", "value"]).await;
+  client.expect(ok()).await;
+  let Value::BulkBytes(payload) = client.call("DUMP", &["key"]).await else {
+    panic!("DUMP didn't return a payload");
+  };
+  client
+    .send_bytes(
+      "RESTORE",
+      vec![b"key".to_vec(), b"0".to_vec(), payload.clone()],
+    )
+    .await;
+  client.expect_error("BUSYKEY ").await;
+}
+
+#[tokio::test]
+async fn shutdown_is_root_only() {
+  let server = TestServer::new();
+
+  let mut client = server.connect_as(USER).await;
+  client.send("SHUTDOWN", &["NOSAVE"]).await;
+  client
+    .expect(error(
+      "NOPERM this user has no permissions to run the 'shutdown' command",
+    ))
+    .await;
+
+  let mut root = server.connect_as(ROOT).await;
+  root.send("SHUTDOWN", &["NOSAVE"]).await;
+  let mode = time::timeout(Duration::from_secs(5), server.shutdown.requested())
+    .await
+    .expect("SHUTDOWN didn't trigger a shutdown");
+  assert_eq!(mode, ShutdownMode::NoSave);
+}
+
+#[tokio::test]
+async fn client_ids_and_names() {
+  let server = TestServer::new();
+  let mut first = server.connect_as(USER).await;
+  let mut second = server.connect_as(USER).await;
+
+  let (Value::Integer(first_id), Value::Integer(second_id)) = (
+    first.call("CLIENT", &["ID"]).await,
+    second.call("CLIENT", &["ID"]).await,
+  ) else {
+    panic!("CLIENT ID didn't return integers");
+  };
+  assert!(second_id > first_id);
+
+  first.send("CLIENT", &["GETNAME"]).await;
+  first.expect(bulk("")).await;
+  first.send("CLIENT", &["SETNAME", "worker-1"]).await;
+  first.expect(ok()).await;
+  first.send("CLIENT", &["GETNAME"]).await;
+  first.expect(bulk("worker-1")).await;
+  first.send("CLIENT", &["SETNAME", "two words"]).await;
+  first
+    .expect_error("ERR Client names cannot contain spaces")
+    .await;
+}
+
+#[tokio::test]
+async fn client_kill_disconnects_the_target() {
+  let server = TestServer::new();
+  let mut killer = server.connect_as(USER).await;
+  let mut target = server.connect_as(USER).await;
+
+  let Value::Integer(id) = target.call("CLIENT", &["ID"]).await else {
+    panic!("CLIENT ID didn't return an integer");
+  };
+  let id = id.to_string();
+  killer.send("CLIENT", &["KILL", "ID", &id]).await;
+  killer.expect(int(1)).await;
+  target.expect_closed().await;
+
+  let Value::BulkString(list) = killer.call("CLIENT", &["LIST"]).await else {
+    panic!("CLIENT LIST didn't return a bulk string");
+  };
+  assert!(!list.contains(&format!("id={} ", id)), "{}", list);
+  killer.send("CLIENT", &["KILL", "ID", &id]).await;
+  killer.expect(int(0)).await;
+}
+
+#[tokio::test]
+async fn redis_cli_handshake() {
+  let server = TestServer::new();
+  let mut client = server.connect();
+
+  // redis-cli asks for the command docs first, and falls back to its own
+  // hints when refused
+  client.send("COMMAND", &["DOCS"]).await;
+  client.expect_error("NOAUTH ").await;
+
+  client.send("HELLO", &["4"]).await;
+  client
+    .expect(error("NOPROTO unsupported protocol version"))
+    .await;
+  client.send("HELLO", &["3"]).await;
+  client.expect_error("NOAUTH ").await;
+
+  client
+    .send(
+      "HELLO",
+      &["2", "AUTH", USER.0, USER.1, "SETNAME", "redis-cli"],
+    )
+    .await;
+  let Value::Array(fields) = client.read().await else {
+    panic!("HELLO didn't return an array");
+  };
+  assert_eq!(fields.len(), 14);
+  assert_eq!(fields[4].serialize(), bulk("proto").serialize());
+  assert_eq!(fields[5].serialize(), int(2).serialize());
+
+  let Value::BulkString(info) = client.call("CLIENT", &["INFO"]).await else {
+    panic!("CLIENT INFO didn't return a bulk string");
+  };
+  assert!(info.contains(" name=redis-cli "), "{}", info);
+  assert_eq!(info.lines().count(), 1);
+
+  client
+    .send("COMMAND", &["INFO", "get", "nosuchcommand"])
+    .await;
+  client
+    .expect(Value::Array(vec![
+      Value::Array(vec![
+        bulk("get"),
+        int(2),
+        Value::Array(vec![Value::SimpleString("readonly".to_string())]),
+        int(0),
+        int(0),
+        int(0),
+      ]),
+      Value::Null,
+    ]))
+    .await;
+
+  client.send("AUTH", &[USER.0, USER.1]).await;
+  client.expect(ok()).await;
+  client.send("SET", &["key", "value"]).await;
+  client.expect(ok()).await;
+  client.send("GET", &["key"]).await;
+  client.expect(bulk("value")).await;
+  client.send("COMMAND", &["DOCS", "get"]).await;
+  let Value::Array(docs) = client.read().await else {
+    panic!("COMMAND DOCS didn't return an array");
+  };
+  assert_eq!(docs[0].serialize(), bulk("get").serialize());
+  assert!(matches!(client.call("COMMAND", &[]).await, Value::Array(_)));
+}
+
+#[tokio::test]
+async fn quit_closes_the_connection() {
+  let server = TestServer::new();
+
+  // Allowed before AUTH, and commands pipelined after it are dropped
+  let mut client = server.connect();
+  client.send("LOLWUT", &[]).await;
+  client
+    .expect(error("NOAUTH Authentication required."))
+    .await;
+  client
+    .send_raw(b"*1\r\n$4\r\nQUIT\r\n*1\r\n$4\r\nPING\r\n")
+    .await;
+  client.expect(ok()).await;
+  client.expect_closed().await;
+
+  let mut client = server.connect_as(USER).await;
+  client.send("MULTI", &[]).await;
+  client.expect(ok()).await;
+  client.send("QUIT", &[]).await;
+  client.expect(ok()).await;
+  client.expect_closed().await;
+}
+
+#[tokio::test]
+async fn lolwut_names_the_build() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  let version = server.settings.read().unwrap().server.version.clone();
+  let Value::BulkString(banner) = client.call("LOLWUT", &[]).await else {
+    panic!("LOLWUT didn't return a bulk string");
+  };
+  assert!(
+    banner.contains(&format!(
+      "rusty-kv ver. {} (server version {})",
+      env!("CARGO_PKG_VERSION"),
+      version
+    )),
+    "{}",
+    banner
+  );
+  assert!(matches!(
+    client.call("LOLWUT", &["VERSION", "5"]).await,
+    Value::BulkString(_)
+  ));
+  client.send("LOLWUT", &["VERSION", "x"]).await;
+  client.expect_error("ERR value is not an integer").await;
+  client.send("LOLWUT", &["COLUMNS", "5"]).await;
+  client.expect_error("ERR syntax error").await;
+}
+
+#[tokio::test]
+async fn hello_3_switches_to_resp3() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  // RESP2 clients get verbatim strings as bulk strings
+  assert!(matches!(
+    client.call("LOLWUT", &[]).await,
+    Value::BulkString(_)
+  ));
+
+  let Value::Array(fields) = client.call("HELLO", &["3"]).await else {
+    panic!("HELLO didn't return an array");
+  };
+  assert_eq!(fields[5].serialize(), int(3).serialize());
+  let Value::VerbatimString(format, banner) = client.call("LOLWUT", &[]).await else {
+    panic!("LOLWUT didn't return a verbatim string");
+  };
+  assert_eq!(format, "txt");
+  assert!(banner.contains("rusty-kv ver. "), "{}", banner);
+  assert!(matches!(
+    client.call("INFO", &["server"]).await,
+    Value::VerbatimString(format, _) if format == "txt"
+  ));
+  // Without a version, HELLO reports the protocol in use
+  let Value::Array(fields) = client.call("HELLO", &[]).await else {
+    panic!("HELLO didn't return an array");
+  };
+  assert_eq!(fields[5].serialize(), int(3).serialize());
+
+  client.send("HELLO", &["2"]).await;
+  let _ = client.read().await;
+  assert!(matches!(
+    client.call("INFO", &["server"]).await,
+    Value::BulkString(_)
+  ));
+
+  // RESET goes back to RESP2
+  client.send("HELLO", &["3"]).await;
+  let _ = client.read().await;
+  client.send("RESET", &[]).await;
+  client
+    .expect(Value::SimpleString("RESET".to_string()))
+    .await;
+  client.send("AUTH", &[USER.0, USER.1]).await;
+  client.expect(ok()).await;
+  assert!(matches!(
+    client.call("LOLWUT", &[]).await,
+    Value::BulkString(_)
+  ));
+}
+
+#[tokio::test]
+async fn connections_start_authenticated_without_requireauth() {
+  let server = TestServer::with_settings(|settings| {
+    settings.server.network.requireauth = false;
+  });
+  let mut client = server.connect();
+
+  client.send("SET", &["key", "value"]).await;
+  client.expect(ok()).await;
+  let Value::Array(whoami) = client.call("WHOAMI", &[]).await else {
+    panic!("WHOAMI didn't return an array");
+  };
+  assert_eq!(whoami[0].serialize(), bulk(USER.0).serialize());
+  client.send("DELPATTERN", &["*"]).await;
+  client.expect_error("NOPERM ").await;
+
+  // RESET goes back to the default user rather than logging out
+  client.send("AUTH", &[ROOT.0, ROOT.1]).await;
+  client.expect(ok()).await;
+  client.send("RESET", &[]).await;
+  client
+    .expect(Value::SimpleString("RESET".to_string()))
+    .await;
+  client.send("GET", &["key"]).await;
+  client.expect(bulk("value")).await;
+
+  client.send("LOGOUT", &[]).await;
+  client.expect(ok()).await;
+  client.send("GET", &["key"]).await;
+  client.expect_error("NOAUTH ").await;
+
+  // Data written without AUTH belongs to the default user
+  let mut admin = server.connect_as(USER).await;
+  admin.send("GET", &["key"]).await;
+  admin.expect(bulk("value")).await;
+}
+
+#[tokio::test]
+async fn subcommands_are_case_insensitive() {
+  let server = TestServer::new();
+  let mut root = server.connect_as(ROOT).await;
+
+  for setname in ["SETNAME", "setname", "SetName"] {
+    root.send("CLIENT", &[setname, setname]).await;
+    root.expect(ok()).await;
+    root.send("client", &["getNAME"]).await;
+    root.expect(bulk(setname)).await;
+  }
+  for get in ["GET", "get", "Get"] {
+    root.send("CONFIG", &[get, "maxmemory"]).await;
+    root.expect(bulks(&["maxmemory", "0"])).await;
+  }
+  root.send("Config", &["set", "maxmemory", "1000"]).await;
+  root.expect(ok()).await;
+  root.send("config", &["Get", "maxmemory"]).await;
+  root.expect(bulks(&["maxmemory", "1000"])).await;
+
+  root.send("SET", &["key", "value"]).await;
+  root.expect(ok()).await;
+  root.send("object", &["Encoding", "key"]).await;
+  assert!(matches!(root.read().await, Value::BulkString(_)));
+  root.send("memory", &["usage", "key", "samples", "5"]).await;
+  assert!(matches!(root.read().await, Value::Integer(_)));
+  root.send("debug", &["object", "key"]).await;
+  assert!(matches!(root.read().await, Value::BulkString(_)));
+  root.send("slowlog", &["len"]).await;
+  assert!(matches!(root.read().await, Value::Integer(_)));
+  root.send("command", &["count"]).await;
+  assert!(matches!(root.read().await, Value::Integer(_)));
+
+  // Queued subcommands are normalized when EXEC runs them
+  root.send("MULTI", &[]).await;
+  root.expect(ok()).await;
+  root.send("client", &["getname"]).await;
+  root.expect(Value::SimpleString("QUEUED".to_string())).await;
+  root.send("EXEC", &[]).await;
+  root.expect(Value::Array(vec![bulk("SetName")])).await;
+
+  // Only the subcommand is upper-cased, not what follows it
+  root.send("CLIENT", &["setname", "lower"]).await;
+  root.expect(ok()).await;
+  root.send("CLIENT", &["GETNAME"]).await;
+  root.expect(bulk("lower")).await;
+
+  root.send("client", &["Bogus"]).await;
+  root.expect_error("ERR unknown subcommand 'bogus'").await;
+}
@@ -0,0 +1,102 @@
+//! Each connection works on the keyspace of the user it authenticated as,
+//! even though all connections share one memory store.
+
+mod common;
+
+use common::{ROOT, TestServer, USER, bulk, ok};
+use rusty_kv_store::{commands::executor::CommandExecutor, resp::value::Value};
+
+async fn run(executor: &mut CommandExecutor, command: &str, args: &[&str]) -> Value {
+  let args = args.iter().map(|arg| bulk(arg)).collect();
+  let mut replies = executor.execute(command, args).await.unwrap();
+  assert_eq!(replies.len(), 1);
+  replies.remove(0)
+}
+
+fn serialized(value: &Value) -> String {
+  String::from_utf8_lossy(&value.serialize()).into_owned()
+}
+
+#[tokio::test]
+async fn executors_sharing_a_store_keep_their_own_user() {
+  let server = TestServer::new();
+  let mut admin = server.executor();
+  let mut root = server.executor();
+
+  // Authenticate both before either writes, so a user shared through the
+  // store would be the last one to authenticate
+  for (executor, (username, password)) in [(&mut admin, USER), (&mut root, ROOT)] {
+    let reply = run(executor, "AUTH", &[username, password]).await;
+    assert_eq!(serialized(&reply), serialized(&ok()));
+  }
+
+  run(&mut admin, "SET", &["shared", "admin's"]).await;
+  run(&mut root, "SET", &["shared", "root's"]).await;
+
+  let reply = run(&mut admin, "GET", &["shared"]).await;
+  assert_eq!(serialized(&reply), serialized(&bulk("admin's")));
+  let reply = run(&mut root, "GET", &["shared"]).await;
+  assert_eq!(serialized(&reply), serialized(&bulk("root's")));
+  let Value::Array(whoami) = run(&mut admin, "WHOAMI", &[]).await else {
+    panic!("WHOAMI didn't return an array");
+  };
+  assert_eq!(serialized(&whoami[0]), serialized(&bulk(USER.0)));
+}
+
+#[tokio::test]
+async fn connections_see_only_their_users_keys() {
+  let server = TestServer::new();
+  let mut root = server.connect_as(ROOT).await;
+  root.send("USER", &["ADD", "alice", "alicepassword"]).await;
+  root.expect(ok()).await;
+
+  let mut admin = server.connect_as(USER).await;
+  let mut alice = server.connect_as(("alice", "alicepassword")).await;
+  let mut other_alice = server.connect_as(("alice", "alicepassword")).await;
+
+  admin.send("SET", &["key", "admin"]).await;
+  admin.expect(ok()).await;
+  alice.send("SET", &["key", "alice"]).await;
+  alice.expect(ok()).await;
+  root.send("SET", &["only-root", "v"]).await;
+  root.expect(ok()).await;
+
+  admin.send("GET", &["key"]).await;
+  admin.expect(bulk("admin")).await;
+  alice.send("GET", &["key"]).await;
+  alice.expect(bulk("alice")).await;
+  // Connections of the same user share its keys
+  other_alice.send("GET", &["key"]).await;
+  other_alice.expect(bulk("alice")).await;
+  alice.send("GET", &["only-root"]).await;
+  alice.expect_error("ERR Key only-root not found").await;
+
+  // Logging out of one connection leaves the others authenticated
+  admin.send("LOGOUT", &[]).await;
+  admin.expect(ok()).await;
+  alice.send("GET", &["key"]).await;
+  alice.expect(bulk("alice")).await;
+  admin.send("GET", &["key"]).await;
+  admin.expect_error("NOAUTH ").await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_users_never_see_each_other() {
+  let server = TestServer::new();
+  let mut tasks = Vec::new();
+  for user in [USER, ROOT] {
+    let mut client = server.connect_as(user).await;
+    tasks.push(tokio::spawn(async move {
+      for i in 0..200 {
+        let value = format!("{}:{}", user.0, i);
+        client.send("SET", &["contested", &value]).await;
+        client.expect(ok()).await;
+        client.send("GET", &["contested"]).await;
+        client.expect(bulk(&value)).await;
+      }
+    }));
+  }
+  for task in tasks {
+    task.await.unwrap();
+  }
+}
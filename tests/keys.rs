@@ -0,0 +1,246 @@
+//! Keyspace commands: DUMP/RESTORE, MIGRATE, DELPATTERN, DEBUG OBJECT and
+//! keyspace notifications.
+
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use common::{ROOT, TestServer, USER, bulk, bulks, int, ok};
+use rusty_kv_store::{resp::value::Value, storage::notify::KeyspaceEvents};
+use tokio::time;
+
+#[tokio::test]
+async fn dump_and_restore() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["key", "value"]).await;
+  client.expect(ok()).await;
+  let Value::BulkBytes(payload) = client.call("DUMP", &["key"]).await else {
+    panic!("DUMP didn't return a payload");
+  };
+  client
+    .send_bytes(
+      "RESTORE",
+      vec![b"copy".to_vec(), b"100000".to_vec(), payload.clone()],
+    )
+    .await;
+  client.expect(ok()).await;
+  client.send("GET", &["copy"]).await;
+  client.expect(bulk("value")).await;
+  let Value::Integer(expires_at) = client.call("EXPIRETIME", &["copy"]).await else {
+    panic!("EXPIRETIME didn't return an integer");
+  };
+  assert!(expires_at > 0);
+
+  client
+    .send_bytes(
+      "RESTORE",
+      vec![b"copy".to_vec(), b"0".to_vec(), payload.clone()],
+    )
+    .await;
+  client.expect_error("BUSYKEY ").await;
+  client
+    .send_bytes(
+      "RESTORE",
+      vec![
+        b"copy".to_vec(),
+        b"0".to_vec(),
+        payload.clone(),
+        b"REPLACE".to_vec(),
+      ],
+    )
+    .await;
+  client.expect(ok()).await;
+
+  // A damaged payload is refused
+  let mut damaged = payload;
+  damaged[2] ^= 0xff;
+  client
+    .send_bytes("RESTORE", vec![b"broken".to_vec(), b"0".to_vec(), damaged])
+    .await;
+  client.expect_error("ERR ").await;
+
+  client.send("DUMP", &["missing"]).await;
+  client.expect(Value::Null).await;
+}
+
+#[tokio::test]
+async fn migrate_to_another_instance() {
+  let source = TestServer::new();
+  let target = TestServer::new();
+  let port = target.listen().await.to_string();
+  let mut client = source.connect_as(ROOT).await;
+
+  client.send("SET", &["moved", "1"]).await;
+  client.expect(ok()).await;
+  client.send("SET", &["copied", "2"]).await;
+  client.expect(ok()).await;
+
+  let auth = ["AUTH2", USER.0, USER.1];
+  let migrate = |key: &'static str, copy: bool| {
+    let mut args = vec!["127.0.0.1", port.as_str(), key, "0", "5000"];
+    if copy {
+      args.push("COPY");
+    }
+    args.extend(auth);
+    args
+  };
+  client.send("MIGRATE", &migrate("moved", false)).await;
+  client.expect(ok()).await;
+  client.send("MIGRATE", &migrate("copied", true)).await;
+  client.expect(ok()).await;
+  client.send("MIGRATE", &migrate("missing", false)).await;
+  client
+    .expect(Value::SimpleString("NOKEY".to_string()))
+    .await;
+
+  // It connects wherever it is told to, so only root may run it
+  let mut user = source.connect_as(USER).await;
+  user.send("MIGRATE", &migrate("copied", true)).await;
+  user.expect_error("NOPERM ").await;
+
+  client.send("EXPIRETIME", &["moved"]).await;
+  client.expect(int(-2)).await;
+  client.send("GET", &["copied"]).await;
+  client.expect(bulk("2")).await;
+
+  let mut remote = target.connect_as(USER).await;
+  remote.send("GET", &["moved"]).await;
+  remote.expect(bulk("1")).await;
+  remote.send("GET", &["copied"]).await;
+  remote.expect(bulk("2")).await;
+}
+
+#[tokio::test]
+async fn migrate_to_an_unreachable_instance_keeps_the_key() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(ROOT).await;
+
+  // Bind and close a port, so nothing listens on it
+  let port = tokio::net::TcpListener::bind("127.0.0.1:0")
+    .await
+    .unwrap()
+    .local_addr()
+    .unwrap()
+    .port()
+    .to_string();
+  client.send("SET", &["key", "v"]).await;
+  client.expect(ok()).await;
+  client
+    .send("MIGRATE", &["127.0.0.1", &port, "key", "0", "1000"])
+    .await;
+  client.expect_error("ERR IOERR ").await;
+  client.send("GET", &["key"]).await;
+  client.expect(bulk("v")).await;
+}
+
+#[tokio::test]
+async fn delpattern_deletes_only_matching_keys() {
+  let server = TestServer::new();
+  let mut root = server.connect_as(ROOT).await;
+
+  // Enough keys for several batches, collections included
+  for i in 1..=2500 {
+    root.send("SET", &[&format!("user:{}", i), "v"]).await;
+    root.expect(ok()).await;
+  }
+  root.send("RPUSH", &["user:list", "v"]).await;
+  root.expect(int(1)).await;
+  for i in 1..=10 {
+    root.send("SET", &[&format!("other:{}", i), "v"]).await;
+    root.expect(ok()).await;
+  }
+  root.send("DELPATTERN", &["user:*"]).await;
+  root.expect(int(2501)).await;
+  for key in ["user:42", "user:2500", "user:list"] {
+    root.send("EXPIRETIME", &[key]).await;
+    root.expect(int(-2)).await;
+  }
+  root.send("GET", &["other:3"]).await;
+  root.expect(bulk("v")).await;
+
+  let mut client = server.connect_as(USER).await;
+  client.send("DELPATTERN", &["*"]).await;
+  client.expect_error("NOPERM ").await;
+}
+
+#[tokio::test]
+async fn debug_object() {
+  let server = TestServer::new();
+  let mut root = server.connect_as(ROOT).await;
+
+  root.send("SET", &["string", "hello", "EX", "100"]).await;
+  root.expect(ok()).await;
+  let Value::BulkString(details) = root.call("DEBUG", &["OBJECT", "string"]).await else {
+    panic!("DEBUG OBJECT didn't return a bulk string");
+  };
+  assert!(
+    details
+      .starts_with("entity:HashMap value:BulkString serializedlength:11 expires:1 inserted_at:"),
+    "{}",
+    details
+  );
+
+  // Reading the key doesn't move its insertion time
+  time::sleep(Duration::from_millis(10)).await;
+  root.send("GET", &["string"]).await;
+  root.expect(bulk("hello")).await;
+  root.send("DEBUG", &["OBJECT", "string"]).await;
+  root.expect(bulk(&details)).await;
+
+  root.send("RPUSH", &["list", "a", "b"]).await;
+  root.expect(int(2)).await;
+  let Value::BulkString(details) = root.call("DEBUG", &["OBJECT", "list"]).await else {
+    panic!("DEBUG OBJECT didn't return a bulk string");
+  };
+  let inserted_at = details
+    .strip_prefix("entity:LinkedList value:- serializedlength:18 expires:0 inserted_at:")
+    .and_then(|millis| millis.parse::<u128>().ok())
+    .unwrap_or_else(|| panic!("{}", details));
+  let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+  assert!(inserted_at <= now.as_millis());
+  root.send("RPUSH", &["list", "c"]).await;
+  root.expect(int(3)).await;
+  let Value::BulkString(details) = root.call("DEBUG", &["OBJECT", "list"]).await else {
+    panic!("DEBUG OBJECT didn't return a bulk string");
+  };
+  assert!(
+    details.ends_with(&format!("inserted_at:{}", inserted_at)),
+    "{}",
+    details
+  );
+
+  root.send("DEBUG", &["OBJECT", "missing"]).await;
+  root.expect_error("ERR no such key").await;
+
+  let mut client = server.connect_as(USER).await;
+  client.send("DEBUG", &["OBJECT", "string"]).await;
+  client.expect_error("NOPERM ").await;
+}
+
+#[tokio::test]
+async fn keyspace_notifications() {
+  let server = TestServer::with_settings(|settings| {
+    settings.server.notify.keyspace_events = KeyspaceEvents::parse("KEA").unwrap();
+  });
+  let mut subscriber = server.connect_as(USER).await;
+  let mut client = server.connect_as(USER).await;
+
+  subscriber.send("SUBSCRIBE", &["__keyevent@0__:del"]).await;
+  subscriber
+    .expect(Value::Array(vec![
+      bulk("subscribe"),
+      bulk("__keyevent@0__:del"),
+      int(1),
+    ]))
+    .await;
+
+  client.send("SET", &["doomed", "v"]).await;
+  client.expect(ok()).await;
+  client.send("DEL", &["doomed"]).await;
+  client.expect(int(1)).await;
+  subscriber
+    .expect(bulks(&["message", "__keyevent@0__:del", "doomed"]))
+    .await;
+}
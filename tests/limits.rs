@@ -0,0 +1,254 @@
+//! Per-user quotas, maxmemory eviction and concurrent access to the store.
+
+mod common;
+
+use std::time::Duration;
+
+use common::{ROOT, TestServer, USER, bulk, int, ok};
+use rusty_kv_store::{resp::value::Value, storage::eviction::EvictionPolicy};
+use tokio::time;
+
+/// Size a key holding a 100 byte value is accounted for.
+async fn entry_size() -> usize {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+  client.send("SET", &["k0", &"v".repeat(100)]).await;
+  client.expect(ok()).await;
+  match client.call("MEMORY", &["USAGE", "k0"]).await {
+    Value::Integer(size) => size as usize,
+    other => panic!("MEMORY USAGE returned {:?}", other),
+  }
+}
+
+/// Opens a connection to a server with room for three 100 byte values.
+async fn limited(policy: EvictionPolicy) -> (TestServer, common::TestClient) {
+  let size = entry_size().await;
+  let server = TestServer::with_settings(|settings| {
+    settings.server.network.maxmemory = size * 3 + size / 2;
+    settings.server.network.maxmemory_policy = policy;
+  });
+  let mut client = server.connect_as(USER).await;
+  for key in ["k1", "k2", "k3"] {
+    client.send("SET", &[key, &"v".repeat(100)]).await;
+    client.expect(ok()).await;
+  }
+  (server, client)
+}
+
+#[tokio::test]
+async fn key_quota() {
+  let server = TestServer::with_settings(|settings| {
+    settings.server.network.max_keys_per_user = 2;
+  });
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["a", "1"]).await;
+  client.expect(ok()).await;
+  client.send("SET", &["b", "2"]).await;
+  client.expect(ok()).await;
+  client.send("SET", &["c", "3"]).await;
+  client
+    .expect(Value::Error("OOM user quota exceeded".to_string()))
+    .await;
+  client.send("SET", &["a", "overwritten"]).await;
+  client.expect(ok()).await;
+
+  let Value::BulkString(info) = client.call("INFO", &["memory"]).await else {
+    panic!("INFO didn't return a bulk string");
+  };
+  assert!(info.contains("user_keys:2\r\n"), "{}", info);
+
+  client.send("DEL", &["a"]).await;
+  client.expect(int(1)).await;
+  client.send("SET", &["c", "3"]).await;
+  client.expect(ok()).await;
+
+  // Other users have their own quota
+  let mut root = server.connect_as(ROOT).await;
+  root.send("SET", &["a", "1"]).await;
+  root.expect(ok()).await;
+}
+
+#[tokio::test]
+async fn byte_quota() {
+  let server = TestServer::with_settings(|settings| {
+    settings.server.network.max_bytes_per_user = 200;
+  });
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["big", &"v".repeat(300)]).await;
+  client.expect_error("OOM ").await;
+  client.send("SET", &["small", "v"]).await;
+  client.expect(ok()).await;
+  client.send("SETRANGE", &["small", "250", "v"]).await;
+  client.expect_error("OOM ").await;
+  client.send("GET", &["small"]).await;
+  client.expect(bulk("v")).await;
+}
+
+#[tokio::test]
+async fn collections_count_against_the_quota() {
+  let server = TestServer::with_settings(|settings| {
+    settings.server.network.max_bytes_per_user = 1000;
+  });
+  let mut client = server.connect_as(USER).await;
+
+  // Fill the quota one list value at a time
+  let value = "v".repeat(100);
+  let mut len = 0;
+  loop {
+    match client.call("RPUSH", &["list", &value]).await {
+      Value::Integer(pushed) => len = pushed,
+      Value::Error(e) => {
+        assert_eq!(e, "OOM user quota exceeded");
+        break;
+      }
+      other => panic!("RPUSH returned {:?}", other),
+    }
+    assert!(len < 10, "the list outgrew the quota");
+  }
+  client.send("LLEN", &["list"]).await;
+  client.expect(int(len)).await;
+
+  client.send("SADD", &["set", &value]).await;
+  client.expect_error("OOM ").await;
+  client.send("HSET", &["hash", "field", &value]).await;
+  client.expect_error("OOM ").await;
+  client.send("ZADD", &["zset", "1", &value]).await;
+  client.expect_error("OOM ").await;
+
+  // Popping a value makes room again
+  client.send("LPOP", &["list"]).await;
+  client.expect(bulk(&value)).await;
+  client.send("SADD", &["set", &value]).await;
+  client.expect(int(1)).await;
+
+  let Value::BulkString(info) = client.call("INFO", &["memory"]).await else {
+    panic!("INFO didn't return a bulk string");
+  };
+  assert!(info.contains("user_keys:2\r\n"), "{}", info);
+}
+
+#[tokio::test]
+async fn noeviction_refuses_writes() {
+  let (_server, mut client) = limited(EvictionPolicy::NoEviction).await;
+
+  client.send("SET", &["k4", &"v".repeat(100)]).await;
+  client
+    .expect(Value::Error(
+      "OOM command not allowed when used memory > 'maxmemory'".to_string(),
+    ))
+    .await;
+  client.send("DEL", &["k1"]).await;
+  client.expect(int(1)).await;
+  client.send("SET", &["k4", &"v".repeat(100)]).await;
+  client.expect(ok()).await;
+}
+
+#[tokio::test]
+async fn allkeys_lru_evicts_the_least_recently_used_key() {
+  let (_server, mut client) = limited(EvictionPolicy::AllKeysLru).await;
+
+  // Reading k1 makes k2 the least recently used
+  time::sleep(Duration::from_millis(10)).await;
+  client.send("GET", &["k1"]).await;
+  let _ = client.read().await;
+  client.send("SET", &["k4", &"v".repeat(100)]).await;
+  client.expect(ok()).await;
+
+  client.send("EXPIRETIME", &["k2"]).await;
+  client.expect(int(-2)).await;
+  for key in ["k1", "k3", "k4"] {
+    client.send("EXPIRETIME", &[key]).await;
+    client.expect(int(-1)).await;
+  }
+}
+
+#[tokio::test]
+async fn allkeys_lru_evicts_collections() {
+  let (_server, mut client) = limited(EvictionPolicy::AllKeysLru).await;
+
+  client.send("DEL", &["k1"]).await;
+  client.expect(int(1)).await;
+  client.send("RPUSH", &["list", &"v".repeat(100)]).await;
+  client.expect(int(1)).await;
+
+  // Reading the strings leaves the list least recently used
+  time::sleep(Duration::from_millis(10)).await;
+  for key in ["k2", "k3"] {
+    client.send("GET", &[key]).await;
+    let _ = client.read().await;
+  }
+  client.send("SET", &["k4", &"v".repeat(100)]).await;
+  client.expect(ok()).await;
+
+  client.send("EXPIRETIME", &["list"]).await;
+  client.expect(int(-2)).await;
+  for key in ["k2", "k3", "k4"] {
+    client.send("EXPIRETIME", &[key]).await;
+    client.expect(int(-1)).await;
+  }
+}
+
+#[tokio::test]
+async fn volatile_ttl_evicts_the_key_closest_to_expiring() {
+  let (_server, mut client) = limited(EvictionPolicy::VolatileTtl).await;
+
+  client.send("GETEX", &["k1", "EX", "1000"]).await;
+  let _ = client.read().await;
+  client.send("GETEX", &["k2", "EX", "10"]).await;
+  let _ = client.read().await;
+  let expires_at = client.call("EXPIRETIME", &["k1"]).await.serialize();
+
+  // Reading a key keeps its expiry
+  client.send("GET", &["k1"]).await;
+  let _ = client.read().await;
+  assert_eq!(
+    client.call("EXPIRETIME", &["k1"]).await.serialize(),
+    expires_at
+  );
+
+  client.send("SET", &["k4", &"v".repeat(100)]).await;
+  client.expect(ok()).await;
+  client.send("EXPIRETIME", &["k2"]).await;
+  client.expect(int(-2)).await;
+  client.send("EXPIRETIME", &["k3"]).await;
+  client.expect(int(-1)).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_access_does_not_deadlock() {
+  let server = TestServer::new();
+  let mut tasks = Vec::new();
+  for task in 0..8 {
+    let user = if task % 2 == 0 { USER } else { ROOT };
+    let mut client = server.connect_as(user).await;
+    let store = server.store.clone();
+    tasks.push(tokio::spawn(async move {
+      for i in 0..100 {
+        let key = format!("key:{}:{}", task, i % 10);
+        let db = (i % 3).to_string();
+        client.send("SELECT", &[&db]).await;
+        client.expect(ok()).await;
+        client.send("SET", &[&key, "value"]).await;
+        client.expect(ok()).await;
+        client.send("GET", &[&key]).await;
+        client.expect(bulk("value")).await;
+        client.send("DEL", &[&key]).await;
+        client.expect(int(1)).await;
+        store.rekey_user("no-such-credential-hash", "no-such-user");
+      }
+    }));
+  }
+
+  time::timeout(Duration::from_secs(30), async {
+    for task in tasks {
+      task.await.unwrap();
+    }
+  })
+  .await
+  .expect("Clients deadlocked");
+
+  let (_, keys, bytes) = server.store.usage();
+  assert_eq!((keys, bytes), (0, 0));
+}
@@ -0,0 +1,46 @@
+//! The Prometheus metrics endpoint.
+
+mod common;
+
+use common::{TestServer, USER};
+use rusty_kv_store::utils::metrics;
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::TcpStream,
+};
+
+/// Sends a GET request for `path` and returns the whole response.
+async fn get(port: u16, path: &str) -> String {
+  let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+  let request = format!(
+    "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    path
+  );
+  stream.write_all(request.as_bytes()).await.unwrap();
+  let mut response = String::new();
+  stream.read_to_string(&mut response).await.unwrap();
+  response
+}
+
+#[tokio::test]
+async fn metrics_endpoint() {
+  let server = TestServer::new();
+  let listener = metrics::bind("127.0.0.1", 0).await.unwrap();
+  let port = listener.local_addr().unwrap().port();
+  tokio::spawn(metrics::serve(listener, server.store.clone()));
+
+  let mut client = server.connect_as(USER).await;
+  client.send("PING", &[]).await;
+  let _ = client.read().await;
+
+  let response = get(port, "/metrics").await;
+  assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+  assert!(
+    response.contains("rustykv_commands_total{command=\"ping\"} "),
+    "{}",
+    response
+  );
+
+  let response = get(port, "/other").await;
+  assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+}
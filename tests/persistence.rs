@@ -0,0 +1,269 @@
+//! KDB snapshots: saving, loading back and the save points.
+
+mod common;
+
+use std::{
+  fs,
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use common::{ROOT, TestClient, TestServer, USER, bulk, bulks, int, ok};
+use rusty_kv_store::{resp::value::Value, storage::kdb};
+
+/// Writes one key of every type, in databases 0 and 1.
+async fn populate(client: &mut TestClient) {
+  client.send("SET", &["string", "value", "EX", "1000"]).await;
+  client.expect(ok()).await;
+  client.send("RPUSH", &["list", "a", "b", "c"]).await;
+  client.expect(int(3)).await;
+  client.send("SADD", &["set", "x", "y"]).await;
+  client.expect(int(2)).await;
+  client.send("HSET", &["hash", "field", "1"]).await;
+  client.expect(int(1)).await;
+  client
+    .send("ZADD", &["zset", "1", "one", "2.5", "two"])
+    .await;
+  client.expect(int(2)).await;
+  client.send("SELECT", &["1"]).await;
+  client.expect(ok()).await;
+  client.send("SET", &["other", "db"]).await;
+  client.expect(ok()).await;
+  client.send("SELECT", &["0"]).await;
+  client.expect(ok()).await;
+}
+
+fn info_field(info: &Value, field: &str) -> String {
+  let Value::BulkString(info) = info else {
+    panic!("INFO didn't return a bulk string");
+  };
+  info
+    .lines()
+    .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))
+    .unwrap_or_else(|| panic!("INFO has no {}", field))
+    .to_string()
+}
+
+#[tokio::test]
+async fn snapshot_round_trip() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+  populate(&mut client).await;
+  let expires_at = client.call("EXPIRETIME", &["string"]).await.serialize();
+
+  kdb::save(&server.store, &server.settings.read().unwrap()).unwrap();
+  client
+    .send("DEL", &["string", "list", "set", "hash", "zset"])
+    .await;
+  client.expect(int(5)).await;
+
+  let loaded = kdb::load(&server.store, &server.settings.read().unwrap()).unwrap();
+  assert!(loaded >= 6, "loaded {} records", loaded);
+
+  let mut client = server.connect_as(USER).await;
+  client.send("GET", &["string"]).await;
+  client.expect(bulk("value")).await;
+  assert_eq!(
+    client.call("EXPIRETIME", &["string"]).await.serialize(),
+    expires_at
+  );
+  client.send("LRANGE", &["list", "0", "-1"]).await;
+  client.expect(bulks(&["a", "b", "c"])).await;
+  client.send("SCARD", &["set"]).await;
+  client.expect(int(2)).await;
+  client.send("SISMEMBER", &["set", "y"]).await;
+  client.expect(int(1)).await;
+  client.send("HGET", &["hash", "field"]).await;
+  client.expect(bulk("1")).await;
+  client
+    .send("ZRANGE", &["zset", "0", "-1", "WITHSCORES"])
+    .await;
+  client
+    .expect(Value::Array(vec![
+      bulk("one"),
+      bulk("1"),
+      bulk("two"),
+      bulk("2.5"),
+    ]))
+    .await;
+  client.send("SELECT", &["1"]).await;
+  client.expect(ok()).await;
+  client.send("GET", &["other"]).await;
+  client.expect(bulk("db")).await;
+}
+
+#[tokio::test]
+async fn expiries_keep_running_while_saved() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+  client
+    .send("SET", &["lasting", "value", "PX", "3000"])
+    .await;
+  client.expect(ok()).await;
+  client.send("SET", &["brief", "value", "PX", "300"]).await;
+  client.expect(ok()).await;
+  let Value::Integer(expires_at) = client.call("PEXPIRETIME", &["lasting"]).await else {
+    panic!("PEXPIRETIME didn't return an integer");
+  };
+  kdb::save(&server.store, &server.settings.read().unwrap()).unwrap();
+
+  // Loaded back later, as after a restart, once the brief key expired
+  tokio::time::sleep(Duration::from_millis(1000)).await;
+  client.send("DEL", &["lasting", "brief"]).await;
+  client.expect(int(1)).await;
+  let loaded = kdb::load(&server.store, &server.settings.read().unwrap()).unwrap();
+  assert_eq!(loaded, 1, "the expired key is dropped while loading");
+
+  // The time spent saved counts against the TTL
+  client.send("PEXPIRETIME", &["lasting"]).await;
+  client.expect(int(expires_at)).await;
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap()
+    .as_millis() as i64;
+  assert!(expires_at - now <= 2000, "{} ms left", expires_at - now);
+  client.send("GET", &["lasting"]).await;
+  client.expect(bulk("value")).await;
+  client.send("PEXPIRETIME", &["brief"]).await;
+  client.expect(int(-2)).await;
+}
+
+#[tokio::test]
+async fn damaged_snapshot_leaves_the_store_untouched() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+  populate(&mut client).await;
+  kdb::save(&server.store, &server.settings.read().unwrap()).unwrap();
+
+  let path = kdb::file_path(&server.settings.read().unwrap());
+  let mut data = fs::read(&path).unwrap();
+  data.truncate(data.len() / 2);
+  fs::write(&path, data).unwrap();
+  client.send("DEL", &["list"]).await;
+  client.expect(int(1)).await;
+
+  assert!(kdb::load(&server.store, &server.settings.read().unwrap()).is_err());
+  client.send("LRANGE", &["list", "0", "-1"]).await;
+  client.expect(Value::Array(vec![])).await;
+  client.send("GET", &["string"]).await;
+  client.expect(bulk("value")).await;
+}
+
+#[tokio::test]
+async fn missing_snapshot_loads_nothing() {
+  let server = TestServer::new();
+  assert_eq!(
+    kdb::load(&server.store, &server.settings.read().unwrap()).unwrap(),
+    0
+  );
+}
+
+#[tokio::test]
+async fn save_updates_lastsave() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+  let started = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  client.send("SET", &["a", "1"]).await;
+  client.expect(ok()).await;
+  // Saving writes every user's data, so it is up to root
+  client.send("SAVE", &[]).await;
+  client.expect_error("NOPERM ").await;
+  client.send("BGSAVE", &[]).await;
+  client.expect_error("NOPERM ").await;
+  let mut root = server.connect_as(ROOT).await;
+  root.send("SAVE", &[]).await;
+  root.expect(ok()).await;
+  let Value::Integer(last_save) = client.call("LASTSAVE", &[]).await else {
+    panic!("LASTSAVE didn't return an integer");
+  };
+  assert!(last_save >= started);
+  assert!(kdb::file_path(&server.settings.read().unwrap()).exists());
+
+  // Every store keeps track of its own saves
+  let other = TestServer::new();
+  let mut client = other.connect_as(USER).await;
+  client.send("LASTSAVE", &[]).await;
+  client.expect(int(0)).await;
+}
+
+#[tokio::test]
+async fn save_resets_the_change_counter() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["a", "1"]).await;
+  client.expect(ok()).await;
+  client.send("SET", &["b", "2"]).await;
+  client.expect(ok()).await;
+  // Reads aren't changes
+  client.send("GET", &["a"]).await;
+  client.expect(bulk("1")).await;
+  assert_eq!(server.store.changes_since_last_save(), 2);
+  let info = client.call("INFO", &["persistence"]).await;
+  assert_eq!(info_field(&info, "rdb_changes_since_last_save"), "2");
+
+  let mut root = server.connect_as(ROOT).await;
+  root.send("SAVE", &[]).await;
+  root.expect(ok()).await;
+  assert_eq!(server.store.changes_since_last_save(), 0);
+  let info = client.call("INFO", &["persistence"]).await;
+  assert_eq!(info_field(&info, "rdb_changes_since_last_save"), "0");
+
+  // Changes made while a save runs stay counted
+  let saving = server.store.changes_since_last_save();
+  client.send("SET", &["c", "3"]).await;
+  client.expect(ok()).await;
+  server.store.mark_saved(saving);
+  assert_eq!(server.store.changes_since_last_save(), 1);
+}
+
+#[test]
+fn save_points() {
+  let points = [(900, 1), (300, 10), (60, 10_000)];
+
+  assert_eq!(kdb::save_point_reached(&points, 0, 10_000), None);
+  assert_eq!(kdb::save_point_reached(&points, 5, 100), None);
+  assert_eq!(kdb::save_point_reached(&points, 5, 900), Some((900, 1)));
+  assert_eq!(kdb::save_point_reached(&points, 10, 300), Some((300, 10)));
+  assert_eq!(
+    kdb::save_point_reached(&points, 10_000, 60),
+    Some((60, 10_000))
+  );
+  assert_eq!(kdb::save_point_reached(&[], 10_000, 10_000), None);
+  // A save point never fires without changes
+  assert_eq!(kdb::save_point_reached(&[(1, 0)], 0, 10), None);
+  assert_eq!(kdb::save_point_reached(&[(1, 0)], 1, 10), Some((1, 0)));
+}
+
+#[tokio::test]
+async fn save_points_start_a_background_save() {
+  let server = TestServer::with_settings(|settings| {
+    settings.server.kdb.save_points = vec![(1, 1)];
+  });
+  let settings = server.settings.read().unwrap().clone();
+  let path = kdb::file_path(&settings);
+  let saver = kdb::spawn_save_points(server.store.clone(), settings);
+  let mut client = server.connect_as(USER).await;
+  let started = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  client.send("SET", &["a", "1"]).await;
+  client.expect(ok()).await;
+  let deadline = Instant::now() + Duration::from_secs(10);
+  while server.store.changes_since_last_save() > 0 {
+    assert!(Instant::now() < deadline, "the save point never saved");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+  }
+  saver.abort();
+
+  assert!(path.exists());
+  let Value::Integer(last_save) = client.call("LASTSAVE", &[]).await else {
+    panic!("LASTSAVE didn't return an integer");
+  };
+  assert!(last_save >= started);
+}
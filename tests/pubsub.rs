@@ -0,0 +1,64 @@
+//! Publish/subscribe, over RESP2 and RESP3 connections.
+
+mod common;
+
+use common::{TestClient, TestServer, USER, bulk, int};
+use rusty_kv_store::resp::value::Value;
+
+fn serialized(value: &Value) -> String {
+  String::from_utf8_lossy(&value.serialize()).into_owned()
+}
+
+async fn subscriber(server: &TestServer, protocol: &str) -> TestClient {
+  let mut client = server.connect_as(USER).await;
+  client.send("HELLO", &[protocol]).await;
+  let _ = client.read().await;
+  client
+}
+
+#[tokio::test]
+async fn resp3_subscribers_receive_pushes() {
+  let server = TestServer::new();
+  let mut resp2 = subscriber(&server, "2").await;
+  let mut resp3 = subscriber(&server, "3").await;
+  let mut publisher = server.connect_as(USER).await;
+
+  resp2.send("SUBSCRIBE", &["news"]).await;
+  resp3.send("SUBSCRIBE", &["news"]).await;
+  let confirmation = [bulk("subscribe"), bulk("news"), int(1)];
+  assert_eq!(
+    serialized(&resp2.read().await),
+    serialized(&Value::Array(confirmation.to_vec()))
+  );
+  assert_eq!(
+    serialized(&resp3.read().await),
+    serialized(&Value::Push(confirmation.to_vec()))
+  );
+
+  publisher.send("PUBLISH", &["news", "hello"]).await;
+  publisher.expect(int(2)).await;
+  let message = [bulk("message"), bulk("news"), bulk("hello")];
+  let received = serialized(&resp2.read().await);
+  assert!(received.starts_with("*3\r\n"), "{:?}", received);
+  assert_eq!(received, serialized(&Value::Array(message.to_vec())));
+  let received = serialized(&resp3.read().await);
+  assert!(received.starts_with(">3\r\n"), "{:?}", received);
+  assert_eq!(received, serialized(&Value::Push(message.to_vec())));
+
+  resp2.send("UNSUBSCRIBE", &[]).await;
+  resp2
+    .expect(Value::Array(vec![
+      bulk("unsubscribe"),
+      bulk("news"),
+      int(0),
+    ]))
+    .await;
+  resp3.send("UNSUBSCRIBE", &[]).await;
+  resp3
+    .expect(Value::Push(vec![bulk("unsubscribe"), bulk("news"), int(0)]))
+    .await;
+  resp3.send("UNSUBSCRIBE", &[]).await;
+  resp3
+    .expect(Value::Push(vec![bulk("unsubscribe"), Value::Null, int(0)]))
+    .await;
+}
@@ -0,0 +1,467 @@
+//! The RESP parser, command decoding and the DUMP serialization format.
+
+use std::time::Duration;
+
+use rusty_kv_store::resp::{
+  handler::RespHandler,
+  parser::{ParserLimits, RespParser},
+  serde,
+  value::Value,
+};
+use tokio::{io::AsyncReadExt, time};
+
+fn parser() -> RespParser {
+  RespParser::new(ParserLimits::default())
+}
+
+/// Parses a complete message, checking all of `input` is consumed.
+fn parse(input: &[u8]) -> Value {
+  let (value, consumed) = parser()
+    .parse_message(input)
+    .expect("Failed to parse")
+    .expect("Incomplete message");
+  assert_eq!(consumed, input.len());
+  value
+}
+
+fn bulk(s: &str) -> Value {
+  Value::BulkString(s.to_string())
+}
+
+fn serialized(value: &Value) -> String {
+  String::from_utf8_lossy(&value.serialize()).into_owned()
+}
+
+/// `depth` arrays nested in one another around an integer.
+fn nested(depth: usize) -> Value {
+  (0..depth).fold(Value::Integer(1), |value, _| Value::Array(vec![value]))
+}
+
+fn every_variant() -> Value {
+  Value::Array(vec![
+    Value::Null,
+    Value::SimpleString("OK".to_string()),
+    Value::BulkString("hello".to_string()),
+    Value::BulkBytes(vec![0xff, 0x00, 0xfe]),
+    Value::Array(vec![]),
+    Value::Error("ERR oops".to_string()),
+    Value::Integer(-42),
+    Value::Boolean(true),
+    Value::Double(2.5),
+    Value::VerbatimString("txt".to_string(), "some text".to_string()),
+    Value::BigNumber("-3492890328409238509324850943850943825024385".to_string()),
+    Value::Push(vec![
+      Value::BulkString("message".to_string()),
+      Value::Push(vec![]),
+    ]),
+  ])
+}
+
+#[test]
+fn serialize_to_writes_what_serialize_returns() {
+  let cases: [(Value, &[u8]); 16] = [
+    (Value::Null, b"$-1\r\n"),
+    (Value::SimpleString("OK".to_string()), b"+OK\r\n"),
+    (Value::BulkString("hello".to_string()), b"$5\r\nhello\r\n"),
+    (Value::BulkString(String::new()), b"$0\r\n\r\n"),
+    (Value::BulkBytes(vec![0xff, 0x00]), b"$2\r\n\xff\x00\r\n"),
+    (Value::Error("ERR oops".to_string()), b"-ERR oops\r\n"),
+    (Value::Integer(-42), b":-42\r\n"),
+    (Value::Boolean(false), b"#f\r\n"),
+    (Value::Double(3.0), b",3\r\n"),
+    (Value::Double(f64::NEG_INFINITY), b",-inf\r\n"),
+    (Value::Double(f64::NAN), b",nan\r\n"),
+    (
+      Value::VerbatimString("txt".to_string(), "Some string".to_string()),
+      b"=15\r\ntxt:Some string\r\n",
+    ),
+    (
+      Value::VerbatimString("mkd".to_string(), String::new()),
+      b"=4\r\nmkd:\r\n",
+    ),
+    (
+      Value::BigNumber("12345678901234567890".to_string()),
+      b"(12345678901234567890\r\n",
+    ),
+    (
+      Value::Push(vec![Value::Integer(1), Value::Array(vec![])]),
+      b">2\r\n:1\r\n*0\r\n",
+    ),
+    (
+      Value::Array(vec![
+        Value::Array(vec![Value::Integer(1)]),
+        Value::Array(vec![]),
+      ]),
+      b"*2\r\n*1\r\n:1\r\n*0\r\n",
+    ),
+  ];
+  for (value, expected) in &cases {
+    let mut written = Vec::new();
+    value.serialize_to(&mut written).unwrap();
+    assert_eq!(written, *expected, "{:?}", value);
+    assert_eq!(value.serialize(), written);
+    assert_eq!(value.serialized_len(), written.len());
+  }
+
+  let nested = Value::Array(vec![every_variant(), large_array()]);
+  let mut written = Vec::new();
+  nested.serialize_to(&mut written).unwrap();
+  assert_eq!(nested.serialize(), written);
+  assert_eq!(nested.serialized_len(), written.len());
+
+  // Errors of the writer are passed on
+  let mut full = [0u8; 8];
+  let value = Value::BulkString("too long for the buffer".to_string());
+  assert!(value.serialize_to(&mut &mut full[..]).is_err());
+}
+
+#[test]
+fn parses_every_type() {
+  let input = b"*7\r\n+OK\r\n-ERR oops\r\n:-42\r\n$5\r\nhello\r\n$-1\r\n#t\r\n,2.5\r\n";
+  assert_eq!(
+    serialized(&parse(input)),
+    serialized(&Value::Array(vec![
+      Value::SimpleString("OK".to_string()),
+      Value::Error("ERR oops".to_string()),
+      Value::Integer(-42),
+      Value::BulkString("hello".to_string()),
+      Value::Null,
+      Value::Boolean(true),
+      Value::Double(2.5),
+    ]))
+  );
+
+  // Invalid UTF-8 is kept as bytes
+  assert!(matches!(
+    parse(b"$2\r\n\xff\xfe\r\n"),
+    Value::BulkBytes(bytes) if bytes == b"\xff\xfe"
+  ));
+  assert!(matches!(parse(b"*-1\r\n"), Value::Null));
+  assert!(matches!(parse(b"*0\r\n"), Value::Array(values) if values.is_empty()));
+}
+
+#[test]
+fn parses_resp3_types() {
+  assert!(matches!(
+    parse(b"=15\r\ntxt:Some string\r\n"),
+    Value::VerbatimString(format, text) if format == "txt" && text == "Some string"
+  ));
+  // The text may hold CRLF and colons of its own
+  assert!(matches!(
+    parse(b"=9\r\nmkd:a:\r\nb\r\n"),
+    Value::VerbatimString(format, text) if format == "mkd" && text == "a:\r\nb"
+  ));
+  assert!(matches!(
+    parse(b"(3492890328409238509324850943850943825024385\r\n"),
+    Value::BigNumber(digits) if digits == "3492890328409238509324850943850943825024385"
+  ));
+  assert!(matches!(parse(b"(-1\r\n"), Value::BigNumber(digits) if digits == "-1"));
+  // Doubles are values of their own at the top level too, not inline commands
+  assert!(matches!(parse(b",1.5\r\n"), Value::Double(d) if d == 1.5));
+  assert!(matches!(parse(b",-inf\r\n"), Value::Double(d) if d == f64::NEG_INFINITY));
+  assert_eq!(
+    serialized(&parse(b">2\r\n+message\r\n>0\r\n")),
+    serialized(&Value::Push(vec![
+      Value::SimpleString("message".to_string()),
+      Value::Push(vec![]),
+    ]))
+  );
+
+  for input in [
+    &b"=3\r\ntxt\r\n"[..],
+    b"=5\r\ntxt-a\r\n",
+    b"=6\r\ntxt:\xff\xfe\r\n",
+    b"=-1\r\n",
+    b"=4\r\ntxt:x\r\n",
+    b"(\r\n",
+    b"(-\r\n",
+    b"(12a\r\n",
+    b"(1.5\r\n",
+  ] {
+    assert!(parser().parse_message(input).is_err(), "{:?}", input);
+  }
+  assert!(
+    parser()
+      .parse_message(b"=15\r\ntxt:Some")
+      .unwrap()
+      .is_none()
+  );
+  assert!(parser().parse_message(b"(123").unwrap().is_none());
+}
+
+#[test]
+fn resp3_types_round_trip() {
+  let value = Value::Array(vec![
+    Value::VerbatimString("txt".to_string(), "line 1\r\nline 2\n".to_string()),
+    Value::VerbatimString("mkd".to_string(), String::new()),
+    Value::BigNumber("-12345678901234567890123456789".to_string()),
+    Value::Push(vec![Value::VerbatimString(
+      "txt".to_string(),
+      "x".to_string(),
+    )]),
+  ]);
+  let serialized_value = value.serialize();
+  assert_eq!(serialized(&parse(&serialized_value)), serialized(&value));
+
+  // RESP2 clients get the text and the digits as bulk strings
+  let mut resp2 = Vec::new();
+  value.serialize_resp2_to(&mut resp2).unwrap();
+  assert_eq!(
+    resp2,
+    Value::Array(vec![
+      bulk("line 1\r\nline 2\n"),
+      bulk(""),
+      bulk("-12345678901234567890123456789"),
+      Value::Array(vec![bulk("x")]),
+    ])
+    .serialize()
+  );
+  let mut resp2 = Vec::new();
+  Value::Integer(1).serialize_resp2_to(&mut resp2).unwrap();
+  assert_eq!(resp2, b":1\r\n");
+
+  // Doubles become bulk strings and booleans integers
+  let mut resp2 = Vec::new();
+  Value::Array(vec![
+    Value::Double(2.5),
+    Value::Double(f64::INFINITY),
+    Value::Boolean(true),
+    Value::Boolean(false),
+  ])
+  .serialize_resp2_to(&mut resp2)
+  .unwrap();
+  assert_eq!(
+    resp2,
+    Value::Array(vec![
+      bulk("2.5"),
+      bulk("inf"),
+      Value::Integer(1),
+      Value::Integer(0)
+    ])
+    .serialize()
+  );
+}
+
+#[test]
+fn values_split_anywhere_parse_the_same() {
+  let value = Value::Array(vec![
+    Value::BulkString("SET".to_string()),
+    Value::Array(vec![Value::Integer(1), Value::BulkString("x".repeat(100))]),
+    Value::BulkBytes(vec![0xff; 10]),
+  ]);
+  let input = value.serialize();
+
+  for split in 1..input.len() {
+    let mut parser = parser();
+    assert!(parser.parse_message(&input[..split]).unwrap().is_none());
+    let (parsed, consumed) = parser.parse_message(&input).unwrap().unwrap();
+    assert_eq!(consumed, input.len());
+    assert_eq!(serialized(&parsed), serialized(&value));
+  }
+
+  // One byte at a time
+  let mut parser = parser();
+  for end in 1..input.len() {
+    assert!(parser.parse_message(&input[..end]).unwrap().is_none());
+  }
+  assert!(parser.parse_message(&input).unwrap().is_some());
+}
+
+#[test]
+fn limits_are_enforced() {
+  let mut parser = RespParser::new(ParserLimits {
+    max_array_len: 4,
+    max_bulk_len: 8,
+  });
+  assert!(parser.parse_message(b"*5\r\n").is_err());
+  assert!(parser.parse_message(b"$9\r\n").is_err());
+  assert!(parser.parse_message(b"*4\r\n").unwrap().is_none());
+
+  for input in [
+    &b"$-2\r\n"[..],
+    b"*-5\r\n",
+    b"$abc\r\n",
+    b"$3\r\nabcde\r\n",
+    b"*1\r\n%1\r\n",
+  ] {
+    let mut parser = RespParser::new(ParserLimits::default());
+    assert!(parser.parse_message(input).is_err(), "{:?}", input);
+  }
+
+  // Declaring a huge array doesn't reserve memory for it
+  let mut parser = RespParser::new(ParserLimits::default());
+  assert!(
+    parser
+      .parse_message(b"*1000000\r\n:1\r\n")
+      .unwrap()
+      .is_none()
+  );
+}
+
+#[test]
+fn nesting_is_limited() {
+  let deep = nested(128).serialize();
+  assert!(parser().parse_message(&deep).unwrap().is_some());
+  let too_deep = nested(129).serialize();
+  assert!(parser().parse_message(&too_deep).is_err());
+}
+
+#[test]
+fn inline_commands() {
+  assert_eq!(
+    serialized(&parse(b"SET key \"two words\\n\" 'it\\'s'\r\n")),
+    serialized(&Value::Array(vec![
+      Value::BulkString("SET".to_string()),
+      Value::BulkString("key".to_string()),
+      Value::BulkString("two words\n".to_string()),
+      Value::BulkString("it's".to_string()),
+    ]))
+  );
+  assert!(parse(b"PING\n").to_command().unwrap().is_some());
+  assert!(parser().parse_message(b"SET \"open\r\n").is_err());
+  assert!(parser().parse_message(b"PING").unwrap().is_none());
+}
+
+#[test]
+fn to_command() {
+  let (command, args) = parse(b"*3\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\n*3\r\nx\r\n")
+    .to_command()
+    .unwrap()
+    .unwrap();
+  assert_eq!(command, "SET");
+  // A bulk string that looks like an array header stays a bulk string
+  assert!(matches!(&args[1], Value::BulkString(s) if s == "*3\r\nx"));
+
+  // Nested arrays are passed on as they are
+  let (_, args) = parse(b"*2\r\n+CMD\r\n*1\r\n*1\r\n:7\r\n")
+    .to_command()
+    .unwrap()
+    .unwrap();
+  assert_eq!(
+    serialized(&args[0]),
+    serialized(&Value::Array(vec![Value::Array(vec![Value::Integer(7)])]))
+  );
+
+  assert!(Value::Null.to_command().unwrap().is_none());
+  assert!(Value::Array(vec![]).to_command().unwrap().is_none());
+  assert!(Value::Integer(1).to_command().is_err());
+  assert!(Value::BulkString("PING".to_string()).to_command().is_err());
+  assert!(Value::Array(vec![Value::Integer(1)]).to_command().is_err());
+}
+
+/// A reply far larger than the handler's output buffer, nested arrays
+/// included, like KEYS on a large keyspace.
+fn large_array() -> Value {
+  Value::Array(
+    (0..50_000)
+      .map(|i| match i % 4 {
+        0 => Value::BulkString(format!("key:{}", i)),
+        1 => Value::Integer(i),
+        2 => Value::Array(vec![every_variant(), Value::BulkBytes(vec![0xff; 100])]),
+        _ => Value::Array(vec![]),
+      })
+      .collect(),
+  )
+}
+
+#[tokio::test]
+async fn large_replies_are_streamed_unchanged() {
+  let value = large_array();
+  let expected = value.serialize();
+  let (client, server) = tokio::io::duplex(4096);
+
+  let reader = tokio::spawn(async move {
+    let mut client = client;
+    let mut received = Vec::new();
+    client.read_to_end(&mut received).await.unwrap();
+    received
+  });
+  let mut handler = RespHandler::new(server, ParserLimits::default());
+  handler.set_protocol(3);
+  handler.write_value(value).await.unwrap();
+  handler.write_value(Value::Integer(1)).await.unwrap();
+  handler.flush().await.unwrap();
+  drop(handler);
+
+  let received = reader.await.unwrap();
+  assert_eq!(received.len(), expected.len() + 4);
+  assert!(received[..expected.len()] == expected[..]);
+  assert_eq!(&received[expected.len()..], b":1\r\n");
+}
+
+#[tokio::test]
+async fn resp2_handlers_downgrade_resp3_values() {
+  let value = Value::Push(vec![
+    Value::Array(vec![Value::BigNumber("100".to_string())]),
+    Value::VerbatimString("txt".to_string(), "hi".to_string()),
+  ]);
+  let (mut client, server) = tokio::io::duplex(4096);
+  let mut handler = RespHandler::new(server, ParserLimits::default());
+  handler.write_value(value.clone()).await.unwrap();
+  handler.set_protocol(3);
+  handler.write_value(value).await.unwrap();
+  handler.flush().await.unwrap();
+  drop(handler);
+
+  let mut received = Vec::new();
+  client.read_to_end(&mut received).await.unwrap();
+  assert_eq!(
+    String::from_utf8_lossy(&received),
+    "*2\r\n*1\r\n$3\r\n100\r\n$2\r\nhi\r\n>2\r\n*1\r\n(100\r\n=6\r\ntxt:hi\r\n"
+  );
+}
+
+#[tokio::test]
+async fn writing_waits_for_the_client_to_read() {
+  let (_client, server) = tokio::io::duplex(4096);
+  let mut handler = RespHandler::new(server, ParserLimits::default());
+
+  // Nobody reads, so the reply can't be written past the buffers
+  let writing = time::timeout(
+    Duration::from_millis(200),
+    handler.write_value(large_array()),
+  );
+  assert!(writing.await.is_err());
+}
+
+#[tokio::test]
+async fn small_replies_wait_for_a_flush() {
+  let (mut client, server) = tokio::io::duplex(4096);
+  let mut handler = RespHandler::new(server, ParserLimits::default());
+  let mut buf = [0; 16];
+
+  handler.write_value(Value::Integer(1)).await.unwrap();
+  handler.write_value(Value::Integer(2)).await.unwrap();
+  let read = time::timeout(Duration::from_millis(50), client.read(&mut buf));
+  assert!(read.await.is_err());
+
+  handler.flush().await.unwrap();
+  let read = client.read(&mut buf).await.unwrap();
+  assert_eq!(&buf[..read], b":1\r\n:2\r\n");
+}
+
+#[test]
+fn dump_and_restore_round_trip() {
+  let value = every_variant();
+  let restored = serde::restore(&serde::dump(&value)).unwrap();
+  assert_eq!(serialized(&restored), serialized(&value));
+
+  let deep = nested(127);
+  let restored = serde::restore(&serde::dump(&deep)).unwrap();
+  assert_eq!(serialized(&restored), serialized(&deep));
+}
+
+#[test]
+fn damaged_dumps_are_rejected() {
+  let blob = serde::dump(&every_variant());
+  for i in 0..blob.len() {
+    let mut damaged = blob.clone();
+    damaged[i] ^= 0x01;
+    let err = serde::restore(&damaged).unwrap_err();
+    assert_eq!(err.to_string(), "Bad data format");
+  }
+  assert!(serde::restore(&blob[..blob.len() - 1]).is_err());
+  assert!(serde::restore(b"").is_err());
+
+  assert!(serde::restore(&serde::dump(&nested(200))).is_err());
+}
@@ -0,0 +1,111 @@
+//! Command statistics and the slow log.
+//!
+//! Both are kept for the whole process, so everything touching them runs in
+//! a single test.
+
+mod common;
+
+use common::{ROOT, TestServer, USER, bulks, int, ok};
+use rusty_kv_store::{commands::stats, resp::value::Value, utils::settings::SlowlogSettings};
+
+/// Returns the `cmdstat_<command>` line of INFO commandstats, if any.
+async fn cmdstat(client: &mut common::TestClient, command: &str) -> Option<String> {
+  let Value::BulkString(info) = client.call("INFO", &["commandstats"]).await else {
+    panic!("INFO didn't return a bulk string");
+  };
+  let prefix = format!("cmdstat_{}:", command);
+  info
+    .lines()
+    .find(|line| line.starts_with(&prefix))
+    .map(str::to_string)
+}
+
+#[tokio::test]
+async fn commandstats_and_slowlog() {
+  stats::configure_slowlog(&SlowlogSettings {
+    threshold_micros: 20_000,
+    max_len: 128,
+  });
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+  let mut root = server.connect_as(ROOT).await;
+
+  // Commands run by EXEC are counted, queueing them isn't
+  client.send("ECHO", &["a"]).await;
+  let _ = client.read().await;
+  client.send("MULTI", &[]).await;
+  client.expect(ok()).await;
+  client.send("ECHO", &["b"]).await;
+  let _ = client.read().await;
+  assert!(
+    cmdstat(&mut root, "echo")
+      .await
+      .unwrap()
+      .starts_with("cmdstat_echo:calls=1,")
+  );
+  client.send("EXEC", &[]).await;
+  let _ = client.read().await;
+  assert!(
+    cmdstat(&mut root, "echo")
+      .await
+      .unwrap()
+      .starts_with("cmdstat_echo:calls=2,")
+  );
+
+  client.send("CONFIG", &["RESETSTAT"]).await;
+  client.expect_error("NOPERM ").await;
+  root.send("CONFIG", &["RESETSTAT"]).await;
+  root.expect(ok()).await;
+  assert!(cmdstat(&mut root, "echo").await.is_none());
+
+  // Only commands slower than the threshold are logged
+  root.send("SLOWLOG", &["RESET"]).await;
+  root.expect(ok()).await;
+  client.send("DEBUG", &["SLEEP", "0.05"]).await;
+  client.expect(ok()).await;
+  client.send("ECHO", &["fast"]).await;
+  let _ = client.read().await;
+  root.send("SLOWLOG", &["LEN"]).await;
+  root.expect(int(1)).await;
+  let Value::Array(entries) = root.call("SLOWLOG", &["GET"]).await else {
+    panic!("SLOWLOG GET didn't return an array");
+  };
+  let Value::Array(entry) = &entries[0] else {
+    panic!("slow log entries are arrays");
+  };
+  assert_eq!(
+    entry[3].serialize(),
+    bulks(&["DEBUG", "SLEEP", "0.05"]).serialize()
+  );
+  root.send("SLOWLOG", &["RESET"]).await;
+  root.expect(ok()).await;
+  root.send("SLOWLOG", &["LEN"]).await;
+  root.expect(int(0)).await;
+
+  client.send("SLOWLOG", &["LEN"]).await;
+  client.expect_error("NOPERM ").await;
+
+  // Credentials never end up in the log
+  let args = [
+    Value::BulkString(USER.0.to_string()),
+    Value::BulkString(USER.1.to_string()),
+  ];
+  assert_eq!(stats::slowlog_args("AUTH", &args), ["AUTH", "(redacted)"]);
+  let args = ["3", "AUTH", USER.0, USER.1].map(|arg| Value::BulkString(arg.to_string()));
+  assert_eq!(stats::slowlog_args("HELLO", &args), ["HELLO", "(redacted)"]);
+  let args = [
+    "127.0.0.1",
+    "6379",
+    "key",
+    "0",
+    "1000",
+    "AUTH2",
+    USER.0,
+    USER.1,
+  ]
+  .map(|arg| Value::BulkString(arg.to_string()));
+  assert_eq!(
+    stats::slowlog_args("MIGRATE", &args),
+    ["MIGRATE", "(redacted)"]
+  );
+}
@@ -0,0 +1,366 @@
+//! String commands and the options of SET, GETEX, CAS and friends.
+
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use common::{TestClient, TestServer, USER, bulk, int, ok};
+use rusty_kv_store::resp::value::Value;
+use tokio::time;
+
+fn unix_time() -> Duration {
+  SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
+}
+
+fn integer(value: Value) -> i64 {
+  match value {
+    Value::Integer(i) => i,
+    other => panic!("expected an integer, got {:?}", other),
+  }
+}
+
+/// Seconds until `key` expires, -1 without expiry and -2 if it is missing.
+async fn ttl(client: &mut TestClient, key: &str) -> i64 {
+  match integer(client.call("EXPIRETIME", &[key]).await) {
+    at if at < 0 => at,
+    at => at - unix_time().as_secs() as i64,
+  }
+}
+
+#[tokio::test]
+async fn getrange_and_setrange() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["greeting", "Hello World"]).await;
+  client.expect(ok()).await;
+  client.send("GETRANGE", &["greeting", "-3", "-1"]).await;
+  client.expect(bulk("rld")).await;
+  client.send("GETRANGE", &["greeting", "0", "4"]).await;
+  client.expect(bulk("Hello")).await;
+  client.send("GETRANGE", &["greeting", "100", "200"]).await;
+  client.expect(bulk("")).await;
+
+  client.send("SETRANGE", &["padded", "5", "x"]).await;
+  client.expect(int(6)).await;
+  client.send("GET", &["padded"]).await;
+  client
+    .expect(Value::BulkBytes(b"\0\0\0\0\0x".to_vec()))
+    .await;
+}
+
+#[tokio::test]
+async fn setbit_and_bitcount() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SETBIT", &["bits", "7", "1"]).await;
+  client.expect(int(0)).await;
+  client.send("GET", &["bits"]).await;
+  client.expect(Value::BulkBytes(b"\x01".to_vec())).await;
+  client.send("GETBIT", &["bits", "7"]).await;
+  client.expect(int(1)).await;
+  client.send("SETBIT", &["bits", "7", "0"]).await;
+  client.expect(int(1)).await;
+
+  client.send("SET", &["text", "foobar"]).await;
+  client.expect(ok()).await;
+  client.send("BITCOUNT", &["text"]).await;
+  client.expect(int(26)).await;
+  client.send("BITCOUNT", &["text", "1", "1"]).await;
+  client.expect(int(6)).await;
+}
+
+#[tokio::test]
+async fn set_option_conflicts() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  for args in [
+    &["key", "v", "EX", "10", "PX", "100"][..],
+    &["key", "v", "EX", "10", "KEEPTTL"],
+    &["key", "v", "EXAT", "10", "PXAT", "100"],
+    &["key", "v", "NX", "XX"],
+    &["key", "v", "IDLE"],
+    &["key", "v", "EX"],
+  ] {
+    client.send("SET", args).await;
+    client.expect_error("ERR syntax error").await;
+  }
+  client.send("SET", &["key", "v", "EX", "0"]).await;
+  client
+    .expect_error("ERR invalid expire time in 'set' command")
+    .await;
+}
+
+#[tokio::test]
+async fn set_expiry_options() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["key", "v", "EX", "100"]).await;
+  client.expect(ok()).await;
+  client.send("SET", &["key", "w", "KEEPTTL"]).await;
+  client.expect(ok()).await;
+  assert!(ttl(&mut client, "key").await > 90);
+  client.send("SET", &["key", "x"]).await;
+  client.expect(ok()).await;
+  assert_eq!(ttl(&mut client, "key").await, -1);
+
+  // A deadline in the past expires the key at once
+  client.send("SET", &["gone", "v", "PXAT", "1000"]).await;
+  client.expect(ok()).await;
+  assert_eq!(ttl(&mut client, "gone").await, -2);
+
+  client.send("SET", &["key", "y", "NX"]).await;
+  client.expect(Value::Null).await;
+  client.send("SET", &["key", "y", "XX", "GET"]).await;
+  client.expect(bulk("x")).await;
+}
+
+#[tokio::test]
+async fn getex_options() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["key", "v"]).await;
+  client.expect(ok()).await;
+  client.send("GETEX", &["key"]).await;
+  client.expect(bulk("v")).await;
+  assert_eq!(ttl(&mut client, "key").await, -1);
+
+  client.send("GETEX", &["key", "EX", "100"]).await;
+  client.expect(bulk("v")).await;
+  assert!(ttl(&mut client, "key").await > 90);
+  client.send("GETEX", &["key", "PX", "500000"]).await;
+  client.expect(bulk("v")).await;
+  assert!(ttl(&mut client, "key").await > 400);
+  client.send("GETEX", &["key", "PERSIST"]).await;
+  client.expect(bulk("v")).await;
+  assert_eq!(ttl(&mut client, "key").await, -1);
+
+  let at = (unix_time().as_secs() + 100).to_string();
+  client.send("GETEX", &["key", "EXAT", &at]).await;
+  client.expect(bulk("v")).await;
+  assert!(ttl(&mut client, "key").await > 90);
+
+  // A deadline in the past returns the value, then the key is gone
+  client.send("GETEX", &["key", "PXAT", "1000"]).await;
+  client.expect(bulk("v")).await;
+  assert_eq!(ttl(&mut client, "key").await, -2);
+  client.send("GETEX", &["key"]).await;
+  client.expect(Value::Null).await;
+
+  client.send("SET", &["key", "v"]).await;
+  client.expect(ok()).await;
+  for args in [
+    &["key", "EX"][..],
+    &["key", "PERSIST", "10"],
+    &["key", "KEEPTTL"],
+  ] {
+    client.send("GETEX", args).await;
+    client.expect_error("ERR syntax error").await;
+  }
+  for ttl in ["0", "-5"] {
+    client.send("GETEX", &["key", "EX", ttl]).await;
+    client
+      .expect_error("ERR invalid expire time in 'getex' command")
+      .await;
+  }
+}
+
+#[tokio::test]
+async fn expiretime_and_pexpiretime() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["key", "v", "EX", "100"]).await;
+  client.expect(ok()).await;
+  let expected = unix_time() + Duration::from_secs(100);
+  let seconds = integer(client.call("EXPIRETIME", &["key"]).await);
+  assert!((seconds - expected.as_secs() as i64).abs() <= 1);
+  let millis = integer(client.call("PEXPIRETIME", &["key"]).await);
+  assert!((millis - expected.as_millis() as i64).abs() <= 1000);
+
+  client.send("SET", &["persistent", "v"]).await;
+  client.expect(ok()).await;
+  client.send("EXPIRETIME", &["persistent"]).await;
+  client.expect(int(-1)).await;
+  client.send("PEXPIRETIME", &["missing"]).await;
+  client.expect(int(-2)).await;
+}
+
+#[tokio::test]
+async fn msetnx_sets_all_or_nothing() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["b", "old"]).await;
+  client.expect(ok()).await;
+  client.send("MSETNX", &["a", "1", "b", "2", "c", "3"]).await;
+  client.expect(int(0)).await;
+  assert_eq!(ttl(&mut client, "a").await, -2);
+  assert_eq!(ttl(&mut client, "c").await, -2);
+  client.send("GET", &["b"]).await;
+  client.expect(bulk("old")).await;
+
+  client.send("MSETNX", &["a", "1", "c", "3"]).await;
+  client.expect(int(1)).await;
+  client.send("MSETNX", &["a", "1", "c"]).await;
+  client.expect_error("ERR wrong number of arguments").await;
+}
+
+#[tokio::test]
+async fn cas_compares_before_writing() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+  let mut other = server.connect_as(USER).await;
+
+  client.send("SET", &["counter", "41"]).await;
+  client.expect(ok()).await;
+  client.send("CAS", &["counter", "41", "42"]).await;
+  client.expect(int(1)).await;
+
+  // Another write between the read and the CAS makes it miss
+  client.send("GET", &["counter"]).await;
+  client.expect(bulk("42")).await;
+  other.send("SET", &["counter", "100"]).await;
+  other.expect(ok()).await;
+  client.send("CAS", &["counter", "42", "43"]).await;
+  client.expect(int(0)).await;
+  client.send("GET", &["counter"]).await;
+  client.expect(bulk("100")).await;
+
+  // NX only matches a missing key
+  client.send("CAS", &["counter", "NX", "created"]).await;
+  client.expect(int(0)).await;
+  client.send("CAS", &["fresh", "nx", "created"]).await;
+  client.expect(int(1)).await;
+  client.send("GET", &["fresh"]).await;
+  client.expect(bulk("created")).await;
+}
+
+#[tokio::test]
+async fn cadel_deletes_only_the_expected_value() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["lock", "owner-1"]).await;
+  client.expect(ok()).await;
+  client.send("CADEL", &["lock", "owner-2"]).await;
+  client.expect(int(0)).await;
+  client.send("GET", &["lock"]).await;
+  client.expect(bulk("owner-1")).await;
+  client.send("CADEL", &["lock", "owner-1"]).await;
+  client.expect(int(1)).await;
+  client.send("CADEL", &["lock", "owner-1"]).await;
+  client.expect(int(0)).await;
+}
+
+#[tokio::test]
+async fn string_commands_on_a_list() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("LPUSH", &["list", "a"]).await;
+  client.expect(int(1)).await;
+  for (command, args) in [
+    ("GET", &["list"][..]),
+    ("GETRANGE", &["list", "0", "1"]),
+    ("SETRANGE", &["list", "0", "x"]),
+    ("SET", &["list", "v", "GET"]),
+  ] {
+    client.send(command, args).await;
+    client.expect_error("WRONGTYPE ").await;
+  }
+
+  client.send("SET", &["list", "v"]).await;
+  client.expect(ok()).await;
+  client.send("GET", &["list"]).await;
+  client.expect(bulk("v")).await;
+}
+
+#[tokio::test]
+async fn object_idletime_and_touch() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["key", "v"]).await;
+  client.expect(ok()).await;
+  client.send("SET", &["other", "v"]).await;
+  client.expect(ok()).await;
+  client.send("SADD", &["set", "a"]).await;
+  client.expect(int(1)).await;
+  time::sleep(Duration::from_millis(1100)).await;
+  assert!(integer(client.call("OBJECT", &["IDLETIME", "key"]).await) >= 1);
+
+  client.send("GET", &["key"]).await;
+  client.expect(bulk("v")).await;
+  client.send("OBJECT", &["IDLETIME", "key"]).await;
+  client.expect(int(0)).await;
+
+  client.send("SET", &["short", "v", "PX", "1"]).await;
+  client.expect(ok()).await;
+  time::sleep(Duration::from_millis(10)).await;
+  client
+    .send("TOUCH", &["other", "short", "set", "missing"])
+    .await;
+  client.expect(int(2)).await;
+  client.send("OBJECT", &["IDLETIME", "other"]).await;
+  client.expect(int(0)).await;
+  client.send("OBJECT", &["IDLETIME", "set"]).await;
+  client.expect(int(0)).await;
+  client.send("OBJECT", &["IDLETIME", "missing"]).await;
+  client.expect(Value::Null).await;
+}
+
+#[tokio::test]
+async fn reads_keep_the_expiry_and_collections_have_idle_times() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["key", "v", "PX", "100000"]).await;
+  client.expect(ok()).await;
+  client.send("RPUSH", &["list", "a"]).await;
+  client.expect(int(1)).await;
+  let expires_at = integer(client.call("PEXPIRETIME", &["key"]).await);
+  time::sleep(Duration::from_millis(1100)).await;
+
+  client.send("GET", &["key"]).await;
+  client.expect(bulk("v")).await;
+  client.send("PEXPIRETIME", &["key"]).await;
+  client.expect(int(expires_at)).await;
+  client.send("OBJECT", &["IDLETIME", "key"]).await;
+  client.expect(int(0)).await;
+
+  assert!(integer(client.call("OBJECT", &["IDLETIME", "list"]).await) >= 1);
+  client.send("LRANGE", &["list", "0", "-1"]).await;
+  let _ = client.read().await;
+  client.send("OBJECT", &["IDLETIME", "list"]).await;
+  client.expect(int(0)).await;
+}
+
+#[tokio::test]
+async fn memory_usage() {
+  let server = TestServer::new();
+  let mut client = server.connect_as(USER).await;
+
+  client.send("SET", &["small", "v"]).await;
+  client.expect(ok()).await;
+  client.send("SET", &["large", &"x".repeat(10_000)]).await;
+  client.expect(ok()).await;
+  let small = integer(client.call("MEMORY", &["USAGE", "small"]).await);
+  let large = integer(
+    client
+      .call("MEMORY", &["USAGE", "large", "SAMPLES", "5"])
+      .await,
+  );
+  assert!(small < large);
+
+  client.send("RPUSH", &["list", &"x".repeat(10_000)]).await;
+  client.expect(int(1)).await;
+  let list = integer(client.call("MEMORY", &["USAGE", "list"]).await);
+  assert!(list > 10_000, "{}", list);
+  client.send("MEMORY", &["USAGE", "missing"]).await;
+  client.expect(Value::Null).await;
+}